@@ -120,6 +120,7 @@ impl From<Converter<Type>> for ir::Type {
             Type::F32 => ir::types::F32,
             Type::F64 => ir::types::F64,
             Type::V128 => ir::types::I32X4,
+            Type::ExternRef | Type::FuncRef => ir::types::I64,
         }
     }
 }
@@ -132,6 +133,7 @@ impl From<Converter<Type>> for ir::AbiParam {
             Type::F32 => ir::AbiParam::new(ir::types::F32),
             Type::F64 => ir::AbiParam::new(ir::types::F64),
             Type::V128 => ir::AbiParam::new(ir::types::I32X4),
+            Type::ExternRef | Type::FuncRef => ir::AbiParam::new(ir::types::I64),
         }
     }
 }
@@ -208,6 +208,7 @@ fn wasm_ty_to_clif(ty: Type) -> ir::types::Type {
         Type::F32 => ir::types::F32,
         Type::F64 => ir::types::F64,
         Type::V128 => ir::types::I32X4,
+        Type::ExternRef | Type::FuncRef => ir::types::I64,
     }
 }
 
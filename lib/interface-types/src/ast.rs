@@ -5,7 +5,7 @@ use crate::interpreter::Instruction;
 use std::str;
 
 /// Represents the types supported by WIT.
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
 pub enum InterfaceType {
     /// A 8-bits signed integer.
     S8,
@@ -48,16 +48,46 @@ pub enum InterfaceType {
 
     /// A 64-bits integer (as defiend in WebAssembly core).
     I64,
+
+    /// A sequence of bytes.
+    Bytes,
+
+    /// A record.
+    Record(RecordType),
 }
 
-/// Represents a type signature.
+/// Represents a record type, i.e. an ordered list of fields.
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct RecordType {
+    /// Types of the fields.
+    pub fields: Vec<InterfaceType>,
+}
+
+/// Represents the kind of type.
 #[derive(PartialEq, Debug)]
-pub struct Type {
-    /// Types for the parameters.
-    pub inputs: Vec<InterfaceType>,
+pub(crate) enum TypeKind {
+    /// A function type.
+    Function,
+
+    /// A record type.
+    Record,
+}
 
-    /// Types for the results.
-    pub outputs: Vec<InterfaceType>,
+/// Represents a type.
+#[derive(PartialEq, Debug, Clone)]
+pub enum Type {
+    /// A function signature.
+    Function {
+        /// Types for the parameters.
+        inputs: Vec<InterfaceType>,
+
+        /// Types for the results.
+        outputs: Vec<InterfaceType>,
+    },
+
+    /// A record type, used by the `record.lift` and `record.lower`
+    /// instructions.
+    Record(RecordType),
 }
 
 /// Represents an imported function.
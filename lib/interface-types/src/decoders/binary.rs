@@ -7,6 +7,10 @@ use nom::{
 };
 use std::{convert::TryFrom, str};
 
+/// The maximum number of nested record types, so that a crafted
+/// input cannot exhaust the stack.
+const MAX_RECORD_DEPTH: usize = 32;
+
 /// Parse an `InterfaceType`.
 impl TryFrom<u8> for InterfaceType {
     type Error = &'static str;
@@ -27,11 +31,25 @@ impl TryFrom<u8> for InterfaceType {
             11 => Self::Anyref,
             12 => Self::I32,
             13 => Self::I64,
+            14 => Self::Bytes,
             _ => return Err("Unknown interface type code."),
         })
     }
 }
 
+/// Parse a type kind.
+impl TryFrom<u8> for TypeKind {
+    type Error = &'static str;
+
+    fn try_from(code: u8) -> Result<Self, Self::Error> {
+        Ok(match code {
+            0x00 => Self::Function,
+            0x01 => Self::Record,
+            _ => return Err("Unknown type kind code."),
+        })
+    }
+}
+
 /// Parse an interface kind.
 impl TryFrom<u8> for InterfaceKind {
     type Error = &'static str;
@@ -134,6 +152,14 @@ fn list<'input, I, E: ParseError<&'input [u8]>>(
 /// Parse a type.
 fn ty<'input, E: ParseError<&'input [u8]>>(
     input: &'input [u8],
+) -> IResult<&'input [u8], InterfaceType, E> {
+    nested_ty(input, 0)
+}
+
+/// Parse a type found inside `depth` record types.
+fn nested_ty<'input, E: ParseError<&'input [u8]>>(
+    input: &'input [u8],
+    depth: usize,
 ) -> IResult<&'input [u8], InterfaceType, E> {
     if input.is_empty() {
         return Err(Err::Error(make_error(input, ErrorKind::Eof)));
@@ -141,12 +167,56 @@ fn ty<'input, E: ParseError<&'input [u8]>>(
 
     let (output, ty) = byte(input)?;
 
+    // A record type is followed by the types of its fields.
+    if ty == 0x0f {
+        if depth >= MAX_RECORD_DEPTH {
+            return Err(Err::Error(make_error(input, ErrorKind::TooLarge)));
+        }
+
+        let (output, record_type) = nested_record_type(output, depth + 1)?;
+
+        return Ok((output, InterfaceType::Record(record_type)));
+    }
+
     match InterfaceType::try_from(ty) {
         Ok(ty) => Ok((output, ty)),
         Err(_) => Err(Err::Error(make_error(input, ErrorKind::ParseTo))),
     }
 }
 
+/// Parse a record type.
+fn record_type<'input, E: ParseError<&'input [u8]>>(
+    input: &'input [u8],
+) -> IResult<&'input [u8], RecordType, E> {
+    nested_record_type(input, 1)
+}
+
+/// Parse a record type found inside `depth - 1` other record types.
+fn nested_record_type<'input, E: ParseError<&'input [u8]>>(
+    input: &'input [u8],
+    depth: usize,
+) -> IResult<&'input [u8], RecordType, E> {
+    if input.is_empty() {
+        return Err(Err::Error(make_error(input, ErrorKind::Eof)));
+    }
+
+    let length = input[0] as usize;
+    let mut input = &input[1..];
+
+    if input.len() < length {
+        return Err(Err::Error(make_error(input, ErrorKind::Eof)));
+    }
+
+    let mut fields = Vec::with_capacity(length);
+
+    for _ in 0..length {
+        consume!((input, field) = nested_ty(input, depth)?);
+        fields.push(field);
+    }
+
+    Ok((input, RecordType { fields }))
+}
+
 /// Parse an instruction with its arguments.
 fn instruction<'input, E: ParseError<&'input [u8]>>(
     input: &'input [u8],
@@ -236,6 +306,66 @@ fn instruction<'input, E: ParseError<&'input [u8]>>(
         0x2c => (input, Instruction::S64ToI64),
         0x2d => (input, Instruction::U64ToI64),
 
+        0x2e => {
+            consume!((input, argument_0) = uleb(input)?);
+            (
+                input,
+                Instruction::RecordLift {
+                    type_index: argument_0 as u32,
+                },
+            )
+        }
+
+        0x2f => {
+            consume!((input, argument_0) = uleb(input)?);
+            (
+                input,
+                Instruction::RecordLower {
+                    type_index: argument_0 as u32,
+                },
+            )
+        }
+
+        0x30 => {
+            consume!((input, argument_0) = string(input)?);
+            (
+                input,
+                Instruction::StringLiftMemory {
+                    deallocator_name: argument_0,
+                },
+            )
+        }
+
+        0x31 => {
+            consume!((input, argument_0) = string(input)?);
+            (
+                input,
+                Instruction::StringLowerMemory {
+                    allocator_name: argument_0,
+                },
+            )
+        }
+
+        0x32 => {
+            consume!((input, argument_0) = string(input)?);
+            (
+                input,
+                Instruction::BytesLiftMemory {
+                    deallocator_name: argument_0,
+                },
+            )
+        }
+
+        0x33 => {
+            consume!((input, argument_0) = string(input)?);
+            (
+                input,
+                Instruction::BytesLowerMemory {
+                    allocator_name: argument_0,
+                },
+            )
+        }
+
         _ => return Err(Err::Error(make_error(input, ErrorKind::ParseTo))),
     })
 }
@@ -249,10 +379,25 @@ fn types<'input, E: ParseError<&'input [u8]>>(
     let mut types = Vec::with_capacity(number_of_types as usize);
 
     for _ in 0..number_of_types {
-        consume!((input, inputs) = list(input, ty)?);
-        consume!((input, outputs) = list(input, ty)?);
+        consume!((input, type_kind) = byte(input)?);
 
-        types.push(Type { inputs, outputs });
+        let type_kind = TypeKind::try_from(type_kind)
+            .map_err(|_| Err::Error(make_error(input, ErrorKind::ParseTo)))?;
+
+        match type_kind {
+            TypeKind::Function => {
+                consume!((input, inputs) = list(input, ty)?);
+                consume!((input, outputs) = list(input, ty)?);
+
+                types.push(Type::Function { inputs, outputs });
+            }
+
+            TypeKind::Record => {
+                consume!((input, record_type) = record_type(input)?);
+
+                types.push(Type::Record(record_type));
+            }
+        }
     }
 
     Ok((input, types))
@@ -418,6 +563,7 @@ fn interfaces<'input, E: ParseError<&'input [u8]>>(
 /// let input = &[
 ///     0x00, // type section
 ///     0x01, // 1 type
+///     0x00, // function type kind
 ///     0x01, // list of 1 item
 ///     0x00, // S8
 ///     0x01, // list of 1 item
@@ -451,7 +597,7 @@ fn interfaces<'input, E: ParseError<&'input [u8]>>(
 /// let output = Ok((
 ///     &[] as &[u8],
 ///     Interfaces {
-///         types: vec![Type {
+///         types: vec![Type::Function {
 ///             inputs: vec![InterfaceType::S8],
 ///             outputs: vec![InterfaceType::S16],
 ///         }],
@@ -594,7 +740,7 @@ mod tests {
     #[test]
     fn test_ty() {
         let input = &[
-            0x0e, // list of 14 items
+            0x10, // list of 16 items
             0x00, // S8
             0x01, // S16
             0x02, // S32
@@ -609,6 +755,8 @@ mod tests {
             0x0b, // Anyref
             0x0c, // I32
             0x0d, // I64
+            0x0e, // Bytes
+            0x0f, 0x01, 0x0c, // Record { fields: [I32] }
             0x01,
         ];
         let output = Ok((
@@ -628,16 +776,42 @@ mod tests {
                 InterfaceType::Anyref,
                 InterfaceType::I32,
                 InterfaceType::I64,
+                InterfaceType::Bytes,
+                InterfaceType::Record(RecordType {
+                    fields: vec![InterfaceType::I32],
+                }),
             ],
         ));
 
         assert_eq!(list::<InterfaceType, ()>(input, ty), output);
     }
 
+    #[test]
+    fn test_ty_nested_records() {
+        let nested_records = |depth: usize| -> Vec<u8> {
+            let mut input = [0x0f, 0x01].repeat(depth);
+            input.push(0x0c);
+
+            input
+        };
+
+        let input = nested_records(MAX_RECORD_DEPTH);
+        assert!(ty::<()>(&input).is_ok());
+
+        let input = nested_records(MAX_RECORD_DEPTH + 1);
+        assert_eq!(
+            ty::<(&[u8], error::ErrorKind)>(&input),
+            Err(Err::Error((
+                &input[2 * MAX_RECORD_DEPTH..],
+                error::ErrorKind::TooLarge
+            ))),
+        );
+    }
+
     #[test]
     fn test_instructions() {
         let input = &[
-            0x2c, // list of 44 items
+            0x32, // list of 50 items
             0x00, 0x01, // ArgumentGet { index: 1 }
             0x01, 0x01, // Call { function_index: 1 }
             0x02, 0x03, 0x61, 0x62, 0x63, // CallExport { export_name: "abc" }
@@ -682,6 +856,12 @@ mod tests {
             0x2b, // U32ToI64
             0x2c, // S64ToI64
             0x2d, // U64ToI64
+            0x2e, 0x01, // RecordLift { type_index: 1 }
+            0x2f, 0x01, // RecordLower { type_index: 1 }
+            0x30, 0x03, 0x61, 0x62, 0x63, // StringLiftMemory { deallocator_name: "abc" }
+            0x31, 0x03, 0x61, 0x62, 0x63, // StringLowerMemory { allocator_name: "abc" }
+            0x32, 0x03, 0x61, 0x62, 0x63, // BytesLiftMemory { deallocator_name: "abc" }
+            0x33, 0x03, 0x61, 0x62, 0x63, // BytesLowerMemory { allocator_name: "abc" }
             0x0a,
         ];
         let output = Ok((
@@ -733,6 +913,20 @@ mod tests {
                 Instruction::U32ToI64,
                 Instruction::S64ToI64,
                 Instruction::U64ToI64,
+                Instruction::RecordLift { type_index: 1 },
+                Instruction::RecordLower { type_index: 1 },
+                Instruction::StringLiftMemory {
+                    deallocator_name: "abc",
+                },
+                Instruction::StringLowerMemory {
+                    allocator_name: "abc",
+                },
+                Instruction::BytesLiftMemory {
+                    deallocator_name: "abc",
+                },
+                Instruction::BytesLowerMemory {
+                    allocator_name: "abc",
+                },
             ],
         ));
 
@@ -771,6 +965,7 @@ mod tests {
     fn test_types() {
         let input = &[
             0x01, // 1 type
+            0x00, // function type kind
             0x02, // list of 2 items
             0x02, // S32
             0x02, // S32
@@ -779,7 +974,7 @@ mod tests {
         ];
         let output = Ok((
             &[] as &[u8],
-            vec![Type {
+            vec![Type::Function {
                 inputs: vec![InterfaceType::S32, InterfaceType::S32],
                 outputs: vec![InterfaceType::S32],
             }],
@@ -788,6 +983,32 @@ mod tests {
         assert_eq!(types::<()>(input), output);
     }
 
+    #[test]
+    fn test_record_types() {
+        let input = &[
+            0x01, // 1 type
+            0x01, // record type kind
+            0x02, // list of 2 items
+            0x0a, // String
+            0x0f, // Record
+            0x01, // list of 1 item
+            0x0e, // Bytes
+        ];
+        let output = Ok((
+            &[] as &[u8],
+            vec![Type::Record(RecordType {
+                fields: vec![
+                    InterfaceType::String,
+                    InterfaceType::Record(RecordType {
+                        fields: vec![InterfaceType::Bytes],
+                    }),
+                ],
+            })],
+        ));
+
+        assert_eq!(types::<()>(input), output);
+    }
+
     #[test]
     fn test_imports() {
         let input = &[
@@ -846,6 +1067,7 @@ mod tests {
         let input = &[
             0x00, // type section
             0x01, // 1 type
+            0x00, // function type kind
             0x01, // list of 1 item
             0x00, // S8
             0x01, // list of 1 item
@@ -879,7 +1101,7 @@ mod tests {
         let output = Ok((
             &[] as &[u8],
             Interfaces {
-                types: vec![Type {
+                types: vec![Type::Function {
                     inputs: vec![InterfaceType::S8],
                     outputs: vec![InterfaceType::S16],
                 }],
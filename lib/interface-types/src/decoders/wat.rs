@@ -24,6 +24,8 @@ mod keyword {
     custom_keyword!(u32);
     custom_keyword!(u64);
     custom_keyword!(string);
+    custom_keyword!(bytes);
+    custom_keyword!(record);
 
     // Instructions.
     custom_keyword!(argument_get = "arg.get");
@@ -70,6 +72,12 @@ mod keyword {
     custom_keyword!(u32_to_i64 = "u32-to-i64");
     custom_keyword!(s64_to_i64 = "s64-to-i64");
     custom_keyword!(u64_to_i64 = "u64-to-i64");
+    custom_keyword!(record_lift = "record.lift");
+    custom_keyword!(record_lower = "record.lower");
+    custom_keyword!(string_lift_memory = "string.lift-memory");
+    custom_keyword!(string_lower_memory = "string.lower-memory");
+    custom_keyword!(bytes_lift_memory = "bytes.lift-memory");
+    custom_keyword!(bytes_lower_memory = "bytes.lower-memory");
 }
 
 impl Parse<'_> for InterfaceType {
@@ -132,12 +140,44 @@ impl Parse<'_> for InterfaceType {
             parser.parse::<keyword::i64>()?;
 
             Ok(InterfaceType::I64)
+        } else if lookahead.peek::<keyword::bytes>() {
+            parser.parse::<keyword::bytes>()?;
+
+            Ok(InterfaceType::Bytes)
+        } else if lookahead.peek::<RecordType>() {
+            Ok(InterfaceType::Record(
+                parser.parens(|parser| parser.parse())?,
+            ))
         } else {
             Err(lookahead.error())
         }
     }
 }
 
+impl Peek for RecordType {
+    fn peek(cursor: Cursor<'_>) -> bool {
+        cursor.lparen().map_or(false, keyword::record::peek)
+    }
+
+    fn display() -> &'static str {
+        "`(record`"
+    }
+}
+
+impl Parse<'_> for RecordType {
+    fn parse(parser: Parser<'_>) -> Result<Self> {
+        parser.parse::<keyword::record>()?;
+
+        let mut fields = vec![];
+
+        while !parser.is_empty() {
+            fields.push(parser.parse()?);
+        }
+
+        Ok(RecordType { fields })
+    }
+}
+
 impl<'a> Parse<'a> for Instruction<'a> {
     #[allow(clippy::cognitive_complexity)]
     fn parse(parser: Parser<'a>) -> Result<Self> {
@@ -327,6 +367,42 @@ impl<'a> Parse<'a> for Instruction<'a> {
             parser.parse::<keyword::u64_to_i64>()?;
 
             Ok(Instruction::U64ToI64)
+        } else if lookahead.peek::<keyword::record_lift>() {
+            parser.parse::<keyword::record_lift>()?;
+
+            Ok(Instruction::RecordLift {
+                type_index: parser.parse()?,
+            })
+        } else if lookahead.peek::<keyword::record_lower>() {
+            parser.parse::<keyword::record_lower>()?;
+
+            Ok(Instruction::RecordLower {
+                type_index: parser.parse()?,
+            })
+        } else if lookahead.peek::<keyword::string_lift_memory>() {
+            parser.parse::<keyword::string_lift_memory>()?;
+
+            Ok(Instruction::StringLiftMemory {
+                deallocator_name: parser.parse()?,
+            })
+        } else if lookahead.peek::<keyword::string_lower_memory>() {
+            parser.parse::<keyword::string_lower_memory>()?;
+
+            Ok(Instruction::StringLowerMemory {
+                allocator_name: parser.parse()?,
+            })
+        } else if lookahead.peek::<keyword::bytes_lift_memory>() {
+            parser.parse::<keyword::bytes_lift_memory>()?;
+
+            Ok(Instruction::BytesLiftMemory {
+                deallocator_name: parser.parse()?,
+            })
+        } else if lookahead.peek::<keyword::bytes_lower_memory>() {
+            parser.parse::<keyword::bytes_lower_memory>()?;
+
+            Ok(Instruction::BytesLowerMemory {
+                allocator_name: parser.parse()?,
+            })
         } else {
             Err(lookahead.error())
         }
@@ -438,25 +514,34 @@ impl<'a> Parse<'a> for Type {
     fn parse(parser: Parser<'a>) -> Result<Self> {
         parser.parse::<keyword::r#type>()?;
 
-        let (inputs, outputs) = parser.parens(|parser| {
-            parser.parse::<keyword::func>()?;
+        parser.parens(|parser| {
+            let mut lookahead = parser.lookahead1();
 
-            let mut input_types = vec![];
-            let mut output_types = vec![];
+            if lookahead.peek::<keyword::func>() {
+                parser.parse::<keyword::func>()?;
 
-            while !parser.is_empty() {
-                let function_type = parser.parse::<FunctionType>()?;
+                let mut input_types = vec![];
+                let mut output_types = vec![];
 
-                match function_type {
-                    FunctionType::Input(mut inputs) => input_types.append(&mut inputs),
-                    FunctionType::Output(mut outputs) => output_types.append(&mut outputs),
-                }
-            }
+                while !parser.is_empty() {
+                    let function_type = parser.parse::<FunctionType>()?;
 
-            Ok((input_types, output_types))
-        })?;
+                    match function_type {
+                        FunctionType::Input(mut inputs) => input_types.append(&mut inputs),
+                        FunctionType::Output(mut outputs) => output_types.append(&mut outputs),
+                    }
+                }
 
-        Ok(Type { inputs, outputs })
+                Ok(Type::Function {
+                    inputs: input_types,
+                    outputs: output_types,
+                })
+            } else if lookahead.peek::<keyword::record>() {
+                Ok(Type::Record(parser.parse()?))
+            } else {
+                Err(lookahead.error())
+            }
+        })
     }
 }
 
@@ -598,7 +683,7 @@ impl<'a> Parse<'a> for Interfaces<'a> {
 /// )
 /// .unwrap();
 /// let output = Interfaces {
-///     types: vec![Type {
+///     types: vec![Type::Function {
 ///         inputs: vec![InterfaceType::I32],
 ///         outputs: vec![InterfaceType::S8],
 ///     }],
@@ -639,8 +724,22 @@ mod tests {
     #[test]
     fn test_interface_type() {
         let inputs = vec![
-            "s8", "s16", "s32", "s64", "u8", "u16", "u32", "u64", "f32", "f64", "string", "anyref",
-            "i32", "i64",
+            "s8",
+            "s16",
+            "s32",
+            "s64",
+            "u8",
+            "u16",
+            "u32",
+            "u64",
+            "f32",
+            "f64",
+            "string",
+            "anyref",
+            "i32",
+            "i64",
+            "bytes",
+            "(record string (record i32))",
         ];
         let outputs = vec![
             InterfaceType::S8,
@@ -657,6 +756,15 @@ mod tests {
             InterfaceType::Anyref,
             InterfaceType::I32,
             InterfaceType::I64,
+            InterfaceType::Bytes,
+            InterfaceType::Record(RecordType {
+                fields: vec![
+                    InterfaceType::String,
+                    InterfaceType::Record(RecordType {
+                        fields: vec![InterfaceType::I32],
+                    }),
+                ],
+            }),
         ];
 
         assert_eq!(inputs.len(), outputs.len());
@@ -716,6 +824,12 @@ mod tests {
             "u32-to-i64",
             "s64-to-i64",
             "u64-to-i64",
+            "record.lift 7",
+            "record.lower 7",
+            r#"string.lift-memory "foo""#,
+            r#"string.lower-memory "foo""#,
+            r#"bytes.lift-memory "foo""#,
+            r#"bytes.lower-memory "foo""#,
         ];
         let outputs = vec![
             Instruction::ArgumentGet { index: 7 },
@@ -764,6 +878,20 @@ mod tests {
             Instruction::U32ToI64,
             Instruction::S64ToI64,
             Instruction::U64ToI64,
+            Instruction::RecordLift { type_index: 7 },
+            Instruction::RecordLower { type_index: 7 },
+            Instruction::StringLiftMemory {
+                deallocator_name: "foo",
+            },
+            Instruction::StringLowerMemory {
+                allocator_name: "foo",
+            },
+            Instruction::BytesLiftMemory {
+                deallocator_name: "foo",
+            },
+            Instruction::BytesLowerMemory {
+                allocator_name: "foo",
+            },
         ];
 
         assert_eq!(inputs.len(), outputs.len());
@@ -811,7 +939,7 @@ mod tests {
     #[test]
     fn test_type() {
         let input = buffer(r#"(@interface type (func (param i32 i32) (result i32)))"#);
-        let output = Interface::Type(Type {
+        let output = Interface::Type(Type::Function {
             inputs: vec![InterfaceType::I32, InterfaceType::I32],
             outputs: vec![InterfaceType::I32],
         });
@@ -819,6 +947,21 @@ mod tests {
         assert_eq!(parser::parse::<Interface>(&input).unwrap(), output);
     }
 
+    #[test]
+    fn test_record_type() {
+        let input = buffer(r#"(@interface type (record string (record i32 i32)))"#);
+        let output = Interface::Type(Type::Record(RecordType {
+            fields: vec![
+                InterfaceType::String,
+                InterfaceType::Record(RecordType {
+                    fields: vec![InterfaceType::I32, InterfaceType::I32],
+                }),
+            ],
+        }));
+
+        assert_eq!(parser::parse::<Interface>(&input).unwrap(), output);
+    }
+
     #[test]
     fn test_export() {
         let input = buffer(r#"(@interface export "foo" (func 0))"#);
@@ -889,7 +1032,7 @@ mod tests {
 (@interface implement (func 0) (func 1))"#,
         );
         let output = Interfaces {
-            types: vec![Type {
+            types: vec![Type::Function {
                 inputs: vec![InterfaceType::I32],
                 outputs: vec![InterfaceType::S8],
             }],
@@ -108,6 +108,36 @@ where
             InterfaceType::Anyref => 0x0b_u8.to_bytes(writer),
             InterfaceType::I32 => 0x0c_u8.to_bytes(writer),
             InterfaceType::I64 => 0x0d_u8.to_bytes(writer),
+            InterfaceType::Bytes => 0x0e_u8.to_bytes(writer),
+            InterfaceType::Record(record_type) => {
+                0x0f_u8.to_bytes(writer)?;
+                record_type.to_bytes(writer)
+            }
+        }
+    }
+}
+
+/// Encode a `RecordType` into bytes.
+///
+/// Decoder is `decoders::binary::record_type`.
+impl<W> ToBytes<W> for RecordType
+where
+    W: Write,
+{
+    fn to_bytes(&self, writer: &mut W) -> io::Result<()> {
+        self.fields.to_bytes(writer)
+    }
+}
+
+/// Encode a `TypeKind` into bytes.
+impl<W> ToBytes<W> for TypeKind
+where
+    W: Write,
+{
+    fn to_bytes(&self, writer: &mut W) -> io::Result<()> {
+        match self {
+            Self::Function => 0x00_u8.to_bytes(writer),
+            Self::Record => 0x01_u8.to_bytes(writer),
         }
     }
 }
@@ -136,8 +166,18 @@ where
     W: Write,
 {
     fn to_bytes(&self, writer: &mut W) -> io::Result<()> {
-        self.inputs.to_bytes(writer)?;
-        self.outputs.to_bytes(writer)?;
+        match self {
+            Type::Function { inputs, outputs } => {
+                TypeKind::Function.to_bytes(writer)?;
+                inputs.to_bytes(writer)?;
+                outputs.to_bytes(writer)?;
+            }
+
+            Type::Record(record_type) => {
+                TypeKind::Record.to_bytes(writer)?;
+                record_type.to_bytes(writer)?;
+            }
+        }
 
         Ok(())
     }
@@ -311,6 +351,36 @@ where
             Instruction::U32ToI64 => 0x2b_u8.to_bytes(writer)?,
             Instruction::S64ToI64 => 0x2c_u8.to_bytes(writer)?,
             Instruction::U64ToI64 => 0x2d_u8.to_bytes(writer)?,
+
+            Instruction::RecordLift { type_index } => {
+                0x2e_u8.to_bytes(writer)?;
+                (*type_index as u64).to_bytes(writer)?;
+            }
+
+            Instruction::RecordLower { type_index } => {
+                0x2f_u8.to_bytes(writer)?;
+                (*type_index as u64).to_bytes(writer)?;
+            }
+
+            Instruction::StringLiftMemory { deallocator_name } => {
+                0x30_u8.to_bytes(writer)?;
+                deallocator_name.to_bytes(writer)?;
+            }
+
+            Instruction::StringLowerMemory { allocator_name } => {
+                0x31_u8.to_bytes(writer)?;
+                allocator_name.to_bytes(writer)?;
+            }
+
+            Instruction::BytesLiftMemory { deallocator_name } => {
+                0x32_u8.to_bytes(writer)?;
+                deallocator_name.to_bytes(writer)?;
+            }
+
+            Instruction::BytesLowerMemory { allocator_name } => {
+                0x33_u8.to_bytes(writer)?;
+                allocator_name.to_bytes(writer)?;
+            }
         }
 
         Ok(())
@@ -409,6 +479,24 @@ mod tests {
         assert_to_bytes!(InterfaceType::Anyref, &[0x0b]);
         assert_to_bytes!(InterfaceType::I32, &[0x0c]);
         assert_to_bytes!(InterfaceType::I64, &[0x0d]);
+        assert_to_bytes!(InterfaceType::Bytes, &[0x0e]);
+        assert_to_bytes!(
+            InterfaceType::Record(RecordType {
+                fields: vec![InterfaceType::String, InterfaceType::I32],
+            }),
+            &[
+                0x0f, // Record
+                0x02, // list of 2 items
+                0x0a, // String
+                0x0c, // I32
+            ]
+        );
+    }
+
+    #[test]
+    fn test_type_kind() {
+        assert_to_bytes!(TypeKind::Function, &[0x00]);
+        assert_to_bytes!(TypeKind::Record, &[0x01]);
     }
 
     #[test]
@@ -440,11 +528,12 @@ mod tests {
     #[test]
     fn test_type() {
         assert_to_bytes!(
-            Type {
+            Type::Function {
                 inputs: vec![InterfaceType::I32, InterfaceType::I64],
                 outputs: vec![InterfaceType::S32],
             },
             &[
+                0x00, // function type kind
                 0x02, // list of 2 items
                 0x0c, // I32
                 0x0d, // I64
@@ -454,6 +543,21 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_record_type() {
+        assert_to_bytes!(
+            Type::Record(RecordType {
+                fields: vec![InterfaceType::Bytes, InterfaceType::S8],
+            }),
+            &[
+                0x01, // record type kind
+                0x02, // list of 2 items
+                0x0e, // Bytes
+                0x00, // S8
+            ]
+        );
+    }
+
     #[test]
     fn test_import() {
         assert_to_bytes!(
@@ -491,7 +595,7 @@ mod tests {
     fn test_interfaces() {
         assert_to_bytes!(
             Interfaces {
-                types: vec![Type {
+                types: vec![Type::Function {
                     inputs: vec![InterfaceType::S8],
                     outputs: vec![InterfaceType::S16],
                 }],
@@ -516,6 +620,7 @@ mod tests {
             &[
                 0x00, // type section
                 0x01, // 1 type
+                0x00, // function type kind
                 0x01, // list of 1 item
                 0x00, // S8
                 0x01, // list of 1 item
@@ -599,9 +704,23 @@ mod tests {
                 Instruction::U32ToI64,
                 Instruction::S64ToI64,
                 Instruction::U64ToI64,
+                Instruction::RecordLift { type_index: 1 },
+                Instruction::RecordLower { type_index: 1 },
+                Instruction::StringLiftMemory {
+                    deallocator_name: "abc",
+                },
+                Instruction::StringLowerMemory {
+                    allocator_name: "abc",
+                },
+                Instruction::BytesLiftMemory {
+                    deallocator_name: "abc",
+                },
+                Instruction::BytesLowerMemory {
+                    allocator_name: "abc",
+                },
             ],
             &[
-                0x2c, // list of 44 items
+                0x32, // list of 50 items
                 0x00, 0x01, // ArgumentGet { index: 1 }
                 0x01, 0x01, // Call { function_index: 1 }
                 0x02, 0x03, 0x61, 0x62, 0x63, // CallExport { export_name: "abc" }
@@ -646,6 +765,12 @@ mod tests {
                 0x2b, // U32ToI64
                 0x2c, // S64ToI64
                 0x2d, // U64ToI64
+                0x2e, 0x01, // RecordLift { type_index: 1 }
+                0x2f, 0x01, // RecordLower { type_index: 1 }
+                0x30, 0x03, 0x61, 0x62, 0x63, // StringLiftMemory { deallocator_name: "abc" }
+                0x31, 0x03, 0x61, 0x62, 0x63, // StringLowerMemory { allocator_name: "abc" }
+                0x32, 0x03, 0x61, 0x62, 0x63, // BytesLiftMemory { deallocator_name: "abc" }
+                0x33, 0x03, 0x61, 0x62, 0x63, // BytesLowerMemory { allocator_name: "abc" }
             ]
         );
     }
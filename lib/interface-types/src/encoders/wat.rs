@@ -10,7 +10,7 @@
 //! };
 //!
 //! let input: String = (&Interfaces {
-//!     types: vec![Type {
+//!     types: vec![Type::Function {
 //!         inputs: vec![InterfaceType::I32],
 //!         outputs: vec![InterfaceType::S8],
 //!     }],
@@ -75,10 +75,26 @@ impl ToString for &InterfaceType {
             InterfaceType::Anyref => "anyref".into(),
             InterfaceType::I32 => "i32".into(),
             InterfaceType::I64 => "i64".into(),
+            InterfaceType::Bytes => "bytes".into(),
+            InterfaceType::Record(record_type) => format!("({})", record_type.to_string()),
         }
     }
 }
 
+/// Encode a `RecordType` into a string, without the surrounding
+/// parenthesis.
+impl ToString for &RecordType {
+    fn to_string(&self) -> String {
+        self.fields
+            .iter()
+            .fold(String::from("record"), |mut accumulator, field| {
+                accumulator.push(' ');
+                accumulator.push_str(&field.to_string());
+                accumulator
+            })
+    }
+}
+
 /// Encode an `Instruction` into a string.
 impl<'input> ToString for &Instruction<'input> {
     fn to_string(&self) -> String {
@@ -129,6 +145,20 @@ impl<'input> ToString for &Instruction<'input> {
             Instruction::U32ToI64 => "u32-to-i64".into(),
             Instruction::S64ToI64 => "s64-to-i64".into(),
             Instruction::U64ToI64 => "u64-to-i64".into(),
+            Instruction::RecordLift { type_index } => format!("record.lift {}", type_index),
+            Instruction::RecordLower { type_index } => format!("record.lower {}", type_index),
+            Instruction::StringLiftMemory { deallocator_name } => {
+                format!(r#"string.lift-memory "{}""#, deallocator_name)
+            }
+            Instruction::StringLowerMemory { allocator_name } => {
+                format!(r#"string.lower-memory "{}""#, allocator_name)
+            }
+            Instruction::BytesLiftMemory { deallocator_name } => {
+                format!(r#"bytes.lift-memory "{}""#, deallocator_name)
+            }
+            Instruction::BytesLowerMemory { allocator_name } => {
+                format!(r#"bytes.lower-memory "{}""#, allocator_name)
+            }
         }
     }
 }
@@ -174,11 +204,18 @@ fn output_types_to_result(output_types: &[InterfaceType]) -> String {
 /// Encode a `Type` into a string.
 impl<'input> ToString for &Type {
     fn to_string(&self) -> String {
-        format!(
-            r#"(@interface type (func{inputs}{outputs}))"#,
-            inputs = input_types_to_param(&self.inputs),
-            outputs = output_types_to_result(&self.outputs),
-        )
+        match self {
+            Type::Function { inputs, outputs } => format!(
+                r#"(@interface type (func{inputs}{outputs}))"#,
+                inputs = input_types_to_param(inputs),
+                outputs = output_types_to_result(outputs),
+            ),
+
+            Type::Record(record_type) => format!(
+                r#"(@interface type ({record_type}))"#,
+                record_type = record_type.to_string(),
+            ),
+        }
     }
 }
 
@@ -348,10 +385,34 @@ mod tests {
             (&InterfaceType::Anyref).to_string(),
             (&InterfaceType::I32).to_string(),
             (&InterfaceType::I64).to_string(),
+            (&InterfaceType::Bytes).to_string(),
+            (&InterfaceType::Record(RecordType {
+                fields: vec![
+                    InterfaceType::String,
+                    InterfaceType::Record(RecordType {
+                        fields: vec![InterfaceType::I32],
+                    }),
+                ],
+            }))
+                .to_string(),
         ];
         let outputs = vec![
-            "s8", "s16", "s32", "s64", "u8", "u16", "u32", "u64", "f32", "f64", "string", "anyref",
-            "i32", "i64",
+            "s8",
+            "s16",
+            "s32",
+            "s64",
+            "u8",
+            "u16",
+            "u32",
+            "u64",
+            "f32",
+            "f64",
+            "string",
+            "anyref",
+            "i32",
+            "i64",
+            "bytes",
+            "(record string (record i32))",
         ];
 
         assert_eq!(inputs, outputs);
@@ -407,6 +468,24 @@ mod tests {
             (&Instruction::U32ToI64).to_string(),
             (&Instruction::S64ToI64).to_string(),
             (&Instruction::U64ToI64).to_string(),
+            (&Instruction::RecordLift { type_index: 7 }).to_string(),
+            (&Instruction::RecordLower { type_index: 7 }).to_string(),
+            (&Instruction::StringLiftMemory {
+                deallocator_name: "foo",
+            })
+                .to_string(),
+            (&Instruction::StringLowerMemory {
+                allocator_name: "foo",
+            })
+                .to_string(),
+            (&Instruction::BytesLiftMemory {
+                deallocator_name: "foo",
+            })
+                .to_string(),
+            (&Instruction::BytesLowerMemory {
+                allocator_name: "foo",
+            })
+                .to_string(),
         ];
         let outputs = vec![
             "arg.get 7",
@@ -453,6 +532,12 @@ mod tests {
             "u32-to-i64",
             "s64-to-i64",
             "u64-to-i64",
+            "record.lift 7",
+            "record.lower 7",
+            r#"string.lift-memory "foo""#,
+            r#"string.lower-memory "foo""#,
+            r#"bytes.lift-memory "foo""#,
+            r#"bytes.lower-memory "foo""#,
         ];
 
         assert_eq!(inputs, outputs);
@@ -461,26 +546,30 @@ mod tests {
     #[test]
     fn test_types() {
         let inputs: Vec<String> = vec![
-            (&Type {
+            (&Type::Function {
                 inputs: vec![InterfaceType::I32, InterfaceType::F32],
                 outputs: vec![InterfaceType::I32],
             })
                 .to_string(),
-            (&Type {
+            (&Type::Function {
                 inputs: vec![InterfaceType::I32],
                 outputs: vec![],
             })
                 .to_string(),
-            (&Type {
+            (&Type::Function {
                 inputs: vec![],
                 outputs: vec![InterfaceType::I32],
             })
                 .to_string(),
-            (&Type {
+            (&Type::Function {
                 inputs: vec![],
                 outputs: vec![],
             })
                 .to_string(),
+            (&Type::Record(RecordType {
+                fields: vec![InterfaceType::String, InterfaceType::I32],
+            }))
+                .to_string(),
         ];
         let outputs = vec![
             r#"(@interface type (func
@@ -491,6 +580,7 @@ mod tests {
             r#"(@interface type (func
   (result i32)))"#,
             r#"(@interface type (func))"#,
+            r#"(@interface type (record string i32))"#,
         ];
 
         assert_eq!(inputs, outputs);
@@ -537,7 +627,7 @@ mod tests {
     #[test]
     fn test_interfaces() {
         let input: String = (&Interfaces {
-            types: vec![Type {
+            types: vec![Type::Function {
                 inputs: vec![InterfaceType::I32],
                 outputs: vec![InterfaceType::S8],
             }],
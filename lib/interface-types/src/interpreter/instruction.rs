@@ -146,4 +146,40 @@ pub enum Instruction<'input> {
 
     /// The `u64-to-i64,` instruction.
     U64ToI64,
+
+    /// The `record.lift` instruction.
+    RecordLift {
+        /// The index of the record type.
+        type_index: u32,
+    },
+
+    /// The `record.lower` instruction.
+    RecordLower {
+        /// The index of the record type.
+        type_index: u32,
+    },
+
+    /// The `string.lift-memory` instruction.
+    StringLiftMemory {
+        /// The deallocator function name.
+        deallocator_name: &'input str,
+    },
+
+    /// The `string.lower-memory` instruction.
+    StringLowerMemory {
+        /// The allocator function name.
+        allocator_name: &'input str,
+    },
+
+    /// The `bytes.lift-memory` instruction.
+    BytesLiftMemory {
+        /// The deallocator function name.
+        deallocator_name: &'input str,
+    },
+
+    /// The `bytes.lower-memory` instruction.
+    BytesLowerMemory {
+        /// The allocator function name.
+        allocator_name: &'input str,
+    },
 }
@@ -0,0 +1,488 @@
+use crate::interpreter::wasm::{
+    self,
+    values::{InterfaceType, InterfaceValue},
+};
+use std::{cell::Cell, convert::TryFrom};
+
+/// Reads `length` bytes at `pointer` in the first memory of
+/// `instance`, then gives the memory back by calling the
+/// `deallocator_name` export.
+fn lift_memory<Instance, Export, LocalImport, Memory, MemoryView>(
+    instance: &Instance,
+    pointer: i32,
+    length: i32,
+    deallocator_name: &str,
+    instruction_name: &str,
+) -> Result<Vec<u8>, String>
+where
+    Export: wasm::structures::Export,
+    LocalImport: wasm::structures::LocalImport,
+    Memory: wasm::structures::Memory<MemoryView>,
+    MemoryView: wasm::structures::MemoryView,
+    Instance: wasm::structures::Instance<Export, LocalImport, Memory, MemoryView>,
+{
+    let deallocator = instance.export(deallocator_name).ok_or_else(|| {
+        format!(
+            "`{}` failed because the exported function `{}` (the deallocator) doesn't exist.",
+            instruction_name, deallocator_name,
+        )
+    })?;
+
+    if deallocator.inputs() != [InterfaceType::I32, InterfaceType::I32]
+        || !deallocator.outputs().is_empty()
+    {
+        return Err(format!(
+            "`{}` failed because the deallocator `{}` has an invalid signature (expects [I32, I32] -> []).",
+            instruction_name, deallocator_name,
+        ));
+    }
+
+    let memory_view = instance
+        .memory(0)
+        .ok_or_else(|| {
+            format!(
+                "`{}` failed because there is no memory to read.",
+                instruction_name
+            )
+        })?
+        .view();
+
+    let start = pointer as u32 as usize;
+    let end = start + length as u32 as usize;
+
+    if memory_view.len() < end {
+        return Err(format!(
+            "`{}` failed because it has to read out of the memory bounds (index {} > memory length {}).",
+            instruction_name,
+            end,
+            memory_view.len(),
+        ));
+    }
+
+    let bytes = memory_view[start..end].iter().map(Cell::get).collect();
+
+    deallocator
+        .call(&[InterfaceValue::I32(pointer), InterfaceValue::I32(length)])
+        .map_err(|_| {
+            format!(
+                "`{}` failed when calling the deallocator `{}`.",
+                instruction_name, deallocator_name,
+            )
+        })?;
+
+    Ok(bytes)
+}
+
+/// Allocates memory for `bytes` by calling the `allocator_name`
+/// export, then writes `bytes` into the first memory of
+/// `instance`. Returns the pointer and the length of the written
+/// bytes.
+fn lower_memory<Instance, Export, LocalImport, Memory, MemoryView>(
+    instance: &Instance,
+    bytes: &[u8],
+    allocator_name: &str,
+    instruction_name: &str,
+) -> Result<(i32, i32), String>
+where
+    Export: wasm::structures::Export,
+    LocalImport: wasm::structures::LocalImport,
+    Memory: wasm::structures::Memory<MemoryView>,
+    MemoryView: wasm::structures::MemoryView,
+    Instance: wasm::structures::Instance<Export, LocalImport, Memory, MemoryView>,
+{
+    let allocator = instance.export(allocator_name).ok_or_else(|| {
+        format!(
+            "`{}` failed because the exported function `{}` (the allocator) doesn't exist.",
+            instruction_name, allocator_name,
+        )
+    })?;
+
+    if allocator.inputs() != [InterfaceType::I32] || allocator.outputs() != [InterfaceType::I32] {
+        return Err(format!(
+            "`{}` failed because the allocator `{}` has an invalid signature (expects [I32] -> [I32]).",
+            instruction_name, allocator_name,
+        ));
+    }
+
+    let memory = instance.memory(0).ok_or_else(|| {
+        format!(
+            "`{}` failed because there is no memory to write into.",
+            instruction_name
+        )
+    })?;
+
+    let length = i32::try_from(bytes.len()).map_err(|error| format!("{}", error))?;
+    let outputs = allocator
+        .call(&[InterfaceValue::I32(length)])
+        .map_err(|_| {
+            format!(
+                "`{}` failed when calling the allocator `{}`.",
+                instruction_name, allocator_name,
+            )
+        })?;
+    let pointer = outputs.get(0).ok_or_else(|| {
+        format!(
+            "`{}` failed because the allocator `{}` returned no pointer.",
+            instruction_name, allocator_name,
+        )
+    })?;
+    let pointer = i32::try_from(pointer)?;
+
+    // The allocator may have grown the memory, so the view is taken
+    // only once it has returned.
+    let memory_view = memory.view();
+    let start = pointer as u32 as usize;
+    let end = start.checked_add(bytes.len()).ok_or_else(|| {
+        format!(
+            "`{}` failed because the allocator `{}` returned an invalid pointer ({}).",
+            instruction_name, allocator_name, pointer,
+        )
+    })?;
+
+    if memory_view.len() < end {
+        return Err(format!(
+            "`{}` failed because it has to write out of the memory bounds (index {} > memory length {}).",
+            instruction_name,
+            end,
+            memory_view.len(),
+        ));
+    }
+
+    for (cell, byte) in memory_view[start..end].iter().zip(bytes) {
+        cell.set(*byte);
+    }
+
+    Ok((pointer, length))
+}
+
+executable_instruction!(
+    string_lift_memory(deallocator_name: String, instruction_name: String) -> _ {
+        move |runtime| -> _ {
+            match runtime.stack.pop(2) {
+                Some(inputs) => {
+                    let length = i32::try_from(&inputs[0])?;
+                    let pointer = i32::try_from(&inputs[1])?;
+                    let bytes = lift_memory(
+                        &*runtime.wasm_instance,
+                        pointer,
+                        length,
+                        &deallocator_name,
+                        &instruction_name,
+                    )?;
+
+                    match String::from_utf8(bytes) {
+                        Ok(string) => {
+                            runtime.stack.push(InterfaceValue::String(string));
+
+                            Ok(())
+                        }
+                        Err(utf8_error) => Err(format!(
+                            "`{}` failed because the read string isn't UTF-8 valid ({}).",
+                            instruction_name,
+                            utf8_error,
+                        ))
+                    }
+                }
+                None => Err(format!(
+                    "`{}` failed because there is not enough data on the stack (needs 2).",
+                    instruction_name,
+                ))
+            }
+        }
+    }
+);
+
+executable_instruction!(
+    string_lower_memory(allocator_name: String, instruction_name: String) -> _ {
+        move |runtime| -> _ {
+            match runtime.stack.pop1() {
+                Some(InterfaceValue::String(string)) => {
+                    let (pointer, length) = lower_memory(
+                        &*runtime.wasm_instance,
+                        string.as_bytes(),
+                        &allocator_name,
+                        &instruction_name,
+                    )?;
+
+                    runtime.stack.push(InterfaceValue::I32(pointer));
+                    runtime.stack.push(InterfaceValue::I32(length));
+
+                    Ok(())
+                }
+                Some(value) => Err(format!(
+                    "`{}` expects a `String` value on the stack, got `{:?}`.",
+                    instruction_name,
+                    value,
+                )),
+                None => Err(format!(
+                    "`{}` failed because there is not enough data on the stack (needs 1).",
+                    instruction_name,
+                ))
+            }
+        }
+    }
+);
+
+executable_instruction!(
+    bytes_lift_memory(deallocator_name: String, instruction_name: String) -> _ {
+        move |runtime| -> _ {
+            match runtime.stack.pop(2) {
+                Some(inputs) => {
+                    let length = i32::try_from(&inputs[0])?;
+                    let pointer = i32::try_from(&inputs[1])?;
+                    let bytes = lift_memory(
+                        &*runtime.wasm_instance,
+                        pointer,
+                        length,
+                        &deallocator_name,
+                        &instruction_name,
+                    )?;
+
+                    runtime.stack.push(InterfaceValue::Bytes(bytes));
+
+                    Ok(())
+                }
+                None => Err(format!(
+                    "`{}` failed because there is not enough data on the stack (needs 2).",
+                    instruction_name,
+                ))
+            }
+        }
+    }
+);
+
+executable_instruction!(
+    bytes_lower_memory(allocator_name: String, instruction_name: String) -> _ {
+        move |runtime| -> _ {
+            match runtime.stack.pop1() {
+                Some(InterfaceValue::Bytes(bytes)) => {
+                    let (pointer, length) = lower_memory(
+                        &*runtime.wasm_instance,
+                        &bytes,
+                        &allocator_name,
+                        &instruction_name,
+                    )?;
+
+                    runtime.stack.push(InterfaceValue::I32(pointer));
+                    runtime.stack.push(InterfaceValue::I32(length));
+
+                    Ok(())
+                }
+                Some(value) => Err(format!(
+                    "`{}` expects a `Bytes` value on the stack, got `{:?}`.",
+                    instruction_name,
+                    value,
+                )),
+                None => Err(format!(
+                    "`{}` failed because there is not enough data on the stack (needs 1).",
+                    instruction_name,
+                ))
+            }
+        }
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    test_executable_instruction!(
+        test_string_lower_memory =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::StringLowerMemory { allocator_name: "alloc" },
+            ],
+            invocation_inputs: [InterfaceValue::String("Hello".into())],
+            instance: Instance::new(),
+            stack: [
+                InterfaceValue::I32(0),
+                //              ^^^^^^ pointer returned by the allocator
+                InterfaceValue::I32(5),
+                //              ^^^^^^ length of the string
+            ],
+    );
+
+    test_executable_instruction!(
+        test_string_lower_memory__roundtrip_with_string_lift_memory =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::StringLowerMemory { allocator_name: "alloc" },
+                Instruction::StringLiftMemory { deallocator_name: "dealloc" },
+            ],
+            invocation_inputs: [InterfaceValue::String("Hello, World!".into())],
+            instance: Instance::new(),
+            stack: [InterfaceValue::String("Hello, World!".into())],
+    );
+
+    test_executable_instruction!(
+        test_string_lower_memory__invalid_value =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::StringLowerMemory { allocator_name: "alloc" },
+            ],
+            invocation_inputs: [InterfaceValue::Bytes(vec![1, 2, 3])],
+            instance: Instance::new(),
+            error: r#"`string.lower-memory "alloc"` expects a `String` value on the stack, got `Bytes([1, 2, 3])`."#,
+    );
+
+    test_executable_instruction!(
+        test_string_lower_memory__allocator_does_not_exist =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::StringLowerMemory { allocator_name: "alloc" },
+            ],
+            invocation_inputs: [InterfaceValue::String("Hello".into())],
+            instance: Instance { ..Default::default() },
+            error: r#"`string.lower-memory "alloc"` failed because the exported function `alloc` (the allocator) doesn't exist."#,
+    );
+
+    test_executable_instruction!(
+        test_string_lower_memory__allocated_out_of_memory =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::StringLowerMemory { allocator_name: "alloc" },
+            ],
+            invocation_inputs: [InterfaceValue::String("Hello".into())],
+            instance: {
+                let mut instance = Instance::new();
+                instance.exports.insert(
+                    "alloc".into(),
+                    Export {
+                        inputs: vec![InterfaceType::I32],
+                        outputs: vec![InterfaceType::I32],
+                        function: |_arguments: &[InterfaceValue]| {
+                            Ok(vec![InterfaceValue::I32(126)])
+                            //                          ^^^ too close to the end of the memory
+                        },
+                    },
+                );
+
+                instance
+            },
+            error: r#"`string.lower-memory "alloc"` failed because it has to write out of the memory bounds (index 131 > memory length 128)."#,
+    );
+
+    test_executable_instruction!(
+        test_string_lift_memory =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::ArgumentGet { index: 1 },
+                Instruction::StringLiftMemory { deallocator_name: "dealloc" },
+            ],
+            invocation_inputs: [
+                InterfaceValue::I32(0),
+                //              ^^^^^^ pointer
+                InterfaceValue::I32(5),
+                //              ^^^^^^ length
+            ],
+            instance: Instance {
+                memory: Memory::new("Hello".as_bytes().iter().map(|u| Cell::new(*u)).collect()),
+                ..Instance::new()
+            },
+            stack: [InterfaceValue::String("Hello".into())],
+    );
+
+    test_executable_instruction!(
+        test_string_lift_memory__read_out_of_memory =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::ArgumentGet { index: 1 },
+                Instruction::StringLiftMemory { deallocator_name: "dealloc" },
+            ],
+            invocation_inputs: [
+                InterfaceValue::I32(0),
+                InterfaceValue::I32(6),
+                //              ^^^^^^ is too long
+            ],
+            instance: Instance {
+                memory: Memory::new("Hello".as_bytes().iter().map(|u| Cell::new(*u)).collect()),
+                ..Instance::new()
+            },
+            error: r#"`string.lift-memory "dealloc"` failed because it has to read out of the memory bounds (index 6 > memory length 5)."#,
+    );
+
+    test_executable_instruction!(
+        test_string_lift_memory__deallocator_does_not_exist =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::ArgumentGet { index: 1 },
+                Instruction::StringLiftMemory { deallocator_name: "free" },
+            ],
+            invocation_inputs: [InterfaceValue::I32(0), InterfaceValue::I32(5)],
+            instance: Instance::new(),
+            error: r#"`string.lift-memory "free"` failed because the exported function `free` (the deallocator) doesn't exist."#,
+    );
+
+    test_executable_instruction!(
+        test_string_lift_memory__invalid_deallocator_signature =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::ArgumentGet { index: 1 },
+                Instruction::StringLiftMemory { deallocator_name: "sum" },
+            ],
+            invocation_inputs: [InterfaceValue::I32(0), InterfaceValue::I32(5)],
+            instance: Instance::new(),
+            error: r#"`string.lift-memory "sum"` failed because the deallocator `sum` has an invalid signature (expects [I32, I32] -> [])."#,
+    );
+
+    test_executable_instruction!(
+        test_string_lift_memory__failure_when_calling_the_deallocator =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::ArgumentGet { index: 1 },
+                Instruction::StringLiftMemory { deallocator_name: "dealloc" },
+            ],
+            invocation_inputs: [InterfaceValue::I32(0), InterfaceValue::I32(5)],
+            instance: {
+                let mut instance = Instance::new();
+                instance.exports.insert(
+                    "dealloc".into(),
+                    Export {
+                        inputs: vec![InterfaceType::I32, InterfaceType::I32],
+                        outputs: vec![],
+                        function: |_| Err(()),
+                        //            ^^^^^^^ function fails
+                    },
+                );
+
+                instance
+            },
+            error: r#"`string.lift-memory "dealloc"` failed when calling the deallocator `dealloc`."#,
+    );
+
+    test_executable_instruction!(
+        test_bytes_lower_memory__roundtrip_with_bytes_lift_memory =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::BytesLowerMemory { allocator_name: "alloc" },
+                Instruction::BytesLiftMemory { deallocator_name: "dealloc" },
+            ],
+            invocation_inputs: [InterfaceValue::Bytes(vec![0, 1, 2, 254, 255])],
+            instance: Instance::new(),
+            stack: [InterfaceValue::Bytes(vec![0, 1, 2, 254, 255])],
+    );
+
+    test_executable_instruction!(
+        test_bytes_lower_memory__invalid_value =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::BytesLowerMemory { allocator_name: "alloc" },
+            ],
+            invocation_inputs: [InterfaceValue::String("Hello".into())],
+            instance: Instance::new(),
+            error: r#"`bytes.lower-memory "alloc"` expects a `Bytes` value on the stack, got `String("Hello")`."#,
+    );
+
+    test_executable_instruction!(
+        test_bytes_lift_memory =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::ArgumentGet { index: 1 },
+                Instruction::BytesLiftMemory { deallocator_name: "dealloc" },
+            ],
+            invocation_inputs: [InterfaceValue::I32(1), InterfaceValue::I32(3)],
+            instance: Instance {
+                memory: Memory::new(vec![0, 1, 2, 3, 4].into_iter().map(Cell::new).collect()),
+                ..Instance::new()
+            },
+            stack: [InterfaceValue::Bytes(vec![1, 2, 3])],
+    );
+}
@@ -1,22 +1,29 @@
 mod argument_get;
 mod call;
 mod call_export;
+mod lift_lower_memory;
 mod lowering_lifting;
 mod read_utf8;
+mod records;
 mod write_utf8;
 
 pub(crate) use argument_get::argument_get;
 pub(crate) use call::call;
 pub(crate) use call_export::call_export;
+pub(crate) use lift_lower_memory::*;
 pub(crate) use lowering_lifting::*;
 pub(crate) use read_utf8::read_utf8;
+pub(crate) use records::*;
 pub(crate) use write_utf8::write_utf8;
 
 #[cfg(test)]
 pub(crate) mod tests {
-    use crate::interpreter::wasm::{
-        self,
-        values::{InterfaceType, InterfaceValue},
+    use crate::{
+        ast::{RecordType, Type},
+        interpreter::wasm::{
+            self,
+            values::{InterfaceType, InterfaceValue},
+        },
     };
     use std::{cell::Cell, collections::HashMap, convert::TryInto, ops::Deref, rc::Rc};
 
@@ -113,6 +120,7 @@ pub(crate) mod tests {
         pub(crate) exports: HashMap<String, Export>,
         pub(crate) locals_or_imports: HashMap<usize, LocalImport>,
         pub(crate) memory: Memory,
+        pub(crate) wit_types: Vec<Type>,
     }
 
     impl Instance {
@@ -145,6 +153,19 @@ pub(crate) mod tests {
                             },
                         },
                     );
+                    hashmap.insert(
+                        "dealloc".into(),
+                        Export {
+                            inputs: vec![InterfaceType::I32, InterfaceType::I32],
+                            outputs: vec![],
+                            function: |arguments: &[InterfaceValue]| {
+                                let _pointer: i32 = (&arguments[0]).try_into().unwrap();
+                                let _length: i32 = (&arguments[1]).try_into().unwrap();
+
+                                Ok(vec![])
+                            },
+                        },
+                    );
 
                     hashmap
                 },
@@ -167,6 +188,15 @@ pub(crate) mod tests {
                     hashmap
                 },
                 memory: Memory::new(vec![Cell::new(0); 128]),
+                wit_types: vec![
+                    Type::Record(RecordType {
+                        fields: vec![InterfaceType::I32, InterfaceType::String],
+                    }),
+                    Type::Function {
+                        inputs: vec![InterfaceType::I32],
+                        outputs: vec![InterfaceType::I32],
+                    },
+                ],
             }
         }
     }
@@ -186,5 +216,9 @@ pub(crate) mod tests {
         fn memory(&self, _index: usize) -> Option<&Memory> {
             Some(&self.memory)
         }
+
+        fn wit_type(&self, index: u32) -> Option<&Type> {
+            self.wit_types.get(index as usize)
+        }
     }
 }
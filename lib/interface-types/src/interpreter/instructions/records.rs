@@ -0,0 +1,220 @@
+use crate::{
+    ast::{RecordType, Type},
+    interpreter::wasm::{
+        self,
+        values::{InterfaceType, InterfaceValue},
+    },
+};
+
+/// Looks up the record type at `type_index` in `instance`.
+fn record_type<'instance, Instance, Export, LocalImport, Memory, MemoryView>(
+    instance: &'instance Instance,
+    type_index: u32,
+    instruction_name: &str,
+) -> Result<&'instance RecordType, String>
+where
+    Export: wasm::structures::Export,
+    LocalImport: wasm::structures::LocalImport,
+    Memory: wasm::structures::Memory<MemoryView>,
+    MemoryView: wasm::structures::MemoryView,
+    Instance: wasm::structures::Instance<Export, LocalImport, Memory, MemoryView>,
+{
+    match instance.wit_type(type_index) {
+        Some(Type::Record(record_type)) => Ok(record_type),
+        Some(_) => Err(format!(
+            "`{}` failed because the type `{}` is not a record.",
+            instruction_name, type_index,
+        )),
+        None => Err(format!(
+            "`{}` failed because the type `{}` doesn't exist.",
+            instruction_name, type_index,
+        )),
+    }
+}
+
+executable_instruction!(
+    record_lift(type_index: u32, instruction_name: String) -> _ {
+        move |runtime| -> _ {
+            let record_type = record_type(&*runtime.wasm_instance, type_index, &instruction_name)?;
+            let fields_cardinality = record_type.fields.len();
+
+            match runtime.stack.pop(fields_cardinality) {
+                Some(mut values) => {
+                    // The first field is the deepest value on the stack.
+                    values.reverse();
+
+                    let value_types = values
+                        .iter()
+                        .map(Into::into)
+                        .collect::<Vec<InterfaceType>>();
+
+                    if value_types != record_type.fields {
+                        return Err(format!(
+                            "`{}` cannot lift the record `{}` because the value types on the stack mismatch the record fields (expects {:?}).",
+                            instruction_name,
+                            type_index,
+                            record_type.fields,
+                        ))
+                    }
+
+                    runtime.stack.push(InterfaceValue::Record(values));
+
+                    Ok(())
+                }
+                None => Err(format!(
+                    "`{}` cannot lift the record `{}` because there is not enough data on the stack (needs {}).",
+                    instruction_name,
+                    type_index,
+                    fields_cardinality,
+                ))
+            }
+        }
+    }
+);
+
+executable_instruction!(
+    record_lower(type_index: u32, instruction_name: String) -> _ {
+        move |runtime| -> _ {
+            let record_type = record_type(&*runtime.wasm_instance, type_index, &instruction_name)?;
+
+            match runtime.stack.pop1() {
+                Some(InterfaceValue::Record(values)) => {
+                    let value_types = values
+                        .iter()
+                        .map(Into::into)
+                        .collect::<Vec<InterfaceType>>();
+
+                    if value_types != record_type.fields {
+                        return Err(format!(
+                            "`{}` cannot lower the record `{}` because the record on the stack mismatches the record fields (expects {:?}).",
+                            instruction_name,
+                            type_index,
+                            record_type.fields,
+                        ))
+                    }
+
+                    for value in values {
+                        runtime.stack.push(value);
+                    }
+
+                    Ok(())
+                }
+                Some(value) => Err(format!(
+                    "`{}` expects a `Record` value on the stack, got `{:?}`.",
+                    instruction_name,
+                    value,
+                )),
+                None => Err(format!(
+                    "`{}` failed because there is not enough data on the stack (needs 1).",
+                    instruction_name,
+                ))
+            }
+        }
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    test_executable_instruction!(
+        test_record_lift =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::ArgumentGet { index: 1 },
+                Instruction::RecordLift { type_index: 0 },
+            ],
+            invocation_inputs: [
+                InterfaceValue::I32(7),
+                InterfaceValue::String("Hello".into()),
+            ],
+            instance: Instance::new(),
+            stack: [InterfaceValue::Record(vec![
+                InterfaceValue::I32(7),
+                InterfaceValue::String("Hello".into()),
+            ])],
+    );
+
+    test_executable_instruction!(
+        test_record_lift__roundtrip_with_record_lower =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::ArgumentGet { index: 1 },
+                Instruction::RecordLift { type_index: 0 },
+                Instruction::RecordLower { type_index: 0 },
+            ],
+            invocation_inputs: [
+                InterfaceValue::I32(7),
+                InterfaceValue::String("Hello".into()),
+            ],
+            instance: Instance::new(),
+            stack: [
+                InterfaceValue::I32(7),
+                InterfaceValue::String("Hello".into()),
+            ],
+    );
+
+    test_executable_instruction!(
+        test_record_lift__type_is_not_a_record =
+            instructions: [Instruction::RecordLift { type_index: 1 }],
+            invocation_inputs: [],
+            instance: Instance::new(),
+            error: r#"`record.lift 1` failed because the type `1` is not a record."#,
+    );
+
+    test_executable_instruction!(
+        test_record_lift__type_does_not_exist =
+            instructions: [Instruction::RecordLift { type_index: 2 }],
+            invocation_inputs: [],
+            instance: Instance::new(),
+            error: r#"`record.lift 2` failed because the type `2` doesn't exist."#,
+    );
+
+    test_executable_instruction!(
+        test_record_lift__stack_is_too_small =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::RecordLift { type_index: 0 },
+                //                                   ^ the record has 2 fields
+            ],
+            invocation_inputs: [InterfaceValue::I32(7)],
+            instance: Instance::new(),
+            error: r#"`record.lift 0` cannot lift the record `0` because there is not enough data on the stack (needs 2)."#,
+    );
+
+    test_executable_instruction!(
+        test_record_lift__invalid_types_in_the_stack =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::ArgumentGet { index: 1 },
+                Instruction::RecordLift { type_index: 0 },
+            ],
+            invocation_inputs: [
+                InterfaceValue::I64(7),
+                //              ^^^ mismatch with the record fields
+                InterfaceValue::String("Hello".into()),
+            ],
+            instance: Instance::new(),
+            error: r#"`record.lift 0` cannot lift the record `0` because the value types on the stack mismatch the record fields (expects [I32, String])."#,
+    );
+
+    test_executable_instruction!(
+        test_record_lower__invalid_record =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::RecordLower { type_index: 0 },
+            ],
+            invocation_inputs: [InterfaceValue::Record(vec![InterfaceValue::I32(7)])],
+            instance: Instance::new(),
+            error: r#"`record.lower 0` cannot lower the record `0` because the record on the stack mismatches the record fields (expects [I32, String])."#,
+    );
+
+    test_executable_instruction!(
+        test_record_lower__invalid_value =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::RecordLower { type_index: 0 },
+            ],
+            invocation_inputs: [InterfaceValue::I32(7)],
+            instance: Instance::new(),
+            error: r#"`record.lower 0` expects a `Record` value on the stack, got `I32(7)`."#,
+    );
+}
@@ -192,7 +192,7 @@ where
             .map(|instruction| {
                 let instruction_name = instruction.to_string();
 
-                match instruction {
+                Ok(match instruction {
                     Instruction::ArgumentGet { index } => {
                         instructions::argument_get(*index, instruction_name)
                     }
@@ -206,22 +206,48 @@ where
                     Instruction::WriteUtf8 { allocator_name } => {
                         instructions::write_utf8((*allocator_name).to_owned(), instruction_name)
                     }
+                    Instruction::RecordLift { type_index } => {
+                        instructions::record_lift(*type_index, instruction_name)
+                    }
+                    Instruction::RecordLower { type_index } => {
+                        instructions::record_lower(*type_index, instruction_name)
+                    }
+                    Instruction::StringLiftMemory { deallocator_name } => {
+                        instructions::string_lift_memory(
+                            (*deallocator_name).to_owned(),
+                            instruction_name,
+                        )
+                    }
+                    Instruction::StringLowerMemory { allocator_name } => {
+                        instructions::string_lower_memory(
+                            (*allocator_name).to_owned(),
+                            instruction_name,
+                        )
+                    }
+                    Instruction::BytesLiftMemory { deallocator_name } => {
+                        instructions::bytes_lift_memory(
+                            (*deallocator_name).to_owned(),
+                            instruction_name,
+                        )
+                    }
+                    Instruction::BytesLowerMemory { allocator_name } => {
+                        instructions::bytes_lower_memory(
+                            (*allocator_name).to_owned(),
+                            instruction_name,
+                        )
+                    }
 
                     Instruction::I32ToS8 => instructions::i32_to_s8(),
-                    //Instruction::I32ToS8X
                     Instruction::I32ToU8 => instructions::i32_to_u8(),
                     Instruction::I32ToS16 => instructions::i32_to_s16(),
-                    //Instruction::I32ToS16X
                     Instruction::I32ToU16 => instructions::i32_to_u16(),
                     Instruction::I32ToS32 => instructions::i32_to_s32(),
                     Instruction::I32ToU32 => instructions::i32_to_u32(),
                     Instruction::I32ToS64 => instructions::i32_to_s64(),
                     Instruction::I32ToU64 => instructions::i32_to_u64(),
                     Instruction::I64ToS8 => instructions::i64_to_s8(),
-                    //Instruction::I64ToS8X
                     Instruction::I64ToU8 => instructions::i64_to_u8(),
                     Instruction::I64ToS16 => instructions::i64_to_s16(),
-                    //Instruction::I64ToS16X
                     Instruction::I64ToU16 => instructions::i64_to_u16(),
                     Instruction::I64ToS32 => instructions::i64_to_s32(),
                     Instruction::I64ToU32 => instructions::i64_to_u32(),
@@ -243,10 +269,19 @@ where
                     Instruction::U32ToI64 => instructions::u32_to_i64(),
                     Instruction::S64ToI64 => instructions::s64_to_i64(),
                     Instruction::U64ToI64 => instructions::u64_to_i64(),
-                    _ => unimplemented!(),
-                }
+                    Instruction::I32ToS8X
+                    | Instruction::I32ToS16X
+                    | Instruction::I64ToS8X
+                    | Instruction::I64ToS16X
+                    | Instruction::I64ToS32X => {
+                        return Err(format!(
+                            "`{}` is not supported by the interpreter yet.",
+                            instruction_name
+                        ))
+                    }
+                })
             })
-            .collect();
+            .collect::<Result<_, _>>()?;
 
         Ok(Interpreter {
             executable_instructions,
@@ -273,4 +308,15 @@ mod tests {
 
         assert_eq!(interpreter.executable_instructions.len(), 5);
     }
+    #[test]
+    fn test_interpreter_from_unsupported_instructions() {
+        let instructions = vec![Instruction::ArgumentGet { index: 0 }, Instruction::I32ToS8X];
+        let interpreter: Result<Interpreter<(), (), (), (), EmptyMemoryView>, _> =
+            (&instructions).try_into();
+
+        assert_eq!(
+            interpreter.err(),
+            Some("`i32-to-s8x` is not supported by the interpreter yet.".to_string())
+        );
+    }
 }
@@ -2,7 +2,7 @@
 
 use std::convert::TryFrom;
 
-pub use crate::ast::InterfaceType;
+pub use crate::ast::{InterfaceType, RecordType};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum InterfaceValue {
@@ -20,6 +20,8 @@ pub enum InterfaceValue {
     //Anyref(?),
     I32(i32),
     I64(i64),
+    Bytes(Vec<u8>),
+    Record(Vec<InterfaceValue>),
 }
 
 impl From<&InterfaceValue> for InterfaceType {
@@ -39,6 +41,10 @@ impl From<&InterfaceValue> for InterfaceType {
             //InterfaceValue::Anyref(_) => Self::Anyref,
             InterfaceValue::I32(_) => Self::I32,
             InterfaceValue::I64(_) => Self::I64,
+            InterfaceValue::Bytes(_) => Self::Bytes,
+            InterfaceValue::Record(values) => Self::Record(RecordType {
+                fields: values.iter().map(Into::into).collect(),
+            }),
         }
     }
 }
@@ -81,3 +87,4 @@ from_x_for_interface_value!(f64, F64);
 from_x_for_interface_value!(String, String);
 from_x_for_interface_value!(i32, I32);
 from_x_for_interface_value!(i64, I64);
+from_x_for_interface_value!(Vec<u8>, Bytes);
@@ -7,14 +7,23 @@ use wasmer_interface_types::{
 fn test_binary_encoding_decoding_roundtrip() {
     let original_ast = Interfaces {
         types: vec![
-            Type {
+            Type::Function {
                 inputs: vec![],
                 outputs: vec![],
             },
-            Type {
+            Type::Function {
                 inputs: vec![InterfaceType::I32, InterfaceType::I32],
                 outputs: vec![InterfaceType::S32],
             },
+            Type::Record(RecordType {
+                fields: vec![
+                    InterfaceType::String,
+                    InterfaceType::Bytes,
+                    InterfaceType::Record(RecordType {
+                        fields: vec![InterfaceType::I32, InterfaceType::I32],
+                    }),
+                ],
+            }),
         ],
         imports: vec![Import {
             namespace: "a",
@@ -23,7 +32,23 @@ fn test_binary_encoding_decoding_roundtrip() {
         }],
         adapters: vec![Adapter {
             function_type: 0,
-            instructions: vec![Instruction::ArgumentGet { index: 1 }],
+            instructions: vec![
+                Instruction::ArgumentGet { index: 1 },
+                Instruction::RecordLift { type_index: 2 },
+                Instruction::RecordLower { type_index: 2 },
+                Instruction::StringLiftMemory {
+                    deallocator_name: "dealloc",
+                },
+                Instruction::StringLowerMemory {
+                    allocator_name: "alloc",
+                },
+                Instruction::BytesLiftMemory {
+                    deallocator_name: "dealloc",
+                },
+                Instruction::BytesLowerMemory {
+                    allocator_name: "alloc",
+                },
+            ],
         }],
         exports: vec![Export {
             name: "ab",
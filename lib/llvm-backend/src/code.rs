@@ -82,6 +82,7 @@ fn type_to_llvm<'ctx>(intrinsics: &Intrinsics<'ctx>, ty: Type) -> BasicTypeEnum<
         Type::F32 => intrinsics.f32_ty.as_basic_type_enum(),
         Type::F64 => intrinsics.f64_ty.as_basic_type_enum(),
         Type::V128 => intrinsics.i128_ty.as_basic_type_enum(),
+        Type::ExternRef | Type::FuncRef => intrinsics.i64_ty.as_basic_type_enum(),
     }
 }
 
@@ -1013,6 +1014,7 @@ impl<'ctx> FunctionCodeGenerator<CodegenError> for LLVMFunctionCodeGenerator<'ct
             Type::F32 => intrinsics.f32_zero.as_basic_value_enum(),
             Type::F64 => intrinsics.f64_zero.as_basic_value_enum(),
             Type::V128 => intrinsics.i128_zero.as_basic_value_enum(),
+            Type::ExternRef | Type::FuncRef => intrinsics.i64_zero.as_basic_value_enum(),
         };
 
         let builder = self.builder.as_ref().unwrap();
@@ -74,6 +74,7 @@ fn generate_trampoline<'ctx>(
         Type::I64 => intrinsics.i64_ptr_ty,
         Type::F64 => intrinsics.f64_ptr_ty,
         Type::V128 => intrinsics.i128_ptr_ty,
+        Type::ExternRef | Type::FuncRef => intrinsics.i64_ptr_ty,
     };
 
     let mut args_vec = Vec::with_capacity(func_sig.params().len() + 1);
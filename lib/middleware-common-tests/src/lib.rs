@@ -173,4 +173,68 @@ mod tests {
         // verify it used the correct number of points
         assert_eq!(get_points_used(&instance), 109); // Used points will be slightly more than `limit` because of the way we do gas checking.
     }
+
+    #[test]
+    fn test_opcode_tracer_records_executed_operators() {
+        use wasmer_middleware_common::opcode_trace::{OpcodeTraceBuffer, OpcodeTracer};
+
+        let wasm_binary = wat2wasm(WAT).unwrap();
+
+        let buffer = OpcodeTraceBuffer::new(4);
+        let compiler_buffer = buffer.clone();
+        let compiler: StreamingCompiler<MCG, _, _, _, _> = StreamingCompiler::new(move || {
+            let mut chain = MiddlewareChain::new();
+            chain.push(OpcodeTracer::runtime(compiler_buffer.clone()));
+            chain
+        });
+        let module = compile_with(&wasm_binary, &compiler).unwrap();
+
+        let import_object = imports! {};
+        let instance = module.instantiate(&import_object).unwrap();
+        assert!(buffer.entries().is_empty());
+
+        let add_to: Func<(i32, i32), i32> = instance.func("add_to").unwrap();
+
+        let cv_pushed = if let Some(msm) = instance.module.runnable_module.get_module_state_map() {
+            push_code_version(CodeVersion {
+                baseline: true,
+                msm: msm,
+                base: instance.module.runnable_module.get_code().unwrap().as_ptr() as usize,
+                backend: MCG::backend_id(),
+                runnable_module: instance.module.runnable_module.clone(),
+            });
+            true
+        } else {
+            false
+        };
+        let value = add_to.call(3, 4).unwrap();
+        if cv_pushed {
+            pop_code_version().unwrap();
+        }
+
+        assert_eq!(value, 7);
+        let entries = buffer.entries();
+        assert_eq!(entries.len(), 4);
+        assert!(entries.iter().all(|&(function, _)| function == 0));
+
+        buffer.clear();
+        assert!(buffer.entries().is_empty());
+    }
+
+    #[test]
+    fn test_opcode_tracer_fails_to_compile_without_output() {
+        use wasmer_middleware_common::opcode_trace::{OpcodeTraceFormat, OpcodeTracer};
+
+        let wasm_binary = wat2wasm(WAT).unwrap();
+
+        let compiler: StreamingCompiler<MCG, _, _, _, _> = StreamingCompiler::new(move || {
+            let mut chain = MiddlewareChain::new();
+            chain.push(OpcodeTracer::with_path(
+                "/nonexistent/opcode.trace",
+                OpcodeTraceFormat::Binary,
+            ));
+            chain
+        });
+        assert!(compile_with(&wasm_binary, &compiler).is_err());
+    }
 }
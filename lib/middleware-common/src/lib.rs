@@ -20,3 +20,4 @@ pub mod metering_costs;
 pub mod runtime_breakpoints;
 pub mod opcode_trace;
 pub mod opcode_control;
+pub mod stack_height;
@@ -1,17 +1,26 @@
 use wasmer_runtime_core::{
     codegen::{Event, EventSink, FunctionMiddleware, InternalEvent},
+    error::{RuntimeErrorKind, Trap},
     module::ModuleInfo,
     vm::{Ctx, InternalField},
     wasmparser::{Operator, Type as WpType, TypeOrFuncType as WpTypeOrFuncType},
     Instance,
 };
 
-use crate::metering_costs::{get_opcode_index, get_local_allocate_cost_index};
-use crate::runtime_breakpoints::{push_runtime_breakpoint, BREAKPOINT_VALUE_OUT_OF_GAS};
+use std::sync::Arc;
+
+use crate::metering_costs::{
+    get_dynamic_cost_index, get_local_allocate_cost_index, get_opcode_index, OpcodeCosts,
+};
+use crate::runtime_breakpoints::{
+    push_runtime_breakpoint, BREAKPOINT_VALUE_OUT_OF_GAS, FIELD_RUNTIME_BREAKPOINT_VALUE,
+};
 
 static FIELD_USED_POINTS: InternalField = InternalField::allocate();
 static FIELD_POINTS_LIMIT: InternalField = InternalField::allocate();
 
+static FIELD_OPERAND_BACKUP: InternalField = InternalField::allocate();
+
 /// Metering is a compiler middleware that calculates the cost of WebAssembly instructions at compile
 /// time and will count the cost of executed instructions at runtime. Within the Metering functionality,
 /// this instruction cost is called `points`.
@@ -24,16 +33,20 @@ static FIELD_POINTS_LIMIT: InternalField = InternalField::allocate();
 /// Each compiler backend with Metering enabled should produce the same cost used at runtime for
 /// the same function calls so we can say that the metering is deterministic.
 ///
-
-pub struct Metering<'a> {
+/// The cost of each instruction is read from the `OpcodeCosts` table given to `Metering::new`,
+/// which allows modules compiled in the same process to use different gas schedules.
+///
+/// Operators whose work depends on a runtime operand, such as `memory.grow` or `memory.copy`, are
+/// additionally charged a per-unit cost multiplied by that operand, right before they execute.
+pub struct Metering {
     unmetered_locals: usize,
     current_block: u64,
     func_locals_costs: u32,
-    opcode_costs: &'a [u32],
+    opcode_costs: Arc<OpcodeCosts>,
 }
 
-impl<'a> Metering<'a> {
-    pub fn new(opcode_costs: &'a [u32], unmetered_locals: usize) -> Metering<'a> {
+impl Metering {
+    pub fn new(opcode_costs: Arc<OpcodeCosts>, unmetered_locals: usize) -> Metering {
         Metering {
             unmetered_locals,
             current_block: 0,
@@ -41,12 +54,63 @@ impl<'a> Metering<'a> {
             opcode_costs,
         }
     }
+
+    fn inject_points_limit_check(&self, sink: &mut EventSink) {
+        sink.push(Event::Internal(InternalEvent::GetInternal(
+            FIELD_USED_POINTS.index() as _,
+        )));
+        sink.push(Event::Internal(InternalEvent::GetInternal(
+            FIELD_POINTS_LIMIT.index() as _,
+        )));
+        sink.push(Event::WasmOwned(Operator::I64GeU));
+        sink.push(Event::WasmOwned(Operator::If {
+            ty: WpTypeOrFuncType::Type(WpType::EmptyBlockType),
+        }));
+        push_runtime_breakpoint(sink, BREAKPOINT_VALUE_OUT_OF_GAS);
+        sink.push(Event::WasmOwned(Operator::End));
+    }
+
+    /// Charges `unit_cost` points per unit of the operand on top of the stack, i.e. per page for
+    /// `memory.grow` and per byte for the bulk memory operators, then checks the limit, so that
+    /// the operator never executes if it cannot be paid for.
+    fn inject_dynamic_cost(&self, unit_cost: u32, sink: &mut EventSink) {
+        // Backup the operand in order to duplicate it: once for the charge and again for the
+        // operator itself, assuming the limit is not reached.
+        sink.push(Event::Internal(InternalEvent::SetInternal(
+            FIELD_OPERAND_BACKUP.index() as _,
+        )));
+
+        sink.push(Event::Internal(InternalEvent::GetInternal(
+            FIELD_USED_POINTS.index() as _,
+        )));
+        sink.push(Event::Internal(InternalEvent::GetInternal(
+            FIELD_OPERAND_BACKUP.index() as _,
+        )));
+        // The operand is an i32, so only the lower half of the backup is meaningful. Neither
+        // factor exceeds 32 bits, hence the multiplication cannot overflow.
+        sink.push(Event::WasmOwned(Operator::I64ExtendI32U));
+        sink.push(Event::WasmOwned(Operator::I64Const {
+            value: unit_cost as i64,
+        }));
+        sink.push(Event::WasmOwned(Operator::I64Mul));
+        sink.push(Event::WasmOwned(Operator::I64Add));
+        sink.push(Event::Internal(InternalEvent::SetInternal(
+            FIELD_USED_POINTS.index() as _,
+        )));
+
+        self.inject_points_limit_check(sink);
+
+        // Bring back the backed-up operand for the operator.
+        sink.push(Event::Internal(InternalEvent::GetInternal(
+            FIELD_OPERAND_BACKUP.index() as _,
+        )));
+    }
 }
 
 #[derive(Copy, Clone, Debug)]
 pub struct ExecutionLimitExceededError;
 
-impl<'q> FunctionMiddleware for Metering<'q> {
+impl FunctionMiddleware for Metering {
     type Error = String;
 
     fn feed_event<'a, 'b: 'a>(
@@ -62,7 +126,7 @@ impl<'q> FunctionMiddleware for Metering<'q> {
             }
             Event::Wasm(&ref op) | Event::WasmOwned(ref op) => {
                 let opcode_index = get_opcode_index(op);
-                self.current_block += self.opcode_costs[opcode_index] as u64;
+                self.current_block += self.opcode_costs.cost(opcode_index) as u64;
                 match *op {
                     Operator::Loop { .. }
                     | Operator::Block { .. }
@@ -96,21 +160,16 @@ impl<'q> FunctionMiddleware for Metering<'q> {
                     | Operator::BrIf { .. }
                     | Operator::Call { .. }
                     | Operator::CallIndirect { .. } => {
-                        sink.push(Event::Internal(InternalEvent::GetInternal(
-                            FIELD_USED_POINTS.index() as _,
-                        )));
-                        sink.push(Event::Internal(InternalEvent::GetInternal(
-                            FIELD_POINTS_LIMIT.index() as _,
-                        )));
-                        sink.push(Event::WasmOwned(Operator::I64GeU));
-                        sink.push(Event::WasmOwned(Operator::If {
-                            ty: WpTypeOrFuncType::Type(WpType::EmptyBlockType),
-                        }));
-                        push_runtime_breakpoint(sink, BREAKPOINT_VALUE_OUT_OF_GAS);
-                        sink.push(Event::WasmOwned(Operator::End));
+                        self.inject_points_limit_check(sink);
                     }
                     _ => {}
                 }
+                if let Some(cost_index) = get_dynamic_cost_index(op) {
+                    let unit_cost = self.opcode_costs.cost(cost_index);
+                    if unit_cost != 0 {
+                        self.inject_dynamic_cost(unit_cost, sink);
+                    }
+                }
             }
             _ => {}
         }
@@ -129,7 +188,7 @@ impl<'q> FunctionMiddleware for Metering<'q> {
         if n > self.unmetered_locals {
             let metered_locals = (n  - self.unmetered_locals) as u32;
             let cost_index = get_local_allocate_cost_index();
-            let cost = self.opcode_costs[cost_index];
+            let cost = self.opcode_costs.cost(cost_index);
             // n is already limited by Wasmparser; the following casting and multiplication are
             // safe from overflowing
             self.func_locals_costs += cost * metered_locals;
@@ -162,3 +221,29 @@ pub fn get_points_used_ctx(ctx: &Ctx) -> u64 {
 pub fn set_points_used_ctx(ctx: &mut Ctx, value: u64) {
     ctx.set_internal(&FIELD_USED_POINTS, value);
 }
+
+/// Charges `points` to the instance running in `ctx`, e.g. for the work done by a host function.
+///
+/// The check against the limit is the same as the one done at the end of a metered block: if the
+/// points used reach the limit, the runtime breakpoint value is set to
+/// `BREAKPOINT_VALUE_OUT_OF_GAS` and the execution traps with `RuntimeErrorKind::OutOfGas`. In that
+/// case this function never returns.
+///
+/// # Safety
+///
+/// Trapping unwinds through the calling host function, so this function must only be called from
+/// within a host function, and without anything on the stack that needs to be dropped.
+pub unsafe fn charge_points_ctx(ctx: &mut Ctx, points: u64) {
+    let points_used = get_points_used_ctx(ctx).saturating_add(points);
+    set_points_used_ctx(ctx, points_used);
+
+    if points_used >= ctx.get_internal(&FIELD_POINTS_LIMIT) {
+        ctx.set_internal(&FIELD_RUNTIME_BREAKPOINT_VALUE, BREAKPOINT_VALUE_OUT_OF_GAS);
+        (&*ctx.module)
+            .runnable_module
+            .do_early_trap(Box::new(Trap::new(
+                RuntimeErrorKind::OutOfGas,
+                "breakpoint reached",
+            )));
+    }
+}
@@ -1,9 +1,80 @@
+use std::sync::Arc;
 use wasmer_runtime_core::wasmparser::Operator;
 
+/// Number of entries in an opcode cost table: one per operator, followed by the cost of
+/// allocating a local (see `get_local_allocate_cost_index`) and by the per-unit costs of the
+/// operators whose cost depends on their runtime operand (see `get_dynamic_cost_index`).
+pub const OPCODE_COUNT: usize = 452;
+
+/// Number of entries in the cost tables that predate the per-unit costs: one per operator,
+/// followed by the cost of allocating a local.
+pub const LEGACY_OPCODE_COUNT: usize = 448;
+
+/// An immutable table of opcode costs, tagged with the version of the gas schedule it
+/// belongs to.
+///
+/// Tables are shared through an `Arc`, so that several gas schedules can coexist in the same
+/// process, each compilation holding on to the table it was configured with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpcodeCosts {
+    version: u32,
+    costs: Box<[u32]>,
+}
+
+impl OpcodeCosts {
+    /// Creates a shared cost table from exactly `OPCODE_COUNT` costs, or from a legacy table of
+    /// `LEGACY_OPCODE_COUNT` costs, in which case the per-unit costs are 0.
+    pub fn new(version: u32, costs: &[u32]) -> Result<Arc<OpcodeCosts>, String> {
+        if costs.len() != OPCODE_COUNT && costs.len() != LEGACY_OPCODE_COUNT {
+            return Err(format!(
+                "opcode cost table must have {} or {} entries, got {}",
+                OPCODE_COUNT,
+                LEGACY_OPCODE_COUNT,
+                costs.len()
+            ));
+        }
+
+        let mut costs = costs.to_vec();
+        costs.resize(OPCODE_COUNT, 0);
+        Ok(Arc::new(OpcodeCosts {
+            version,
+            costs: costs.into_boxed_slice(),
+        }))
+    }
+
+    /// The version of the gas schedule this table belongs to.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// The cost stored at `index`, as returned by `get_opcode_index`.
+    pub fn cost(&self, index: usize) -> u32 {
+        self.costs[index]
+    }
+
+    /// All the costs, in opcode index order.
+    pub fn as_slice(&self) -> &[u32] {
+        &self.costs
+    }
+}
+
 pub fn get_local_allocate_cost_index() -> usize {
     447
 }
 
+/// Returns the index of the per-unit cost of `op`, for the operators whose cost grows with one
+/// of their runtime operands: the number of pages for `memory.grow`, and the number of bytes for
+/// the bulk memory operators. This cost is charged on top of the fixed cost of the operator.
+pub fn get_dynamic_cost_index(op: &Operator) -> Option<usize> {
+    match *op {
+        Operator::MemoryGrow { .. } => Some(448),
+        Operator::MemoryCopy { .. } => Some(449),
+        Operator::MemoryFill { .. } => Some(450),
+        Operator::MemoryInit { .. } => Some(451),
+        _ => None,
+    }
+}
+
 pub fn get_opcode_index(op: &Operator) -> usize {
     match *op {
         Operator::Unreachable { .. } => { 0 }
@@ -6,65 +6,120 @@ use wasmer_runtime_core::{
     Instance,
 };
 
-use crate::runtime_breakpoints::{push_runtime_breakpoint, BREAKPOINT_VALUE_MEMORY_LIMIT};
+use crate::runtime_breakpoints::{
+    push_runtime_breakpoint, BREAKPOINT_VALUE_MEMORY_LIMIT, BREAKPOINT_VALUE_TABLE_LIMIT,
+};
 
 static FIELD_MEMORY_GROW_COUNT: InternalField = InternalField::allocate();
 
+static FIELD_TABLE_GROW_COUNT: InternalField = InternalField::allocate();
+
 static FIELD_OPERAND_BACKUP: InternalField = InternalField::allocate();
 
 pub struct OpcodeControl {
     pub max_memory_grow: usize,
     pub max_memory_grow_delta: usize,
+    pub max_table_grow: usize,
+    pub max_table_grow_delta: usize,
 }
 
 impl OpcodeControl {
-    pub fn new(max_memory_grow: usize, max_memory_grow_delta: usize) -> OpcodeControl {
+    pub fn new(
+        max_memory_grow: usize,
+        max_memory_grow_delta: usize,
+        max_table_grow: usize,
+        max_table_grow_delta: usize,
+    ) -> OpcodeControl {
         OpcodeControl {
             max_memory_grow,
             max_memory_grow_delta,
+            max_table_grow,
+            max_table_grow_delta,
         }
     }
 
-    fn inject_memory_grow_count_limit(&mut self, sink: &mut EventSink) {
+    fn inject_grow_count_limit(
+        &mut self,
+        sink: &mut EventSink,
+        count_field: &InternalField,
+        max_grow: usize,
+        breakpoint_value: u64,
+    ) {
         sink.push(Event::Internal(InternalEvent::GetInternal(
-            FIELD_MEMORY_GROW_COUNT.index() as _,
+            count_field.index() as _,
         )));
         sink.push(Event::WasmOwned(Operator::I64Const {
-            value: self.max_memory_grow as i64,
+            value: max_grow as i64,
         }));
         sink.push(Event::WasmOwned(Operator::I64GeU));
         sink.push(Event::WasmOwned(Operator::If {
             ty: WpTypeOrFuncType::Type(WpType::EmptyBlockType),
         }));
-        push_runtime_breakpoint(sink, BREAKPOINT_VALUE_MEMORY_LIMIT);
+        push_runtime_breakpoint(sink, breakpoint_value);
         sink.push(Event::WasmOwned(Operator::End));
     }
 
-    fn inject_memory_grow_count_increment(&mut self, sink: &mut EventSink) {
+    fn inject_grow_count_increment(&mut self, sink: &mut EventSink, count_field: &InternalField) {
         sink.push(Event::Internal(InternalEvent::GetInternal(
-            FIELD_MEMORY_GROW_COUNT.index() as _,
+            count_field.index() as _,
         )));
         sink.push(Event::WasmOwned(Operator::I64Const { value: 1 as i64 }));
         sink.push(Event::WasmOwned(Operator::I64Add));
         sink.push(Event::Internal(InternalEvent::SetInternal(
-            FIELD_MEMORY_GROW_COUNT.index() as _,
+            count_field.index() as _,
         )));
     }
 
-    fn inject_memory_grow_delta_limit(&mut self, sink: &mut EventSink) {
+    fn inject_grow_delta_limit(
+        &mut self,
+        sink: &mut EventSink,
+        max_grow_delta: usize,
+        breakpoint_value: u64,
+    ) {
         sink.push(Event::Internal(InternalEvent::GetInternal(
             FIELD_OPERAND_BACKUP.index() as _,
         )));
         sink.push(Event::WasmOwned(Operator::I64Const {
-            value: self.max_memory_grow_delta as i64,
+            value: max_grow_delta as i64,
         }));
         sink.push(Event::WasmOwned(Operator::I64GtU));
         sink.push(Event::WasmOwned(Operator::If {
             ty: WpTypeOrFuncType::Type(WpType::EmptyBlockType),
         }));
-        push_runtime_breakpoint(sink, BREAKPOINT_VALUE_MEMORY_LIMIT);
+        push_runtime_breakpoint(sink, breakpoint_value);
         sink.push(Event::WasmOwned(Operator::End));
     }
+
+    /// Injects the checks guarding a `memory.grow` or a `table.grow`, whose delta operand is on
+    /// top of the stack.
+    fn inject_grow_limits(
+        &mut self,
+        sink: &mut EventSink,
+        count_field: &InternalField,
+        max_grow: usize,
+        max_grow_delta: usize,
+        breakpoint_value: u64,
+    ) {
+        // Before attempting anything with the grow operator, the current grow count is checked
+        // against the max_grow limit.
+        self.inject_grow_count_limit(sink, count_field, max_grow, breakpoint_value);
+        self.inject_grow_count_increment(sink, count_field);
+
+        // Backup the top of the stack (the delta parameter of the grow operator) in order to
+        // duplicate it: once for the comparison against max_grow_delta and again for the grow
+        // operator itself, assuming the comparison passes.
+        sink.push(Event::Internal(InternalEvent::SetInternal(
+            FIELD_OPERAND_BACKUP.index() as _,
+        )));
+
+        // Set up the comparison against max_grow_delta.
+        self.inject_grow_delta_limit(sink, max_grow_delta, breakpoint_value);
+
+        // Bring back the backed-up operand for the grow operator.
+        sink.push(Event::Internal(InternalEvent::GetInternal(
+            FIELD_OPERAND_BACKUP.index() as _,
+        )));
+    }
 }
 
 impl FunctionMiddleware for OpcodeControl {
@@ -84,25 +139,23 @@ impl FunctionMiddleware for OpcodeControl {
                             return Err("MemoryGrow must have memory index 0".to_string());
                         }
 
-                        // Before attempting anything with memory.grow, the current memory.grow
-                        // count is checked against the self.max_memory_grow limit.
-                        self.inject_memory_grow_count_limit(sink);
-                        self.inject_memory_grow_count_increment(sink);
-
-                        // Backup the top of the stack (the parameter for memory.grow) in order to
-                        // duplicate it: once for the comparison against max_memory_grow_delta and
-                        // again for memory.grow itself, assuming the comparison passes.
-                        sink.push(Event::Internal(InternalEvent::SetInternal(
-                            FIELD_OPERAND_BACKUP.index() as _,
-                        )));
-
-                        // Set up the comparison against max_memory_grow_delta.
-                        self.inject_memory_grow_delta_limit(sink);
-
-                        // Bring back the backed-up operand for memory.grow.
-                        sink.push(Event::Internal(InternalEvent::GetInternal(
-                            FIELD_OPERAND_BACKUP.index() as _,
-                        )));
+                        self.inject_grow_limits(
+                            sink,
+                            &FIELD_MEMORY_GROW_COUNT,
+                            self.max_memory_grow,
+                            self.max_memory_grow_delta,
+                            BREAKPOINT_VALUE_MEMORY_LIMIT,
+                        );
+                    }
+                    Operator::TableGrow { .. } => {
+                        // A single count covers the growth of every table.
+                        self.inject_grow_limits(
+                            sink,
+                            &FIELD_TABLE_GROW_COUNT,
+                            self.max_table_grow,
+                            self.max_table_grow_delta,
+                            BREAKPOINT_VALUE_TABLE_LIMIT,
+                        );
                     }
                     _ => {}
                 }
@@ -119,3 +172,8 @@ impl FunctionMiddleware for OpcodeControl {
 pub fn reset_memory_grow_count(instance: &mut Instance) {
     instance.set_internal(&FIELD_MEMORY_GROW_COUNT, 0);
 }
+
+/// Set internal field `FIELD_TABLE_GROW_COUNT` to 0.
+pub fn reset_table_grow_count(instance: &mut Instance) {
+    instance.set_internal(&FIELD_TABLE_GROW_COUNT, 0);
+}
@@ -1,5 +1,8 @@
+use std::collections::VecDeque;
 use std::fs::File;
-use std::io::Write;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
 
 use wasmer_runtime_core::{
     codegen::{Event, EventSink, FunctionMiddleware, InternalEvent},
@@ -11,58 +14,220 @@ use wasmer_runtime_core::{
 
 static OPCODE_LAST_LOCATION: InternalField = InternalField::allocate();
 
+/// The format of the trace written while compiling.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OpcodeTraceFormat {
+    /// One human-readable line per operator.
+    Text,
+    /// One 12-byte record per operator: the local function index, the operator index in the
+    /// function and the offset of the operator in the wasm binary, as little-endian `u32`s.
+    Binary,
+}
+
+/// A ring buffer of the last (local function index, operator index) pairs executed by the
+/// instances compiled with a runtime `OpcodeTracer`. Clones share the same entries.
+#[derive(Clone, Debug)]
+pub struct OpcodeTraceBuffer {
+    entries: Arc<Mutex<VecDeque<(u32, u32)>>>,
+    capacity: usize,
+}
+
+impl OpcodeTraceBuffer {
+    /// Creates a buffer keeping the last `capacity` executed operators.
+    pub fn new(capacity: usize) -> OpcodeTraceBuffer {
+        OpcodeTraceBuffer {
+            entries: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity,
+        }
+    }
+
+    /// Returns the maximum number of entries kept.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the recorded entries, oldest first.
+    pub fn entries(&self) -> Vec<(u32, u32)> {
+        self.entries.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Removes every recorded entry.
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    fn record(&self, local_function_index: u32, operator_index: u32) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() == self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back((local_function_index, operator_index));
+    }
+}
+
 pub struct OpcodeTracer {
-    pub output_file: File,
+    output: Option<Box<dyn Write>>,
+    output_error: Option<String>,
+    format: OpcodeTraceFormat,
+    runtime_buffer: Option<OpcodeTraceBuffer>,
     local_function_index: u32,
     counter: u32,
 }
 
 impl OpcodeTracer {
+    /// Creates a tracer writing a text trace to `opcode.trace`.
     pub fn new() -> OpcodeTracer {
+        OpcodeTracer::with_path("opcode.trace", OpcodeTraceFormat::Text)
+    }
+
+    /// Creates a tracer writing its trace to the file at `path`. Failing to create the file
+    /// makes the compilation fail.
+    pub fn with_path<P: AsRef<Path>>(path: P, format: OpcodeTraceFormat) -> OpcodeTracer {
+        let path = path.as_ref();
+        match File::create(path) {
+            Ok(file) => OpcodeTracer::with_writer(BufWriter::new(file), format),
+            Err(err) => OpcodeTracer {
+                output_error: Some(format!(
+                    "cannot create the opcode trace file {}: {}",
+                    path.display(),
+                    err
+                )),
+                ..OpcodeTracer::without_output(format)
+            },
+        }
+    }
+
+    /// Creates a tracer writing its trace to `writer`.
+    pub fn with_writer<W: Write + 'static>(writer: W, format: OpcodeTraceFormat) -> OpcodeTracer {
         OpcodeTracer {
-            output_file: File::create("opcode.trace").unwrap(),
+            output: Some(Box::new(writer)),
+            ..OpcodeTracer::without_output(format)
+        }
+    }
+
+    /// Creates a tracer which writes nothing while compiling, and records the operators
+    /// executed at runtime into `buffer`.
+    pub fn runtime(buffer: OpcodeTraceBuffer) -> OpcodeTracer {
+        OpcodeTracer::without_output(OpcodeTraceFormat::Text).with_runtime_buffer(buffer)
+    }
+
+    /// Also records the operators executed at runtime into `buffer`.
+    pub fn with_runtime_buffer(mut self, buffer: OpcodeTraceBuffer) -> OpcodeTracer {
+        self.runtime_buffer = Some(buffer);
+        self
+    }
+
+    fn without_output(format: OpcodeTraceFormat) -> OpcodeTracer {
+        OpcodeTracer {
+            output: None,
+            output_error: None,
+            format,
+            runtime_buffer: None,
             local_function_index: 0,
             counter: 0,
         }
     }
 
-    fn trace_operator(&mut self, event: &Event) {
-        match *event {
+    fn trace_operator(&mut self, op: &Operator, source_loc: u32) -> Result<(), String> {
+        let output = match self.output {
+            Some(ref mut output) => output,
+            None => return Ok(()),
+        };
+        let result = match self.format {
+            OpcodeTraceFormat::Text => output.write_all(
+                format!(
+                    "[fn: {:08b}({}), operator: {:08b}({})]\t{:?}\n",
+                    self.local_function_index,
+                    self.local_function_index,
+                    self.counter,
+                    self.counter,
+                    op
+                )
+                .as_bytes(),
+            ),
+            OpcodeTraceFormat::Binary => {
+                let mut record = [0u8; 12];
+                record[0..4].copy_from_slice(&self.local_function_index.to_le_bytes());
+                record[4..8].copy_from_slice(&self.counter.to_le_bytes());
+                record[8..12].copy_from_slice(&source_loc.to_le_bytes());
+                output.write_all(&record)
+            }
+        };
+        result.map_err(|err| format!("cannot write the opcode trace: {}", err))
+    }
+
+    fn push_runtime_tracer(&self, sink: &mut EventSink) {
+        if let Some(ref buffer) = self.runtime_buffer {
+            let buffer = buffer.clone();
+            let (local_function_index, operator_index) = (self.local_function_index, self.counter);
+            sink.push(Event::Internal(InternalEvent::Breakpoint(Box::new(
+                move |_| {
+                    buffer.record(local_function_index, operator_index);
+                    Ok(())
+                },
+            ))));
+        }
+    }
+}
+
+impl FunctionMiddleware for OpcodeTracer {
+    type Error = String;
+    fn feed_event<'a, 'b: 'a>(
+        &mut self,
+        op: Event<'a, 'b>,
+        _module_info: &ModuleInfo,
+        sink: &mut EventSink<'a, 'b>,
+        source_loc: u32,
+    ) -> Result<(), Self::Error> {
+        if let Some(ref err) = self.output_error {
+            return Err(err.clone());
+        }
+
+        match op {
             Event::Internal(InternalEvent::FunctionBegin(local_function_index)) => {
                 self.local_function_index = local_function_index;
                 self.counter = 0;
             }
+            Event::Internal(InternalEvent::FunctionEnd) => {
+                if let Some(ref mut output) = self.output {
+                    output
+                        .flush()
+                        .map_err(|err| format!("cannot write the opcode trace: {}", err))?;
+                }
+            }
             Event::Wasm(&ref op) | Event::WasmOwned(ref op) => {
-                self.output_file
-                    .write_all(
-                        format!(
-                            "[fn: {:08b}({}), operator: {:08b}({})]\t{:?}\n",
-                            self.local_function_index,
-                            self.local_function_index,
-                            self.counter,
-                            self.counter,
-                            op
-                        )
-                        .as_bytes(),
-                    )
-                    .unwrap();
+                self.trace_operator(op, source_loc)?;
+                self.push_runtime_tracer(sink);
                 self.counter += 1;
             }
             _ => {}
         }
+
+        sink.push(op);
+
+        Ok(())
     }
+}
 
-    fn push_last_location_tracer(&self, sink: &mut EventSink, source_loc: u32) {
-        sink.push(Event::WasmOwned(Operator::I64Const {
-            value: source_loc as i64,
-        }));
-        sink.push(Event::Internal(InternalEvent::SetInternal(
-            OPCODE_LAST_LOCATION.index() as _,
-        )));
+/// Records the location of the last operator executed by an instance, read back with
+/// `get_opcodetracer_last_function_location()`. It must come after the middlewares which may
+/// trap, so that the location is set before their checks.
+pub struct LastLocationTracker {
+    local_function_index: u32,
+}
+
+impl LastLocationTracker {
+    pub fn new() -> LastLocationTracker {
+        LastLocationTracker {
+            local_function_index: 0,
+        }
     }
 }
 
-impl FunctionMiddleware for OpcodeTracer {
+impl FunctionMiddleware for LastLocationTracker {
     type Error = String;
     fn feed_event<'a, 'b: 'a>(
         &mut self,
@@ -71,8 +236,18 @@ impl FunctionMiddleware for OpcodeTracer {
         sink: &mut EventSink<'a, 'b>,
         source_loc: u32,
     ) -> Result<(), Self::Error> {
-        self.trace_operator(&op);
-        self.push_last_location_tracer(sink, source_loc);
+        if let Event::Internal(InternalEvent::FunctionBegin(local_function_index)) = op {
+            self.local_function_index = local_function_index;
+        }
+
+        // The local function index is shifted by one so that 0 still means no location.
+        let location = ((self.local_function_index as u64 + 1) << 32) | source_loc as u64;
+        sink.push(Event::WasmOwned(Operator::I64Const {
+            value: location as i64,
+        }));
+        sink.push(Event::Internal(InternalEvent::SetInternal(
+            OPCODE_LAST_LOCATION.index() as _,
+        )));
 
         sink.push(op);
 
@@ -80,8 +255,18 @@ impl FunctionMiddleware for OpcodeTracer {
     }
 }
 
+/// Returns the offset in the wasm binary of the last operator executed by `instance`, or 0.
 pub fn get_opcodetracer_last_location(instance: &mut Instance) -> u64 {
-    instance.get_internal(&OPCODE_LAST_LOCATION)
+    instance.get_internal(&OPCODE_LAST_LOCATION) & 0xFFFF_FFFF
+}
+
+/// Returns the local function index and the offset in the wasm binary of the last operator
+/// executed by `instance`, if any ran since the last reset.
+pub fn get_opcodetracer_last_function_location(instance: &Instance) -> Option<(u32, u32)> {
+    match instance.get_internal(&OPCODE_LAST_LOCATION) {
+        0 => None,
+        location => Some(((location >> 32) as u32 - 1, location as u32)),
+    }
 }
 
 pub fn reset_opcodetracer_last_location(instance: &mut Instance) {
@@ -3,7 +3,7 @@ use wasmer_runtime_core::{
     module::ModuleInfo,
     vm::InternalField,
     wasmparser::{Operator, Type as WpType, TypeOrFuncType as WpTypeOrFuncType},
-    error::RuntimeError,
+    error::{RuntimeErrorKind, Trap},
     Instance,
 };
 
@@ -12,6 +12,8 @@ pub const BREAKPOINT_VALUE_NO_BREAKPOINT: u64 = 0;
 pub const BREAKPOINT_VALUE_EXECUTION_FAILED: u64 = 1;
 pub const BREAKPOINT_VALUE_OUT_OF_GAS: u64 = 4;
 pub const BREAKPOINT_VALUE_MEMORY_LIMIT: u64 = 5;
+pub const BREAKPOINT_VALUE_STACK_LIMIT: u64 = 6;
+pub const BREAKPOINT_VALUE_TABLE_LIMIT: u64 = 7;
 
 
 pub struct RuntimeBreakpointHandler {}
@@ -59,7 +61,7 @@ impl FunctionMiddleware for RuntimeBreakpointHandler {
                 ty: WpTypeOrFuncType::Type(WpType::EmptyBlockType),
             }));
             sink.push(Event::Internal(InternalEvent::Breakpoint(Box::new(|_| {
-                Err(Box::new(RuntimeError(Box::new("breakpoint reached".to_string()))))
+                Err(Box::new(Trap::new(RuntimeErrorKind::UserTrap, "breakpoint reached")))
             }))));
             sink.push(Event::WasmOwned(Operator::End));
         }
@@ -75,11 +77,23 @@ pub fn push_runtime_breakpoint(sink: &mut EventSink, value: u64) {
     sink.push(Event::Internal(InternalEvent::SetInternal(
         FIELD_RUNTIME_BREAKPOINT_VALUE.index() as _,
     )));
-    sink.push(Event::Internal(InternalEvent::Breakpoint(Box::new(|_| {
-        Err(Box::new(RuntimeError(Box::new("breakpoint reached".to_string()))))
+    let kind = get_breakpoint_kind(value);
+    sink.push(Event::Internal(InternalEvent::Breakpoint(Box::new(move |_| {
+        Err(Box::new(Trap::new(kind, "breakpoint reached")))
     }))));
 }
 
+/// Returns the `RuntimeErrorKind` reported when execution stops on the given breakpoint value.
+pub fn get_breakpoint_kind(value: u64) -> RuntimeErrorKind {
+    match value {
+        BREAKPOINT_VALUE_OUT_OF_GAS => RuntimeErrorKind::OutOfGas,
+        BREAKPOINT_VALUE_MEMORY_LIMIT => RuntimeErrorKind::MemoryGrowLimit,
+        BREAKPOINT_VALUE_STACK_LIMIT => RuntimeErrorKind::StackHeightLimit,
+        BREAKPOINT_VALUE_TABLE_LIMIT => RuntimeErrorKind::TableGrowLimit,
+        _ => RuntimeErrorKind::UserTrap,
+    }
+}
+
 pub fn set_runtime_breakpoint_value(instance: &mut Instance, value: u64) {
     instance.set_internal(&FIELD_RUNTIME_BREAKPOINT_VALUE, value);
 }
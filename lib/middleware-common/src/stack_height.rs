@@ -0,0 +1,192 @@
+use wasmer_runtime_core::{
+    codegen::{Event, EventSink, FunctionMiddleware, InternalEvent},
+    module::ModuleInfo,
+    parse::type_to_wp_type,
+    structures::TypedIndex,
+    types::{FuncIndex, SigIndex},
+    vm::InternalField,
+    wasmparser::{Operator, Type as WpType, TypeOrFuncType as WpTypeOrFuncType},
+    Instance,
+};
+
+use crate::runtime_breakpoints::{push_runtime_breakpoint, BREAKPOINT_VALUE_STACK_LIMIT};
+
+static FIELD_STACK_HEIGHT: InternalField = InternalField::allocate();
+
+/// StackHeight is a compiler middleware that bounds the call depth deterministically, instead of
+/// relying on the native stack, whose size depends on the machine and on the backend.
+///
+/// Each function has a static frame cost: one for the call itself, plus one per parameter and per
+/// local. The cost is added to the stack height of the instance when the function is entered, and
+/// subtracted when it returns. If the stack height exceeds `max_stack_height`, the execution
+/// traps with `BREAKPOINT_VALUE_STACK_LIMIT`.
+///
+/// So that every way out of a function is accounted for, including branches to its outermost
+/// label, the body is wrapped in a block, after which the frame cost is subtracted.
+pub struct StackHeight {
+    pub max_stack_height: usize,
+    func_locals_count: u64,
+    frame_cost: u64,
+    depth: usize,
+}
+
+impl StackHeight {
+    pub fn new(max_stack_height: usize) -> StackHeight {
+        StackHeight {
+            max_stack_height,
+            func_locals_count: 0,
+            frame_cost: 0,
+            depth: 0,
+        }
+    }
+
+    fn inject_stack_height_increment(&self, sink: &mut EventSink) {
+        sink.push(Event::Internal(InternalEvent::GetInternal(
+            FIELD_STACK_HEIGHT.index() as _,
+        )));
+        sink.push(Event::WasmOwned(Operator::I64Const {
+            value: self.frame_cost as i64,
+        }));
+        sink.push(Event::WasmOwned(Operator::I64Add));
+        sink.push(Event::Internal(InternalEvent::SetInternal(
+            FIELD_STACK_HEIGHT.index() as _,
+        )));
+    }
+
+    fn inject_stack_height_limit(&self, sink: &mut EventSink) {
+        sink.push(Event::Internal(InternalEvent::GetInternal(
+            FIELD_STACK_HEIGHT.index() as _,
+        )));
+        sink.push(Event::WasmOwned(Operator::I64Const {
+            value: self.max_stack_height as i64,
+        }));
+        sink.push(Event::WasmOwned(Operator::I64GtU));
+        sink.push(Event::WasmOwned(Operator::If {
+            ty: WpTypeOrFuncType::Type(WpType::EmptyBlockType),
+        }));
+        push_runtime_breakpoint(sink, BREAKPOINT_VALUE_STACK_LIMIT);
+        sink.push(Event::WasmOwned(Operator::End));
+    }
+
+    fn inject_stack_height_decrement(&self, sink: &mut EventSink) {
+        sink.push(Event::Internal(InternalEvent::GetInternal(
+            FIELD_STACK_HEIGHT.index() as _,
+        )));
+        sink.push(Event::WasmOwned(Operator::I64Const {
+            value: self.frame_cost as i64,
+        }));
+        sink.push(Event::WasmOwned(Operator::I64Sub));
+        sink.push(Event::Internal(InternalEvent::SetInternal(
+            FIELD_STACK_HEIGHT.index() as _,
+        )));
+    }
+}
+
+/// Returns the signature index of the given local function.
+fn get_sig_index(module_info: &ModuleInfo, local_func_index: u32) -> SigIndex {
+    let func_index =
+        FuncIndex::new(module_info.imported_functions.len() + local_func_index as usize);
+    module_info.func_assoc[func_index]
+}
+
+/// Returns the type of a block that yields the results of the given signature.
+fn get_body_block_type(module_info: &ModuleInfo, sig_index: SigIndex) -> WpTypeOrFuncType {
+    let returns = module_info.signatures[sig_index].returns();
+
+    match returns.len() {
+        0 => WpTypeOrFuncType::Type(WpType::EmptyBlockType),
+        1 => WpTypeOrFuncType::Type(type_to_wp_type(returns[0])),
+        _ => WpTypeOrFuncType::FuncType(sig_index.index() as u32),
+    }
+}
+
+impl FunctionMiddleware for StackHeight {
+    type Error = String;
+    fn feed_event<'a, 'b: 'a>(
+        &mut self,
+        op: Event<'a, 'b>,
+        module_info: &ModuleInfo,
+        sink: &mut EventSink<'a, 'b>,
+        _: u32,
+    ) -> Result<(), Self::Error> {
+        let mut is_body_end = false;
+
+        match op {
+            Event::Internal(InternalEvent::FunctionBegin(local_func_index)) => {
+                let sig_index = get_sig_index(module_info, local_func_index);
+                let params_count = module_info.signatures[sig_index].params().len() as u64;
+
+                self.frame_cost = 1 + params_count + self.func_locals_count;
+                self.func_locals_count = 0;
+                self.depth = 0;
+
+                sink.push(op);
+                self.inject_stack_height_increment(sink);
+                self.inject_stack_height_limit(sink);
+
+                // A block with several results is typed by the signature of the function, so it
+                // also takes the params, which are dropped right away.
+                let body_block_type = get_body_block_type(module_info, sig_index);
+                let block_params_count = match body_block_type {
+                    WpTypeOrFuncType::FuncType(_) => params_count as u32,
+                    WpTypeOrFuncType::Type(_) => 0,
+                };
+                for local_index in 0..block_params_count {
+                    sink.push(Event::WasmOwned(Operator::GetLocal { local_index }));
+                }
+                sink.push(Event::WasmOwned(Operator::Block {
+                    ty: body_block_type,
+                }));
+                for _ in 0..block_params_count {
+                    sink.push(Event::WasmOwned(Operator::Drop));
+                }
+                return Ok(());
+            }
+            Event::Wasm(&ref op) | Event::WasmOwned(ref op) => match *op {
+                Operator::Block { .. } | Operator::Loop { .. } | Operator::If { .. } => {
+                    self.depth += 1;
+                }
+                Operator::Return => {
+                    self.inject_stack_height_decrement(sink);
+                }
+                Operator::End if self.depth > 0 => {
+                    self.depth -= 1;
+                }
+                Operator::End => {
+                    is_body_end = true;
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+
+        sink.push(op);
+
+        if is_body_end {
+            // The `end` of the function body closed the wrapping block; a new one closes the
+            // function, once the frame cost is subtracted.
+            self.inject_stack_height_decrement(sink);
+            sink.push(Event::WasmOwned(Operator::End));
+        }
+
+        Ok(())
+    }
+
+    fn feed_local(&mut self, _ty: WpType, n: usize, _loc: u32) -> Result<(), Self::Error> {
+        self.func_locals_count += n as u64;
+        Ok(())
+    }
+}
+
+/// Returns the current stack height of an Instance.
+pub fn get_stack_height(instance: &Instance) -> u64 {
+    instance.get_internal(&FIELD_STACK_HEIGHT)
+}
+
+/// Set internal field `FIELD_STACK_HEIGHT` to 0.
+///
+/// A trap leaves the frame costs of the aborted functions in the stack height, so it must be
+/// reset before the instance is called again.
+pub fn reset_stack_height(instance: &mut Instance) {
+    instance.set_internal(&FIELD_STACK_HEIGHT, 0);
+}
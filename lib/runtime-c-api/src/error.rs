@@ -1,28 +1,240 @@
 //! Read runtime errors.
 
+use crate::{wasmer_byte_array, wasmer_result_t};
 use libc::{c_char, c_int};
 use std::{
-    cell::RefCell,
+    any::Any,
+    cell::{Cell, RefCell},
     error::Error,
     fmt::{self, Display, Formatter},
     ptr, slice,
 };
+use wasmer_runtime_core::{
+    backend::ExceptionCode,
+    error::{
+        get_last_trap_backtrace, CallError, Error as RuntimeCoreError, RuntimeError,
+        RuntimeErrorKind, WasmBacktrace,
+    },
+    structures::TypedIndex,
+};
 
 thread_local! {
     static LAST_ERROR: RefCell<Option<Box<dyn Error>>> = RefCell::new(None);
+    static LAST_ERROR_KIND: Cell<wasmer_error_kind_t> = Cell::new(wasmer_error_kind_t::WASMER_ERROR_KIND_NONE);
+    static LAST_ERROR_BACKTRACE: RefCell<Option<WasmBacktrace>> = RefCell::new(None);
+}
+
+/// The kind of the last error, as returned by `wasmer_last_error_kind()`.
+///
+/// It allows classifying the failure of a call without matching on
+/// the error message.
+#[allow(non_camel_case_types)]
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum wasmer_error_kind_t {
+    /// No error happened.
+    WASMER_ERROR_KIND_NONE = 0,
+    /// The error did not happen while running WebAssembly code, e.g.
+    /// a compilation error or an invalid argument.
+    WASMER_ERROR_KIND_OTHER = 1,
+    /// The gas limit was exceeded.
+    WASMER_ERROR_KIND_OUT_OF_GAS = 2,
+    /// The limits on `memory.grow` were exceeded.
+    WASMER_ERROR_KIND_MEMORY_GROW_LIMIT = 3,
+    /// A host function trapped, e.g. with `wasmer_trap()`.
+    WASMER_ERROR_KIND_USER_TRAP = 4,
+    /// The `unreachable` instruction was executed.
+    WASMER_ERROR_KIND_UNREACHABLE = 5,
+    /// A memory access was out of bounds.
+    WASMER_ERROR_KIND_MEMORY_OUT_OF_BOUNDS = 6,
+    /// An arithmetic operation was illegal, e.g. a division by zero.
+    WASMER_ERROR_KIND_ILLEGAL_ARITHMETIC = 7,
+    /// A `call_indirect` was out of bounds or had the wrong signature.
+    WASMER_ERROR_KIND_INDIRECT_CALL = 8,
+    /// An atomic access was misaligned.
+    WASMER_ERROR_KIND_MISALIGNED_ATOMIC_ACCESS = 9,
+    /// The native stack was exhausted.
+    WASMER_ERROR_KIND_STACK_OVERFLOW = 10,
+    /// A host function panicked.
+    WASMER_ERROR_KIND_HOST_PANIC = 11,
+    /// The WebAssembly code failed for an unknown reason.
+    WASMER_ERROR_KIND_UNKNOWN = 12,
+    /// The call depth limit was exceeded.
+    WASMER_ERROR_KIND_STACK_HEIGHT_LIMIT = 13,
+    /// A table access was out of bounds, e.g. by `table.copy` or `table.init`.
+    WASMER_ERROR_KIND_TABLE_OUT_OF_BOUNDS = 14,
+    /// The limits on `table.grow` were exceeded.
+    WASMER_ERROR_KIND_TABLE_GROW_LIMIT = 15,
+}
+
+impl From<RuntimeErrorKind> for wasmer_error_kind_t {
+    fn from(kind: RuntimeErrorKind) -> Self {
+        match kind {
+            RuntimeErrorKind::OutOfGas => wasmer_error_kind_t::WASMER_ERROR_KIND_OUT_OF_GAS,
+            RuntimeErrorKind::MemoryGrowLimit => {
+                wasmer_error_kind_t::WASMER_ERROR_KIND_MEMORY_GROW_LIMIT
+            }
+            RuntimeErrorKind::StackHeightLimit => {
+                wasmer_error_kind_t::WASMER_ERROR_KIND_STACK_HEIGHT_LIMIT
+            }
+            RuntimeErrorKind::TableGrowLimit => {
+                wasmer_error_kind_t::WASMER_ERROR_KIND_TABLE_GROW_LIMIT
+            }
+            RuntimeErrorKind::UserTrap => wasmer_error_kind_t::WASMER_ERROR_KIND_USER_TRAP,
+            RuntimeErrorKind::WasmTrap(exc_code) => match exc_code {
+                ExceptionCode::Unreachable => wasmer_error_kind_t::WASMER_ERROR_KIND_UNREACHABLE,
+                ExceptionCode::MemoryOutOfBounds => {
+                    wasmer_error_kind_t::WASMER_ERROR_KIND_MEMORY_OUT_OF_BOUNDS
+                }
+                ExceptionCode::IllegalArithmetic => {
+                    wasmer_error_kind_t::WASMER_ERROR_KIND_ILLEGAL_ARITHMETIC
+                }
+                ExceptionCode::IncorrectCallIndirectSignature | ExceptionCode::CallIndirectOOB => {
+                    wasmer_error_kind_t::WASMER_ERROR_KIND_INDIRECT_CALL
+                }
+                ExceptionCode::MisalignedAtomicAccess => {
+                    wasmer_error_kind_t::WASMER_ERROR_KIND_MISALIGNED_ATOMIC_ACCESS
+                }
+                ExceptionCode::StackOverflow => {
+                    wasmer_error_kind_t::WASMER_ERROR_KIND_STACK_OVERFLOW
+                }
+                ExceptionCode::TableOutOfBounds => {
+                    wasmer_error_kind_t::WASMER_ERROR_KIND_TABLE_OUT_OF_BOUNDS
+                }
+            },
+            RuntimeErrorKind::HostPanic => wasmer_error_kind_t::WASMER_ERROR_KIND_HOST_PANIC,
+            RuntimeErrorKind::Unknown => wasmer_error_kind_t::WASMER_ERROR_KIND_UNKNOWN,
+        }
+    }
 }
 
 pub fn update_last_error<E: Error + 'static>(err: E) {
+    let runtime_error = get_runtime_error(&err);
+    set_last_error_kind(
+        runtime_error
+            .map(|runtime_error| runtime_error.kind().into())
+            .unwrap_or(wasmer_error_kind_t::WASMER_ERROR_KIND_OTHER),
+    );
+    LAST_ERROR_BACKTRACE.with(|prev| {
+        *prev.borrow_mut() = runtime_error.and_then(|_| get_last_trap_backtrace());
+    });
     LAST_ERROR.with(|prev| {
         *prev.borrow_mut() = Some(Box::new(err));
     });
 }
 
+/// Overrides the kind of the last error, when the caller knows better.
+pub(crate) fn set_last_error_kind(kind: wasmer_error_kind_t) {
+    LAST_ERROR_KIND.with(|prev| prev.set(kind));
+}
+
+fn get_runtime_error(err: &dyn Any) -> Option<&RuntimeError> {
+    if let Some(CallError::Runtime(runtime_error)) = err.downcast_ref() {
+        Some(runtime_error)
+    } else if let Some(RuntimeCoreError::RuntimeError(runtime_error)) = err.downcast_ref() {
+        Some(runtime_error)
+    } else {
+        err.downcast_ref::<RuntimeError>()
+    }
+}
+
 /// Retrieve the most recent error, clearing it in the process.
 pub(crate) fn take_last_error() -> Option<Box<dyn Error>> {
     LAST_ERROR.with(|prev| prev.borrow_mut().take())
 }
 
+/// Gets the kind of the last error, or `WASMER_ERROR_KIND_NONE` if
+/// no error happened yet.
+///
+/// Unlike `wasmer_last_error_message()`, reading the kind does not
+/// clear the last error.
+///
+/// Example:
+///
+/// ```c
+/// wasmer_result_t call_result = wasmer_instance_call(instance, "sum", params, 2, results, 1);
+///
+/// if (call_result != WASMER_OK && wasmer_last_error_kind() == WASMER_ERROR_KIND_OUT_OF_GAS) {
+///     // Handle the exhausted gas.
+/// }
+/// ```
+#[no_mangle]
+pub extern "C" fn wasmer_last_error_kind() -> wasmer_error_kind_t {
+    LAST_ERROR_KIND.with(|prev| prev.get())
+}
+
+/// A frame of the backtrace of the last error, see `wasmer_last_error_backtrace_frame()`.
+#[repr(C)]
+pub struct wasmer_backtrace_frame_t {
+    /// The index of the function, imported functions included.
+    pub function_index: u32,
+    /// The index of the current operator in the function body.
+    pub operator_index: u32,
+    /// The name of the function from the name section, empty if it has none. It stays valid
+    /// until the next error.
+    pub function_name: wasmer_byte_array,
+}
+
+/// Gets the number of wasm frames of the backtrace of the last error, innermost first.
+///
+/// It is 0 if the last error is not a trap of the WebAssembly code, or if its frames are not
+/// known. Like `wasmer_last_error_kind()`, reading the backtrace does not clear the last error.
+#[no_mangle]
+pub extern "C" fn wasmer_last_error_backtrace_length() -> c_int {
+    LAST_ERROR_BACKTRACE.with(|prev| match *prev.borrow() {
+        Some(ref backtrace) => backtrace.frames.len() as c_int,
+        None => 0,
+    })
+}
+
+/// Gets the frame at `index` of the backtrace of the last error into `frame`.
+///
+/// Returns `wasmer_result_t::WASMER_ERROR` if `frame` is null or `index` is not lower than
+/// `wasmer_last_error_backtrace_length()`.
+///
+/// Example:
+///
+/// ```c
+/// wasmer_backtrace_frame_t frame;
+///
+/// for (int i = 0; i < wasmer_last_error_backtrace_length(); ++i) {
+///     wasmer_last_error_backtrace_frame(i, &frame);
+///     fprintf(stderr, "%d: %.*s (function %u) @ operator %u\n", i,
+///             frame.function_name.bytes_len, frame.function_name.bytes,
+///             frame.function_index, frame.operator_index);
+/// }
+/// ```
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_last_error_backtrace_frame(
+    index: c_int,
+    frame: *mut wasmer_backtrace_frame_t,
+) -> wasmer_result_t {
+    if frame.is_null() || index < 0 {
+        return wasmer_result_t::WASMER_ERROR;
+    }
+
+    LAST_ERROR_BACKTRACE.with(|prev| {
+        let prev = prev.borrow();
+        let wasm_frame = match prev
+            .as_ref()
+            .and_then(|backtrace| backtrace.frames.get(index as usize))
+        {
+            Some(wasm_frame) => wasm_frame,
+            None => return wasmer_result_t::WASMER_ERROR,
+        };
+        let function_name = wasm_frame.function_name.as_ref().map_or("", String::as_str);
+        *frame = wasmer_backtrace_frame_t {
+            function_index: wasm_frame.function_index.index() as u32,
+            operator_index: wasm_frame.operator_index as u32,
+            function_name: wasmer_byte_array {
+                bytes: function_name.as_ptr(),
+                bytes_len: function_name.len() as u32,
+            },
+        };
+        wasmer_result_t::WASMER_OK
+    })
+}
+
 /// Gets the length in bytes of the last error if any.
 ///
 /// This can be used to dynamically allocate a buffer with the correct number of
@@ -8,7 +8,7 @@ use crate::{
     memory::wasmer_memory_t,
     module::wasmer_module_t,
     table::wasmer_table_t,
-    value::{wasmer_value, wasmer_value_t, wasmer_value_tag},
+    value::{wasmer_value_t, wasmer_value_tag},
     wasmer_byte_array, wasmer_result_t,
 };
 use libc::{c_int, c_uint};
@@ -436,7 +436,8 @@ pub unsafe extern "C" fn wasmer_export_name(export: *mut wasmer_export_t) -> was
 }
 
 /// Calls a `func` with the provided parameters.
-/// Results are set using the provided `results` pointer.
+/// Results are set using the provided `results` pointer, which must hold at least as many
+/// values as the function returns.
 ///
 /// Returns `wasmer_result_t::WASMER_OK` upon success.
 ///
@@ -485,28 +486,19 @@ pub unsafe extern "C" fn wasmer_export_func_call(
     let result = instance.call(&named_export.name, &params[..]);
 
     match result {
+        Ok(results_vec) if results_vec.len() > results.len() => {
+            update_last_error(CApiError {
+                msg: format!(
+                    "the function returned {} results, but results_len is {}",
+                    results_vec.len(),
+                    results.len()
+                ),
+            });
+            wasmer_result_t::WASMER_ERROR
+        }
         Ok(results_vec) => {
-            if !results_vec.is_empty() {
-                let ret = match results_vec[0] {
-                    Value::I32(x) => wasmer_value_t {
-                        tag: wasmer_value_tag::WASM_I32,
-                        value: wasmer_value { I32: x },
-                    },
-                    Value::I64(x) => wasmer_value_t {
-                        tag: wasmer_value_tag::WASM_I64,
-                        value: wasmer_value { I64: x },
-                    },
-                    Value::F32(x) => wasmer_value_t {
-                        tag: wasmer_value_tag::WASM_F32,
-                        value: wasmer_value { F32: x },
-                    },
-                    Value::F64(x) => wasmer_value_t {
-                        tag: wasmer_value_tag::WASM_F64,
-                        value: wasmer_value { F64: x },
-                    },
-                    Value::V128(_) => unimplemented!("returning V128 type"),
-                };
-                results[0] = ret;
+            for (result, value) in results.iter_mut().zip(results_vec) {
+                *result = value.into();
             }
             wasmer_result_t::WASMER_OK
         }
@@ -1,7 +1,11 @@
 //! Create, set, get and destroy global variables of an instance.
 
-use crate::value::{wasmer_value_t, wasmer_value_tag};
-use wasmer_runtime::Global;
+use crate::{
+    error::{update_last_error, CApiError},
+    value::{wasmer_value_t, wasmer_value_tag},
+};
+use std::ptr;
+use wasmer_runtime::{Global, Value};
 
 #[repr(C)]
 #[derive(Clone)]
@@ -16,15 +20,26 @@ pub struct wasmer_global_t;
 
 /// Creates a new Global and returns a pointer to it.
 /// The caller owns the object and should call `wasmer_global_destroy` to free it.
+///
+/// Function references are opaque to the host: a null pointer is returned if `value` is a
+/// function reference other than the null one.
 #[no_mangle]
 pub unsafe extern "C" fn wasmer_global_new(
     value: wasmer_value_t,
     mutable: bool,
 ) -> *mut wasmer_global_t {
+    let value: Value = value.into();
+    if !value.is_valid_from_host() {
+        update_last_error(CApiError {
+            msg: "only the null function reference can be passed by the host".to_string(),
+        });
+        return ptr::null_mut();
+    }
+
     let global = if mutable {
-        Global::new_mutable(value.into())
+        Global::new_mutable(value)
     } else {
-        Global::new(value.into())
+        Global::new(value)
     };
     Box::into_raw(Box::new(global)) as *mut wasmer_global_t
 }
@@ -39,11 +54,22 @@ pub extern "C" fn wasmer_global_get(global: *mut wasmer_global_t) -> wasmer_valu
 }
 
 /// Sets the value stored by the given Global
+///
+/// Function references are opaque to the host: the Global is left unchanged if `value` is a
+/// function reference other than the null one.
 #[allow(clippy::cast_ptr_alignment)]
 #[no_mangle]
 pub extern "C" fn wasmer_global_set(global: *mut wasmer_global_t, value: wasmer_value_t) {
     let global = unsafe { &*(global as *mut Global) };
-    global.set(value.into());
+    let value: Value = value.into();
+    if !value.is_valid_from_host() {
+        update_last_error(CApiError {
+            msg: "only the null function reference can be passed by the host".to_string(),
+        });
+        return;
+    }
+
+    global.set(value);
 }
 
 /// Returns a descriptor (type, mutability) of the given Global
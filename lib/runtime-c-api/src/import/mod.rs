@@ -15,11 +15,12 @@ use std::{
     ffi::{c_void, CStr},
     os::raw::c_char,
     ptr, slice,
-    sync::Arc,
+    sync::{Arc, RwLock},
     result::Result,
 };
 use wasmer_runtime::{Ctx, Global, Memory, Module, Table};
 use wasmer_runtime_core::{
+    error::{RuntimeErrorKind, Trap},
     export::{Context, Export, FuncPointer},
     import::{ImportObject, Namespace, ImportObjectIterator},
     module::ImportName,
@@ -32,7 +33,47 @@ pub enum ImportError {
     ImportNameError,
 }
 
-pub static mut GLOBAL_IMPORT_OBJECT: *mut ImportObject = 0 as *mut ImportObject;
+lazy_static! {
+    /// The import object used when none is given explicitly, set by
+    /// `wasmer_import_object_cache_from_imports()`.
+    static ref GLOBAL_IMPORT_OBJECT: RwLock<Option<Arc<ImportObject>>> = RwLock::new(None);
+}
+
+/// Returns the import object set by `wasmer_import_object_cache_from_imports()`, or an empty
+/// one if none was set.
+pub fn get_global_import_object() -> Arc<ImportObject> {
+    match &*GLOBAL_IMPORT_OBJECT.read().unwrap() {
+        Some(import_object) => Arc::clone(import_object),
+        None => Arc::new(ImportObject::new()),
+    }
+}
+
+/// Calls `f` with the given import object, or with the global one if `import_object` is null.
+pub unsafe fn with_import_object<T>(
+    import_object: *const wasmer_import_object_t,
+    f: impl FnOnce(&ImportObject) -> T,
+) -> T {
+    if import_object.is_null() {
+        f(&get_global_import_object())
+    } else {
+        f(&*(import_object as *const ImportObject))
+    }
+}
+
+/// Returns a new reference to the namespaces of the given import object, for callers that keep
+/// resolving imports after it may have been destroyed, or `None` if `import_object` is null.
+pub unsafe fn clone_import_object(
+    import_object: *const wasmer_import_object_t,
+) -> Option<ImportObject> {
+    if import_object.is_null() {
+        return None;
+    }
+
+    let import_object = &*(import_object as *const ImportObject);
+    let mut clone = import_object.clone_ref();
+    clone.allow_missing_functions = import_object.allow_missing_functions;
+    Some(clone)
+}
 
 #[repr(C)]
 pub struct wasmer_import_t {
@@ -71,6 +112,12 @@ pub unsafe extern "C" fn wasmer_import_object_new() -> *mut wasmer_import_object
     Box::into_raw(import_object) as *mut wasmer_import_object_t
 }
 
+/// Sets the default import object, used by `wasmer_instantiate_with_options()` and
+/// `wasmer_instance_from_cache()`, and by the variants of these functions when they are given a
+/// null import object.
+///
+/// It is safe to call this function while other threads are instantiating: they keep using the
+/// previous import object.
 #[allow(clippy::cast_ptr_alignment)]
 #[no_mangle]
 pub unsafe extern "C" fn wasmer_import_object_cache_from_imports(
@@ -90,11 +137,8 @@ pub unsafe extern "C" fn wasmer_import_object_cache_from_imports(
         Ok(created_imports_object) => created_imports_object
     };
 
-    if GLOBAL_IMPORT_OBJECT != (0 as *mut ImportObject) {
-        let _ = Box::from_raw(GLOBAL_IMPORT_OBJECT); // deallocate previous GLOBAL_IMPORT_OBJECT
-    }
-
-    GLOBAL_IMPORT_OBJECT = Box::into_raw(Box::new(import_object));
+    // The previous import object is deallocated once no instantiation uses it anymore.
+    *GLOBAL_IMPORT_OBJECT.write().unwrap() = Some(Arc::new(import_object));
     return wasmer_result_t::WASMER_OK
 }
 
@@ -755,6 +799,10 @@ pub unsafe extern "C" fn wasmer_import_func_params_arity(
 /// The function pointer must have a lifetime greater than the
 /// WebAssembly instance lifetime.
 ///
+/// Function references are opaque to the host, so a function cannot
+/// return `funcref`: a null pointer is returned if `returns` contains
+/// `WASM_FUNCREF`.
+///
 /// The caller owns the object and should call
 /// `wasmer_import_func_destroy` to free it.
 #[no_mangle]
@@ -770,6 +818,12 @@ pub unsafe extern "C" fn wasmer_import_func_new(
     let params: Vec<Type> = params.iter().cloned().map(|x| x.into()).collect();
     let returns: &[wasmer_value_tag] = slice::from_raw_parts(returns, returns_len as usize);
     let returns: Vec<Type> = returns.iter().cloned().map(|x| x.into()).collect();
+    if returns.contains(&Type::FuncRef) {
+        update_last_error(CApiError {
+            msg: "host functions cannot return function references".to_string(),
+        });
+        return ptr::null_mut();
+    }
 
     let export = Box::new(Export::Function {
         func: FuncPointer::new(func as _),
@@ -820,7 +874,7 @@ pub unsafe extern "C" fn wasmer_trap(
 
     (&*ctx.module)
         .runnable_module
-        .do_early_trap(Box::new(error_message)); // never returns
+        .do_early_trap(Box::new(Trap::new(RuntimeErrorKind::UserTrap, error_message))); // never returns
 
     // cbindgen does not generate a binding for a function that
     // returns `!`. Since we also need to error in some cases, the
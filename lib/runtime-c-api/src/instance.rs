@@ -1,31 +1,45 @@
 //! Instantiate a module, call functions, and read exports.
 
 use crate::{
-    error::{update_last_error, CApiError},
+    error::{
+        set_last_error_kind, update_last_error, wasmer_error_kind_t, wasmer_last_error_kind,
+        CApiError,
+    },
     export::{wasmer_exports_t, wasmer_import_export_kind, NamedExport, NamedExports},
-    import::{wasmer_import_t, GLOBAL_IMPORT_OBJECT},
+    import::{wasmer_import_object_t, wasmer_import_t, with_import_object},
     memory::wasmer_memory_t,
-    value::{wasmer_value, wasmer_value_t, wasmer_value_tag},
-    wasmer_result_t,
+    opcode_trace::{wasmer_opcode_trace_buffer_t, wasmer_opcode_trace_format_t},
+    value::wasmer_value_t,
+    wasmer_byte_array, wasmer_result_t,
 };
 use libc::{c_char, c_int, c_void};
-use std::{collections::HashMap, ffi::CStr, ptr, slice};
-use wasmer_runtime::{Ctx, Global, Instance, Memory, Table, Value};
+use std::{collections::HashMap, convert::TryFrom, ffi::CStr, path::PathBuf, ptr, slice};
+use wasmer_runtime::{Ctx, Global, Instance, Memory, Module, Table, Value};
 use wasmer_runtime_core::{
+    cache::CompilationFingerprint,
+    error::{CompileError, CompileResult},
     export::Export,
     import::{ImportObject, Namespace},
+    structures::TypedIndex,
+    types::FuncIndex,
 };
 
-use crate::metering::OPCODE_COSTS;
-use wasmer_runtime_core::backend::Compiler;
+use crate::metering::{get_opcode_costs, wasmer_opcode_costs_t};
+use wasmer_runtime_core::backend::{Compiler, CompilerConfig, Features, MemoryBoundCheckMode};
 use wasmer_runtime_core::codegen::{MiddlewareChain, StreamingCompiler};
 
 #[cfg(not(feature = "cranelift-backend"))]
 use wasmer_middleware_common::metering;
 
-use wasmer_middleware_common::opcode_control::{self, reset_memory_grow_count};
-use wasmer_middleware_common::opcode_trace;
-use wasmer_middleware_common::runtime_breakpoints;
+use wasmer_middleware_common::opcode_control::{
+    self, reset_memory_grow_count, reset_table_grow_count,
+};
+use wasmer_middleware_common::opcode_trace::{self, OpcodeTraceBuffer};
+use wasmer_middleware_common::runtime_breakpoints::{
+    self, get_breakpoint_kind, get_runtime_breakpoint_value, set_runtime_breakpoint_value,
+    BREAKPOINT_VALUE_NO_BREAKPOINT,
+};
+use wasmer_middleware_common::stack_height::{self, reset_stack_height};
 
 /// Opaque pointer to a `wasmer_runtime::Instance` value in Rust.
 ///
@@ -189,22 +203,113 @@ pub unsafe extern "C" fn wasmer_instantiate(
     wasmer_result_t::WASMER_OK
 }
 
-#[repr(C)]
-pub struct wasmer_import_object_t;
-
+/// The C view of `CompilationOptions`, whose fields are declared in the headers.
 #[repr(C)]
 pub struct wasmer_compilation_options_t;
 
+/// How the memory accesses are bound checked, as set in the compilation options. Compilation
+/// fails for any other value.
+#[allow(non_camel_case_types)]
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum wasmer_memory_bound_check_mode_t {
+    /// Let the backend decide, usually relying on guard pages for static memories.
+    WASMER_MEMORY_BOUND_CHECK_MODE_DEFAULT = 0,
+    /// Always emit explicit bound checks.
+    WASMER_MEMORY_BOUND_CHECK_MODE_ENABLE = 1,
+    /// Never emit explicit bound checks for the memories protected by guard pages; dynamic
+    /// memories are still checked.
+    WASMER_MEMORY_BOUND_CHECK_MODE_DISABLE = 2,
+}
+
+impl TryFrom<u32> for wasmer_memory_bound_check_mode_t {
+    type Error = String;
+
+    fn try_from(mode: u32) -> Result<Self, Self::Error> {
+        match mode {
+            0 => Ok(wasmer_memory_bound_check_mode_t::WASMER_MEMORY_BOUND_CHECK_MODE_DEFAULT),
+            1 => Ok(wasmer_memory_bound_check_mode_t::WASMER_MEMORY_BOUND_CHECK_MODE_ENABLE),
+            2 => Ok(wasmer_memory_bound_check_mode_t::WASMER_MEMORY_BOUND_CHECK_MODE_DISABLE),
+            _ => Err(format!("invalid memory bound check mode: {}", mode)),
+        }
+    }
+}
+
+impl From<wasmer_memory_bound_check_mode_t> for MemoryBoundCheckMode {
+    fn from(mode: wasmer_memory_bound_check_mode_t) -> Self {
+        match mode {
+            wasmer_memory_bound_check_mode_t::WASMER_MEMORY_BOUND_CHECK_MODE_DEFAULT => {
+                MemoryBoundCheckMode::Default
+            }
+            wasmer_memory_bound_check_mode_t::WASMER_MEMORY_BOUND_CHECK_MODE_ENABLE => {
+                MemoryBoundCheckMode::Enable
+            }
+            wasmer_memory_bound_check_mode_t::WASMER_MEMORY_BOUND_CHECK_MODE_DISABLE => {
+                MemoryBoundCheckMode::Disable
+            }
+        }
+    }
+}
+
+/// The options a module is compiled with, filled in by C hosts as a
+/// `wasmer_compilation_options_t`, hence the layout must match the one declared in the headers.
+#[repr(C)]
 pub struct CompilationOptions {
+    /// The number of points each instance may use, when metered.
     pub gas_limit: u64,
+    /// The number of locals of each function that are not charged for.
     pub unmetered_locals: usize,
+    /// The number of `memory.grow` executions allowed between two resets of an instance.
     pub max_memory_grow: usize,
+    /// The largest delta accepted by a single `memory.grow`.
     pub max_memory_grow_delta: usize,
+    /// Whether the operators are written to a trace file while compiling, see
+    /// `opcode_trace_path` and `opcode_trace_format`.
     pub opcode_trace: bool,
+    /// Whether the executed operators are charged for, following `opcode_costs`.
     pub metering: bool,
+    /// Whether the runtime breakpoints, which the gas and grow limits rely on, are enabled.
     pub runtime_breakpoints: bool,
+    /// The cost table used for metering; the default one set by `wasmer_set_opcode_costs()`
+    /// is used when null.
+    pub opcode_costs: *const wasmer_opcode_costs_t,
+    /// The call depth limit enforced by the stack height middleware, which is left out when 0.
+    pub max_stack_height: usize,
+    /// The number of `table.grow` executions allowed between two resets of an instance.
+    pub max_table_grow: usize,
+    /// The largest delta accepted by a single `table.grow`.
+    pub max_table_grow_delta: usize,
+    /// Whether float operators are accepted, with their NaN results canonicalized so that they
+    /// are bit-exact across CPUs.
+    pub nan_canonicalization: bool,
+    /// Whether the SIMD proposal is accepted.
+    pub simd: bool,
+    /// Whether the threads proposal is accepted.
+    pub threads: bool,
+    /// Whether the bulk memory proposal is accepted.
+    pub bulk_memory: bool,
+    /// Whether the multi-value proposal is accepted.
+    pub multi_value: bool,
+    /// Whether the reference types proposal is accepted.
+    pub reference_types: bool,
+    /// How the memory accesses are bound checked, as a `wasmer_memory_bound_check_mode_t`.
+    pub memory_bound_check_mode: u32,
+    /// The trace file written when `opcode_trace` is set, as a null-terminated UTF-8 path;
+    /// `opcode.trace` in the working directory when null.
+    pub opcode_trace_path: *const c_char,
+    /// The format of the trace file, as a `wasmer_opcode_trace_format_t`.
+    pub opcode_trace_format: u32,
+    /// The buffer recording the operators executed at runtime, unless null.
+    pub opcode_trace_buffer: *const wasmer_opcode_trace_buffer_t,
+    /// Whether the location of the last operator executed is tracked, see
+    /// `wasmer_instance_last_location()`.
+    pub last_location: bool,
 }
 
+/// Creates a new WebAssembly instance from the given bytes, compiled with the given options,
+/// using the default import object set by `wasmer_import_object_cache_from_imports()`.
+///
+/// See `wasmer_instantiate_with_options_and_import_object()` to use another import object.
 #[allow(clippy::cast_ptr_alignment)]
 #[cfg(feature = "metering")]
 #[no_mangle]
@@ -213,6 +318,35 @@ pub unsafe extern "C" fn wasmer_instantiate_with_options(
     wasm_bytes: *mut u8,
     wasm_bytes_len: u32,
     options: *const wasmer_compilation_options_t,
+) -> wasmer_result_t {
+    wasmer_instantiate_with_options_and_import_object(
+        instance,
+        wasm_bytes,
+        wasm_bytes_len,
+        options,
+        ptr::null(),
+    )
+}
+
+/// Creates a new WebAssembly instance from the given bytes, compiled with the given options,
+/// resolving its imports against `import_object`.
+///
+/// The default import object set by `wasmer_import_object_cache_from_imports()` is used if
+/// `import_object` is null.
+///
+/// Returns `wasmer_result_t::WASMER_OK` upon success.
+///
+/// Returns `wasmer_result_t::WASMER_ERROR` upon failure. Use `wasmer_last_error_length`
+/// and `wasmer_last_error_message` to get an error message.
+#[allow(clippy::cast_ptr_alignment)]
+#[cfg(feature = "metering")]
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_instantiate_with_options_and_import_object(
+    instance: *mut *mut wasmer_instance_t,
+    wasm_bytes: *mut u8,
+    wasm_bytes_len: u32,
+    options: *const wasmer_compilation_options_t,
+    import_object: *const wasmer_import_object_t,
 ) -> wasmer_result_t {
     if wasm_bytes.is_null() {
         update_last_error(CApiError {
@@ -223,9 +357,7 @@ pub unsafe extern "C" fn wasmer_instantiate_with_options(
 
     let bytes: &[u8] = slice::from_raw_parts_mut(wasm_bytes, wasm_bytes_len as usize);
     let options: &CompilationOptions = &*(options as *const CompilationOptions);
-    let compiler_chain_generator = prepare_middleware_chain_generator(&options);
-    let compiler = get_compiler(compiler_chain_generator);
-    let result_compilation = wasmer_runtime_core::compile_with(bytes, &compiler);
+    let result_compilation = compile_with_options(bytes, options);
     let new_module = match result_compilation {
         Ok(module) => module,
         Err(_) => {
@@ -236,8 +368,9 @@ pub unsafe extern "C" fn wasmer_instantiate_with_options(
         }
     };
 
-    let import_object: &mut ImportObject = &mut *(GLOBAL_IMPORT_OBJECT as *mut ImportObject);
-    let result_instantiation = new_module.instantiate(&import_object);
+    let result_instantiation = with_import_object(import_object, |import_object| {
+        new_module.instantiate(import_object)
+    });
     let mut new_instance = match result_instantiation {
         Ok(instance) => instance,
         Err(error) => {
@@ -250,7 +383,21 @@ pub unsafe extern "C" fn wasmer_instantiate_with_options(
     wasmer_result_t::WASMER_OK
 }
 
-/// Reset an WebAssembly instance, cleaning memories and globals
+/// Compiles the given wasm bytes with the middlewares and settings described by `options`.
+pub unsafe fn compile_with_options(
+    bytes: &[u8],
+    options: &CompilationOptions,
+) -> CompileResult<Module> {
+    let compiler_chain_generator = prepare_middleware_chain_generator(options)?;
+    let compiler = get_compiler(compiler_chain_generator);
+    let compiler_config = prepare_compiler_config(options)?;
+    wasmer_runtime_core::compile_with_config(bytes, &compiler, compiler_config)
+}
+
+/// Reset an WebAssembly instance, cleaning memories, tables and globals.
+///
+/// If `wasmer_instance_snapshot_memories()` was called on the instance, the memories are
+/// restored from the snapshot, which only copies back the pages written since.
 #[allow(clippy::cast_ptr_alignment)]
 #[no_mangle]
 pub unsafe extern "C" fn wasmer_instance_reset(
@@ -273,14 +420,137 @@ pub unsafe extern "C" fn wasmer_instance_reset(
     }
 
     reset_memory_grow_count(instance);
+    reset_table_grow_count(instance);
+    reset_stack_height(instance);
+
+    wasmer_result_t::WASMER_OK
+}
+
+/// Take a copy-on-write snapshot of the memories of an WebAssembly instance, which
+/// `wasmer_instance_reset()` restores afterwards. It is meant to be called right after
+/// instantiation.
+///
+/// Returns `wasmer_result_t::WASMER_ERROR` if the instance is null or if one of its memories
+/// cannot be snapshotted, e.g. because it is a static memory.
+#[allow(clippy::cast_ptr_alignment)]
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_instance_snapshot_memories(
+    instance: *mut wasmer_instance_t,
+) -> wasmer_result_t {
+    if instance.is_null() {
+        update_last_error(CApiError {
+            msg: "null instance".to_string(),
+        });
+        return wasmer_result_t::WASMER_ERROR;
+    }
+
+    let instance = &mut *(instance as *mut Instance);
+
+    if let Err(error) = instance.snapshot_memories() {
+        update_last_error(CApiError {
+            msg: error.to_string(),
+        });
+        return wasmer_result_t::WASMER_ERROR;
+    }
 
     wasmer_result_t::WASMER_OK
 }
 
+/// Returns the version of the gas schedule the given options compile with, if metered.
+pub unsafe fn get_gas_schedule_version(options: &CompilationOptions) -> Option<u32> {
+    if options.metering {
+        Some(get_opcode_costs(options.opcode_costs).version())
+    } else {
+        None
+    }
+}
+
+/// Hashes every option that shapes the generated code, including the opcode costs used for
+/// metering. The gas limit is left out, since it is set on each instance.
+pub unsafe fn get_compilation_fingerprint(options: &CompilationOptions) -> CompilationFingerprint {
+    let mut settings: Vec<u8> = Vec::new();
+    settings.extend_from_slice(&(options.unmetered_locals as u64).to_le_bytes());
+    settings.extend_from_slice(&(options.max_memory_grow as u64).to_le_bytes());
+    settings.extend_from_slice(&(options.max_memory_grow_delta as u64).to_le_bytes());
+    settings.push(options.opcode_trace as u8);
+    settings.push(options.metering as u8);
+    settings.push(options.runtime_breakpoints as u8);
+    settings.extend_from_slice(&(options.max_stack_height as u64).to_le_bytes());
+    settings.extend_from_slice(&(options.max_table_grow as u64).to_le_bytes());
+    settings.extend_from_slice(&(options.max_table_grow_delta as u64).to_le_bytes());
+    settings.push(options.nan_canonicalization as u8);
+    settings.push(options.simd as u8);
+    settings.push(options.threads as u8);
+    settings.push(options.bulk_memory as u8);
+    settings.push(options.multi_value as u8);
+    settings.push(options.reference_types as u8);
+    settings.extend_from_slice(&options.memory_bound_check_mode.to_le_bytes());
+    settings.extend_from_slice(&options.opcode_trace_format.to_le_bytes());
+    // The runtime buffer is baked into the generated code, so only the same buffer matches.
+    settings.extend_from_slice(&(options.opcode_trace_buffer as usize as u64).to_le_bytes());
+    settings.push(options.last_location as u8);
+
+    if options.metering {
+        let opcode_costs = get_opcode_costs(options.opcode_costs);
+        settings.extend_from_slice(&opcode_costs.version().to_le_bytes());
+        for cost in opcode_costs.as_slice() {
+            settings.extend_from_slice(&cost.to_le_bytes());
+        }
+    }
+
+    CompilationFingerprint::generate(&settings)
+}
+
+/// Returns the WebAssembly proposals accepted by the given options.
+pub fn get_features(options: &CompilationOptions) -> Features {
+    Features {
+        simd: options.simd,
+        threads: options.threads,
+        bulk_memory: options.bulk_memory,
+        multi_value: options.multi_value,
+        reference_types: options.reference_types,
+    }
+}
+
+pub unsafe fn prepare_compiler_config(
+    options: &CompilationOptions,
+) -> CompileResult<CompilerConfig> {
+    let memory_bound_check_mode =
+        wasmer_memory_bound_check_mode_t::try_from(options.memory_bound_check_mode)
+            .map_err(|msg| CompileError::InternalError { msg })?;
+
+    Ok(CompilerConfig {
+        gas_schedule_version: get_gas_schedule_version(options),
+        fingerprint: get_compilation_fingerprint(options),
+        nan_canonicalization: options.nan_canonicalization,
+        features: get_features(options),
+        memory_bound_check_mode: memory_bound_check_mode.into(),
+        ..Default::default()
+    })
+}
+
 pub unsafe fn prepare_middleware_chain_generator(
     options: &CompilationOptions,
-) -> impl Fn() -> MiddlewareChain + '_ {
+) -> CompileResult<impl Fn() -> MiddlewareChain + '_> {
     let options = options.clone();
+    let opcode_costs = get_opcode_costs(options.opcode_costs);
+    let opcode_trace_format = wasmer_opcode_trace_format_t::try_from(options.opcode_trace_format)
+        .map_err(|msg| CompileError::InternalError { msg })?;
+    let opcode_trace_path = if options.opcode_trace_path.is_null() {
+        PathBuf::from("opcode.trace")
+    } else {
+        let path = CStr::from_ptr(options.opcode_trace_path)
+            .to_str()
+            .map_err(|_| CompileError::InternalError {
+                msg: "opcode trace path is not valid UTF-8".to_string(),
+            })?;
+        PathBuf::from(path)
+    };
+    let opcode_trace_buffer = if options.opcode_trace_buffer.is_null() {
+        None
+    } else {
+        Some((*(options.opcode_trace_buffer as *const OpcodeTraceBuffer)).clone())
+    };
 
     let chain_generator = move || {
         let mut chain = MiddlewareChain::new();
@@ -288,7 +558,7 @@ pub unsafe fn prepare_middleware_chain_generator(
         if options.metering {
             #[cfg(feature = "metering")]
             chain.push(metering::Metering::new(
-                &OPCODE_COSTS,
+                opcode_costs.clone(),
                 options.unmetered_locals,
             ));
         }
@@ -296,22 +566,41 @@ pub unsafe fn prepare_middleware_chain_generator(
         chain.push(opcode_control::OpcodeControl::new(
             options.max_memory_grow,
             options.max_memory_grow_delta,
+            options.max_table_grow,
+            options.max_table_grow_delta,
         ));
 
-        // The RuntimeBreakpointHandler must be the last middleware in the chain (OpcodeTracer is
-        // an exception since it does not alter the opcodes meaningfully.
+        if options.max_stack_height > 0 {
+            chain.push(stack_height::StackHeight::new(options.max_stack_height));
+        }
+
+        // The RuntimeBreakpointHandler must be the last middleware in the chain (OpcodeTracer and
+        // LastLocationTracker are exceptions since they do not alter the opcodes meaningfully).
         if options.runtime_breakpoints {
             chain.push(runtime_breakpoints::RuntimeBreakpointHandler::new());
         }
 
         if options.opcode_trace {
-            chain.push(opcode_trace::OpcodeTracer::new());
-        };
+            let tracer = opcode_trace::OpcodeTracer::with_path(
+                &opcode_trace_path,
+                opcode_trace_format.into(),
+            );
+            chain.push(match opcode_trace_buffer {
+                Some(ref buffer) => tracer.with_runtime_buffer(buffer.clone()),
+                None => tracer,
+            });
+        } else if let Some(ref buffer) = opcode_trace_buffer {
+            chain.push(opcode_trace::OpcodeTracer::runtime(buffer.clone()));
+        }
+
+        if options.last_location {
+            chain.push(opcode_trace::LastLocationTracker::new());
+        }
 
         chain
     };
 
-    chain_generator
+    Ok(chain_generator)
 }
 
 pub unsafe fn get_compiler(chain_generator: impl Fn() -> MiddlewareChain) -> impl Compiler {
@@ -387,7 +676,7 @@ pub unsafe extern "C" fn wasmer_instance_is_function_imported(
 
 /// Calls an exported function of a WebAssembly instance by `name`
 /// with the provided parameters. The exported function results are
-/// stored on the provided `results` pointer.
+/// stored on the provided `results` pointer, one value per result.
 ///
 /// This function returns `wasmer_result_t::WASMER_OK` upon success,
 /// `wasmer_result_t::WASMER_ERROR` otherwise. You can use
@@ -397,7 +686,8 @@ pub unsafe extern "C" fn wasmer_instance_is_function_imported(
 ///
 ///   * `instance` is a null pointer,
 ///   * `name` is a null pointer,
-///   * `params` is a null pointer.
+///   * `params` is a null pointer,
+///   * `results_len` is smaller than the number of results.
 ///
 /// Example of calling an exported function that needs two parameters, and returns one value:
 ///
@@ -477,60 +767,119 @@ pub unsafe extern "C" fn wasmer_instance_call(
     let results: &mut [wasmer_value_t] = slice::from_raw_parts_mut(results, results_len as usize);
     let instance = &mut *(instance as *mut Instance);
 
-    wasmer_middleware_common::opcode_trace::reset_opcodetracer_last_location(instance);
+    opcode_trace::reset_opcodetracer_last_location(instance);
+    // The value left by a previous call must not reclassify the errors of this one.
+    set_runtime_breakpoint_value(instance, BREAKPOINT_VALUE_NO_BREAKPOINT);
     let result = instance.call(func_name_r, &params[..]);
 
     let result = match result {
+        Ok(results_vec) if results_vec.len() > results.len() => {
+            update_last_error(CApiError {
+                msg: format!(
+                    "the function returned {} results, but results_len is {}",
+                    results_vec.len(),
+                    results.len()
+                ),
+            });
+            wasmer_result_t::WASMER_ERROR
+        }
         Ok(results_vec) => {
-            if !results_vec.is_empty() {
-                let ret = match results_vec[0] {
-                    Value::I32(x) => wasmer_value_t {
-                        tag: wasmer_value_tag::WASM_I32,
-                        value: wasmer_value { I32: x },
-                    },
-                    Value::I64(x) => wasmer_value_t {
-                        tag: wasmer_value_tag::WASM_I64,
-                        value: wasmer_value { I64: x },
-                    },
-                    Value::F32(x) => wasmer_value_t {
-                        tag: wasmer_value_tag::WASM_F32,
-                        value: wasmer_value { F32: x },
-                    },
-                    Value::F64(x) => wasmer_value_t {
-                        tag: wasmer_value_tag::WASM_F64,
-                        value: wasmer_value { F64: x },
-                    },
-                    Value::V128(_) => unimplemented!("calling function with V128 parameter"),
-                };
-                results[0] = ret;
+            for (result, value) in results.iter_mut().zip(results_vec) {
+                *result = value.into();
             }
             wasmer_result_t::WASMER_OK
         }
         Err(err) => {
             update_last_error(err);
+
+            // A breakpoint requested by the host is reported as a user trap; its value tells
+            // whether it actually stands for a more specific reason.
+            let breakpoint_value = get_runtime_breakpoint_value(instance);
+            if breakpoint_value != BREAKPOINT_VALUE_NO_BREAKPOINT
+                && wasmer_last_error_kind() == wasmer_error_kind_t::WASMER_ERROR_KIND_USER_TRAP
+            {
+                set_last_error_kind(get_breakpoint_kind(breakpoint_value).into());
+            }
+
+            // The functions aborted by the error never subtracted their frame costs.
+            reset_stack_height(instance);
+
             wasmer_result_t::WASMER_ERROR
         }
     };
 
-    let last_opcode_location =
-        wasmer_middleware_common::opcode_trace::get_opcodetracer_last_location(instance);
-    if last_opcode_location > 0 {
-        let imported_functions = instance.module.info.name_table.to_vec();
-        for i in 0..imported_functions.len() {
-            println!("Import {}\t{}", i, imported_functions[i]);
-        }
+    result
+}
 
-        for (k, v) in instance.module.info.exports.iter() {
-            println!("Export {:?}\t{}", v, k);
-        }
+/// The location of the last operator executed by an instance, see
+/// `wasmer_instance_last_location()`.
+#[repr(C)]
+pub struct wasmer_instance_location_t {
+    /// The index of the function, imported functions included.
+    pub function_index: u32,
+    /// The offset of the operator in the wasm binary.
+    pub operator_offset: u32,
+    /// The name of the function from the name section, empty if it has none. It is owned by
+    /// the instance.
+    pub function_name: wasmer_byte_array,
+}
 
-        println!(
-            "wasmer_instance_call OPCODE_LAST_LOCATION = {}",
-            last_opcode_location
-        );
+/// Gets the location of the last operator executed by the last `wasmer_instance_call()` on
+/// `instance`.
+///
+/// The instance must have been compiled with the `last_location` compilation option.
+///
+/// Returns `wasmer_result_t::WASMER_ERROR` if the location is unknown, e.g. because the option
+/// is not set, without touching the last error: the error of the failed call can still be read
+/// afterwards. Null pointers are reported through `wasmer_last_error_message` as usual.
+///
+/// Example:
+///
+/// ```c
+/// wasmer_instance_location_t location;
+///
+/// if (wasmer_instance_call(instance, "sum", params, 2, results, 1) != WASMER_OK &&
+///     wasmer_instance_last_location(instance, &location) == WASMER_OK) {
+///     fprintf(stderr, "trapped in function %u (%.*s) at offset %u\n",
+///             location.function_index, location.function_name.bytes_len,
+///             location.function_name.bytes, location.operator_offset);
+/// }
+/// ```
+#[allow(clippy::cast_ptr_alignment)]
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_instance_last_location(
+    instance: *const wasmer_instance_t,
+    location: *mut wasmer_instance_location_t,
+) -> wasmer_result_t {
+    if instance.is_null() || location.is_null() {
+        update_last_error(CApiError {
+            msg: "instance or location ptr is null".to_string(),
+        });
+        return wasmer_result_t::WASMER_ERROR;
     }
 
-    result
+    let instance = &*(instance as *const Instance);
+    let (local_function_index, operator_offset) =
+        match opcode_trace::get_opcodetracer_last_function_location(instance) {
+            Some(last_location) => last_location,
+            None => return wasmer_result_t::WASMER_ERROR,
+        };
+
+    let info = &instance.module.info;
+    let function_index = info.imported_functions.len() as u32 + local_function_index;
+    let function_name = info
+        .function_name(FuncIndex::new(function_index as usize))
+        .unwrap_or("");
+    *location = wasmer_instance_location_t {
+        function_index,
+        operator_offset,
+        function_name: wasmer_byte_array {
+            bytes: function_name.as_ptr(),
+            bytes_len: function_name.len() as u32,
+        },
+    };
+
+    wasmer_result_t::WASMER_OK
 }
 
 /// Gets all the exports of the given WebAssembly instance.
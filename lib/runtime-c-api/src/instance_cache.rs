@@ -1,6 +1,6 @@
 use crate::{
     error::{update_last_error, CApiError},
-    instance::{wasmer_instance_t, wasmer_compilation_options_t, CompilationOptions, prepare_middleware_chain_generator, get_compiler},
+    instance::{wasmer_instance_t, wasmer_compilation_options_t, CompilationOptions, prepare_middleware_chain_generator, get_compiler, get_compilation_fingerprint},
     wasmer_result_t,
 };
 
@@ -10,12 +10,9 @@ use rkyv::{
     ser::serializers::AllocSerializer,
 };
 
-use wasmer_runtime_core::{
-    cache::{Artifact, Error as CacheError},
-    import::ImportObject,
-};
-use std::slice;
-use crate::import::GLOBAL_IMPORT_OBJECT;
+use wasmer_runtime_core::cache::{Artifact, Error as CacheError};
+use std::{ptr, slice};
+use crate::import::{wasmer_import_object_t, with_import_object};
 
 #[cfg(not(feature = "cranelift-backend"))]
 use wasmer_middleware_common::metering;
@@ -89,6 +86,10 @@ pub unsafe extern "C" fn wasmer_instance_cache(
     wasmer_result_t::WASMER_OK
 }
 
+/// Creates a new WebAssembly instance from a cached artifact, using the default import object
+/// set by `wasmer_import_object_cache_from_imports()`.
+///
+/// See `wasmer_instance_from_cache_with_import_object()` to use another import object.
 #[allow(clippy::cast_ptr_alignment)]
 #[no_mangle]
 pub unsafe extern "C" fn wasmer_instance_from_cache(
@@ -96,6 +97,29 @@ pub unsafe extern "C" fn wasmer_instance_from_cache(
     cache_bytes: *mut u8,
     cache_len: u32,
     options: *const wasmer_compilation_options_t,
+) -> wasmer_result_t {
+    wasmer_instance_from_cache_with_import_object(
+        instance,
+        cache_bytes,
+        cache_len,
+        options,
+        ptr::null(),
+    )
+}
+
+/// Creates a new WebAssembly instance from a cached artifact, resolving its imports against
+/// `import_object`.
+///
+/// The default import object set by `wasmer_import_object_cache_from_imports()` is used if
+/// `import_object` is null.
+#[allow(clippy::cast_ptr_alignment)]
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_instance_from_cache_with_import_object(
+    instance: *mut *mut wasmer_instance_t,
+    cache_bytes: *mut u8,
+    cache_len: u32,
+    options: *const wasmer_compilation_options_t,
+    import_object: *const wasmer_import_object_t,
 ) -> wasmer_result_t {
     if cache_bytes.is_null() {
         update_last_error(CApiError {
@@ -106,7 +130,15 @@ pub unsafe extern "C" fn wasmer_instance_from_cache(
 
     let bytes: &[u8] = slice::from_raw_parts(cache_bytes, cache_len as usize);
     let options: &CompilationOptions = &*(options as *const CompilationOptions);
-    let compiler_chain_generator = prepare_middleware_chain_generator(&options);
+    let compiler_chain_generator = match prepare_middleware_chain_generator(&options) {
+        Ok(compiler_chain_generator) => compiler_chain_generator,
+        Err(error) => {
+            update_last_error(CApiError {
+                msg: format!("wasmer_instance_from_cache: {:?}", error),
+            });
+            return wasmer_result_t::WASMER_ERROR;
+        }
+    };
     let compiler = get_compiler(compiler_chain_generator);
 
     let artifact = match deserialize_artifact(bytes) {
@@ -119,10 +151,17 @@ pub unsafe extern "C" fn wasmer_instance_from_cache(
         }
     };
 
-    let new_module = match wasmer_runtime_core::load_cache_with(artifact, &compiler) {
+    let fingerprint = get_compilation_fingerprint(&options);
+    let new_module = match wasmer_runtime_core::load_cache_with_fingerprint(artifact, &compiler, fingerprint) {
         Ok(deserialized_module) => {
             deserialized_module
         }
+        Err(CacheError::MismatchedFingerprint { .. }) => {
+            update_last_error(CApiError {
+                msg: "wasmer_instance_from_cache: artifact was compiled with different compilation options".to_string(),
+            });
+            return wasmer_result_t::WASMER_ERROR;
+        }
         Err(_) => {
             update_last_error(CApiError {
                 msg: "wasmer_instance_from_cache: artifact instantiation into module failed".to_string(),
@@ -131,8 +170,8 @@ pub unsafe extern "C" fn wasmer_instance_from_cache(
         }
     };
 
-    let import_object: &mut ImportObject = &mut *(GLOBAL_IMPORT_OBJECT as *mut ImportObject);
-    let result_instantiation = new_module.instantiate(&import_object);
+    let result_instantiation =
+        with_import_object(import_object, |import_object| new_module.instantiate(import_object));
     let mut new_instance = match result_instantiation {
         Ok(instance) => instance,
         Err(error) => {
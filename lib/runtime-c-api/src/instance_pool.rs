@@ -0,0 +1,380 @@
+//! Reuse instances of the same module instead of instantiating them for every call.
+
+use crate::{
+    error::{update_last_error, CApiError},
+    import::{clone_import_object, get_global_import_object, wasmer_import_object_t},
+    instance::{
+        compile_with_options, get_compilation_fingerprint, wasmer_compilation_options_t,
+        wasmer_instance_t, CompilationOptions,
+    },
+    wasmer_result_t,
+};
+use std::{
+    collections::{HashMap, VecDeque},
+    slice,
+};
+use wasmer_runtime::{Instance, Module};
+use wasmer_runtime_core::{
+    cache::{CompilationFingerprint, WasmHash},
+    import::ImportObject,
+};
+
+use wasmer_middleware_common::metering;
+use wasmer_middleware_common::opcode_control::{reset_memory_grow_count, reset_table_grow_count};
+use wasmer_middleware_common::runtime_breakpoints::{
+    set_runtime_breakpoint_value, BREAKPOINT_VALUE_NO_BREAKPOINT,
+};
+use wasmer_middleware_common::stack_height::reset_stack_height;
+
+/// Opaque pointer to an `InstancePool` in Rust.
+///
+/// A pool hands out instances through `wasmer_instance_pool_acquire()` and takes them back
+/// through `wasmer_instance_pool_release()`, which resets them so that the next acquisition
+/// gets an instance indistinguishable from a fresh one.
+///
+/// A pool is not thread-safe: it must only be used from one thread at a time.
+#[repr(C)]
+pub struct wasmer_instance_pool_t;
+
+/// Instances are only interchangeable if they come from the same bytes, compiled with the same
+/// options.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+struct PoolKey {
+    module_hash: WasmHash,
+    options_fingerprint: CompilationFingerprint,
+}
+
+struct IdleInstance {
+    key: PoolKey,
+    instance: Box<Instance>,
+    memory_size: u64,
+}
+
+struct AcquiredInstance {
+    key: PoolKey,
+    memory_size: u64,
+}
+
+pub struct InstancePool {
+    /// The import object the instances resolve their imports against, the default one if `None`.
+    import_object: Option<ImportObject>,
+    max_instances: usize,
+    memory_budget: u64,
+    /// The compiled modules, kept as long as the pool holds one of their instances.
+    modules: HashMap<PoolKey, Module>,
+    /// The reset instances, from the least to the most recently released.
+    idle_instances: VecDeque<IdleInstance>,
+    acquired_instances: HashMap<usize, AcquiredInstance>,
+}
+
+impl InstancePool {
+    fn new(import_object: Option<ImportObject>, max_instances: usize, memory_budget: u64) -> Self {
+        InstancePool {
+            import_object,
+            max_instances,
+            memory_budget,
+            modules: HashMap::new(),
+            idle_instances: VecDeque::new(),
+            acquired_instances: HashMap::new(),
+        }
+    }
+
+    unsafe fn acquire(
+        &mut self,
+        bytes: &[u8],
+        options: &CompilationOptions,
+    ) -> Result<Box<Instance>, String> {
+        let key = PoolKey {
+            module_hash: WasmHash::generate(bytes),
+            options_fingerprint: get_compilation_fingerprint(options),
+        };
+
+        let (mut instance, memory_size) = match self.take_idle_instance(&key) {
+            Some(idle_instance) => (idle_instance.instance, idle_instance.memory_size),
+            None => {
+                let result = self.instantiate(key, bytes, options).and_then(|instance| {
+                    let memory_size = get_memory_size(&instance);
+                    self.make_room(memory_size)?;
+                    Ok((instance, memory_size))
+                });
+                if result.is_err() {
+                    self.forget_unused_module(&key);
+                }
+                result?
+            }
+        };
+
+        metering::set_points_limit(&mut instance, options.gas_limit);
+        metering::set_points_used(&mut instance, 0);
+        set_runtime_breakpoint_value(&mut instance, BREAKPOINT_VALUE_NO_BREAKPOINT);
+
+        self.acquired_instances.insert(
+            &*instance as *const Instance as usize,
+            AcquiredInstance { key, memory_size },
+        );
+        Ok(instance)
+    }
+
+    fn take_idle_instance(&mut self, key: &PoolKey) -> Option<IdleInstance> {
+        let position = self
+            .idle_instances
+            .iter()
+            .rposition(|idle_instance| idle_instance.key == *key)?;
+        self.idle_instances.remove(position)
+    }
+
+    /// The number of instances held by the pool, acquired or idle.
+    fn instance_count(&self) -> usize {
+        self.idle_instances.len() + self.acquired_instances.len()
+    }
+
+    /// The size in bytes of the memories of the instances held by the pool, as measured when
+    /// they were acquired or released.
+    fn memory_size(&self) -> u64 {
+        let idle_memory_size: u64 = self
+            .idle_instances
+            .iter()
+            .map(|idle_instance| idle_instance.memory_size)
+            .sum();
+        let acquired_memory_size: u64 = self
+            .acquired_instances
+            .values()
+            .map(|acquired_instance| acquired_instance.memory_size)
+            .sum();
+        idle_memory_size + acquired_memory_size
+    }
+
+    /// Destroys idle instances, least recently released first, until one more instance with
+    /// `memory_size` bytes of memory fits in the limits of the pool.
+    fn make_room(&mut self, memory_size: u64) -> Result<(), String> {
+        while self.instance_count() >= self.max_instances
+            || self.memory_size() + memory_size > self.memory_budget
+        {
+            let idle_instance = self.idle_instances.pop_front().ok_or_else(|| {
+                "the limits of the instance pool are reached by the acquired instances".to_string()
+            })?;
+            self.forget_unused_module(&idle_instance.key);
+        }
+        Ok(())
+    }
+
+    /// Drops the compiled module of `key` once the pool holds none of its instances.
+    fn forget_unused_module(&mut self, key: &PoolKey) {
+        let is_used = self
+            .idle_instances
+            .iter()
+            .any(|idle_instance| idle_instance.key == *key)
+            || self
+                .acquired_instances
+                .values()
+                .any(|acquired_instance| acquired_instance.key == *key);
+        if !is_used {
+            self.modules.remove(key);
+        }
+    }
+
+    unsafe fn instantiate(
+        &mut self,
+        key: PoolKey,
+        bytes: &[u8],
+        options: &CompilationOptions,
+    ) -> Result<Box<Instance>, String> {
+        if !self.modules.contains_key(&key) {
+            let module = compile_with_options(bytes, options)
+                .map_err(|error| format!("compile error: {:?}", error))?;
+            self.modules.insert(key, module);
+        }
+
+        let module = &self.modules[&key];
+        let mut instance = match &self.import_object {
+            Some(import_object) => module.instantiate(import_object),
+            None => module.instantiate(&get_global_import_object()),
+        }
+        .map_err(|error| error.to_string())?;
+
+        // Without a snapshot, the memories are zeroed and initialized again on reset, if they can
+        // be reset at all; `release` destroys the instances it cannot reset.
+        let _ = instance.snapshot_memories();
+
+        Ok(Box::new(instance))
+    }
+
+    fn release(&mut self, mut instance: Box<Instance>) -> Result<(), String> {
+        let key = self
+            .acquired_instances
+            .remove(&(&*instance as *const Instance as usize))
+            .ok_or_else(|| "the instance was not acquired from this pool".to_string())?
+            .key;
+
+        if let Err(error) = instance.reset() {
+            self.forget_unused_module(&key);
+            return Err(error.to_string());
+        }
+        reset_memory_grow_count(&mut instance);
+        reset_table_grow_count(&mut instance);
+        reset_stack_height(&mut instance);
+
+        // The memories are back to the size they had when the instance was acquired, so it
+        // still fits in the limits of the pool.
+        let memory_size = get_memory_size(&instance);
+        self.idle_instances.push_back(IdleInstance {
+            key,
+            instance,
+            memory_size,
+        });
+        Ok(())
+    }
+}
+
+/// Returns the size in bytes of the memories owned by the instance.
+fn get_memory_size(instance: &Instance) -> u64 {
+    let module = instance.module();
+    let info = module.info();
+    let imported_memories = info.imported_memories.len();
+    let ctx = instance.context();
+
+    (imported_memories..imported_memories + info.memories.len())
+        .map(|index| ctx.memory(index as u32).size().bytes().0 as u64)
+        .sum()
+}
+
+/// Creates a new instance pool, whose instances resolve their imports against `import_object`.
+///
+/// The default import object set by `wasmer_import_object_cache_from_imports()` is used if
+/// `import_object` is null. Otherwise, the pool keeps its own reference to the namespaces of the
+/// import object, which may then be destroyed at any time.
+///
+/// The pool holds at most `max_instances` instances at once, acquired or idle, whose memories
+/// must not exceed `memory_budget` bytes in total, as measured when the instances are acquired
+/// and released. An acquisition that would exceed these limits first destroys the least recently
+/// released idle instances, and fails if that is not enough.
+///
+/// The caller owns the object and should call `wasmer_instance_pool_destroy()` to free it.
+///
+/// Returns `wasmer_result_t::WASMER_OK` upon success.
+///
+/// Returns `wasmer_result_t::WASMER_ERROR` upon failure. Use `wasmer_last_error_length`
+/// and `wasmer_last_error_message` to get an error message.
+#[allow(clippy::cast_ptr_alignment)]
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_instance_pool_new(
+    pool: *mut *mut wasmer_instance_pool_t,
+    import_object: *const wasmer_import_object_t,
+    max_instances: u32,
+    memory_budget: u64,
+) -> wasmer_result_t {
+    if pool.is_null() {
+        update_last_error(CApiError {
+            msg: "pool ptr is null".to_string(),
+        });
+        return wasmer_result_t::WASMER_ERROR;
+    }
+
+    let new_pool = InstancePool::new(
+        clone_import_object(import_object),
+        max_instances as usize,
+        memory_budget,
+    );
+    *pool = Box::into_raw(Box::new(new_pool)) as *mut wasmer_instance_pool_t;
+    wasmer_result_t::WASMER_OK
+}
+
+/// Acquires an instance of the given wasm bytes, compiled with the given options, from the pool.
+///
+/// A reset instance of the same bytes and options is reused if the pool holds one; otherwise the
+/// module is instantiated, and compiled first unless the pool holds other instances of it. In both
+/// cases, the gas limit of the options is set, and the points used as well as the runtime
+/// breakpoint value are cleared.
+///
+/// The instance must be given back with `wasmer_instance_pool_release()`, and must not be
+/// destroyed with `wasmer_instance_destroy()`.
+///
+/// Returns `wasmer_result_t::WASMER_OK` upon success.
+///
+/// Returns `wasmer_result_t::WASMER_ERROR` upon failure. Use `wasmer_last_error_length`
+/// and `wasmer_last_error_message` to get an error message.
+#[allow(clippy::cast_ptr_alignment)]
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_instance_pool_acquire(
+    pool: *mut wasmer_instance_pool_t,
+    instance: *mut *mut wasmer_instance_t,
+    wasm_bytes: *mut u8,
+    wasm_bytes_len: u32,
+    options: *const wasmer_compilation_options_t,
+) -> wasmer_result_t {
+    if pool.is_null() || instance.is_null() || wasm_bytes.is_null() || options.is_null() {
+        update_last_error(CApiError {
+            msg: "pool, instance, wasm bytes or options ptr is null".to_string(),
+        });
+        return wasmer_result_t::WASMER_ERROR;
+    }
+
+    let pool = &mut *(pool as *mut InstancePool);
+    let bytes: &[u8] = slice::from_raw_parts(wasm_bytes, wasm_bytes_len as usize);
+    let options: &CompilationOptions = &*(options as *const CompilationOptions);
+
+    match pool.acquire(bytes, options) {
+        Ok(new_instance) => {
+            *instance = Box::into_raw(new_instance) as *mut wasmer_instance_t;
+            wasmer_result_t::WASMER_OK
+        }
+        Err(msg) => {
+            update_last_error(CApiError { msg });
+            wasmer_result_t::WASMER_ERROR
+        }
+    }
+}
+
+/// Gives an instance acquired with `wasmer_instance_pool_acquire()` back to the pool.
+///
+/// The instance is reset, then kept for a later acquisition, until the pool destroys it to make
+/// room for other instances. The caller must not use the instance afterwards.
+///
+/// Returns `wasmer_result_t::WASMER_ERROR` if the instance was not acquired from this pool, in
+/// which case it is left untouched, or if it could not be reset, in which case it is destroyed.
+#[allow(clippy::cast_ptr_alignment)]
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_instance_pool_release(
+    pool: *mut wasmer_instance_pool_t,
+    instance: *mut wasmer_instance_t,
+) -> wasmer_result_t {
+    if pool.is_null() || instance.is_null() {
+        update_last_error(CApiError {
+            msg: "pool or instance ptr is null".to_string(),
+        });
+        return wasmer_result_t::WASMER_ERROR;
+    }
+
+    let pool = &mut *(pool as *mut InstancePool);
+
+    if !pool.acquired_instances.contains_key(&(instance as usize)) {
+        update_last_error(CApiError {
+            msg: "the instance was not acquired from this pool".to_string(),
+        });
+        return wasmer_result_t::WASMER_ERROR;
+    }
+
+    let instance = Box::from_raw(instance as *mut Instance);
+
+    match pool.release(instance) {
+        Ok(()) => wasmer_result_t::WASMER_OK,
+        Err(msg) => {
+            update_last_error(CApiError { msg });
+            wasmer_result_t::WASMER_ERROR
+        }
+    }
+}
+
+/// Frees memory for the given pool, including its idle instances and compiled modules.
+///
+/// Instances still acquired from the pool stay valid, and must then be destroyed with
+/// `wasmer_instance_destroy()`.
+///
+/// If `pool` is a null pointer, this function does nothing.
+#[allow(clippy::cast_ptr_alignment)]
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_instance_pool_destroy(pool: *mut wasmer_instance_pool_t) {
+    if !pool.is_null() {
+        Box::from_raw(pool as *mut InstancePool);
+    }
+}
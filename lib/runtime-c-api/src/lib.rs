@@ -93,6 +93,9 @@
 extern crate wasmer_runtime;
 extern crate wasmer_runtime_core;
 
+#[macro_use]
+extern crate lazy_static;
+
 pub mod error;
 pub mod export;
 pub mod global;
@@ -104,10 +107,14 @@ pub mod memory;
 pub mod metering;
 
 pub mod instance_cache;
+#[cfg(feature = "metering")]
+pub mod instance_pool;
 pub mod runtime_breakpoints;
 pub mod signals;
 
 pub mod module;
+pub mod opcode_trace;
+pub mod policy;
 pub mod table;
 // `not(target_family = "windows")` is simpler than `unix`.  See build.rs
 // if you want to change the meaning of these `cfg`s in the header file.
@@ -1,29 +1,202 @@
 use crate::{
     error::{update_last_error, CApiError},
-    instance::wasmer_instance_t,
+    instance::{wasmer_instance_context_t, wasmer_instance_t},
     module::wasmer_module_t,
     wasmer_result_t,
 };
-use std::slice;
+use std::{
+    slice,
+    sync::{Arc, RwLock},
+};
 
 #[cfg(feature = "metering")]
-use wasmer_runtime_core::backend::Compiler;
+use wasmer_runtime_core::backend::{Compiler, CompilerConfig};
 
 #[cfg(not(feature = "cranelift-backend"))]
 use wasmer_middleware_common::metering;
 
-pub const OPCODE_COUNT: usize = 448;
-pub static mut OPCODE_COSTS: [u32; OPCODE_COUNT] = [0; OPCODE_COUNT];
+pub use wasmer_middleware_common::metering_costs::{
+    OpcodeCosts, LEGACY_OPCODE_COUNT, OPCODE_COUNT,
+};
+
+/// Opaque pointer to a shared `OpcodeCosts` table in Rust.
+///
+/// A cost table is created with `wasmer_opcode_costs_new()` and passed to the compilation
+/// through `wasmer_compilation_options_t`. Modules compiled with different tables use different
+/// gas schedules, even within the same process.
+#[repr(C)]
+pub struct wasmer_opcode_costs_t;
+
+lazy_static! {
+    /// The table used when no cost table is given in the compilation options, set with
+    /// `wasmer_set_opcode_costs()`.
+    static ref DEFAULT_OPCODE_COSTS: RwLock<Option<Arc<OpcodeCosts>>> = RwLock::new(None);
+}
+
+/// Creates a new cost table from `LEGACY_OPCODE_COUNT` costs, tagged with the given gas schedule
+/// `version`. The per-unit costs of the memory operators are 0: use
+/// `wasmer_opcode_costs_new_with_length()` to set them.
+///
+/// The costs are copied, so the caller keeps ownership of `opcode_costs_pointer`.
+///
+/// The caller is responsible to free the table with `wasmer_opcode_costs_destroy()`. Modules
+/// compiled with it keep their own reference, so it may be destroyed right after compilation.
+///
+/// Returns `wasmer_result_t::WASMER_OK` upon success.
+///
+/// Returns `wasmer_result_t::WASMER_ERROR` upon failure. Use `wasmer_last_error_length`
+/// and `wasmer_last_error_message` to get an error message.
+#[allow(clippy::cast_ptr_alignment)]
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_opcode_costs_new(
+    opcode_costs: *mut *mut wasmer_opcode_costs_t,
+    opcode_costs_pointer: *const u32,
+    version: u32,
+) -> wasmer_result_t {
+    wasmer_opcode_costs_new_with_length(
+        opcode_costs,
+        opcode_costs_pointer,
+        LEGACY_OPCODE_COUNT as u32,
+        version,
+    )
+}
+
+/// Creates a new cost table from the `opcode_costs_length` costs at `opcode_costs_pointer`,
+/// tagged with the given gas schedule `version`.
+///
+/// `opcode_costs_length` must be either `OPCODE_COUNT` or `LEGACY_OPCODE_COUNT`, in which case
+/// the per-unit costs of the memory operators are 0. The costs are copied, so the caller keeps
+/// ownership of `opcode_costs_pointer`.
+///
+/// The caller is responsible to free the table with `wasmer_opcode_costs_destroy()`.
+///
+/// Returns `wasmer_result_t::WASMER_OK` upon success.
+///
+/// Returns `wasmer_result_t::WASMER_ERROR` upon failure. Use `wasmer_last_error_length`
+/// and `wasmer_last_error_message` to get an error message.
+#[allow(clippy::cast_ptr_alignment)]
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_opcode_costs_new_with_length(
+    opcode_costs: *mut *mut wasmer_opcode_costs_t,
+    opcode_costs_pointer: *const u32,
+    opcode_costs_length: u32,
+    version: u32,
+) -> wasmer_result_t {
+    if opcode_costs.is_null() {
+        update_last_error(CApiError {
+            msg: "opcode costs is null".to_string(),
+        });
+        return wasmer_result_t::WASMER_ERROR;
+    }
+
+    let new_costs = match read_opcode_costs(opcode_costs_pointer, opcode_costs_length, version) {
+        Ok(new_costs) => new_costs,
+        Err(msg) => {
+            update_last_error(CApiError { msg });
+            return wasmer_result_t::WASMER_ERROR;
+        }
+    };
+    *opcode_costs = Box::into_raw(Box::new(new_costs)) as *mut wasmer_opcode_costs_t;
+    wasmer_result_t::WASMER_OK
+}
+
+/// Copies a cost table out of a C array, checking its length before reading it.
+unsafe fn read_opcode_costs(
+    opcode_costs_pointer: *const u32,
+    opcode_costs_length: u32,
+    version: u32,
+) -> Result<Arc<OpcodeCosts>, String> {
+    if opcode_costs_pointer.is_null() {
+        return Err("opcode costs pointer is null".to_string());
+    }
+    let length = opcode_costs_length as usize;
+    if length != OPCODE_COUNT && length != LEGACY_OPCODE_COUNT {
+        return Err(format!(
+            "opcode cost table must have {} or {} entries, got {}",
+            OPCODE_COUNT, LEGACY_OPCODE_COUNT, length
+        ));
+    }
+
+    OpcodeCosts::new(version, slice::from_raw_parts(opcode_costs_pointer, length))
+}
+
+/// Returns the gas schedule version of the given cost table, or `0` if it is null.
+#[allow(clippy::cast_ptr_alignment)]
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_opcode_costs_version(
+    opcode_costs: *const wasmer_opcode_costs_t,
+) -> u32 {
+    if opcode_costs.is_null() {
+        return 0;
+    }
+    let opcode_costs = &*(opcode_costs as *const Arc<OpcodeCosts>);
+    opcode_costs.version()
+}
 
+/// Frees the caller's reference to the given cost table.
+///
+/// If `opcode_costs` is a null pointer, this function does nothing.
+#[allow(clippy::cast_ptr_alignment)]
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_opcode_costs_destroy(opcode_costs: *mut wasmer_opcode_costs_t) {
+    if !opcode_costs.is_null() {
+        Box::from_raw(opcode_costs as *mut Arc<OpcodeCosts>);
+    }
+}
+
+/// Sets the process-wide default cost table from `LEGACY_OPCODE_COUNT` costs, used when the
+/// compilation options do not carry one. The table is tagged with gas schedule version `0`, and
+/// the per-unit costs of the memory operators are 0: use `wasmer_set_opcode_costs_with_length()`
+/// to set them.
 #[allow(clippy::cast_ptr_alignment)]
 #[cfg(feature = "metering")]
 #[no_mangle]
-pub unsafe extern "C" fn wasmer_set_opcode_costs(
+pub unsafe extern "C" fn wasmer_set_opcode_costs(opcode_costs_pointer: *const u32) {
+    wasmer_set_opcode_costs_with_length(opcode_costs_pointer, LEGACY_OPCODE_COUNT as u32);
+}
+
+/// Sets the process-wide default cost table from the `opcode_costs_length` costs at
+/// `opcode_costs_pointer`, used when the compilation options do not carry one. The table is
+/// tagged with gas schedule version `0`.
+///
+/// `opcode_costs_length` must be either `OPCODE_COUNT` or `LEGACY_OPCODE_COUNT`, in which case
+/// the per-unit costs of the memory operators are 0.
+///
+/// Returns `wasmer_result_t::WASMER_OK` upon success.
+///
+/// Returns `wasmer_result_t::WASMER_ERROR` upon failure, leaving the default table unchanged.
+/// Use `wasmer_last_error_length` and `wasmer_last_error_message` to get an error message.
+#[allow(clippy::cast_ptr_alignment)]
+#[cfg(feature = "metering")]
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_set_opcode_costs_with_length(
     opcode_costs_pointer: *const u32,
-) {
-    OPCODE_COSTS.copy_from_slice(slice::from_raw_parts(opcode_costs_pointer, OPCODE_COUNT));
+    opcode_costs_length: u32,
+) -> wasmer_result_t {
+    match read_opcode_costs(opcode_costs_pointer, opcode_costs_length, 0) {
+        Ok(default_costs) => {
+            *DEFAULT_OPCODE_COSTS.write().unwrap() = Some(default_costs);
+            wasmer_result_t::WASMER_OK
+        }
+        Err(msg) => {
+            update_last_error(CApiError { msg });
+            wasmer_result_t::WASMER_ERROR
+        }
+    }
 }
 
+/// Returns a new reference to the cost table behind `opcode_costs`, or to the default table
+/// if it is null.
+pub unsafe fn get_opcode_costs(opcode_costs: *const wasmer_opcode_costs_t) -> Arc<OpcodeCosts> {
+    if !opcode_costs.is_null() {
+        return (&*(opcode_costs as *const Arc<OpcodeCosts>)).clone();
+    }
+
+    match &*DEFAULT_OPCODE_COSTS.read().unwrap() {
+        Some(default_costs) => Arc::clone(default_costs),
+        None => OpcodeCosts::new(0, &[0; OPCODE_COUNT]).unwrap(),
+    }
+}
 
 // returns gas used
 #[allow(clippy::cast_ptr_alignment)]
@@ -68,6 +241,35 @@ pub unsafe extern "C" fn wasmer_instance_set_points_limit(
     metering::set_points_limit(instance, limit)
 }
 
+/// Charges `points` to the running instance from within a host function, aka imported function.
+///
+/// The pointer to `wasmer_instance_context_t` is received by the host function as its first
+/// argument. Just passing it to `ctx` is fine.
+///
+/// If the points used reach the limit, the runtime breakpoint value is set to
+/// `BREAKPOINT_VALUE_OUT_OF_GAS` and the execution traps exactly like a metered block would:
+/// `wasmer_last_error_kind()` then returns `WASMER_ERROR_KIND_OUT_OF_GAS`. In that case this
+/// function never returns.
+///
+/// This function returns `wasmer_result_t::WASMER_ERROR` if `ctx` is null, and
+/// `wasmer_result_t::WASMER_OK` if the points were charged within the limit.
+#[allow(clippy::cast_ptr_alignment)]
+#[no_mangle]
+#[cfg(feature = "metering")]
+pub unsafe extern "C" fn wasmer_instance_context_charge_points(
+    ctx: *mut wasmer_instance_context_t,
+    points: u64,
+) -> wasmer_result_t {
+    if ctx.is_null() {
+        update_last_error(CApiError {
+            msg: "ctx ptr is null in wasmer_instance_context_charge_points".to_string(),
+        });
+        return wasmer_result_t::WASMER_ERROR;
+    }
+    let ctx = &mut *(ctx as *mut wasmer_runtime::Ctx);
+    metering::charge_points_ctx(ctx, points);
+    wasmer_result_t::WASMER_OK
+}
 
 /// Creates a new Module with gas limit from the given wasm bytes.
 ///
@@ -96,10 +298,15 @@ pub unsafe extern "C" fn wasmer_compile_with_gas_metering(
         return wasmer_result_t::WASMER_ERROR;
     }
 
-    let compiler = get_metered_compiler();
+    let opcode_costs = get_opcode_costs(std::ptr::null());
+    let compiler_config = CompilerConfig {
+        gas_schedule_version: Some(opcode_costs.version()),
+        ..Default::default()
+    };
+    let compiler = get_metered_compiler(opcode_costs);
 
     let bytes: &[u8] = slice::from_raw_parts_mut(wasm_bytes, wasm_bytes_len as usize);
-    let result = wasmer_runtime_core::compile_with(bytes, &compiler);
+    let result = wasmer_runtime_core::compile_with_config(bytes, &compiler, compiler_config);
     let new_module = match result {
         Ok(instance) => instance,
         Err(_) => {
@@ -114,7 +321,7 @@ pub unsafe extern "C" fn wasmer_compile_with_gas_metering(
 }
 
 #[cfg(feature = "metering")]
-unsafe fn get_metered_compiler() -> impl Compiler {
+unsafe fn get_metered_compiler(opcode_costs: Arc<OpcodeCosts>) -> impl Compiler {
     use wasmer_runtime_core::codegen::{MiddlewareChain, StreamingCompiler};
 
     #[cfg(feature = "llvm-backend")]
@@ -131,7 +338,7 @@ unsafe fn get_metered_compiler() -> impl Compiler {
     let c: StreamingCompiler<MeteredMCG, _, _, _, _> = StreamingCompiler::new(move || {
         let mut chain = MiddlewareChain::new();
 
-        chain.push(metering::Metering::new(&OPCODE_COSTS, 0));
+        chain.push(metering::Metering::new(opcode_costs.clone(), 0));
         chain.push(runtime_breakpoints::RuntimeBreakpointHandler::new());
 
         chain
@@ -139,7 +346,6 @@ unsafe fn get_metered_compiler() -> impl Compiler {
     c
 }
 
-
 /*** placeholder implementation if metering feature off ***/
 
 // Without metering, wasmer_compile_with_gas_metering is a copy of wasmer_compile
@@ -0,0 +1,150 @@
+//! Trace the operators of a module, at compile time into a file or at runtime into a buffer.
+
+use crate::{
+    error::{update_last_error, CApiError},
+    wasmer_result_t,
+};
+use std::convert::TryFrom;
+use wasmer_middleware_common::opcode_trace::{OpcodeTraceBuffer, OpcodeTraceFormat};
+
+/// The format of the trace file written while compiling, as set in the compilation options.
+/// Compilation fails for any other value.
+#[allow(non_camel_case_types)]
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum wasmer_opcode_trace_format_t {
+    /// One human-readable line per operator.
+    WASMER_OPCODE_TRACE_FORMAT_TEXT = 0,
+    /// One 12-byte record per operator: the local function index, the operator index in the
+    /// function and the offset of the operator in the wasm binary, as little-endian `u32`s.
+    WASMER_OPCODE_TRACE_FORMAT_BINARY = 1,
+}
+
+impl TryFrom<u32> for wasmer_opcode_trace_format_t {
+    type Error = String;
+
+    fn try_from(format: u32) -> Result<Self, Self::Error> {
+        match format {
+            0 => Ok(wasmer_opcode_trace_format_t::WASMER_OPCODE_TRACE_FORMAT_TEXT),
+            1 => Ok(wasmer_opcode_trace_format_t::WASMER_OPCODE_TRACE_FORMAT_BINARY),
+            _ => Err(format!("invalid opcode trace format: {}", format)),
+        }
+    }
+}
+
+impl From<wasmer_opcode_trace_format_t> for OpcodeTraceFormat {
+    fn from(format: wasmer_opcode_trace_format_t) -> Self {
+        match format {
+            wasmer_opcode_trace_format_t::WASMER_OPCODE_TRACE_FORMAT_TEXT => {
+                OpcodeTraceFormat::Text
+            }
+            wasmer_opcode_trace_format_t::WASMER_OPCODE_TRACE_FORMAT_BINARY => {
+                OpcodeTraceFormat::Binary
+            }
+        }
+    }
+}
+
+/// Opaque pointer to an `OpcodeTraceBuffer` in Rust.
+///
+/// A buffer is created with `wasmer_opcode_trace_buffer_new()` and passed to the compilation
+/// through `wasmer_compilation_options_t`. Every instance of the modules compiled with it records
+/// the operators it executes into the buffer.
+#[repr(C)]
+pub struct wasmer_opcode_trace_buffer_t;
+
+/// An operator executed at runtime, as recorded in a `wasmer_opcode_trace_buffer_t`.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct wasmer_opcode_trace_entry_t {
+    /// The index of the function among the functions defined by the module.
+    pub local_function_index: u32,
+    /// The index of the operator in the function body.
+    pub operator_index: u32,
+}
+
+/// Creates a new buffer keeping the last `capacity` operators executed.
+///
+/// The caller is responsible to free the buffer with `wasmer_opcode_trace_buffer_destroy()`.
+/// Modules compiled with it keep their own reference, so it may be destroyed right after
+/// compilation.
+///
+/// Returns `wasmer_result_t::WASMER_OK` upon success.
+///
+/// Returns `wasmer_result_t::WASMER_ERROR` upon failure. Use `wasmer_last_error_length`
+/// and `wasmer_last_error_message` to get an error message.
+#[allow(clippy::cast_ptr_alignment)]
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_opcode_trace_buffer_new(
+    buffer: *mut *mut wasmer_opcode_trace_buffer_t,
+    capacity: u32,
+) -> wasmer_result_t {
+    if buffer.is_null() {
+        update_last_error(CApiError {
+            msg: "opcode trace buffer is null".to_string(),
+        });
+        return wasmer_result_t::WASMER_ERROR;
+    }
+
+    let new_buffer = OpcodeTraceBuffer::new(capacity as usize);
+    *buffer = Box::into_raw(Box::new(new_buffer)) as *mut wasmer_opcode_trace_buffer_t;
+    wasmer_result_t::WASMER_OK
+}
+
+/// Copies the recorded operators, oldest first, into `entries`, which holds `entries_len`
+/// entries, and returns the number of operators recorded, which may be larger than
+/// `entries_len`.
+///
+/// Returns `0` if `buffer` is null.
+#[allow(clippy::cast_ptr_alignment)]
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_opcode_trace_buffer_entries(
+    buffer: *const wasmer_opcode_trace_buffer_t,
+    entries: *mut wasmer_opcode_trace_entry_t,
+    entries_len: u32,
+) -> u32 {
+    if buffer.is_null() {
+        return 0;
+    }
+    let buffer = &*(buffer as *const OpcodeTraceBuffer);
+    let recorded = buffer.entries();
+
+    if !entries.is_null() {
+        for (i, &(local_function_index, operator_index)) in
+            recorded.iter().take(entries_len as usize).enumerate()
+        {
+            *entries.add(i) = wasmer_opcode_trace_entry_t {
+                local_function_index,
+                operator_index,
+            };
+        }
+    }
+    recorded.len() as u32
+}
+
+/// Removes every operator recorded in the given buffer.
+///
+/// If `buffer` is a null pointer, this function does nothing.
+#[allow(clippy::cast_ptr_alignment)]
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_opcode_trace_buffer_clear(
+    buffer: *const wasmer_opcode_trace_buffer_t,
+) {
+    if !buffer.is_null() {
+        let buffer = &*(buffer as *const OpcodeTraceBuffer);
+        buffer.clear();
+    }
+}
+
+/// Frees the caller's reference to the given buffer.
+///
+/// If `buffer` is a null pointer, this function does nothing.
+#[allow(clippy::cast_ptr_alignment)]
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_opcode_trace_buffer_destroy(
+    buffer: *mut wasmer_opcode_trace_buffer_t,
+) {
+    if !buffer.is_null() {
+        Box::from_raw(buffer as *mut OpcodeTraceBuffer);
+    }
+}
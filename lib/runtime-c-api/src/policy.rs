@@ -0,0 +1,268 @@
+//! Check modules against a policy before compiling them.
+
+use crate::{
+    error::{update_last_error, CApiError},
+    wasmer_byte_array, wasmer_limit_option_t, wasmer_result_t,
+};
+use std::{collections::HashMap, slice};
+use wasmer_runtime_core::{
+    policy::{check_module_policy, ModulePolicy, PolicyViolation},
+    structures::TypedIndex,
+};
+
+/// An import allowed by a `wasmer_module_policy_t`.
+#[repr(C)]
+pub struct wasmer_policy_import_t {
+    /// The namespace of the import.
+    pub module_name: wasmer_byte_array,
+    /// The name of the import; an empty name allows every import of the namespace.
+    pub import_name: wasmer_byte_array,
+}
+
+/// The rules checked by `wasmer_validate_with_policy()`. Unset limits are not checked.
+#[repr(C)]
+pub struct wasmer_module_policy_t {
+    /// The allowed imports; every import is allowed when null.
+    pub allowed_imports: *const wasmer_policy_import_t,
+    /// The number of allowed imports.
+    pub allowed_imports_len: u32,
+    /// The maximum number of functions defined by the module, imports excluded.
+    pub max_functions: wasmer_limit_option_t,
+    /// The maximum number of locals declared by a function, params excluded.
+    pub max_locals: wasmer_limit_option_t,
+    /// The maximum initial size of a memory, in pages.
+    pub max_initial_memory_pages: wasmer_limit_option_t,
+    /// The maximum maximum size of a memory, in pages.
+    pub max_maximum_memory_pages: wasmer_limit_option_t,
+    /// Whether a start function is rejected.
+    pub forbid_start_function: bool,
+    /// Whether floats are rejected, be it in operators, function types, globals or locals.
+    pub forbid_floats: bool,
+    /// The names of the exports the module must have.
+    pub required_exports: *const wasmer_byte_array,
+    /// The number of required exports.
+    pub required_exports_len: u32,
+}
+
+/// The kind of a `wasmer_policy_violation_t`.
+#[allow(non_camel_case_types)]
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum wasmer_policy_violation_kind_t {
+    /// An import is not allowed; the index is the one of the import.
+    WASMER_POLICY_VIOLATION_FORBIDDEN_IMPORT = 0,
+    /// The module defines too many functions; the index is the number of functions.
+    WASMER_POLICY_VIOLATION_TOO_MANY_FUNCTIONS = 1,
+    /// A function declares too many locals; the index is the one of the function.
+    WASMER_POLICY_VIOLATION_TOO_MANY_LOCALS = 2,
+    /// A memory is initially too large; the index is the one of the memory.
+    WASMER_POLICY_VIOLATION_INITIAL_MEMORY_TOO_LARGE = 3,
+    /// A memory may grow too large; the index is the one of the memory.
+    WASMER_POLICY_VIOLATION_MAXIMUM_MEMORY_TOO_LARGE = 4,
+    /// The module has a start function; the index is the one of the function.
+    WASMER_POLICY_VIOLATION_START_FUNCTION = 5,
+    /// A function uses float operators; the index is the one of the function.
+    WASMER_POLICY_VIOLATION_FLOAT_OPERATOR = 6,
+    /// A required export is missing; the index is the one of the export in the policy.
+    WASMER_POLICY_VIOLATION_MISSING_EXPORT = 7,
+    /// A function type takes or returns floats; the index is the one of the type.
+    WASMER_POLICY_VIOLATION_FLOAT_SIGNATURE = 8,
+    /// A global holds a float; the index is the one of the global, imports included.
+    WASMER_POLICY_VIOLATION_FLOAT_GLOBAL = 9,
+    /// A function declares float locals; the index is the one of the function.
+    WASMER_POLICY_VIOLATION_FLOAT_LOCAL = 10,
+}
+
+/// A rule of a `wasmer_module_policy_t` broken by a module.
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct wasmer_policy_violation_t {
+    /// The kind of the violation.
+    pub kind: wasmer_policy_violation_kind_t,
+    /// What the violation is about, depending on its kind.
+    pub index: u32,
+}
+
+/// Checks the given wasm bytes against `policy`, without compiling them.
+///
+/// Up to `violations_len` violations are written to `violations`, and their total number to
+/// `violations_count`: the module is accepted when it is 0.
+///
+/// The code is not validated, see `wasmer_validate()`.
+///
+/// Returns `wasmer_result_t::WASMER_ERROR` if the bytes are malformed or the policy is invalid.
+/// Use `wasmer_last_error_length` and `wasmer_last_error_message` to get an error message.
+///
+/// Example:
+///
+/// ```c
+/// wasmer_policy_violation_t violations[16];
+/// uint32_t violations_count;
+/// wasmer_result_t result = wasmer_validate_with_policy(bytes, bytes_length, &policy,
+///                                                      violations, 16, &violations_count);
+///
+/// if (result != WASMER_OK || violations_count > 0) {
+///     // Reject the module…
+/// }
+/// ```
+#[allow(clippy::cast_ptr_alignment)]
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_validate_with_policy(
+    wasm_bytes: *const u8,
+    wasm_bytes_len: u32,
+    policy: *const wasmer_module_policy_t,
+    violations: *mut wasmer_policy_violation_t,
+    violations_len: u32,
+    violations_count: *mut u32,
+) -> wasmer_result_t {
+    if wasm_bytes.is_null() || policy.is_null() || violations_count.is_null() {
+        update_last_error(CApiError {
+            msg: "wasm bytes, policy or violations count ptr is null".to_string(),
+        });
+        return wasmer_result_t::WASMER_ERROR;
+    }
+
+    let bytes: &[u8] = slice::from_raw_parts(wasm_bytes, wasm_bytes_len as usize);
+    let policy = &*policy;
+    let module_policy = match get_module_policy(policy) {
+        Ok(module_policy) => module_policy,
+        Err(error) => {
+            update_last_error(error);
+            return wasmer_result_t::WASMER_ERROR;
+        }
+    };
+
+    let module_violations = match check_module_policy(bytes, &module_policy) {
+        Ok(module_violations) => module_violations,
+        Err(error) => {
+            update_last_error(error);
+            return wasmer_result_t::WASMER_ERROR;
+        }
+    };
+
+    *violations_count = module_violations.len() as u32;
+    if !violations.is_null() {
+        let violations = slice::from_raw_parts_mut(violations, violations_len as usize);
+        for (violation, module_violation) in violations.iter_mut().zip(&module_violations) {
+            *violation = to_policy_violation(&module_policy, module_violation);
+        }
+    }
+
+    wasmer_result_t::WASMER_OK
+}
+
+unsafe fn get_module_policy(policy: &wasmer_module_policy_t) -> Result<ModulePolicy, CApiError> {
+    let allowed_imports = if policy.allowed_imports.is_null() {
+        None
+    } else {
+        let mut allowed_imports = HashMap::new();
+        let imports =
+            slice::from_raw_parts(policy.allowed_imports, policy.allowed_imports_len as usize);
+        for import in imports {
+            let namespace = get_str(&import.module_name)?;
+            let name = get_str(&import.import_name)?;
+            let names = allowed_imports
+                .entry(namespace.to_string())
+                .or_insert_with(|| Some(Default::default()));
+            if name.is_empty() {
+                *names = None;
+            } else if let Some(names) = names {
+                names.insert(name.to_string());
+            }
+        }
+        Some(allowed_imports)
+    };
+
+    let required_exports = if policy.required_exports.is_null() {
+        vec![]
+    } else {
+        slice::from_raw_parts(
+            policy.required_exports,
+            policy.required_exports_len as usize,
+        )
+        .iter()
+        .map(|name| get_str(name).map(str::to_string))
+        .collect::<Result<_, _>>()?
+    };
+
+    Ok(ModulePolicy {
+        allowed_imports,
+        max_functions: get_limit(&policy.max_functions),
+        max_locals: get_limit(&policy.max_locals),
+        max_initial_memory_pages: get_limit(&policy.max_initial_memory_pages),
+        max_maximum_memory_pages: get_limit(&policy.max_maximum_memory_pages),
+        forbid_start_function: policy.forbid_start_function,
+        forbid_floats: policy.forbid_floats,
+        required_exports,
+    })
+}
+
+unsafe fn get_str(bytes: &wasmer_byte_array) -> Result<&str, CApiError> {
+    bytes.as_str().map_err(|_| CApiError {
+        msg: "policy names must be valid UTF-8".to_string(),
+    })
+}
+
+fn get_limit(limit: &wasmer_limit_option_t) -> Option<u32> {
+    if limit.has_some {
+        Some(limit.some)
+    } else {
+        None
+    }
+}
+
+fn to_policy_violation(
+    module_policy: &ModulePolicy,
+    violation: &PolicyViolation,
+) -> wasmer_policy_violation_t {
+    use self::wasmer_policy_violation_kind_t::*;
+
+    let (kind, index) = match violation {
+        PolicyViolation::ForbiddenImport { index, .. } => {
+            (WASMER_POLICY_VIOLATION_FORBIDDEN_IMPORT, *index)
+        }
+        PolicyViolation::TooManyFunctions { count, .. } => {
+            (WASMER_POLICY_VIOLATION_TOO_MANY_FUNCTIONS, *count)
+        }
+        PolicyViolation::TooManyLocals { function, .. } => (
+            WASMER_POLICY_VIOLATION_TOO_MANY_LOCALS,
+            function.index() as u32,
+        ),
+        PolicyViolation::InitialMemoryTooLarge { memory, .. } => (
+            WASMER_POLICY_VIOLATION_INITIAL_MEMORY_TOO_LARGE,
+            memory.index() as u32,
+        ),
+        PolicyViolation::MaximumMemoryTooLarge { memory, .. } => (
+            WASMER_POLICY_VIOLATION_MAXIMUM_MEMORY_TOO_LARGE,
+            memory.index() as u32,
+        ),
+        PolicyViolation::StartFunction { function } => (
+            WASMER_POLICY_VIOLATION_START_FUNCTION,
+            function.index() as u32,
+        ),
+        PolicyViolation::FloatOperator { function, .. } => (
+            WASMER_POLICY_VIOLATION_FLOAT_OPERATOR,
+            function.index() as u32,
+        ),
+        PolicyViolation::FloatSignature { signature } => (
+            WASMER_POLICY_VIOLATION_FLOAT_SIGNATURE,
+            signature.index() as u32,
+        ),
+        PolicyViolation::FloatGlobal { global } => {
+            (WASMER_POLICY_VIOLATION_FLOAT_GLOBAL, global.index() as u32)
+        }
+        PolicyViolation::FloatLocal { function } => {
+            (WASMER_POLICY_VIOLATION_FLOAT_LOCAL, function.index() as u32)
+        }
+        PolicyViolation::MissingExport { name } => (
+            WASMER_POLICY_VIOLATION_MISSING_EXPORT,
+            module_policy
+                .required_exports
+                .iter()
+                .position(|required_export| required_export == name)
+                .unwrap_or_default() as u32,
+        ),
+    };
+
+    wasmer_policy_violation_t { kind, index }
+}
@@ -21,6 +21,12 @@ pub enum wasmer_value_tag {
 
     /// Represents the `f64` WebAssembly type.
     WASM_F64,
+
+    /// Represents the `externref` WebAssembly type.
+    WASM_EXTERNREF,
+
+    /// Represents the `funcref` WebAssembly type.
+    WASM_FUNCREF,
 }
 
 /// Represents a WebAssembly value.
@@ -37,6 +43,11 @@ pub union wasmer_value {
     pub I64: i64,
     pub F32: f32,
     pub F64: f64,
+    /// An opaque reference to a host value; `0` is the null reference.
+    pub EXTERNREF: u64,
+    /// A reference to a function, opaque to the host, which can only pass the null reference
+    /// `0` to WebAssembly.
+    pub FUNCREF: u64,
 }
 
 /// Represents a WebAssembly type and value pair,
@@ -90,6 +101,14 @@ impl From<wasmer_value_t> for Value {
                     tag: wasmer_value_tag::WASM_F64,
                     value: wasmer_value { F64 },
                 } => Value::F64(F64),
+                wasmer_value_t {
+                    tag: wasmer_value_tag::WASM_EXTERNREF,
+                    value: wasmer_value { EXTERNREF },
+                } => Value::ExternRef(EXTERNREF),
+                wasmer_value_t {
+                    tag: wasmer_value_tag::WASM_FUNCREF,
+                    value: wasmer_value { FUNCREF },
+                } => Value::FuncRef(FUNCREF),
                 _ => unreachable!("unknown WASM type"),
             }
         }
@@ -116,6 +135,14 @@ impl From<Value> for wasmer_value_t {
                 value: wasmer_value { F64: x },
             },
             Value::V128(_) => unimplemented!("V128 not supported in C API"),
+            Value::ExternRef(x) => wasmer_value_t {
+                tag: wasmer_value_tag::WASM_EXTERNREF,
+                value: wasmer_value { EXTERNREF: x },
+            },
+            Value::FuncRef(x) => wasmer_value_t {
+                tag: wasmer_value_tag::WASM_FUNCREF,
+                value: wasmer_value { FUNCREF: x },
+            },
         }
     }
 }
@@ -129,6 +156,8 @@ impl From<Type> for wasmer_value_tag {
             Type::F32 => wasmer_value_tag::WASM_F32,
             Type::F64 => wasmer_value_tag::WASM_F64,
             Type::V128 => unreachable!("V128 not supported in C API"),
+            Type::ExternRef => wasmer_value_tag::WASM_EXTERNREF,
+            Type::FuncRef => wasmer_value_tag::WASM_FUNCREF,
         }
     }
 }
@@ -141,6 +170,8 @@ impl From<wasmer_value_tag> for Type {
             wasmer_value_tag::WASM_I64 => Type::I64,
             wasmer_value_tag::WASM_F32 => Type::F32,
             wasmer_value_tag::WASM_F64 => Type::F64,
+            wasmer_value_tag::WASM_EXTERNREF => Type::ExternRef,
+            wasmer_value_tag::WASM_FUNCREF => Type::FuncRef,
             _ => unreachable!("unknown WASM type"),
         }
     }
@@ -154,6 +185,8 @@ impl From<&wasmer_runtime::wasm::Type> for wasmer_value_tag {
             Type::F32 => wasmer_value_tag::WASM_F32,
             Type::F64 => wasmer_value_tag::WASM_F64,
             Type::V128 => unimplemented!("V128 not supported in C API"),
+            Type::ExternRef => wasmer_value_tag::WASM_EXTERNREF,
+            Type::FuncRef => wasmer_value_tag::WASM_FUNCREF,
         }
     }
 }
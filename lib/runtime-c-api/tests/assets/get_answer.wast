@@ -0,0 +1,5 @@
+(module
+  (global $answer (import "env" "answer") i32)
+
+  (func (export "get") (result i32)
+      global.get $answer))
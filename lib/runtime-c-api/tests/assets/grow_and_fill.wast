@@ -0,0 +1,12 @@
+(module
+  (memory 1)
+
+  (func (export "grow") (param i32) (result i32)
+      local.get 0
+      memory.grow)
+
+  (func (export "fill") (param i32)
+      i32.const 0
+      i32.const 0
+      local.get 0
+      memory.fill))
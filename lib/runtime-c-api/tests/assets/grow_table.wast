@@ -0,0 +1,7 @@
+(module
+  (table 0 anyfunc)
+
+  (func (export "grow") (param i32) (result i32)
+      ref.null
+      local.get 0
+      table.grow 0))
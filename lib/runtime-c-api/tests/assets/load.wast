@@ -0,0 +1,6 @@
+(module
+  (memory 1)
+
+  (func (export "load") (param i32) (result i32)
+      local.get 0
+      i32.load))
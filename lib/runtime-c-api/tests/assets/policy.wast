@@ -0,0 +1,17 @@
+(module
+  (import "env" "allowed" (func $allowed))
+  (import "env" "forbidden" (func $forbidden))
+  (import "env" "answer" (global f64))
+  (memory 2 10)
+  (global (mut f32) (f32.const 0))
+
+  (func $start
+      call $allowed)
+
+  (func $floats (param f32) (result f32) (local f64)
+      local.get 0
+      f32.const 1
+      f32.add)
+
+  (start $start)
+  (export "main" (func $start)))
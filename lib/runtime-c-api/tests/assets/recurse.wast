@@ -0,0 +1,9 @@
+(module
+  (func $recurse (export "recurse") (param i32)
+      local.get 0
+      if
+        local.get 0
+        i32.const 1
+        i32.sub
+        call $recurse
+      end))
@@ -0,0 +1,151 @@
+#include <stdio.h>
+#include "../wasmer.h"
+#include <assert.h>
+#include <stdint.h>
+#include <string.h>
+
+typedef struct {
+    uint8_t* bytes;
+    long bytes_len;
+} wasm_file_t;
+
+wasm_file_t read_wasm_file(const char* file_name) {
+    wasm_file_t wasm_file;
+
+    FILE *file = fopen(file_name, "r");
+    fseek(file, 0, SEEK_END);
+    wasm_file.bytes_len = ftell(file);
+
+    wasm_file.bytes = malloc(wasm_file.bytes_len);
+    fseek(file, 0, SEEK_SET);
+    fread(wasm_file.bytes, 1, wasm_file.bytes_len, file);
+    fclose(file);
+
+    return wasm_file;
+}
+
+uint64_t call_sum(wasmer_compilation_options_t *options, uint8_t *bytes, long len)
+{
+    wasmer_instance_t *instance = NULL;
+    wasmer_result_t instantiate_result = wasmer_instantiate_with_options(&instance, bytes, len, options);
+    printf("Instantiate result: %d\n", instantiate_result);
+    assert(instantiate_result == WASMER_OK);
+
+    wasmer_value_t param_one;
+    param_one.tag = WASM_I32;
+    param_one.value.I32 = 7;
+    wasmer_value_t param_two;
+    param_two.tag = WASM_I32;
+    param_two.value.I32 = 8;
+    wasmer_value_t params[] = {param_one, param_two};
+
+    wasmer_value_t result_one;
+    wasmer_value_t results[] = {result_one};
+
+    wasmer_result_t call_result = wasmer_instance_call(instance, "sum", params, 2, results, 1);
+    printf("Call result:  %d\n", call_result);
+    assert(call_result == WASMER_OK);
+    assert(results[0].value.I32 == 15);
+
+    uint64_t points_used = wasmer_instance_get_points_used(instance);
+    printf("Points used: %llu\n", (unsigned long long) points_used);
+    wasmer_instance_destroy(instance);
+    return points_used;
+}
+
+int main()
+{
+    wasm_file_t sum_wasm = read_wasm_file("assets/sum.wasm");
+    uint8_t *bytes = sum_wasm.bytes;
+    long len = sum_wasm.bytes_len;
+
+    uint32_t costs_v1[OPCODE_COUNT];
+    uint32_t costs_v2[OPCODE_COUNT];
+    for (int i = 0; i < OPCODE_COUNT; i++) {
+        costs_v1[i] = 1;
+        costs_v2[i] = 2;
+    }
+
+    wasmer_opcode_costs_t *opcode_costs_v1 = NULL;
+    wasmer_result_t result = wasmer_opcode_costs_new_with_length(&opcode_costs_v1, costs_v1, OPCODE_COUNT, 1);
+    assert(result == WASMER_OK);
+    wasmer_opcode_costs_t *opcode_costs_v2 = NULL;
+    result = wasmer_opcode_costs_new_with_length(&opcode_costs_v2, costs_v2, OPCODE_COUNT, 2);
+    assert(result == WASMER_OK);
+
+    // The options are filled in field by field, as any host would.
+    wasmer_compilation_options_t options;
+    memset(&options, 0, sizeof(options));
+    options.gas_limit = 1000000;
+    options.unmetered_locals = 0;
+    options.max_memory_grow = 1;
+    options.max_memory_grow_delta = 1;
+    options.opcode_trace = false;
+    options.metering = true;
+    options.runtime_breakpoints = true;
+    options.opcode_costs = opcode_costs_v1;
+    options.max_stack_height = 100;
+    options.max_table_grow = 1;
+    options.max_table_grow_delta = 1;
+    options.nan_canonicalization = false;
+    options.simd = false;
+    options.threads = false;
+    options.bulk_memory = true;
+    options.multi_value = true;
+    options.reference_types = true;
+    options.memory_bound_check_mode = WASMER_MEMORY_BOUND_CHECK_MODE_DEFAULT;
+
+    uint64_t points_v1 = call_sum(&options, bytes, len);
+    assert(points_v1 > 0);
+
+    // Doubling every cost doubles the points used.
+    options.opcode_costs = opcode_costs_v2;
+    uint64_t points_v2 = call_sum(&options, bytes, len);
+    assert(points_v2 == 2 * points_v1);
+
+    // The default table is used when none is given.
+    result = wasmer_set_opcode_costs_with_length(costs_v1, OPCODE_COUNT);
+    assert(result == WASMER_OK);
+    options.opcode_costs = NULL;
+    assert(call_sum(&options, bytes, len) == points_v1);
+
+    // Without metering, nothing is charged.
+    options.metering = false;
+    assert(call_sum(&options, bytes, len) == 0);
+
+    // An unknown bound check mode fails the compilation.
+    wasmer_instance_t *instance = NULL;
+    options.memory_bound_check_mode = 3;
+    result = wasmer_instantiate_with_options(&instance, bytes, len, &options);
+    assert(result == WASMER_ERROR);
+
+    // The memory of `load.wasm` has no maximum, so it is dynamic and has no guard pages to rely
+    // on: its accesses are still checked.
+    wasm_file_t load_wasm = read_wasm_file("assets/load.wasm");
+    options.memory_bound_check_mode = WASMER_MEMORY_BOUND_CHECK_MODE_DISABLE;
+    result = wasmer_instantiate_with_options(&instance, load_wasm.bytes, load_wasm.bytes_len, &options);
+    assert(result == WASMER_OK);
+
+    wasmer_value_t address;
+    address.tag = WASM_I32;
+    address.value.I32 = 0;
+    wasmer_value_t params[] = {address};
+    wasmer_value_t loaded;
+    wasmer_value_t results[] = {loaded};
+
+    result = wasmer_instance_call(instance, "load", params, 1, results, 1);
+    assert(result == WASMER_OK);
+    assert(results[0].value.I32 == 0);
+
+    params[0].value.I32 = 0x7fff0000;
+    result = wasmer_instance_call(instance, "load", params, 1, results, 1);
+    printf("Out of bounds load result: %d\n", result);
+    assert(result == WASMER_ERROR);
+    wasmer_instance_destroy(instance);
+
+    wasmer_opcode_costs_destroy(opcode_costs_v1);
+    wasmer_opcode_costs_destroy(opcode_costs_v2);
+    free(sum_wasm.bytes);
+    free(load_wasm.bytes);
+    return 0;
+}
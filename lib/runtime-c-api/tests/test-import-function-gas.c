@@ -0,0 +1,92 @@
+#include <stdio.h>
+#include "../wasmer.h"
+#include <assert.h>
+#include <stdint.h>
+#include <string.h>
+
+static const uint64_t host_function_cost = 1000;
+
+void print_str(wasmer_instance_context_t *ctx, int32_t _ptr, int32_t _len)
+{
+    wasmer_result_t charge_result = wasmer_instance_context_charge_points(ctx, host_function_cost);
+    assert(charge_result == WASMER_OK);
+}
+
+int main()
+{
+    wasmer_value_tag params_sig[] = {WASM_I32, WASM_I32};
+    wasmer_value_tag returns_sig[] = {};
+
+    printf("Creating new func\n");
+    wasmer_import_func_t *func = wasmer_import_func_new((void (*)(void *)) print_str, params_sig, 2, returns_sig, 0);
+
+    char *module_name = "env";
+    wasmer_byte_array module_name_bytes = {
+        .bytes = (const uint8_t *) module_name,
+        .bytes_len = strlen(module_name),
+    };
+
+    char *import_name = "print_str";
+    wasmer_byte_array import_name_bytes = {
+        .bytes = (const uint8_t *) import_name,
+        .bytes_len = strlen(import_name),
+    };
+
+    wasmer_import_t import = {
+        .module_name = module_name_bytes,
+        .import_name = import_name_bytes,
+        .tag = WASM_FUNCTION,
+        .value.func = func,
+    };
+
+    wasmer_import_t imports[] = {import};
+
+    // Read the wasm file bytes
+    FILE *file = fopen("assets/wasm_sample_app.wasm", "r");
+    fseek(file, 0, SEEK_END);
+    long len = ftell(file);
+    uint8_t *bytes = malloc(len);
+    fseek(file, 0, SEEK_SET);
+    fread(bytes, 1, len, file);
+    fclose(file);
+
+    wasmer_module_t *module = NULL;
+    wasmer_result_t compile_result = wasmer_compile_with_gas_metering(&module, bytes, len);
+    printf("Compile result: %d\n", compile_result);
+    assert(compile_result == WASMER_OK);
+
+    wasmer_instance_t *instance = NULL;
+    wasmer_result_t instantiate_result = wasmer_module_instantiate(module, &instance, imports, 1);
+    printf("Instantiate result: %d\n", instantiate_result);
+    assert(instantiate_result == WASMER_OK);
+
+    wasmer_value_t params[] = {};
+    wasmer_value_t results[] = {};
+
+    // The host function is charged within the limit.
+    wasmer_instance_set_points_limit(instance, 10 * host_function_cost);
+    wasmer_result_t call_result = wasmer_instance_call(instance, "hello_wasm", params, 0, results, 0);
+    printf("Call result: %d\n", call_result);
+    assert(call_result == WASMER_OK);
+    assert(wasmer_instance_get_points_used(instance) >= host_function_cost);
+
+    // The host function exceeds the limit and traps like a metered block.
+    wasmer_instance_set_points_used(instance, 0);
+    wasmer_instance_set_points_limit(instance, host_function_cost / 2);
+    call_result = wasmer_instance_call(instance, "hello_wasm", params, 0, results, 0);
+    printf("Call result: %d\n", call_result);
+    assert(call_result == WASMER_ERROR);
+    assert(wasmer_last_error_kind() == WASMER_ERROR_KIND_OUT_OF_GAS);
+    assert(wasmer_instance_get_runtime_breakpoint_value(instance) == 4);
+
+    // A null context is rejected.
+    assert(wasmer_instance_context_charge_points(NULL, 1) == WASMER_ERROR);
+
+    printf("Destroying func\n");
+    wasmer_import_func_destroy(func);
+    printf("Destroy instance\n");
+    wasmer_instance_destroy(instance);
+    wasmer_module_destroy(module);
+
+    return 0;
+}
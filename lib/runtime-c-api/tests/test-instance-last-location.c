@@ -0,0 +1,106 @@
+#include <stdio.h>
+#include "../wasmer.h"
+#include <assert.h>
+#include <stdint.h>
+#include <string.h>
+
+typedef struct {
+    uint8_t* bytes;
+    long bytes_len;
+} wasm_file_t;
+
+wasm_file_t read_wasm_file(const char* file_name) {
+    wasm_file_t wasm_file;
+
+    FILE *file = fopen(file_name, "r");
+    fseek(file, 0, SEEK_END);
+    wasm_file.bytes_len = ftell(file);
+
+    wasm_file.bytes = malloc(wasm_file.bytes_len);
+    fseek(file, 0, SEEK_SET);
+    fread(wasm_file.bytes, 1, wasm_file.bytes_len, file);
+    fclose(file);
+
+    return wasm_file;
+}
+
+wasmer_instance_t *instantiate(wasm_file_t *wasm_file, wasmer_compilation_options_t *options)
+{
+    wasmer_instance_t *instance = NULL;
+    wasmer_result_t result = wasmer_instantiate_with_options(&instance, wasm_file->bytes, wasm_file->bytes_len, options);
+    printf("Instantiate result: %d\n", result);
+    assert(result == WASMER_OK);
+    return instance;
+}
+
+wasmer_result_t call_load(wasmer_instance_t *instance, int32_t address)
+{
+    wasmer_value_t param_one;
+    param_one.tag = WASM_I32;
+    param_one.value.I32 = address;
+    wasmer_value_t params[] = {param_one};
+    wasmer_value_t result_one;
+    wasmer_value_t results[] = {result_one};
+    return wasmer_instance_call(instance, "load", params, 1, results, 1);
+}
+
+int main()
+{
+    wasm_file_t load_wasm = read_wasm_file("assets/load.wasm");
+
+    wasmer_compilation_options_t options;
+    memset(&options, 0, sizeof(options));
+    options.memory_bound_check_mode = WASMER_MEMORY_BOUND_CHECK_MODE_DEFAULT;
+
+    wasmer_instance_location_t location;
+
+    // Without the option, the location is unknown and the error of the call is kept.
+    {
+        remove("opcode.trace");
+        wasmer_instance_t *instance = instantiate(&load_wasm, &options);
+        assert(call_load(instance, 0x7fff0000) == WASMER_ERROR);
+
+        int error_len = wasmer_last_error_length();
+        char *error_str = malloc(error_len);
+        wasmer_last_error_message(error_str, error_len);
+        wasmer_error_kind_t error_kind = wasmer_last_error_kind();
+
+        assert(wasmer_instance_last_location(instance, &location) == WASMER_ERROR);
+        assert(wasmer_last_error_length() == error_len);
+        char *error_str_after = malloc(error_len);
+        wasmer_last_error_message(error_str_after, error_len);
+        assert(strcmp(error_str, error_str_after) == 0);
+        assert(wasmer_last_error_kind() == error_kind);
+
+        free(error_str);
+        free(error_str_after);
+        wasmer_instance_destroy(instance);
+    }
+
+    // The location is tracked without writing any trace file.
+    {
+        wasmer_compilation_options_t last_location_options = options;
+        last_location_options.last_location = true;
+        wasmer_instance_t *instance = instantiate(&load_wasm, &last_location_options);
+        assert(fopen("opcode.trace", "r") == NULL);
+
+        assert(wasmer_instance_last_location(instance, &location) == WASMER_ERROR);
+
+        // The `i32.load` is at offset 43 of `load.wasm`, which has no name section.
+        assert(call_load(instance, 0x7fff0000) == WASMER_ERROR);
+        assert(wasmer_instance_last_location(instance, &location) == WASMER_OK);
+        printf("Trapped in function %u at offset %u\n", location.function_index, location.operator_offset);
+        assert(location.function_index == 0);
+        assert(location.operator_offset == 43);
+        assert(location.function_name.bytes_len == 0);
+
+        assert(call_load(instance, 0) == WASMER_OK);
+        assert(wasmer_instance_last_location(instance, &location) == WASMER_OK);
+        assert(location.operator_offset > 43);
+
+        wasmer_instance_destroy(instance);
+    }
+
+    free(load_wasm.bytes);
+    return 0;
+}
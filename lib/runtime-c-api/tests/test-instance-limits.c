@@ -0,0 +1,117 @@
+#include <stdio.h>
+#include "../wasmer.h"
+#include <assert.h>
+#include <stdint.h>
+#include <string.h>
+
+typedef struct {
+    uint8_t* bytes;
+    long bytes_len;
+} wasm_file_t;
+
+wasm_file_t read_wasm_file(const char* file_name) {
+    wasm_file_t wasm_file;
+
+    FILE *file = fopen(file_name, "r");
+    fseek(file, 0, SEEK_END);
+    wasm_file.bytes_len = ftell(file);
+
+    wasm_file.bytes = malloc(wasm_file.bytes_len);
+    fseek(file, 0, SEEK_SET);
+    fread(wasm_file.bytes, 1, wasm_file.bytes_len, file);
+    fclose(file);
+
+    return wasm_file;
+}
+
+wasmer_instance_t *instantiate(wasm_file_t *wasm_file, wasmer_compilation_options_t *options)
+{
+    wasmer_instance_t *instance = NULL;
+    wasmer_result_t result = wasmer_instantiate_with_options(&instance, wasm_file->bytes, wasm_file->bytes_len, options);
+    printf("Instantiate result: %d\n", result);
+    assert(result == WASMER_OK);
+    return instance;
+}
+
+wasmer_result_t call_recurse(wasmer_instance_t *instance, int32_t depth)
+{
+    wasmer_value_t param_one;
+    param_one.tag = WASM_I32;
+    param_one.value.I32 = depth;
+    wasmer_value_t params[] = {param_one};
+    wasmer_value_t results[] = {};
+    return wasmer_instance_call(instance, "recurse", params, 1, results, 0);
+}
+
+// Returns the previous size of the table, or -1 if the call failed.
+int32_t call_grow(wasmer_instance_t *instance, int32_t delta)
+{
+    wasmer_value_t param_one;
+    param_one.tag = WASM_I32;
+    param_one.value.I32 = delta;
+    wasmer_value_t params[] = {param_one};
+    wasmer_value_t result_one;
+    wasmer_value_t results[] = {result_one};
+    if (wasmer_instance_call(instance, "grow", params, 1, results, 1) != WASMER_OK) {
+        return -1;
+    }
+    return results[0].value.I32;
+}
+
+int main()
+{
+    wasmer_compilation_options_t options;
+    memset(&options, 0, sizeof(options));
+    options.runtime_breakpoints = true;
+    options.memory_bound_check_mode = WASMER_MEMORY_BOUND_CHECK_MODE_DEFAULT;
+
+    // The stack height limit.
+    {
+        // `recurse` has a frame cost of 2, one for the call and one for its param, and a call
+        // with `depth` enters `depth + 1` frames.
+        wasm_file_t recurse_wasm = read_wasm_file("assets/recurse.wasm");
+        wasmer_compilation_options_t stack_height_options = options;
+        stack_height_options.max_stack_height = 20;
+        wasmer_instance_t *instance = instantiate(&recurse_wasm, &stack_height_options);
+
+        assert(call_recurse(instance, 9) == WASMER_OK);
+        assert(call_recurse(instance, 10) == WASMER_ERROR);
+        assert(wasmer_last_error_kind() == WASMER_ERROR_KIND_STACK_HEIGHT_LIMIT);
+
+        // The frame costs left by the trap are dropped.
+        assert(call_recurse(instance, 9) == WASMER_OK);
+
+        wasmer_instance_destroy(instance);
+        free(recurse_wasm.bytes);
+    }
+
+    // The `table.grow` count and delta limits.
+    {
+        wasm_file_t grow_table_wasm = read_wasm_file("assets/grow_table.wasm");
+        wasmer_compilation_options_t table_grow_options = options;
+        table_grow_options.max_table_grow = 2;
+        table_grow_options.max_table_grow_delta = 3;
+        table_grow_options.bulk_memory = true;
+        table_grow_options.reference_types = true;
+        wasmer_instance_t *instance = instantiate(&grow_table_wasm, &table_grow_options);
+
+        assert(call_grow(instance, 3) == 0);
+        assert(call_grow(instance, 1) == 3);
+        // A third grow exceeds the count limit.
+        assert(call_grow(instance, 1) == -1);
+        assert(wasmer_last_error_kind() == WASMER_ERROR_KIND_TABLE_GROW_LIMIT);
+
+        // The reset gives back the grows, but not a larger delta.
+        assert(wasmer_instance_reset(instance) == WASMER_OK);
+        assert(call_grow(instance, 4) == -1);
+        assert(wasmer_last_error_kind() == WASMER_ERROR_KIND_TABLE_GROW_LIMIT);
+
+        assert(wasmer_instance_reset(instance) == WASMER_OK);
+        assert(call_grow(instance, 3) == 0);
+
+        wasmer_instance_destroy(instance);
+        free(grow_table_wasm.bytes);
+    }
+
+    return 0;
+}
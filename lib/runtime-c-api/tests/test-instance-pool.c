@@ -0,0 +1,173 @@
+#include <stdio.h>
+#include "../wasmer.h"
+#include <assert.h>
+#include <stdint.h>
+#include <string.h>
+
+typedef struct {
+    uint8_t* bytes;
+    long bytes_len;
+} wasm_file_t;
+
+wasm_file_t read_wasm_file(const char* file_name) {
+    wasm_file_t wasm_file;
+
+    FILE *file = fopen(file_name, "r");
+    fseek(file, 0, SEEK_END);
+    wasm_file.bytes_len = ftell(file);
+
+    wasm_file.bytes = malloc(wasm_file.bytes_len);
+    fseek(file, 0, SEEK_SET);
+    fread(wasm_file.bytes, 1, wasm_file.bytes_len, file);
+    fclose(file);
+
+    return wasm_file;
+}
+
+wasmer_instance_t *acquire(wasmer_instance_pool_t *pool, wasm_file_t *wasm_file, wasmer_compilation_options_t *options)
+{
+    wasmer_instance_t *instance = NULL;
+    wasmer_result_t result = wasmer_instance_pool_acquire(pool, &instance, wasm_file->bytes, wasm_file->bytes_len, options);
+    printf("Acquire result: %d\n", result);
+    return result == WASMER_OK ? instance : NULL;
+}
+
+int main()
+{
+    wasm_file_t sum_wasm = read_wasm_file("assets/sum.wasm");
+    wasm_file_t one_page_wasm = read_wasm_file("assets/one_page.wasm");
+    wasm_file_t get_answer_wasm = read_wasm_file("assets/get_answer.wasm");
+
+    wasmer_compilation_options_t options;
+    memset(&options, 0, sizeof(options));
+    options.memory_bound_check_mode = WASMER_MEMORY_BOUND_CHECK_MODE_DEFAULT;
+    wasmer_compilation_options_t other_options = options;
+    other_options.max_stack_height = 100;
+
+    // Released instances are reused.
+    {
+        wasmer_instance_pool_t *pool = NULL;
+        assert(wasmer_instance_pool_new(&pool, NULL, 3, 1 << 20) == WASMER_OK);
+
+        wasmer_instance_t *first = acquire(pool, &sum_wasm, &options);
+        wasmer_instance_t *second = acquire(pool, &sum_wasm, &options);
+        assert(first != NULL && second != NULL);
+        assert(first != second);
+
+        assert(wasmer_instance_pool_release(pool, first) == WASMER_OK);
+        wasmer_instance_t *reused = acquire(pool, &sum_wasm, &options);
+        assert(reused == first);
+
+        wasmer_value_t param_one;
+        param_one.tag = WASM_I32;
+        param_one.value.I32 = 1;
+        wasmer_value_t param_two;
+        param_two.tag = WASM_I32;
+        param_two.value.I32 = 2;
+        wasmer_value_t params[] = {param_one, param_two};
+        wasmer_value_t result_one;
+        wasmer_value_t results[] = {result_one};
+        assert(wasmer_instance_call(reused, "sum", params, 2, results, 1) == WASMER_OK);
+        assert(results[0].value.I32 == 3);
+        assert(wasmer_instance_pool_release(pool, reused) == WASMER_OK);
+
+        // Other options make other instances.
+        wasmer_instance_t *other = acquire(pool, &sum_wasm, &other_options);
+        assert(other != NULL && other != first);
+
+        // An instance released twice is refused the second time.
+        assert(wasmer_instance_pool_release(pool, other) == WASMER_OK);
+        assert(wasmer_instance_pool_release(pool, other) == WASMER_ERROR);
+        assert(wasmer_instance_pool_release(pool, second) == WASMER_OK);
+        wasmer_instance_pool_destroy(pool);
+    }
+
+    // The pool holds at most `max_instances` instances.
+    {
+        wasmer_instance_pool_t *pool = NULL;
+        assert(wasmer_instance_pool_new(&pool, NULL, 1, 1 << 20) == WASMER_OK);
+
+        wasmer_instance_t *sum = acquire(pool, &sum_wasm, &options);
+        assert(sum != NULL);
+        // The only instance allowed is acquired.
+        assert(acquire(pool, &one_page_wasm, &options) == NULL);
+
+        // The idle instance is destroyed to make room for another module.
+        assert(wasmer_instance_pool_release(pool, sum) == WASMER_OK);
+        wasmer_instance_t *one_page = acquire(pool, &one_page_wasm, &options);
+        assert(one_page != NULL);
+        assert(wasmer_instance_pool_release(pool, one_page) == WASMER_OK);
+        wasmer_instance_pool_destroy(pool);
+    }
+
+    // The memories of the instances must fit in the budget.
+    {
+        wasmer_instance_pool_t *pool = NULL;
+        assert(wasmer_instance_pool_new(&pool, NULL, 10, 65536) == WASMER_OK);
+
+        wasmer_instance_t *first = acquire(pool, &one_page_wasm, &options);
+        assert(first != NULL);
+        assert(acquire(pool, &one_page_wasm, &other_options) == NULL);
+        // An instance without memory still fits.
+        wasmer_instance_t *sum = acquire(pool, &sum_wasm, &options);
+        assert(sum != NULL);
+
+        // The idle instance is destroyed to make room for the other one.
+        assert(wasmer_instance_pool_release(pool, first) == WASMER_OK);
+        wasmer_instance_t *second = acquire(pool, &one_page_wasm, &other_options);
+        assert(second != NULL);
+
+        assert(wasmer_instance_pool_release(pool, second) == WASMER_OK);
+        assert(wasmer_instance_pool_release(pool, sum) == WASMER_OK);
+        wasmer_instance_pool_destroy(pool);
+    }
+
+    // The pool keeps its own reference to the imports.
+    {
+        const char *module_name = "env";
+        wasmer_byte_array module_name_bytes;
+        module_name_bytes.bytes = (const uint8_t *) module_name;
+        module_name_bytes.bytes_len = strlen(module_name);
+        const char *import_name = "answer";
+        wasmer_byte_array import_name_bytes;
+        import_name_bytes.bytes = (const uint8_t *) import_name;
+        import_name_bytes.bytes_len = strlen(import_name);
+
+        wasmer_value_t val;
+        val.tag = WASM_I32;
+        val.value.I32 = 42;
+        wasmer_global_t *global = wasmer_global_new(val, false);
+
+        wasmer_import_t global_import;
+        global_import.module_name = module_name_bytes;
+        global_import.import_name = import_name_bytes;
+        global_import.tag = WASM_GLOBAL;
+        global_import.value.global = global;
+
+        wasmer_import_object_t *import_object = wasmer_import_object_new();
+        wasmer_import_t imports[] = {global_import};
+        assert(wasmer_import_object_extend(import_object, imports, 1) == WASMER_OK);
+
+        wasmer_instance_pool_t *pool = NULL;
+        assert(wasmer_instance_pool_new(&pool, import_object, 1, 0) == WASMER_OK);
+        wasmer_import_object_destroy(import_object);
+
+        wasmer_instance_t *instance = acquire(pool, &get_answer_wasm, &options);
+        assert(instance != NULL);
+
+        wasmer_value_t params[] = {};
+        wasmer_value_t result_one;
+        wasmer_value_t results[] = {result_one};
+        assert(wasmer_instance_call(instance, "get", params, 0, results, 1) == WASMER_OK);
+        assert(results[0].value.I32 == 42);
+
+        assert(wasmer_instance_pool_release(pool, instance) == WASMER_OK);
+        wasmer_instance_pool_destroy(pool);
+        wasmer_global_destroy(global);
+    }
+
+    free(sum_wasm.bytes);
+    free(one_page_wasm.bytes);
+    free(get_answer_wasm.bytes);
+    return 0;
+}
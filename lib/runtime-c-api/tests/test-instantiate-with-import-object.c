@@ -0,0 +1,90 @@
+#include <stdio.h>
+#include "../wasmer.h"
+#include <assert.h>
+#include <stdint.h>
+#include <string.h>
+
+int32_t call_get(wasmer_instance_t *instance)
+{
+    wasmer_value_t params[] = {};
+    wasmer_value_t result_one;
+    wasmer_value_t results[] = {result_one};
+    wasmer_result_t call_result = wasmer_instance_call(instance, "get", params, 0, results, 1);
+    printf("Call result:  %d\n", call_result);
+    assert(call_result == WASMER_OK);
+    return results[0].value.I32;
+}
+
+int main()
+{
+    // Read the wasm file bytes
+    FILE *file = fopen("assets/get_answer.wasm", "r");
+    fseek(file, 0, SEEK_END);
+    long len = ftell(file);
+    uint8_t *bytes = malloc(len);
+    fseek(file, 0, SEEK_SET);
+    fread(bytes, 1, len, file);
+    fclose(file);
+
+    // Define the `env.answer` global imported by the module
+    const char *module_name = "env";
+    wasmer_byte_array module_name_bytes;
+    module_name_bytes.bytes = (const uint8_t *) module_name;
+    module_name_bytes.bytes_len = strlen(module_name);
+    const char *import_name = "answer";
+    wasmer_byte_array import_name_bytes;
+    import_name_bytes.bytes = (const uint8_t *) import_name;
+    import_name_bytes.bytes_len = strlen(import_name);
+
+    wasmer_value_t val;
+    val.tag = WASM_I32;
+    val.value.I32 = 42;
+    wasmer_global_t *global = wasmer_global_new(val, false);
+
+    wasmer_import_t global_import;
+    global_import.module_name = module_name_bytes;
+    global_import.import_name = import_name_bytes;
+    global_import.tag = WASM_GLOBAL;
+    global_import.value.global = global;
+
+    wasmer_import_object_t *import_object = wasmer_import_object_new();
+    wasmer_import_t imports[] = {global_import};
+    assert(wasmer_import_object_extend(import_object, imports, 1) == WASMER_OK);
+
+    wasmer_compilation_options_t options;
+    memset(&options, 0, sizeof(options));
+    options.memory_bound_check_mode = WASMER_MEMORY_BOUND_CHECK_MODE_DEFAULT;
+
+    // The default import object does not provide the global.
+    wasmer_instance_t *instance = NULL;
+    wasmer_result_t result = wasmer_instantiate_with_options_and_import_object(&instance, bytes, len, &options, NULL);
+    printf("Instantiate with the default import object result: %d\n", result);
+    assert(result == WASMER_ERROR);
+
+    result = wasmer_instantiate_with_options_and_import_object(&instance, bytes, len, &options, import_object);
+    printf("Instantiate result: %d\n", result);
+    assert(result == WASMER_OK);
+    assert(call_get(instance) == 42);
+
+    // The same goes for the instances created from the cache.
+    const uint8_t *cache_bytes = NULL;
+    uint32_t cache_len = 0;
+    result = wasmer_instance_cache(instance, &cache_bytes, &cache_len);
+    assert(result == WASMER_OK);
+    wasmer_instance_destroy(instance);
+
+    wasmer_instance_t *cached_instance = NULL;
+    result = wasmer_instance_from_cache_with_import_object(&cached_instance, (uint8_t *) cache_bytes, cache_len, &options, NULL);
+    assert(result == WASMER_ERROR);
+
+    result = wasmer_instance_from_cache_with_import_object(&cached_instance, (uint8_t *) cache_bytes, cache_len, &options, import_object);
+    printf("Instantiate from cache result: %d\n", result);
+    assert(result == WASMER_OK);
+    assert(call_get(cached_instance) == 42);
+    wasmer_instance_destroy(cached_instance);
+
+    wasmer_import_object_destroy(import_object);
+    wasmer_global_destroy(global);
+    free(bytes);
+    return 0;
+}
@@ -0,0 +1,141 @@
+#include <stdio.h>
+#include "../wasmer.h"
+#include <assert.h>
+#include <stdint.h>
+#include <string.h>
+
+wasmer_result_t call_with_i32(wasmer_instance_t *instance, const char *name, int32_t param, uint32_t results_len)
+{
+    wasmer_value_t param_one;
+    param_one.tag = WASM_I32;
+    param_one.value.I32 = param;
+    wasmer_value_t params[] = {param_one};
+    wasmer_value_t result_one;
+    wasmer_value_t results[] = {result_one};
+    return wasmer_instance_call(instance, name, params, 1, results, results_len);
+}
+
+wasmer_instance_t *instantiate_metered(uint8_t *bytes, long len, wasmer_opcode_costs_t *opcode_costs)
+{
+    wasmer_compilation_options_t options;
+    memset(&options, 0, sizeof(options));
+    options.gas_limit = 1000000;
+    options.metering = true;
+    options.max_memory_grow = 10;
+    options.max_memory_grow_delta = 10;
+    options.bulk_memory = true;
+    options.opcode_costs = opcode_costs;
+    options.memory_bound_check_mode = WASMER_MEMORY_BOUND_CHECK_MODE_DEFAULT;
+
+    wasmer_instance_t *instance = NULL;
+    wasmer_result_t result = wasmer_instantiate_with_options(&instance, bytes, len, &options);
+    printf("Instantiate result: %d\n", result);
+    assert(result == WASMER_OK);
+    return instance;
+}
+
+int main()
+{
+    uint32_t costs_v1[OPCODE_COUNT];
+    uint32_t costs_v2[OPCODE_COUNT];
+    for (int i = 0; i < OPCODE_COUNT; i++) {
+        costs_v1[i] = 1;
+        costs_v2[i] = 2;
+    }
+
+    // Two schedules can be alive side by side.
+    wasmer_opcode_costs_t *opcode_costs_v1 = NULL;
+    wasmer_result_t result = wasmer_opcode_costs_new(&opcode_costs_v1, costs_v1, 1);
+    printf("Create v1 result: %d\n", result);
+    assert(result == WASMER_OK);
+
+    wasmer_opcode_costs_t *opcode_costs_v2 = NULL;
+    result = wasmer_opcode_costs_new(&opcode_costs_v2, costs_v2, 2);
+    printf("Create v2 result: %d\n", result);
+    assert(result == WASMER_OK);
+
+    assert(wasmer_opcode_costs_version(opcode_costs_v1) == 1);
+    assert(wasmer_opcode_costs_version(opcode_costs_v2) == 2);
+    assert(wasmer_opcode_costs_version(NULL) == 0);
+
+    // A null cost array is rejected.
+    wasmer_opcode_costs_t *opcode_costs_invalid = NULL;
+    result = wasmer_opcode_costs_new(&opcode_costs_invalid, NULL, 3);
+    assert(result == WASMER_ERROR);
+    assert(opcode_costs_invalid == NULL);
+
+    int error_len = wasmer_last_error_length();
+    char *error_str = malloc(error_len);
+    wasmer_last_error_message(error_str, error_len);
+    printf("Error str: `%s`\n", error_str);
+    assert(0 == strcmp(error_str, "opcode costs pointer is null"));
+    free(error_str);
+
+    // The costs of the memory operators are set through the full-length table only, and any
+    // other length is rejected before the array is read.
+    wasmer_opcode_costs_t *opcode_costs_v3 = NULL;
+    result = wasmer_opcode_costs_new_with_length(&opcode_costs_v3, costs_v1, OPCODE_COUNT, 3);
+    assert(result == WASMER_OK);
+    assert(wasmer_opcode_costs_version(opcode_costs_v3) == 3);
+
+    result = wasmer_opcode_costs_new_with_length(&opcode_costs_invalid, costs_v1, LEGACY_OPCODE_COUNT, 4);
+    assert(result == WASMER_OK);
+    wasmer_opcode_costs_destroy(opcode_costs_invalid);
+
+    opcode_costs_invalid = NULL;
+    result = wasmer_opcode_costs_new_with_length(&opcode_costs_invalid, costs_v1, OPCODE_COUNT + 1, 5);
+    assert(result == WASMER_ERROR);
+    assert(opcode_costs_invalid == NULL);
+
+    result = wasmer_set_opcode_costs_with_length(costs_v1, 3);
+    assert(result == WASMER_ERROR);
+    result = wasmer_set_opcode_costs_with_length(costs_v1, OPCODE_COUNT);
+    assert(result == WASMER_OK);
+
+    // The memory operators are charged per page grown and per byte filled.
+    FILE *file = fopen("assets/grow_and_fill.wasm", "r");
+    fseek(file, 0, SEEK_END);
+    long len = ftell(file);
+    uint8_t *bytes = malloc(len);
+    fseek(file, 0, SEEK_SET);
+    fread(bytes, 1, len, file);
+    fclose(file);
+
+    uint32_t per_unit_costs[OPCODE_COUNT];
+    memset(per_unit_costs, 0, sizeof(per_unit_costs));
+    per_unit_costs[LEGACY_OPCODE_COUNT] = 10; // memory.grow
+    per_unit_costs[LEGACY_OPCODE_COUNT + 2] = 3; // memory.fill
+
+    wasmer_opcode_costs_t *opcode_costs_per_unit = NULL;
+    result = wasmer_opcode_costs_new_with_length(&opcode_costs_per_unit, per_unit_costs, OPCODE_COUNT, 6);
+    assert(result == WASMER_OK);
+    wasmer_instance_t *instance = instantiate_metered(bytes, len, opcode_costs_per_unit);
+    // Modules keep their own reference to the table.
+    wasmer_opcode_costs_destroy(opcode_costs_per_unit);
+
+    assert(call_with_i32(instance, "grow", 2, 1) == WASMER_OK);
+    assert(wasmer_instance_get_points_used(instance) == 20);
+    assert(call_with_i32(instance, "fill", 100, 0) == WASMER_OK);
+    assert(wasmer_instance_get_points_used(instance) == 320);
+    wasmer_instance_destroy(instance);
+
+    // The legacy length leaves the per-unit costs at 0.
+    wasmer_opcode_costs_t *opcode_costs_legacy = NULL;
+    result = wasmer_opcode_costs_new(&opcode_costs_legacy, per_unit_costs, 7);
+    assert(result == WASMER_OK);
+    instance = instantiate_metered(bytes, len, opcode_costs_legacy);
+    wasmer_opcode_costs_destroy(opcode_costs_legacy);
+
+    assert(call_with_i32(instance, "grow", 2, 1) == WASMER_OK);
+    assert(call_with_i32(instance, "fill", 100, 0) == WASMER_OK);
+    assert(wasmer_instance_get_points_used(instance) == 0);
+    wasmer_instance_destroy(instance);
+    free(bytes);
+
+    wasmer_opcode_costs_destroy(opcode_costs_v1);
+    wasmer_opcode_costs_destroy(opcode_costs_v2);
+    wasmer_opcode_costs_destroy(opcode_costs_v3);
+    wasmer_opcode_costs_destroy(NULL);
+
+    return 0;
+}
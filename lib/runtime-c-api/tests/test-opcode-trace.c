@@ -0,0 +1,121 @@
+#include <stdio.h>
+#include "../wasmer.h"
+#include <assert.h>
+#include <stdint.h>
+#include <string.h>
+
+typedef struct {
+    uint8_t* bytes;
+    long bytes_len;
+} wasm_file_t;
+
+wasm_file_t read_wasm_file(const char* file_name) {
+    wasm_file_t wasm_file;
+
+    FILE *file = fopen(file_name, "r");
+    fseek(file, 0, SEEK_END);
+    wasm_file.bytes_len = ftell(file);
+
+    wasm_file.bytes = malloc(wasm_file.bytes_len);
+    fseek(file, 0, SEEK_SET);
+    fread(wasm_file.bytes, 1, wasm_file.bytes_len, file);
+    fclose(file);
+
+    return wasm_file;
+}
+
+void call_sum(wasmer_instance_t *instance)
+{
+    wasmer_value_t param_one;
+    param_one.tag = WASM_I32;
+    param_one.value.I32 = 7;
+    wasmer_value_t param_two;
+    param_two.tag = WASM_I32;
+    param_two.value.I32 = 8;
+    wasmer_value_t params[] = {param_one, param_two};
+    wasmer_value_t result_one;
+    wasmer_value_t results[] = {result_one};
+    wasmer_result_t call_result = wasmer_instance_call(instance, "sum", params, 2, results, 1);
+    printf("Call result:  %d\n", call_result);
+    assert(call_result == WASMER_OK);
+    assert(results[0].value.I32 == 15);
+}
+
+int main()
+{
+    wasm_file_t sum_wasm = read_wasm_file("assets/sum.wasm");
+
+    wasmer_compilation_options_t options;
+    memset(&options, 0, sizeof(options));
+    options.runtime_breakpoints = true;
+    options.memory_bound_check_mode = WASMER_MEMORY_BOUND_CHECK_MODE_DEFAULT;
+
+    // The binary trace is written to the given path, one 12-byte record per operator.
+    remove("opcode.trace");
+    wasmer_compilation_options_t file_options = options;
+    file_options.opcode_trace = true;
+    file_options.opcode_trace_path = "opcode-trace.bin";
+    file_options.opcode_trace_format = WASMER_OPCODE_TRACE_FORMAT_BINARY;
+    wasmer_instance_t *instance = NULL;
+    wasmer_result_t result = wasmer_instantiate_with_options(&instance, sum_wasm.bytes, sum_wasm.bytes_len, &file_options);
+    printf("Instantiate with a trace file result: %d\n", result);
+    assert(result == WASMER_OK);
+    wasmer_instance_destroy(instance);
+
+    // `sum` is made of `local.get`, `local.get`, `i32.add` and `end`.
+    uint32_t records[4][3];
+    FILE *trace = fopen("opcode-trace.bin", "rb");
+    assert(trace != NULL);
+    assert(fread(records, 12, 4, trace) == 4);
+    assert(fgetc(trace) == EOF);
+    fclose(trace);
+    remove("opcode-trace.bin");
+    for (uint32_t i = 0; i < 4; i++) {
+        assert(records[i][0] == 0);
+        assert(records[i][1] == i);
+    }
+    assert(fopen("opcode.trace", "r") == NULL);
+
+    // A trace file which cannot be created fails the compilation.
+    file_options.opcode_trace_path = "missing-directory/opcode.trace";
+    result = wasmer_instantiate_with_options(&instance, sum_wasm.bytes, sum_wasm.bytes_len, &file_options);
+    assert(result == WASMER_ERROR);
+
+    // An unknown format fails the compilation.
+    file_options.opcode_trace_path = "opcode-trace.bin";
+    file_options.opcode_trace_format = 2;
+    result = wasmer_instantiate_with_options(&instance, sum_wasm.bytes, sum_wasm.bytes_len, &file_options);
+    assert(result == WASMER_ERROR);
+
+    // The runtime buffer keeps the last operators executed, without writing any file.
+    wasmer_opcode_trace_buffer_t *buffer = NULL;
+    assert(wasmer_opcode_trace_buffer_new(&buffer, 2) == WASMER_OK);
+    wasmer_compilation_options_t buffer_options = options;
+    buffer_options.opcode_trace_buffer = buffer;
+    result = wasmer_instantiate_with_options(&instance, sum_wasm.bytes, sum_wasm.bytes_len, &buffer_options);
+    printf("Instantiate with a trace buffer result: %d\n", result);
+    assert(result == WASMER_OK);
+    assert(fopen("opcode.trace", "r") == NULL);
+
+    wasmer_opcode_trace_entry_t entries[4];
+    assert(wasmer_opcode_trace_buffer_entries(buffer, entries, 4) == 0);
+
+    call_sum(instance);
+    assert(wasmer_opcode_trace_buffer_entries(buffer, entries, 4) == 2);
+    assert(entries[0].local_function_index == 0 && entries[0].operator_index == 2);
+    assert(entries[1].local_function_index == 0 && entries[1].operator_index == 3);
+
+    // Only `entries_len` entries are copied, but all of them are counted.
+    memset(entries, 0, sizeof(entries));
+    assert(wasmer_opcode_trace_buffer_entries(buffer, entries, 1) == 2);
+    assert(entries[0].operator_index == 2);
+    assert(entries[1].operator_index == 0);
+
+    wasmer_opcode_trace_buffer_clear(buffer);
+    assert(wasmer_opcode_trace_buffer_entries(buffer, entries, 4) == 0);
+
+    wasmer_instance_destroy(instance);
+    wasmer_opcode_trace_buffer_destroy(buffer);
+    free(sum_wasm.bytes);
+    return 0;
+}
@@ -0,0 +1,100 @@
+#include <stdio.h>
+#include "../wasmer.h"
+#include <assert.h>
+#include <stdint.h>
+#include <string.h>
+
+wasmer_byte_array byte_array(const char *string)
+{
+    wasmer_byte_array bytes;
+    bytes.bytes = (const uint8_t *) string;
+    bytes.bytes_len = strlen(string);
+    return bytes;
+}
+
+wasmer_limit_option_t limit(uint32_t value)
+{
+    wasmer_limit_option_t limit;
+    limit.has_some = true;
+    limit.some = value;
+    return limit;
+}
+
+int main()
+{
+    // Read the wasm file bytes
+    FILE *file = fopen("assets/policy.wasm", "r");
+    fseek(file, 0, SEEK_END);
+    long len = ftell(file);
+    uint8_t *bytes = malloc(len);
+    fseek(file, 0, SEEK_SET);
+    fread(bytes, 1, len, file);
+    fclose(file);
+
+    // The default policy accepts every module.
+    wasmer_module_policy_t policy;
+    memset(&policy, 0, sizeof(policy));
+    wasmer_policy_violation_t violations[16];
+    uint32_t violations_count = 42;
+    wasmer_result_t result = wasmer_validate_with_policy(bytes, len, &policy, violations, 16, &violations_count);
+    printf("Validate with the default policy result: %d\n", result);
+    assert(result == WASMER_OK);
+    assert(violations_count == 0);
+
+    wasmer_policy_import_t allowed_imports[2];
+    allowed_imports[0].module_name = byte_array("env");
+    allowed_imports[0].import_name = byte_array("allowed");
+    allowed_imports[1].module_name = byte_array("env");
+    allowed_imports[1].import_name = byte_array("answer");
+    wasmer_byte_array required_exports[] = {byte_array("main"), byte_array("init")};
+
+    policy.allowed_imports = allowed_imports;
+    policy.allowed_imports_len = 2;
+    policy.max_functions = limit(1);
+    policy.max_initial_memory_pages = limit(1);
+    policy.max_maximum_memory_pages = limit(5);
+    policy.forbid_start_function = true;
+    policy.forbid_floats = true;
+    policy.required_exports = required_exports;
+    policy.required_exports_len = 2;
+
+    result = wasmer_validate_with_policy(bytes, len, &policy, violations, 16, &violations_count);
+    printf("Validate result: %d\n", result);
+    assert(result == WASMER_OK);
+    printf("Violations: %u\n", violations_count);
+    assert(violations_count == 11);
+
+    wasmer_policy_violation_t expected[] = {
+        {WASMER_POLICY_VIOLATION_FLOAT_SIGNATURE, 1},
+        {WASMER_POLICY_VIOLATION_FORBIDDEN_IMPORT, 1},
+        {WASMER_POLICY_VIOLATION_FLOAT_GLOBAL, 0},
+        {WASMER_POLICY_VIOLATION_INITIAL_MEMORY_TOO_LARGE, 0},
+        {WASMER_POLICY_VIOLATION_MAXIMUM_MEMORY_TOO_LARGE, 0},
+        {WASMER_POLICY_VIOLATION_FLOAT_GLOBAL, 1},
+        {WASMER_POLICY_VIOLATION_START_FUNCTION, 2},
+        {WASMER_POLICY_VIOLATION_FLOAT_LOCAL, 3},
+        {WASMER_POLICY_VIOLATION_FLOAT_OPERATOR, 3},
+        {WASMER_POLICY_VIOLATION_TOO_MANY_FUNCTIONS, 2},
+        {WASMER_POLICY_VIOLATION_MISSING_EXPORT, 1},
+    };
+    for (uint32_t i = 0; i < violations_count; i++) {
+        printf("Violation %u: kind %u, index %u\n", i, violations[i].kind, violations[i].index);
+        assert(violations[i].kind == expected[i].kind);
+        assert(violations[i].index == expected[i].index);
+    }
+
+    // The count covers the violations that do not fit.
+    memset(violations, 0, sizeof(violations));
+    result = wasmer_validate_with_policy(bytes, len, &policy, violations, 2, &violations_count);
+    assert(result == WASMER_OK);
+    assert(violations_count == 11);
+    assert(violations[1].kind == WASMER_POLICY_VIOLATION_FORBIDDEN_IMPORT);
+    assert(violations[2].kind == 0 && violations[2].index == 0);
+
+    // Malformed bytes are rejected.
+    result = wasmer_validate_with_policy(bytes, 9, &policy, violations, 16, &violations_count);
+    assert(result == WASMER_ERROR);
+
+    free(bytes);
+    return 0;
+}
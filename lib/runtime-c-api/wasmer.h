@@ -38,7 +38,9 @@
 #include <stdint.h>
 #include <stdlib.h>
 
-#define OPCODE_COUNT 448
+#define LEGACY_OPCODE_COUNT 448
+
+#define OPCODE_COUNT 452
 
 #if defined(WASMER_WASI_ENABLED)
 enum Version {
@@ -63,6 +65,81 @@ enum Version {
 typedef uint8_t Version;
 #endif
 
+/**
+ * The kind of the last error, as returned by `wasmer_last_error_kind()`.
+ *
+ * It allows classifying the failure of a call without matching on
+ * the error message.
+ */
+enum wasmer_error_kind_t {
+  /**
+   * No error happened.
+   */
+  WASMER_ERROR_KIND_NONE = 0,
+  /**
+   * The error did not happen while running WebAssembly code, e.g.
+   * a compilation error or an invalid argument.
+   */
+  WASMER_ERROR_KIND_OTHER = 1,
+  /**
+   * The gas limit was exceeded.
+   */
+  WASMER_ERROR_KIND_OUT_OF_GAS = 2,
+  /**
+   * The limits on `memory.grow` were exceeded.
+   */
+  WASMER_ERROR_KIND_MEMORY_GROW_LIMIT = 3,
+  /**
+   * A host function trapped, e.g. with `wasmer_trap()`.
+   */
+  WASMER_ERROR_KIND_USER_TRAP = 4,
+  /**
+   * The `unreachable` instruction was executed.
+   */
+  WASMER_ERROR_KIND_UNREACHABLE = 5,
+  /**
+   * A memory access was out of bounds.
+   */
+  WASMER_ERROR_KIND_MEMORY_OUT_OF_BOUNDS = 6,
+  /**
+   * An arithmetic operation was illegal, e.g. a division by zero.
+   */
+  WASMER_ERROR_KIND_ILLEGAL_ARITHMETIC = 7,
+  /**
+   * A `call_indirect` was out of bounds or had the wrong signature.
+   */
+  WASMER_ERROR_KIND_INDIRECT_CALL = 8,
+  /**
+   * An atomic access was misaligned.
+   */
+  WASMER_ERROR_KIND_MISALIGNED_ATOMIC_ACCESS = 9,
+  /**
+   * The native stack was exhausted.
+   */
+  WASMER_ERROR_KIND_STACK_OVERFLOW = 10,
+  /**
+   * A host function panicked.
+   */
+  WASMER_ERROR_KIND_HOST_PANIC = 11,
+  /**
+   * The WebAssembly code failed for an unknown reason.
+   */
+  WASMER_ERROR_KIND_UNKNOWN = 12,
+  /**
+   * The call depth limit was exceeded.
+   */
+  WASMER_ERROR_KIND_STACK_HEIGHT_LIMIT = 13,
+  /**
+   * A table access was out of bounds, e.g. by `table.copy` or `table.init`.
+   */
+  WASMER_ERROR_KIND_TABLE_OUT_OF_BOUNDS = 14,
+  /**
+   * The limits on `table.grow` were exceeded.
+   */
+  WASMER_ERROR_KIND_TABLE_GROW_LIMIT = 15,
+};
+typedef uint32_t wasmer_error_kind_t;
+
 /**
  * List of export/import kinds.
  */
@@ -86,6 +163,95 @@ enum wasmer_import_export_kind {
 };
 typedef uint32_t wasmer_import_export_kind;
 
+/**
+ * How the memory accesses are bound checked, as set in the compilation options. Compilation
+ * fails for any other value.
+ */
+enum wasmer_memory_bound_check_mode_t {
+  /**
+   * Let the backend decide, usually relying on guard pages for static memories.
+   */
+  WASMER_MEMORY_BOUND_CHECK_MODE_DEFAULT = 0,
+  /**
+   * Always emit explicit bound checks.
+   */
+  WASMER_MEMORY_BOUND_CHECK_MODE_ENABLE = 1,
+  /**
+   * Never emit explicit bound checks for the memories protected by guard pages; dynamic
+   * memories are still checked.
+   */
+  WASMER_MEMORY_BOUND_CHECK_MODE_DISABLE = 2,
+};
+typedef uint32_t wasmer_memory_bound_check_mode_t;
+
+/**
+ * The format of the trace file written while compiling, as set in the compilation options.
+ * Compilation fails for any other value.
+ */
+enum wasmer_opcode_trace_format_t {
+  /**
+   * One human-readable line per operator.
+   */
+  WASMER_OPCODE_TRACE_FORMAT_TEXT = 0,
+  /**
+   * One 12-byte record per operator: the local function index, the operator index in the
+   * function and the offset of the operator in the wasm binary, as little-endian `u32`s.
+   */
+  WASMER_OPCODE_TRACE_FORMAT_BINARY = 1,
+};
+typedef uint32_t wasmer_opcode_trace_format_t;
+
+/**
+ * The kind of a `wasmer_policy_violation_t`.
+ */
+enum wasmer_policy_violation_kind_t {
+  /**
+   * An import is not allowed; the index is the one of the import.
+   */
+  WASMER_POLICY_VIOLATION_FORBIDDEN_IMPORT = 0,
+  /**
+   * The module defines too many functions; the index is the number of functions.
+   */
+  WASMER_POLICY_VIOLATION_TOO_MANY_FUNCTIONS = 1,
+  /**
+   * A function declares too many locals; the index is the one of the function.
+   */
+  WASMER_POLICY_VIOLATION_TOO_MANY_LOCALS = 2,
+  /**
+   * A memory is initially too large; the index is the one of the memory.
+   */
+  WASMER_POLICY_VIOLATION_INITIAL_MEMORY_TOO_LARGE = 3,
+  /**
+   * A memory may grow too large; the index is the one of the memory.
+   */
+  WASMER_POLICY_VIOLATION_MAXIMUM_MEMORY_TOO_LARGE = 4,
+  /**
+   * The module has a start function; the index is the one of the function.
+   */
+  WASMER_POLICY_VIOLATION_START_FUNCTION = 5,
+  /**
+   * A function uses float operators; the index is the one of the function.
+   */
+  WASMER_POLICY_VIOLATION_FLOAT_OPERATOR = 6,
+  /**
+   * A required export is missing; the index is the one of the export in the policy.
+   */
+  WASMER_POLICY_VIOLATION_MISSING_EXPORT = 7,
+  /**
+   * A function type takes or returns floats; the index is the one of the type.
+   */
+  WASMER_POLICY_VIOLATION_FLOAT_SIGNATURE = 8,
+  /**
+   * A global holds a float; the index is the one of the global, imports included.
+   */
+  WASMER_POLICY_VIOLATION_FLOAT_GLOBAL = 9,
+  /**
+   * A function declares float locals; the index is the one of the function.
+   */
+  WASMER_POLICY_VIOLATION_FLOAT_LOCAL = 10,
+};
+typedef uint32_t wasmer_policy_violation_kind_t;
+
 /**
  * The `wasmer_result_t` enum is a type that represents either a
  * success, or a failure.
@@ -123,6 +289,14 @@ enum wasmer_value_tag {
    * Represents the `f64` WebAssembly type.
    */
   WASM_F64,
+  /**
+   * Represents the `externref` WebAssembly type.
+   */
+  WASM_EXTERNREF,
+  /**
+   * Represents the `funcref` WebAssembly type.
+   */
+  WASM_FUNCREF,
 };
 typedef uint32_t wasmer_value_tag;
 
@@ -146,6 +320,25 @@ typedef struct {
   uint32_t bytes_len;
 } wasmer_byte_array;
 
+/**
+ * A frame of the backtrace of the last error, see `wasmer_last_error_backtrace_frame()`.
+ */
+typedef struct {
+  /**
+   * The index of the function, imported functions included.
+   */
+  uint32_t function_index;
+  /**
+   * The index of the current operator in the function body.
+   */
+  uint32_t operator_index;
+  /**
+   * The name of the function from the name section, empty if it has none. It stays valid
+   * until the next error.
+   */
+  wasmer_byte_array function_name;
+} wasmer_backtrace_frame_t;
+
 #if defined(WASMER_EMSCRIPTEN_ENABLED)
 /**
  * Type used to construct an import_object_t with Emscripten imports.
@@ -193,6 +386,15 @@ typedef union {
   int64_t I64;
   float F32;
   double F64;
+  /**
+   * An opaque reference to a host value; `0` is the null reference.
+   */
+  uint64_t EXTERNREF;
+  /**
+   * A reference to a function, opaque to the host, which can only pass the null reference
+   * `0` to WebAssembly.
+   */
+  uint64_t FUNCREF;
 } wasmer_value;
 
 /**
@@ -335,8 +537,172 @@ typedef struct {
 
 } wasmer_instance_context_t;
 
+/**
+ * The location of the last operator executed by an instance, see
+ * `wasmer_instance_last_location()`.
+ */
+typedef struct {
+  /**
+   * The index of the function, imported functions included.
+   */
+  uint32_t function_index;
+  /**
+   * The offset of the operator in the wasm binary.
+   */
+  uint32_t operator_offset;
+  /**
+   * The name of the function from the name section, empty if it has none. It is owned by
+   * the instance.
+   */
+  wasmer_byte_array function_name;
+} wasmer_instance_location_t;
+
+/**
+ * Opaque pointer to an `InstancePool` in Rust.
+ *
+ * A pool hands out instances through `wasmer_instance_pool_acquire()` and takes them back
+ * through `wasmer_instance_pool_release()`, which resets them so that the next acquisition
+ * gets an instance indistinguishable from a fresh one.
+ *
+ * A pool is not thread-safe: it must only be used from one thread at a time.
+ */
+typedef struct {
+
+} wasmer_instance_pool_t;
+
+/**
+ * Opaque pointer to a shared `OpcodeCosts` table in Rust.
+ *
+ * A cost table is created with `wasmer_opcode_costs_new()` and passed to the compilation
+ * through `wasmer_compilation_options_t`. Modules compiled with different tables use different
+ * gas schedules, even within the same process.
+ */
+typedef struct {
+
+} wasmer_opcode_costs_t;
+
+/**
+ * Opaque pointer to an `OpcodeTraceBuffer` in Rust.
+ *
+ * A buffer is created with `wasmer_opcode_trace_buffer_new()` and passed to the compilation
+ * through `wasmer_compilation_options_t`. Every instance of the modules compiled with it records
+ * the operators it executes into the buffer.
+ */
 typedef struct {
 
+} wasmer_opcode_trace_buffer_t;
+
+/**
+ * An operator executed at runtime, as recorded in a `wasmer_opcode_trace_buffer_t`.
+ */
+typedef struct {
+  /**
+   * The index of the function among the functions defined by the module.
+   */
+  uint32_t local_function_index;
+  /**
+   * The index of the operator in the function body.
+   */
+  uint32_t operator_index;
+} wasmer_opcode_trace_entry_t;
+
+/**
+ * The options a module is compiled with.
+ */
+typedef struct {
+  /**
+   * The number of points each instance may use, when metered.
+   */
+  uint64_t gas_limit;
+  /**
+   * The number of locals of each function that are not charged for.
+   */
+  uintptr_t unmetered_locals;
+  /**
+   * The number of `memory.grow` executions allowed between two resets of an instance.
+   */
+  uintptr_t max_memory_grow;
+  /**
+   * The largest delta accepted by a single `memory.grow`.
+   */
+  uintptr_t max_memory_grow_delta;
+  /**
+   * Whether the operators are written to a trace file while compiling, see
+   * `opcode_trace_path` and `opcode_trace_format`.
+   */
+  bool opcode_trace;
+  /**
+   * Whether the executed operators are charged for, following `opcode_costs`.
+   */
+  bool metering;
+  /**
+   * Whether the runtime breakpoints, which the gas and grow limits rely on, are enabled.
+   */
+  bool runtime_breakpoints;
+  /**
+   * The cost table used for metering; the default one set by `wasmer_set_opcode_costs()`
+   * is used when null.
+   */
+  const wasmer_opcode_costs_t *opcode_costs;
+  /**
+   * The call depth limit enforced by the stack height middleware, which is left out when 0.
+   */
+  uintptr_t max_stack_height;
+  /**
+   * The number of `table.grow` executions allowed between two resets of an instance.
+   */
+  uintptr_t max_table_grow;
+  /**
+   * The largest delta accepted by a single `table.grow`.
+   */
+  uintptr_t max_table_grow_delta;
+  /**
+   * Whether float operators are accepted, with their NaN results canonicalized so that they
+   * are bit-exact across CPUs.
+   */
+  bool nan_canonicalization;
+  /**
+   * Whether the SIMD proposal is accepted.
+   */
+  bool simd;
+  /**
+   * Whether the threads proposal is accepted.
+   */
+  bool threads;
+  /**
+   * Whether the bulk memory proposal is accepted.
+   */
+  bool bulk_memory;
+  /**
+   * Whether the multi-value proposal is accepted.
+   */
+  bool multi_value;
+  /**
+   * Whether the reference types proposal is accepted.
+   */
+  bool reference_types;
+  /**
+   * How the memory accesses are bound checked, as a `wasmer_memory_bound_check_mode_t`.
+   */
+  uint32_t memory_bound_check_mode;
+  /**
+   * The trace file written when `opcode_trace` is set, as a null-terminated UTF-8 path;
+   * `opcode.trace` in the working directory when null.
+   */
+  const char *opcode_trace_path;
+  /**
+   * The format of the trace file, as a `wasmer_opcode_trace_format_t`.
+   */
+  uint32_t opcode_trace_format;
+  /**
+   * The buffer recording the operators executed at runtime, unless null.
+   */
+  const wasmer_opcode_trace_buffer_t *opcode_trace_buffer;
+  /**
+   * Whether the location of the last operator executed is tracked, see
+   * `wasmer_instance_last_location()`.
+   */
+  bool last_location;
 } wasmer_compilation_options_t;
 
 /**
@@ -370,6 +736,80 @@ typedef struct {
   wasmer_limit_option_t max;
 } wasmer_limits_t;
 
+/**
+ * An import allowed by a `wasmer_module_policy_t`.
+ */
+typedef struct {
+  /**
+   * The namespace of the import.
+   */
+  wasmer_byte_array module_name;
+  /**
+   * The name of the import; an empty name allows every import of the namespace.
+   */
+  wasmer_byte_array import_name;
+} wasmer_policy_import_t;
+
+/**
+ * The rules checked by `wasmer_validate_with_policy()`. Unset limits are not checked.
+ */
+typedef struct {
+  /**
+   * The allowed imports; every import is allowed when null.
+   */
+  const wasmer_policy_import_t *allowed_imports;
+  /**
+   * The number of allowed imports.
+   */
+  uint32_t allowed_imports_len;
+  /**
+   * The maximum number of functions defined by the module, imports excluded.
+   */
+  wasmer_limit_option_t max_functions;
+  /**
+   * The maximum number of locals declared by a function, params excluded.
+   */
+  wasmer_limit_option_t max_locals;
+  /**
+   * The maximum initial size of a memory, in pages.
+   */
+  wasmer_limit_option_t max_initial_memory_pages;
+  /**
+   * The maximum maximum size of a memory, in pages.
+   */
+  wasmer_limit_option_t max_maximum_memory_pages;
+  /**
+   * Whether a start function is rejected.
+   */
+  bool forbid_start_function;
+  /**
+   * Whether floats are rejected, be it in operators, function types, globals or locals.
+   */
+  bool forbid_floats;
+  /**
+   * The names of the exports the module must have.
+   */
+  const wasmer_byte_array *required_exports;
+  /**
+   * The number of required exports.
+   */
+  uint32_t required_exports_len;
+} wasmer_module_policy_t;
+
+/**
+ * A rule of a `wasmer_module_policy_t` broken by a module.
+ */
+typedef struct {
+  /**
+   * The kind of the violation.
+   */
+  wasmer_policy_violation_kind_t kind;
+  /**
+   * What the violation is about, depending on its kind.
+   */
+  uint32_t index;
+} wasmer_policy_violation_t;
+
 typedef struct {
 
 } wasmer_serialized_module_t;
@@ -531,7 +971,8 @@ int wasmer_export_descriptors_len(wasmer_export_descriptors_t *exports);
 
 /**
  * Calls a `func` with the provided parameters.
- * Results are set using the provided `results` pointer.
+ * Results are set using the provided `results` pointer, which must hold at least as many
+ * values as the function returns.
  *
  * Returns `wasmer_result_t::WASMER_OK` upon success.
  *
@@ -665,11 +1106,17 @@ wasmer_global_descriptor_t wasmer_global_get_descriptor(wasmer_global_t *global)
 /**
  * Creates a new Global and returns a pointer to it.
  * The caller owns the object and should call `wasmer_global_destroy` to free it.
+ *
+ * Function references are opaque to the host: a null pointer is returned if `value` is a
+ * function reference other than the null one.
  */
 wasmer_global_t *wasmer_global_new(wasmer_value_t value, bool mutable_);
 
 /**
  * Sets the value stored by the given Global
+ *
+ * Function references are opaque to the host: the Global is left unchanged if `value` is a
+ * function reference other than the null one.
  */
 void wasmer_global_set(wasmer_global_t *global, wasmer_value_t value);
 
@@ -733,6 +1180,10 @@ void wasmer_import_func_destroy(wasmer_import_func_t *func);
  * The function pointer must have a lifetime greater than the
  * WebAssembly instance lifetime.
  *
+ * Function references are opaque to the host, so a function cannot
+ * return `funcref`: a null pointer is returned if `returns` contains
+ * `WASM_FUNCREF`.
+ *
  * The caller owns the object and should call
  * `wasmer_import_func_destroy` to free it.
  */
@@ -787,6 +1238,14 @@ wasmer_result_t wasmer_import_func_returns(const wasmer_import_func_t *func,
 wasmer_result_t wasmer_import_func_returns_arity(const wasmer_import_func_t *func,
                                                  uint32_t *result);
 
+/**
+ * Sets the default import object, used by `wasmer_instantiate_with_options()` and
+ * `wasmer_instance_from_cache()`, and by the variants of these functions when they are given a
+ * null import object.
+ *
+ * It is safe to call this function while other threads are instantiating: they keep using the
+ * previous import object.
+ */
 wasmer_result_t wasmer_import_object_cache_from_imports(wasmer_import_t *imports,
                                                         unsigned int imports_len);
 
@@ -866,7 +1325,7 @@ wasmer_result_t wasmer_instance_cache(wasmer_instance_t *instance,
 /**
  * Calls an exported function of a WebAssembly instance by `name`
  * with the provided parameters. The exported function results are
- * stored on the provided `results` pointer.
+ * stored on the provided `results` pointer, one value per result.
  *
  * This function returns `wasmer_result_t::WASMER_OK` upon success,
  * `wasmer_result_t::WASMER_ERROR` otherwise. You can use
@@ -876,7 +1335,8 @@ wasmer_result_t wasmer_instance_cache(wasmer_instance_t *instance,
  *
  *   * `instance` is a null pointer,
  *   * `name` is a null pointer,
- *   * `params` is a null pointer.
+ *   * `params` is a null pointer,
+ *   * `results_len` is smaller than the number of results.
  *
  * Example of calling an exported function that needs two parameters, and returns one value:
  *
@@ -921,6 +1381,23 @@ wasmer_result_t wasmer_instance_call(wasmer_instance_t *instance,
                                      wasmer_value_t *results,
                                      uint32_t results_len);
 
+/**
+ * Charges `points` to the running instance from within a host function, aka imported function.
+ *
+ * The pointer to `wasmer_instance_context_t` is received by the host function as its first
+ * argument. Just passing it to `ctx` is fine.
+ *
+ * If the points used reach the limit, the runtime breakpoint value is set to
+ * `BREAKPOINT_VALUE_OUT_OF_GAS` and the execution traps exactly like a metered block would:
+ * `wasmer_last_error_kind()` then returns `WASMER_ERROR_KIND_OUT_OF_GAS`. In that case this
+ * function never returns.
+ *
+ * This function returns `wasmer_result_t::WASMER_ERROR` if `ctx` is null, and
+ * `wasmer_result_t::WASMER_OK` if the points were charged within the limit.
+ */
+wasmer_result_t wasmer_instance_context_charge_points(wasmer_instance_context_t *ctx,
+                                                      uint64_t points);
+
 /**
  * Gets the data that can be hold by an instance.
  *
@@ -1080,11 +1557,30 @@ void wasmer_instance_enable_rkyv(void);
  */
 void wasmer_instance_exports(wasmer_instance_t *instance, wasmer_exports_t **exports);
 
+/**
+ * Creates a new WebAssembly instance from a cached artifact, using the default import object
+ * set by `wasmer_import_object_cache_from_imports()`.
+ *
+ * See `wasmer_instance_from_cache_with_import_object()` to use another import object.
+ */
 wasmer_result_t wasmer_instance_from_cache(wasmer_instance_t **instance,
                                            uint8_t *cache_bytes,
                                            uint32_t cache_len,
                                            const wasmer_compilation_options_t *options);
 
+/**
+ * Creates a new WebAssembly instance from a cached artifact, resolving its imports against
+ * `import_object`.
+ *
+ * The default import object set by `wasmer_import_object_cache_from_imports()` is used if
+ * `import_object` is null.
+ */
+wasmer_result_t wasmer_instance_from_cache_with_import_object(wasmer_instance_t **instance,
+                                                              uint8_t *cache_bytes,
+                                                              uint32_t cache_len,
+                                                              const wasmer_compilation_options_t *options,
+                                                              const wasmer_import_object_t *import_object);
+
 uint64_t wasmer_instance_get_points_used(wasmer_instance_t *instance);
 
 uint64_t wasmer_instance_get_runtime_breakpoint_value(wasmer_instance_t *instance);
@@ -1095,7 +1591,104 @@ uint64_t wasmer_instance_get_runtime_breakpoint_value(wasmer_instance_t *instanc
 bool wasmer_instance_is_function_imported(wasmer_instance_t *instance, const char *name);
 
 /**
- * Reset an WebAssembly instance, cleaning memories and globals
+ * Gets the location of the last operator executed by the last `wasmer_instance_call()` on
+ * `instance`.
+ *
+ * The instance must have been compiled with the `last_location` compilation option.
+ *
+ * Returns `wasmer_result_t::WASMER_ERROR` if the location is unknown, e.g. because the option
+ * is not set, without touching the last error: the error of the failed call can still be read
+ * afterwards. Null pointers are reported through `wasmer_last_error_message` as usual.
+ *
+ * Example:
+ *
+ * ```c
+ * wasmer_instance_location_t location;
+ *
+ * if (wasmer_instance_call(instance, "sum", params, 2, results, 1) != WASMER_OK &&
+ *     wasmer_instance_last_location(instance, &location) == WASMER_OK) {
+ *     fprintf(stderr, "trapped in function %u (%.*s) at offset %u\n",
+ *             location.function_index, location.function_name.bytes_len,
+ *             location.function_name.bytes, location.operator_offset);
+ * }
+ * ```
+ */
+wasmer_result_t wasmer_instance_last_location(const wasmer_instance_t *instance,
+                                              wasmer_instance_location_t *location);
+
+/**
+ * Acquires an instance of the given wasm bytes, compiled with the given options, from the pool.
+ *
+ * A reset instance of the same bytes and options is reused if the pool holds one; otherwise the
+ * module is instantiated, and compiled first unless the pool holds other instances of it. In both
+ * cases, the gas limit of the options is set, and the points used as well as the runtime
+ * breakpoint value are cleared.
+ *
+ * The instance must be given back with `wasmer_instance_pool_release()`, and must not be
+ * destroyed with `wasmer_instance_destroy()`.
+ *
+ * Returns `wasmer_result_t::WASMER_OK` upon success.
+ *
+ * Returns `wasmer_result_t::WASMER_ERROR` upon failure. Use `wasmer_last_error_length`
+ * and `wasmer_last_error_message` to get an error message.
+ */
+wasmer_result_t wasmer_instance_pool_acquire(wasmer_instance_pool_t *pool,
+                                             wasmer_instance_t **instance,
+                                             uint8_t *wasm_bytes,
+                                             uint32_t wasm_bytes_len,
+                                             const wasmer_compilation_options_t *options);
+
+/**
+ * Frees memory for the given pool, including its idle instances and compiled modules.
+ *
+ * Instances still acquired from the pool stay valid, and must then be destroyed with
+ * `wasmer_instance_destroy()`.
+ *
+ * If `pool` is a null pointer, this function does nothing.
+ */
+void wasmer_instance_pool_destroy(wasmer_instance_pool_t *pool);
+
+/**
+ * Creates a new instance pool, whose instances resolve their imports against `import_object`.
+ *
+ * The default import object set by `wasmer_import_object_cache_from_imports()` is used if
+ * `import_object` is null. Otherwise, the pool keeps its own reference to the namespaces of the
+ * import object, which may then be destroyed at any time.
+ *
+ * The pool holds at most `max_instances` instances at once, acquired or idle, whose memories
+ * must not exceed `memory_budget` bytes in total, as measured when the instances are acquired
+ * and released. An acquisition that would exceed these limits first destroys the least recently
+ * released idle instances, and fails if that is not enough.
+ *
+ * The caller owns the object and should call `wasmer_instance_pool_destroy()` to free it.
+ *
+ * Returns `wasmer_result_t::WASMER_OK` upon success.
+ *
+ * Returns `wasmer_result_t::WASMER_ERROR` upon failure. Use `wasmer_last_error_length`
+ * and `wasmer_last_error_message` to get an error message.
+ */
+wasmer_result_t wasmer_instance_pool_new(wasmer_instance_pool_t **pool,
+                                         const wasmer_import_object_t *import_object,
+                                         uint32_t max_instances,
+                                         uint64_t memory_budget);
+
+/**
+ * Gives an instance acquired with `wasmer_instance_pool_acquire()` back to the pool.
+ *
+ * The instance is reset, then kept for a later acquisition, until the pool destroys it to make
+ * room for other instances. The caller must not use the instance afterwards.
+ *
+ * Returns `wasmer_result_t::WASMER_ERROR` if the instance was not acquired from this pool, in
+ * which case it is left untouched, or if it could not be reset, in which case it is destroyed.
+ */
+wasmer_result_t wasmer_instance_pool_release(wasmer_instance_pool_t *pool,
+                                             wasmer_instance_t *instance);
+
+/**
+ * Reset an WebAssembly instance, cleaning memories, tables and globals.
+ *
+ * If `wasmer_instance_snapshot_memories()` was called on the instance, the memories are
+ * restored from the snapshot, which only copies back the pages written since.
  */
 wasmer_result_t wasmer_instance_reset(wasmer_instance_t *instance);
 
@@ -1105,6 +1698,16 @@ void wasmer_instance_set_points_used(wasmer_instance_t *instance, uint64_t new_g
 
 void wasmer_instance_set_runtime_breakpoint_value(wasmer_instance_t *instance, uint64_t value);
 
+/**
+ * Take a copy-on-write snapshot of the memories of an WebAssembly instance, which
+ * `wasmer_instance_reset()` restores afterwards. It is meant to be called right after
+ * instantiation.
+ *
+ * Returns `wasmer_result_t::WASMER_ERROR` if the instance is null or if one of its memories
+ * cannot be snapshotted, e.g. because it is a static memory.
+ */
+wasmer_result_t wasmer_instance_snapshot_memories(wasmer_instance_t *instance);
+
 /**
  * Creates a new WebAssembly instance from the given bytes and imports.
  *
@@ -1155,11 +1758,83 @@ wasmer_result_t wasmer_instantiate(wasmer_instance_t **instance,
                                    wasmer_import_t *imports,
                                    int imports_len);
 
+/**
+ * Creates a new WebAssembly instance from the given bytes, compiled with the given options,
+ * using the default import object set by `wasmer_import_object_cache_from_imports()`.
+ *
+ * See `wasmer_instantiate_with_options_and_import_object()` to use another import object.
+ */
 wasmer_result_t wasmer_instantiate_with_options(wasmer_instance_t **instance,
                                                 uint8_t *wasm_bytes,
                                                 uint32_t wasm_bytes_len,
                                                 const wasmer_compilation_options_t *options);
 
+/**
+ * Creates a new WebAssembly instance from the given bytes, compiled with the given options,
+ * resolving its imports against `import_object`.
+ *
+ * The default import object set by `wasmer_import_object_cache_from_imports()` is used if
+ * `import_object` is null.
+ *
+ * Returns `wasmer_result_t::WASMER_OK` upon success.
+ *
+ * Returns `wasmer_result_t::WASMER_ERROR` upon failure. Use `wasmer_last_error_length`
+ * and `wasmer_last_error_message` to get an error message.
+ */
+wasmer_result_t wasmer_instantiate_with_options_and_import_object(wasmer_instance_t **instance,
+                                                                  uint8_t *wasm_bytes,
+                                                                  uint32_t wasm_bytes_len,
+                                                                  const wasmer_compilation_options_t *options,
+                                                                  const wasmer_import_object_t *import_object);
+
+/**
+ * Gets the frame at `index` of the backtrace of the last error into `frame`.
+ *
+ * Returns `wasmer_result_t::WASMER_ERROR` if `frame` is null or `index` is not lower than
+ * `wasmer_last_error_backtrace_length()`.
+ *
+ * Example:
+ *
+ * ```c
+ * wasmer_backtrace_frame_t frame;
+ *
+ * for (int i = 0; i < wasmer_last_error_backtrace_length(); ++i) {
+ *     wasmer_last_error_backtrace_frame(i, &frame);
+ *     fprintf(stderr, "%d: %.*s (function %u) @ operator %u\n", i,
+ *             frame.function_name.bytes_len, frame.function_name.bytes,
+ *             frame.function_index, frame.operator_index);
+ * }
+ * ```
+ */
+wasmer_result_t wasmer_last_error_backtrace_frame(int index, wasmer_backtrace_frame_t *frame);
+
+/**
+ * Gets the number of wasm frames of the backtrace of the last error, innermost first.
+ *
+ * It is 0 if the last error is not a trap of the WebAssembly code, or if its frames are not
+ * known. Like `wasmer_last_error_kind()`, reading the backtrace does not clear the last error.
+ */
+int wasmer_last_error_backtrace_length(void);
+
+/**
+ * Gets the kind of the last error, or `WASMER_ERROR_KIND_NONE` if
+ * no error happened yet.
+ *
+ * Unlike `wasmer_last_error_message()`, reading the kind does not
+ * clear the last error.
+ *
+ * Example:
+ *
+ * ```c
+ * wasmer_result_t call_result = wasmer_instance_call(instance, "sum", params, 2, results, 1);
+ *
+ * if (call_result != WASMER_OK && wasmer_last_error_kind() == WASMER_ERROR_KIND_OUT_OF_GAS) {
+ *     // Handle the exhausted gas.
+ * }
+ * ```
+ */
+wasmer_error_kind_t wasmer_last_error_kind(void);
+
 /**
  * Gets the length in bytes of the last error if any.
  *
@@ -1390,6 +2065,97 @@ wasmer_result_t wasmer_module_instantiate(const wasmer_module_t *module,
 wasmer_result_t wasmer_module_serialize(wasmer_serialized_module_t **serialized_module,
                                         const wasmer_module_t *module);
 
+/**
+ * Frees the caller's reference to the given cost table.
+ *
+ * If `opcode_costs` is a null pointer, this function does nothing.
+ */
+void wasmer_opcode_costs_destroy(wasmer_opcode_costs_t *opcode_costs);
+
+/**
+ * Creates a new cost table from `LEGACY_OPCODE_COUNT` costs, tagged with the given gas schedule
+ * `version`. The per-unit costs of the memory operators are 0: use
+ * `wasmer_opcode_costs_new_with_length()` to set them.
+ *
+ * The costs are copied, so the caller keeps ownership of `opcode_costs_pointer`.
+ *
+ * The caller is responsible to free the table with `wasmer_opcode_costs_destroy()`. Modules
+ * compiled with it keep their own reference, so it may be destroyed right after compilation.
+ *
+ * Returns `wasmer_result_t::WASMER_OK` upon success.
+ *
+ * Returns `wasmer_result_t::WASMER_ERROR` upon failure. Use `wasmer_last_error_length`
+ * and `wasmer_last_error_message` to get an error message.
+ */
+wasmer_result_t wasmer_opcode_costs_new(wasmer_opcode_costs_t **opcode_costs,
+                                        const uint32_t *opcode_costs_pointer,
+                                        uint32_t version);
+
+/**
+ * Creates a new cost table from the `opcode_costs_length` costs at `opcode_costs_pointer`,
+ * tagged with the given gas schedule `version`.
+ *
+ * `opcode_costs_length` must be either `OPCODE_COUNT` or `LEGACY_OPCODE_COUNT`, in which case
+ * the per-unit costs of the memory operators are 0. The costs are copied, so the caller keeps
+ * ownership of `opcode_costs_pointer`.
+ *
+ * The caller is responsible to free the table with `wasmer_opcode_costs_destroy()`.
+ *
+ * Returns `wasmer_result_t::WASMER_OK` upon success.
+ *
+ * Returns `wasmer_result_t::WASMER_ERROR` upon failure. Use `wasmer_last_error_length`
+ * and `wasmer_last_error_message` to get an error message.
+ */
+wasmer_result_t wasmer_opcode_costs_new_with_length(wasmer_opcode_costs_t **opcode_costs,
+                                                    const uint32_t *opcode_costs_pointer,
+                                                    uint32_t opcode_costs_length,
+                                                    uint32_t version);
+
+/**
+ * Returns the gas schedule version of the given cost table, or `0` if it is null.
+ */
+uint32_t wasmer_opcode_costs_version(const wasmer_opcode_costs_t *opcode_costs);
+
+/**
+ * Removes every operator recorded in the given buffer.
+ *
+ * If `buffer` is a null pointer, this function does nothing.
+ */
+void wasmer_opcode_trace_buffer_clear(const wasmer_opcode_trace_buffer_t *buffer);
+
+/**
+ * Frees the caller's reference to the given buffer.
+ *
+ * If `buffer` is a null pointer, this function does nothing.
+ */
+void wasmer_opcode_trace_buffer_destroy(wasmer_opcode_trace_buffer_t *buffer);
+
+/**
+ * Copies the recorded operators, oldest first, into `entries`, which holds `entries_len`
+ * entries, and returns the number of operators recorded, which may be larger than
+ * `entries_len`.
+ *
+ * Returns `0` if `buffer` is null.
+ */
+uint32_t wasmer_opcode_trace_buffer_entries(const wasmer_opcode_trace_buffer_t *buffer,
+                                            wasmer_opcode_trace_entry_t *entries,
+                                            uint32_t entries_len);
+
+/**
+ * Creates a new buffer keeping the last `capacity` operators executed.
+ *
+ * The caller is responsible to free the buffer with `wasmer_opcode_trace_buffer_destroy()`.
+ * Modules compiled with it keep their own reference, so it may be destroyed right after
+ * compilation.
+ *
+ * Returns `wasmer_result_t::WASMER_OK` upon success.
+ *
+ * Returns `wasmer_result_t::WASMER_ERROR` upon failure. Use `wasmer_last_error_length`
+ * and `wasmer_last_error_message` to get an error message.
+ */
+wasmer_result_t wasmer_opcode_trace_buffer_new(wasmer_opcode_trace_buffer_t **buffer,
+                                               uint32_t capacity);
+
 /**
  * Get bytes of the serialized module.
  */
@@ -1414,8 +2180,30 @@ wasmer_result_t wasmer_serialized_module_from_bytes(wasmer_serialized_module_t *
                                                     const uint8_t *serialized_module_bytes,
                                                     uint32_t serialized_module_bytes_length);
 
+/**
+ * Sets the process-wide default cost table from `LEGACY_OPCODE_COUNT` costs, used when the
+ * compilation options do not carry one. The table is tagged with gas schedule version `0`, and
+ * the per-unit costs of the memory operators are 0: use `wasmer_set_opcode_costs_with_length()`
+ * to set them.
+ */
 void wasmer_set_opcode_costs(const uint32_t *opcode_costs_pointer);
 
+/**
+ * Sets the process-wide default cost table from the `opcode_costs_length` costs at
+ * `opcode_costs_pointer`, used when the compilation options do not carry one. The table is
+ * tagged with gas schedule version `0`.
+ *
+ * `opcode_costs_length` must be either `OPCODE_COUNT` or `LEGACY_OPCODE_COUNT`, in which case
+ * the per-unit costs of the memory operators are 0.
+ *
+ * Returns `wasmer_result_t::WASMER_OK` upon success.
+ *
+ * Returns `wasmer_result_t::WASMER_ERROR` upon failure, leaving the default table unchanged.
+ * Use `wasmer_last_error_length` and `wasmer_last_error_message` to get an error message.
+ */
+wasmer_result_t wasmer_set_opcode_costs_with_length(const uint32_t *opcode_costs_pointer,
+                                                    uint32_t opcode_costs_length);
+
 void wasmer_set_sigsegv_passthrough(void);
 
 /**
@@ -1541,6 +2329,37 @@ wasmer_result_t wasmer_trap(const wasmer_instance_context_t *ctx, const char *er
  */
 bool wasmer_validate(const uint8_t *wasm_bytes, uint32_t wasm_bytes_len);
 
+/**
+ * Checks the given wasm bytes against `policy`, without compiling them.
+ *
+ * Up to `violations_len` violations are written to `violations`, and their total number to
+ * `violations_count`: the module is accepted when it is 0.
+ *
+ * The code is not validated, see `wasmer_validate()`.
+ *
+ * Returns `wasmer_result_t::WASMER_ERROR` if the bytes are malformed or the policy is invalid.
+ * Use `wasmer_last_error_length` and `wasmer_last_error_message` to get an error message.
+ *
+ * Example:
+ *
+ * ```c
+ * wasmer_policy_violation_t violations[16];
+ * uint32_t violations_count;
+ * wasmer_result_t result = wasmer_validate_with_policy(bytes, bytes_length, &policy,
+ *                                                      violations, 16, &violations_count);
+ *
+ * if (result != WASMER_OK || violations_count > 0) {
+ *     // Reject the module…
+ * }
+ * ```
+ */
+wasmer_result_t wasmer_validate_with_policy(const uint8_t *wasm_bytes,
+                                            uint32_t wasm_bytes_len,
+                                            const wasmer_module_policy_t *policy,
+                                            wasmer_policy_violation_t *violations,
+                                            uint32_t violations_len,
+                                            uint32_t *violations_count);
+
 #if defined(WASMER_WASI_ENABLED)
 /**
  * Convenience function that creates a WASI import object with no arguments,
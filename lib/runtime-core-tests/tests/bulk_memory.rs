@@ -0,0 +1,150 @@
+use wasmer_runtime_core::{
+    backend::{CompilerConfig, ExceptionCode, Features},
+    compile_with_config,
+    error::{CallError, RuntimeErrorKind},
+    imports,
+    types::Value,
+    Instance,
+};
+use wasmer_runtime_core_tests::get_compiler;
+
+const MODULE: &str = r#"
+(module
+  (type $get_t (func (result i32)))
+  (memory 1)
+  (table 4 anyfunc)
+  (data passive "\01\02\03")
+  (elem passive $one $two)
+  (func $one (type $get_t) (i32.const 1))
+  (func $two (type $get_t) (i32.const 2))
+  (func (export "load") (param i32) (result i32)
+    (i32.load8_u (get_local 0)))
+  (func (export "call_table") (param i32) (result i32)
+    (call_indirect (type $get_t) (get_local 0)))
+  (func (export "memory_init") (param i32 i32 i32)
+    (memory.init 0 (get_local 0) (get_local 1) (get_local 2)))
+  (func (export "data_drop")
+    (data.drop 0))
+  (func (export "memory_copy") (param i32 i32 i32)
+    (memory.copy (get_local 0) (get_local 1) (get_local 2)))
+  (func (export "memory_grow") (param i32) (result i32)
+    (memory.grow (get_local 0)))
+  (func (export "memory_fill") (param i32 i32 i32)
+    (memory.fill (get_local 0) (get_local 1) (get_local 2)))
+  (func (export "table_init") (param i32 i32 i32)
+    (table.init 0 (get_local 0) (get_local 1) (get_local 2)))
+  (func (export "elem_drop")
+    (elem.drop 0))
+  (func (export "table_copy") (param i32 i32 i32)
+    (table.copy (get_local 0) (get_local 1) (get_local 2))))
+"#;
+
+fn instantiate() -> Instance {
+    let mut features = wabt::Features::new();
+    features.enable_bulk_memory();
+    let wasm_binary = wabt::wat2wasm_with_features(MODULE.as_bytes(), features)
+        .expect("WAST not valid or malformed");
+    let config = CompilerConfig {
+        features: Features {
+            bulk_memory: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let module = compile_with_config(&wasm_binary, &get_compiler(), config).unwrap();
+    module.instantiate(&imports! {}).unwrap()
+}
+
+fn call(instance: &Instance, name: &str, params: &[i32]) -> Result<Vec<Value>, CallError> {
+    let params: Vec<Value> = params.iter().map(|&param| Value::I32(param)).collect();
+    instance.call(name, &params)
+}
+
+fn load(instance: &Instance, offset: i32) -> Vec<Value> {
+    call(instance, "load", &[offset]).unwrap()
+}
+
+fn trap_kind(result: Result<Vec<Value>, CallError>) -> RuntimeErrorKind {
+    match result {
+        Err(CallError::Runtime(error)) => error.kind(),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn memory_bulk_operations() {
+    let instance = instantiate();
+
+    call(&instance, "memory_init", &[10, 1, 2]).unwrap();
+    assert_eq!(load(&instance, 10), vec![Value::I32(2)]);
+    assert_eq!(load(&instance, 11), vec![Value::I32(3)]);
+
+    call(&instance, "memory_copy", &[20, 10, 2]).unwrap();
+    assert_eq!(load(&instance, 21), vec![Value::I32(3)]);
+
+    call(&instance, "memory_fill", &[30, 0x1ff, 2]).unwrap();
+    assert_eq!(load(&instance, 31), vec![Value::I32(0xff)]);
+
+    assert_eq!(
+        trap_kind(call(&instance, "memory_fill", &[65535, 0, 2])),
+        RuntimeErrorKind::WasmTrap(ExceptionCode::MemoryOutOfBounds)
+    );
+
+    call(&instance, "data_drop", &[]).unwrap();
+    call(&instance, "memory_init", &[10, 0, 0]).unwrap();
+    assert_eq!(
+        trap_kind(call(&instance, "memory_init", &[10, 0, 1])),
+        RuntimeErrorKind::WasmTrap(ExceptionCode::MemoryOutOfBounds)
+    );
+}
+
+#[test]
+fn memory_bulk_operations_after_reset() {
+    let mut instance = instantiate();
+
+    assert_eq!(
+        call(&instance, "memory_grow", &[1]).unwrap(),
+        vec![Value::I32(1)]
+    );
+    call(&instance, "memory_fill", &[65535, 1, 2]).unwrap();
+
+    // the bulk operators must bound check against the memory restored by the reset
+    instance.reset().unwrap();
+    assert_eq!(
+        trap_kind(call(&instance, "memory_fill", &[65535, 1, 2])),
+        RuntimeErrorKind::WasmTrap(ExceptionCode::MemoryOutOfBounds)
+    );
+    assert_eq!(
+        trap_kind(call(&instance, "memory_copy", &[65535, 0, 2])),
+        RuntimeErrorKind::WasmTrap(ExceptionCode::MemoryOutOfBounds)
+    );
+    assert_eq!(
+        trap_kind(call(&instance, "memory_init", &[65535, 0, 2])),
+        RuntimeErrorKind::WasmTrap(ExceptionCode::MemoryOutOfBounds)
+    );
+    call(&instance, "memory_fill", &[65534, 1, 2]).unwrap();
+    assert_eq!(load(&instance, 65535), vec![Value::I32(1)]);
+}
+
+#[test]
+fn table_bulk_operations() {
+    let instance = instantiate();
+
+    call(&instance, "table_init", &[1, 0, 2]).unwrap();
+    call(&instance, "table_copy", &[2, 1, 2]).unwrap();
+    assert_eq!(
+        call(&instance, "call_table", &[3]).unwrap(),
+        vec![Value::I32(2)]
+    );
+
+    assert_eq!(
+        trap_kind(call(&instance, "table_copy", &[3, 0, 2])),
+        RuntimeErrorKind::WasmTrap(ExceptionCode::TableOutOfBounds)
+    );
+
+    call(&instance, "elem_drop", &[]).unwrap();
+    assert_eq!(
+        trap_kind(call(&instance, "table_init", &[0, 0, 1])),
+        RuntimeErrorKind::WasmTrap(ExceptionCode::TableOutOfBounds)
+    );
+}
@@ -1,4 +1,10 @@
-use wasmer_runtime_core::{compile_with, imports};
+use wasmer_runtime_core::{
+    backend::ExceptionCode,
+    compile_with,
+    error::{CallError, RuntimeErrorKind},
+    imports,
+    typed_func::Func,
+};
 use wasmer_runtime_core_tests::{get_compiler, wat2wasm};
 
 #[test]
@@ -19,3 +25,53 @@ fn exception_handling_works() {
         assert!(instance.call("throw_trap", &[]).is_err());
     }
 }
+
+#[test]
+fn runtime_error_kinds() {
+    const MODULE: &str = r#"
+(module
+  (import "env" "host_error" (func $host_error))
+  (import "env" "host_panic" (func $host_panic))
+  (func (export "throw_trap")
+    unreachable)
+  (func (export "divide_by_zero") (result i32)
+    i32.const 1
+    i32.const 0
+    i32.div_u)
+  (func (export "call_host_error")
+    call $host_error)
+  (func (export "call_host_panic")
+    call $host_panic))
+"#;
+
+    let wasm_binary = wat2wasm(MODULE.as_bytes()).expect("WAST not valid or malformed");
+    let module = compile_with(&wasm_binary, &get_compiler()).unwrap();
+
+    let imports = imports! {
+        "env" => {
+            "host_error" => Func::new(|| -> Result<(), String> {
+                Err("host error".to_string())
+            }),
+            "host_panic" => Func::new(|| -> Result<(), String> {
+                panic!("host panic")
+            }),
+        },
+    };
+    let instance = module.instantiate(&imports).unwrap();
+
+    let kind_of = |name: &str| match instance.call(name, &[]) {
+        Err(CallError::Runtime(error)) => error.kind(),
+        other => panic!("unexpected result for `{}`: {:?}", name, other),
+    };
+
+    assert_eq!(
+        kind_of("throw_trap"),
+        RuntimeErrorKind::WasmTrap(ExceptionCode::Unreachable)
+    );
+    assert_eq!(
+        kind_of("divide_by_zero"),
+        RuntimeErrorKind::WasmTrap(ExceptionCode::IllegalArithmetic)
+    );
+    assert_eq!(kind_of("call_host_error"), RuntimeErrorKind::UserTrap);
+    assert_eq!(kind_of("call_host_panic"), RuntimeErrorKind::HostPanic);
+}
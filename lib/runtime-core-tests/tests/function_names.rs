@@ -0,0 +1,34 @@
+use wasmer_runtime_core::{compile_with, structures::TypedIndex, types::FuncIndex};
+use wasmer_runtime_core_tests::get_compiler;
+
+const MODULE: &str = r#"
+(module
+  (import "env" "host" (func $host))
+  (func $first (call $host))
+  (func (nop))
+  (func $third (nop)))
+"#;
+
+#[test]
+fn function_names_from_the_name_section() {
+    let wasm_binary = wabt::Wat2Wasm::new()
+        .write_debug_names(true)
+        .convert(MODULE)
+        .expect("WAST not valid or malformed");
+    let module = compile_with(wasm_binary.as_ref(), &get_compiler()).unwrap();
+    let info = module.info();
+
+    assert_eq!(info.function_name(FuncIndex::new(0)), Some("host"));
+    assert_eq!(info.function_name(FuncIndex::new(1)), Some("first"));
+    assert_eq!(info.function_name(FuncIndex::new(2)), None);
+    assert_eq!(info.function_name(FuncIndex::new(3)), Some("third"));
+    assert_eq!(info.function_name(FuncIndex::new(4)), None);
+}
+
+#[test]
+fn function_names_without_name_section() {
+    let wasm_binary = wasmer_runtime_core_tests::wat2wasm(MODULE).unwrap();
+    let module = compile_with(&wasm_binary, &get_compiler()).unwrap();
+
+    assert_eq!(module.info().function_name(FuncIndex::new(1)), None);
+}
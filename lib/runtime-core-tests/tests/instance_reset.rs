@@ -0,0 +1,188 @@
+use wasmer_runtime_core::{
+    compile_with,
+    export::Export,
+    global::Global,
+    imports,
+    memory::Memory,
+    table::{Anyfunc, Element, Table},
+    types::{MemoryDescriptor, Value},
+    units::Pages,
+    Instance,
+};
+use wasmer_runtime_core_tests::{get_compiler, wat2wasm};
+
+const MODULE: &str = r#"
+(module
+  (import "env" "base" (global $base i32))
+  (type $get_t (func (result i32)))
+  (table (export "table") 3 10 anyfunc)
+  (memory 1)
+  (global $counter (mut i32) (get_global $base))
+  (elem (get_global $base) $one $two)
+  (elem (i32.const 0) $one $two)
+  (data (get_global $base) "\2a")
+  (func $one (type $get_t) (i32.const 1))
+  (func $two (type $get_t) (i32.const 2))
+  (func (export "two") (type $get_t) (call $two))
+  (func (export "call_table") (param i32) (result i32)
+    (call_indirect (type $get_t) (get_local 0)))
+  (func (export "counter") (result i32)
+    (get_global $counter))
+  (func (export "byte") (result i32)
+    (i32.load8_u (get_global $base)))
+  (func (export "pages") (result i32)
+    (memory.size))
+  (func (export "load") (param i32) (result i32)
+    (i32.load8_u (get_local 0)))
+  (func (export "store") (param i32 i32)
+    (i32.store8 (get_local 0) (get_local 1)))
+  (func (export "mutate")
+    (set_global $counter (i32.add (get_global $counter) (i32.const 1)))
+    (i32.store8 (get_global $base) (i32.const 7))
+    (drop (memory.grow (i32.const 1)))))
+"#;
+
+fn get_table(instance: &Instance) -> Table {
+    instance
+        .exports()
+        .find_map(|(name, export)| match export {
+            Export::Table(table) if name == "table" => Some(table),
+            _ => None,
+        })
+        .expect("exported table")
+}
+
+/// Everything an instance exposes that `reset` must restore.
+fn snapshot(instance: &Instance) -> Vec<String> {
+    let table = get_table(instance);
+    let mut state = vec![
+        format!("table size {}", table.size()),
+        format!("counter {:?}", instance.call("counter", &[])),
+        format!("byte {:?}", instance.call("byte", &[])),
+        format!("pages {:?}", instance.call("pages", &[])),
+    ];
+    for index in 0..table.size() as i32 + 1 {
+        state.push(format!(
+            "call_table {} {:?}",
+            index,
+            instance
+                .call("call_table", &[Value::I32(index)])
+                .map_err(|_| "trap")
+        ));
+    }
+    state
+}
+
+/// Changes the memory, the tables and the globals of the instance.
+fn mutate(instance: &Instance) {
+    instance.call("mutate", &[]).unwrap();
+
+    let table = get_table(instance);
+    table.grow(2).unwrap();
+    for &index in &[0, 3] {
+        let two = instance.dyn_func("two").unwrap();
+        table
+            .set(index, Element::Anyfunc(Anyfunc::from(two)))
+            .unwrap();
+    }
+}
+
+fn instantiate() -> (Vec<String>, Instance) {
+    let wasm_binary = wat2wasm(MODULE.as_bytes()).expect("WAST not valid or malformed");
+    let module = compile_with(&wasm_binary, &get_compiler()).unwrap();
+    let imports = imports! {
+        "env" => {
+            "base" => Global::new(Value::I32(1)),
+        },
+    };
+
+    let fresh_instance = module.instantiate(&imports).unwrap();
+    let instance = module.instantiate(&imports).unwrap();
+    (snapshot(&fresh_instance), instance)
+}
+
+#[test]
+fn reset_instance_is_indistinguishable_from_fresh_instance() {
+    let (fresh_state, mut instance) = instantiate();
+
+    mutate(&instance);
+    assert_ne!(snapshot(&instance), fresh_state);
+
+    instance.reset().unwrap();
+    assert_eq!(snapshot(&instance), fresh_state);
+}
+
+#[test]
+fn reset_instance_from_memory_snapshot() {
+    let (fresh_state, mut instance) = instantiate();
+    instance.snapshot_memories().unwrap();
+
+    for _ in 0..2 {
+        mutate(&instance);
+        assert_ne!(snapshot(&instance), fresh_state);
+
+        instance.reset().unwrap();
+        assert_eq!(snapshot(&instance), fresh_state);
+    }
+}
+
+#[test]
+fn memory_is_accessible_after_reset() {
+    for &snapshot_memories in &[false, true] {
+        let (_fresh_state, mut instance) = instantiate();
+        if snapshot_memories {
+            instance.snapshot_memories().unwrap();
+        }
+
+        // grows the memory, so that the reset moves or shrinks it
+        mutate(&instance);
+        instance.reset().unwrap();
+
+        let last_byte = Value::I32(65535);
+        instance
+            .call("store", &[last_byte.clone(), Value::I32(9)])
+            .unwrap();
+        assert_eq!(
+            instance.call("load", &[last_byte]).unwrap(),
+            vec![Value::I32(9)]
+        );
+        // the page added by `mutate` is gone
+        assert!(instance.call("load", &[Value::I32(65536)]).is_err());
+    }
+}
+
+#[test]
+fn reset_rejects_memories_it_cannot_restore() {
+    let wasm_binary = wat2wasm(
+        br#"
+        (module
+          (memory 1 2)
+          (data (i32.const 0) "\2a"))
+        "#,
+    )
+    .expect("WAST not valid or malformed");
+    let module = compile_with(&wasm_binary, &get_compiler()).unwrap();
+    let mut instance = module.instantiate(&imports! {}).unwrap();
+    assert!(instance.snapshot_memories().is_err());
+    assert!(instance.reset().is_err());
+
+    let wasm_binary = wat2wasm(
+        br#"
+        (module
+          (import "env" "memory" (memory 1))
+          (data (i32.const 0) "\2a"))
+        "#,
+    )
+    .expect("WAST not valid or malformed");
+    let module = compile_with(&wasm_binary, &get_compiler()).unwrap();
+    let memory = Memory::new(MemoryDescriptor::new(Pages(1), None, false).unwrap()).unwrap();
+    let mut instance = module
+        .instantiate(&imports! {
+            "env" => {
+                "memory" => memory,
+            },
+        })
+        .unwrap();
+    assert!(instance.snapshot_memories().is_err());
+    assert!(instance.reset().is_err());
+}
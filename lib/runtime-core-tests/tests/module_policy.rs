@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+use wasmer_runtime_core::{
+    policy::{check_module_policy, ModulePolicy, PolicyViolation},
+    structures::TypedIndex,
+    types::{FuncIndex, GlobalIndex, MemoryIndex, SigIndex},
+};
+use wasmer_runtime_core_tests::wat2wasm;
+
+const MODULE: &str = r#"
+(module
+  (import "env" "allowed" (func $allowed))
+  (import "env" "forbidden" (func $forbidden))
+  (import "other" "anything" (func $anything))
+  (memory 2 10)
+  (func $start
+    (call $allowed))
+  (func $locals (local i32 i64 i64)
+    (nop))
+  (func $floats (param f32) (result f32)
+    (f32.add (get_local 0) (f32.const 1))
+    (f32.mul (f32.const 2)))
+  (start $start)
+  (export "main" (func $locals)))
+"#;
+
+fn check(policy: &ModulePolicy) -> Vec<PolicyViolation> {
+    let wasm_binary = wat2wasm(MODULE.as_bytes()).expect("WAST not valid or malformed");
+    check_module_policy(&wasm_binary, policy).unwrap()
+}
+
+#[test]
+fn default_policy_accepts_everything() {
+    assert_eq!(check(&ModulePolicy::default()), vec![]);
+}
+
+#[test]
+fn reports_every_violation() {
+    let mut allowed_imports = HashMap::new();
+    allowed_imports.insert(
+        "env".to_string(),
+        Some(vec!["allowed".to_string()].into_iter().collect()),
+    );
+    allowed_imports.insert("other".to_string(), None);
+    let policy = ModulePolicy {
+        allowed_imports: Some(allowed_imports),
+        max_functions: Some(2),
+        max_locals: Some(2),
+        max_initial_memory_pages: Some(1),
+        max_maximum_memory_pages: Some(5),
+        forbid_start_function: true,
+        forbid_floats: true,
+        required_exports: vec!["main".to_string(), "init".to_string()],
+    };
+
+    let violations = check(&policy);
+    assert_eq!(violations.len(), 9);
+    assert_eq!(
+        violations[0],
+        PolicyViolation::FloatSignature {
+            signature: SigIndex::new(1),
+        }
+    );
+    assert_eq!(
+        violations[1],
+        PolicyViolation::ForbiddenImport {
+            index: 1,
+            namespace: "env".to_string(),
+            name: "forbidden".to_string(),
+        }
+    );
+    assert_eq!(
+        violations[2],
+        PolicyViolation::InitialMemoryTooLarge {
+            memory: MemoryIndex::new(0),
+            pages: 2,
+            max: 1,
+        }
+    );
+    assert_eq!(
+        violations[3],
+        PolicyViolation::MaximumMemoryTooLarge {
+            memory: MemoryIndex::new(0),
+            pages: Some(10),
+            max: 5,
+        }
+    );
+    assert_eq!(
+        violations[4],
+        PolicyViolation::StartFunction {
+            function: FuncIndex::new(3),
+        }
+    );
+    assert_eq!(
+        violations[5],
+        PolicyViolation::TooManyLocals {
+            function: FuncIndex::new(4),
+            count: 3,
+            max: 2,
+        }
+    );
+    match violations[6] {
+        PolicyViolation::FloatOperator { function, .. } => {
+            assert_eq!(function, FuncIndex::new(5));
+        }
+        ref violation => panic!("unexpected violation: {:?}", violation),
+    }
+    assert_eq!(
+        violations[7],
+        PolicyViolation::TooManyFunctions { count: 3, max: 2 }
+    );
+    assert_eq!(
+        violations[8],
+        PolicyViolation::MissingExport {
+            name: "init".to_string(),
+        }
+    );
+}
+
+#[test]
+fn forbid_floats_checks_globals_and_locals() {
+    let wasm_binary = wat2wasm(
+        r#"
+(module
+  (import "env" "answer" (global f64))
+  (global $counter (mut f32) (f32.const 0))
+  (func $floats (local i32 f64)
+    (nop))
+  (func $integers (param i32) (result i32) (local i64)
+    (get_local 0)))
+"#
+        .as_bytes(),
+    )
+    .expect("WAST not valid or malformed");
+    let policy = ModulePolicy {
+        forbid_floats: true,
+        ..ModulePolicy::default()
+    };
+
+    assert_eq!(
+        check_module_policy(&wasm_binary, &policy).unwrap(),
+        vec![
+            PolicyViolation::FloatGlobal {
+                global: GlobalIndex::new(0),
+            },
+            PolicyViolation::FloatGlobal {
+                global: GlobalIndex::new(1),
+            },
+            PolicyViolation::FloatLocal {
+                function: FuncIndex::new(0),
+            },
+        ]
+    );
+}
+
+#[test]
+fn rejects_malformed_modules() {
+    assert!(check_module_policy(b"\0asm\x01\0\0\0\x01", &ModulePolicy::default()).is_err());
+}
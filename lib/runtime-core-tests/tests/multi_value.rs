@@ -0,0 +1,233 @@
+use std::sync::Arc;
+use wasmer_runtime_core::{
+    backend::{CompilerConfig, Features},
+    compile_with_config,
+    error::{CallError, RuntimeErrorKind},
+    imports,
+    typed_func::{DynamicFunc, Func},
+    types::{FuncSig, Type, Value},
+    Instance,
+};
+use wasmer_runtime_core_tests::get_compiler;
+
+const MODULE: &str = r#"
+(module
+  (type $swap_t (func (param i32 i64) (result i64 i32)))
+  (import "env" "host_pair" (func $host_pair (param i32) (result i32 i64)))
+  (import "env" "dynamic_pair" (func $dynamic_pair (param i32) (result i32 i64)))
+  (import "env" "dynamic_negate" (func $dynamic_negate (param i32) (result i32)))
+  (table 1 anyfunc)
+  (elem (i32.const 0) $swap)
+  (func $swap (export "swap") (type $swap_t)
+    (get_local 1)
+    (get_local 0))
+  (func (export "call_swap") (param i32 i64) (result i64 i32)
+    (call $swap (get_local 0) (get_local 1)))
+  (func (export "call_indirect_swap") (param i32 i64) (result i64 i32)
+    (call_indirect (type $swap_t) (get_local 0) (get_local 1) (i32.const 0)))
+  (func (export "call_host") (param i32) (result i32 i64)
+    (call $host_pair (get_local 0)))
+  (func (export "call_dynamic") (param i32) (result i32 i64)
+    (call $dynamic_pair (get_local 0)))
+  (func (export "call_dynamic_negate") (param i32) (result i32)
+    (call $dynamic_negate (get_local 0)))
+  (func (export "early_return") (param i32) (result i32 i32)
+    (if (get_local 0)
+      (then (return (i32.const 1) (i32.const 2))))
+    (i32.const 3)
+    (i32.const 4))
+  (func (export "block") (param i32) (result i32 i64)
+    (block (result i32 i64)
+      (i32.const 1)
+      (i64.const 2)
+      (br_if 0 (get_local 0))
+      (drop)
+      (drop)
+      (i32.const 3)
+      (i64.const 4)))
+  (func (export "if_else") (param i32 i32) (result i32)
+    (get_local 0)
+    (if (param i32) (result i32) (get_local 1)
+      (then (i32.const 10) (i32.add))
+      (else (i32.const 20) (i32.sub))))
+  (func (export "if_without_else") (param i32 i32) (result i32)
+    (get_local 0)
+    (if (param i32) (result i32) (get_local 1)
+      (then (i32.const 10) (i32.add))))
+  (func (export "sum") (param $n i32) (result i32)
+    (i32.const 0)
+    (get_local $n)
+    (loop $continue (param i32 i32) (result i32)
+      (set_local $n)
+      (i32.add (get_local $n))
+      (i32.sub (get_local $n) (i32.const 1))
+      (br_if $continue (i32.ne (get_local $n) (i32.const 1)))
+      (drop))))
+"#;
+
+fn instantiate() -> Instance {
+    let mut features = wabt::Features::new();
+    features.enable_multi_value();
+    let wasm_binary = wabt::wat2wasm_with_features(MODULE.as_bytes(), features)
+        .expect("WAST not valid or malformed");
+    let config = CompilerConfig {
+        features: Features {
+            multi_value: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let module = compile_with_config(&wasm_binary, &get_compiler(), config).unwrap();
+    let import_object = imports! {
+        "env" => {
+            "host_pair" => Func::new(|n: i32| -> (i32, i64) { (n + 1, n as i64 * 2) }),
+            "dynamic_pair" => DynamicFunc::new(
+                Arc::new(FuncSig::new(vec![Type::I32], vec![Type::I32, Type::I64])),
+                |_, params| -> Vec<Value> {
+                    // negative params make the function misbehave
+                    match params[0] {
+                        Value::I32(-1) => vec![Value::I32(0)],
+                        Value::I32(-2) => vec![Value::I64(0), Value::I64(0)],
+                        Value::I32(-3) => panic!("host function failure"),
+                        Value::I32(n) => vec![Value::I32(n + 1), Value::I64(n as i64 * 3)],
+                        _ => unreachable!(),
+                    }
+                }
+            ),
+            "dynamic_negate" => DynamicFunc::new(
+                Arc::new(FuncSig::new(vec![Type::I32], vec![Type::I32])),
+                |_, params| -> Vec<Value> {
+                    match params[0] {
+                        Value::I32(0) => vec![Value::I32(0), Value::I32(0)],
+                        Value::I32(n) => vec![Value::I32(-n)],
+                        _ => unreachable!(),
+                    }
+                }
+            ),
+        },
+    };
+    module.instantiate(&import_object).unwrap()
+}
+
+fn call(instance: &Instance, name: &str, params: &[Value]) -> Vec<Value> {
+    instance.call(name, params).unwrap()
+}
+
+#[test]
+fn multi_value_functions() {
+    let instance = instantiate();
+
+    for name in &["swap", "call_swap", "call_indirect_swap"] {
+        assert_eq!(
+            call(&instance, name, &[Value::I32(1), Value::I64(2)]),
+            vec![Value::I64(2), Value::I32(1)]
+        );
+    }
+
+    let swap: Func<(i32, i64), (i64, i32)> = instance.func("swap").unwrap();
+    assert_eq!(swap.call(3, 4), Ok((4, 3)));
+
+    assert_eq!(
+        call(&instance, "call_host", &[Value::I32(5)]),
+        vec![Value::I32(6), Value::I64(10)]
+    );
+    assert_eq!(
+        call(&instance, "call_dynamic", &[Value::I32(5)]),
+        vec![Value::I32(6), Value::I64(15)]
+    );
+
+    assert_eq!(
+        call(&instance, "early_return", &[Value::I32(1)]),
+        vec![Value::I32(1), Value::I32(2)]
+    );
+    assert_eq!(
+        call(&instance, "early_return", &[Value::I32(0)]),
+        vec![Value::I32(3), Value::I32(4)]
+    );
+}
+
+#[test]
+fn multi_value_blocks() {
+    let instance = instantiate();
+
+    assert_eq!(
+        call(&instance, "block", &[Value::I32(1)]),
+        vec![Value::I32(1), Value::I64(2)]
+    );
+    assert_eq!(
+        call(&instance, "block", &[Value::I32(0)]),
+        vec![Value::I32(3), Value::I64(4)]
+    );
+
+    assert_eq!(
+        call(&instance, "if_else", &[Value::I32(30), Value::I32(1)]),
+        vec![Value::I32(40)]
+    );
+    assert_eq!(
+        call(&instance, "if_else", &[Value::I32(30), Value::I32(0)]),
+        vec![Value::I32(10)]
+    );
+    assert_eq!(
+        call(
+            &instance,
+            "if_without_else",
+            &[Value::I32(30), Value::I32(1)]
+        ),
+        vec![Value::I32(40)]
+    );
+    assert_eq!(
+        call(
+            &instance,
+            "if_without_else",
+            &[Value::I32(30), Value::I32(0)]
+        ),
+        vec![Value::I32(30)]
+    );
+
+    assert_eq!(
+        call(&instance, "sum", &[Value::I32(4)]),
+        vec![Value::I32(10)]
+    );
+}
+
+fn trap_kind(instance: &Instance, name: &str, params: &[Value]) -> RuntimeErrorKind {
+    match instance.call(name, params) {
+        Err(CallError::Runtime(error)) => error.kind(),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn dynamic_functions_results_are_checked() {
+    let instance = instantiate();
+
+    assert_eq!(
+        call(&instance, "call_dynamic_negate", &[Value::I32(5)]),
+        vec![Value::I32(-5)]
+    );
+
+    // too few, mistyped and too many results
+    assert_eq!(
+        trap_kind(&instance, "call_dynamic", &[Value::I32(-1)]),
+        RuntimeErrorKind::UserTrap
+    );
+    assert_eq!(
+        trap_kind(&instance, "call_dynamic", &[Value::I32(-2)]),
+        RuntimeErrorKind::UserTrap
+    );
+    assert_eq!(
+        trap_kind(&instance, "call_dynamic_negate", &[Value::I32(0)]),
+        RuntimeErrorKind::UserTrap
+    );
+
+    assert_eq!(
+        trap_kind(&instance, "call_dynamic", &[Value::I32(-3)]),
+        RuntimeErrorKind::HostPanic
+    );
+
+    // the instance is still usable
+    assert_eq!(
+        call(&instance, "call_dynamic", &[Value::I32(5)]),
+        vec![Value::I32(6), Value::I64(15)]
+    );
+}
@@ -0,0 +1,84 @@
+#![cfg(feature = "backend-singlepass")]
+
+use wasmer_runtime_core::{
+    backend::CompilerConfig, compile_with_config, imports, types::Value, Instance,
+};
+use wasmer_runtime_core_tests::{get_compiler, wat2wasm};
+
+const MODULE: &str = r#"
+(module
+  (func (export "f32_div") (param f32 f32) (result i32)
+    (i32.reinterpret/f32 (f32.div (get_local 0) (get_local 1))))
+  (func (export "f32_add") (param f32 f32) (result i32)
+    (i32.reinterpret/f32 (f32.add (get_local 0) (get_local 1))))
+  (func (export "f64_sqrt") (param f64) (result i64)
+    (i64.reinterpret/f64 (f64.sqrt (get_local 0))))
+  (func (export "f64_promote") (param f32) (result i64)
+    (i64.reinterpret/f64 (f64.promote/f32 (get_local 0))))
+  (func (export "f32_neg") (param f32) (result i32)
+    (i32.reinterpret/f32 (f32.neg (get_local 0)))))
+"#;
+
+fn instantiate() -> Instance {
+    let wasm_binary = wat2wasm(MODULE.as_bytes()).expect("WAST not valid or malformed");
+    let config = CompilerConfig {
+        nan_canonicalization: true,
+        ..Default::default()
+    };
+    let module = compile_with_config(&wasm_binary, &get_compiler(), config).unwrap();
+    module.instantiate(&imports! {}).unwrap()
+}
+
+fn call(instance: &Instance, name: &str, params: &[Value]) -> Vec<Value> {
+    instance.call(name, params).unwrap()
+}
+
+#[test]
+fn canonicalizes_nan_results() {
+    let instance = instantiate();
+    let f32_nan = f32::from_bits(0xFFC0_1234);
+
+    assert_eq!(
+        call(&instance, "f32_div", &[Value::F32(0.0), Value::F32(0.0)]),
+        vec![Value::I32(0x7FC0_0000)]
+    );
+    assert_eq!(
+        call(
+            &instance,
+            "f32_add",
+            &[Value::F32(f32_nan), Value::F32(1.0)]
+        ),
+        vec![Value::I32(0x7FC0_0000)]
+    );
+    assert_eq!(
+        call(&instance, "f64_sqrt", &[Value::F64(-1.0)]),
+        vec![Value::I64(0x7FF8_0000_0000_0000)]
+    );
+    assert_eq!(
+        call(&instance, "f64_promote", &[Value::F32(f32_nan)]),
+        vec![Value::I64(0x7FF8_0000_0000_0000)]
+    );
+}
+
+#[test]
+fn keeps_other_results() {
+    let instance = instantiate();
+
+    assert_eq!(
+        call(&instance, "f32_div", &[Value::F32(1.0), Value::F32(2.0)]),
+        vec![Value::I32(0.5f32.to_bits() as i32)]
+    );
+    assert_eq!(
+        call(&instance, "f64_sqrt", &[Value::F64(4.0)]),
+        vec![Value::I64(2.0f64.to_bits() as i64)]
+    );
+    // Bitwise operators keep the payload of their NaN operand.
+    assert_eq!(
+        call(
+            &instance,
+            "f32_neg",
+            &[Value::F32(f32::from_bits(0xFFC0_1234))]
+        ),
+        vec![Value::I32(0x7FC0_1234)]
+    );
+}
@@ -0,0 +1,239 @@
+use std::sync::Arc;
+use wasmer_runtime_core::{
+    backend::{CompilerConfig, ExceptionCode, Features},
+    compile_with_config,
+    error::{CallError, ResolveError, RuntimeErrorKind},
+    imports,
+    typed_func::DynamicFunc,
+    types::{FuncSig, Type, Value},
+    Instance,
+};
+use wasmer_runtime_core_tests::get_compiler;
+
+const MODULE: &str = r#"
+(module
+  (type $get_t (func (result i32)))
+  (import "env" "host_ref" (func $host_ref (param anyref) (result anyref)))
+  (table $funcs 2 anyfunc)
+  (table $refs 1 4 anyref)
+  (elem (i32.const 0) $one)
+  (elem passive anyfunc (ref.func $two) (ref.null))
+  (func $one (type $get_t) (i32.const 1))
+  (func $two (type $get_t) (i32.const 2))
+  (func (export "get_ref") (param i32) (result anyref)
+    (table.get $refs (get_local 0)))
+  (func (export "set_ref") (param i32 anyref)
+    (table.set $refs (get_local 0) (get_local 1)))
+  (func (export "grow_refs") (param anyref i32) (result i32)
+    (table.grow $refs (get_local 0) (get_local 1)))
+  (func (export "size_refs") (result i32)
+    (table.size $refs))
+  (func (export "fill_refs") (param i32 anyref i32)
+    (table.fill $refs (get_local 0) (get_local 1) (get_local 2)))
+  (func (export "is_null") (param anyref) (result i32)
+    (ref.is_null (get_local 0)))
+  (func (export "call_host") (param anyref) (result anyref)
+    (call $host_ref (get_local 0)))
+  (func (export "set_func") (param i32)
+    (table.set $funcs (get_local 0) (ref.func $two)))
+  (func (export "copy_func") (param i32 i32)
+    (table.set $funcs (get_local 0) (table.get $funcs (get_local 1))))
+  (func (export "clear_func") (param i32)
+    (table.set $funcs (get_local 0) (ref.null)))
+  (func (export "store_func") (param i32 anyfunc)
+    (table.set $funcs (get_local 0) (get_local 1)))
+  (func (export "init_funcs") (param i32 i32 i32)
+    (table.init 1 (get_local 0) (get_local 1) (get_local 2)))
+  (func (export "call_func") (param i32) (result i32)
+    (call_indirect (type $get_t) (get_local 0))))
+"#;
+
+fn instantiate() -> Instance {
+    let mut features = wabt::Features::new();
+    features.enable_bulk_memory();
+    features.enable_reference_types();
+    let wasm_binary = wabt::wat2wasm_with_features(MODULE.as_bytes(), features)
+        .expect("WAST not valid or malformed");
+    let config = CompilerConfig {
+        features: Features {
+            bulk_memory: true,
+            reference_types: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let module = compile_with_config(&wasm_binary, &get_compiler(), config).unwrap();
+    let import_object = imports! {
+        "env" => {
+            "host_ref" => DynamicFunc::new(
+                Arc::new(FuncSig::new(vec![Type::ExternRef], vec![Type::ExternRef])),
+                |_, params| -> Vec<Value> {
+                    match params[0] {
+                        Value::ExternRef(reference) => vec![Value::ExternRef(reference + 1)],
+                        _ => unreachable!(),
+                    }
+                }
+            ),
+        },
+    };
+    module.instantiate(&import_object).unwrap()
+}
+
+fn call(instance: &Instance, name: &str, params: &[Value]) -> Result<Vec<Value>, CallError> {
+    instance.call(name, params)
+}
+
+fn trap_kind(result: Result<Vec<Value>, CallError>) -> RuntimeErrorKind {
+    match result {
+        Err(CallError::Runtime(error)) => error.kind(),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn externref_tables() {
+    let instance = instantiate();
+
+    assert_eq!(
+        call(&instance, "get_ref", &[Value::I32(0)]).unwrap(),
+        vec![Value::ExternRef(0)]
+    );
+    call(&instance, "set_ref", &[Value::I32(0), Value::ExternRef(42)]).unwrap();
+    assert_eq!(
+        call(&instance, "get_ref", &[Value::I32(0)]).unwrap(),
+        vec![Value::ExternRef(42)]
+    );
+
+    assert_eq!(
+        call(
+            &instance,
+            "grow_refs",
+            &[Value::ExternRef(7), Value::I32(2)]
+        )
+        .unwrap(),
+        vec![Value::I32(1)]
+    );
+    assert_eq!(
+        call(
+            &instance,
+            "grow_refs",
+            &[Value::ExternRef(7), Value::I32(2)]
+        )
+        .unwrap(),
+        vec![Value::I32(-1)]
+    );
+    assert_eq!(
+        call(&instance, "size_refs", &[]).unwrap(),
+        vec![Value::I32(3)]
+    );
+    assert_eq!(
+        call(&instance, "get_ref", &[Value::I32(2)]).unwrap(),
+        vec![Value::ExternRef(7)]
+    );
+
+    call(
+        &instance,
+        "fill_refs",
+        &[Value::I32(1), Value::ExternRef(9), Value::I32(2)],
+    )
+    .unwrap();
+    assert_eq!(
+        call(&instance, "get_ref", &[Value::I32(1)]).unwrap(),
+        vec![Value::ExternRef(9)]
+    );
+
+    assert_eq!(
+        trap_kind(call(&instance, "get_ref", &[Value::I32(3)])),
+        RuntimeErrorKind::WasmTrap(ExceptionCode::TableOutOfBounds)
+    );
+    assert_eq!(
+        trap_kind(call(
+            &instance,
+            "fill_refs",
+            &[Value::I32(2), Value::ExternRef(9), Value::I32(2)]
+        )),
+        RuntimeErrorKind::WasmTrap(ExceptionCode::TableOutOfBounds)
+    );
+
+    assert_eq!(
+        call(&instance, "is_null", &[Value::ExternRef(0)]).unwrap(),
+        vec![Value::I32(1)]
+    );
+    assert_eq!(
+        call(&instance, "is_null", &[Value::ExternRef(42)]).unwrap(),
+        vec![Value::I32(0)]
+    );
+    assert_eq!(
+        call(&instance, "call_host", &[Value::ExternRef(42)]).unwrap(),
+        vec![Value::ExternRef(43)]
+    );
+}
+
+#[test]
+fn funcref_tables() {
+    let instance = instantiate();
+
+    assert_eq!(
+        call(&instance, "call_func", &[Value::I32(0)]).unwrap(),
+        vec![Value::I32(1)]
+    );
+
+    call(&instance, "set_func", &[Value::I32(1)]).unwrap();
+    assert_eq!(
+        call(&instance, "call_func", &[Value::I32(1)]).unwrap(),
+        vec![Value::I32(2)]
+    );
+
+    call(&instance, "copy_func", &[Value::I32(1), Value::I32(0)]).unwrap();
+    assert_eq!(
+        call(&instance, "call_func", &[Value::I32(1)]).unwrap(),
+        vec![Value::I32(1)]
+    );
+
+    call(&instance, "clear_func", &[Value::I32(0)]).unwrap();
+    assert_eq!(
+        trap_kind(call(&instance, "call_func", &[Value::I32(0)])),
+        RuntimeErrorKind::WasmTrap(ExceptionCode::IncorrectCallIndirectSignature)
+    );
+}
+
+#[test]
+fn passive_segments_with_null_elements() {
+    let instance = instantiate();
+
+    call(
+        &instance,
+        "init_funcs",
+        &[Value::I32(0), Value::I32(0), Value::I32(2)],
+    )
+    .unwrap();
+    assert_eq!(
+        call(&instance, "call_func", &[Value::I32(0)]).unwrap(),
+        vec![Value::I32(2)]
+    );
+    assert_eq!(
+        trap_kind(call(&instance, "call_func", &[Value::I32(1)])),
+        RuntimeErrorKind::WasmTrap(ExceptionCode::IncorrectCallIndirectSignature)
+    );
+}
+
+#[test]
+fn host_func_refs_are_rejected() {
+    let instance = instantiate();
+
+    call(&instance, "store_func", &[Value::I32(0), Value::FuncRef(0)]).unwrap();
+    assert_eq!(
+        trap_kind(call(&instance, "call_func", &[Value::I32(0)])),
+        RuntimeErrorKind::WasmTrap(ExceptionCode::IncorrectCallIndirectSignature)
+    );
+
+    // any other reference would be read as a function
+    match call(
+        &instance,
+        "store_func",
+        &[Value::I32(0), Value::FuncRef(0xdead_beef)],
+    ) {
+        Err(CallError::Resolve(ResolveError::HostFuncRef)) => {}
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
@@ -0,0 +1,125 @@
+#![cfg(feature = "backend-singlepass")]
+
+use wasmer_runtime_core::{
+    backend::ExceptionCode,
+    codegen::{
+        Event, EventSink, FunctionMiddleware, InternalEvent, MiddlewareChain, StreamingCompiler,
+    },
+    compile_with,
+    error::{get_last_trap_backtrace, CallError, RuntimeError, RuntimeErrorKind, Trap, WasmFrame},
+    imports,
+    module::ModuleInfo,
+    structures::TypedIndex,
+    types::{FuncIndex, Value},
+    wasmparser::Operator,
+};
+use wasmer_runtime_core_tests::get_compiler;
+use wasmer_singlepass_backend::ModuleCodeGenerator as MCG;
+
+const MODULE: &str = r#"
+(module
+  (func $inner
+    (nop)
+    (unreachable))
+  (func $outer (export "outer")
+    (call $inner)))
+"#;
+
+fn runtime_error(result: Result<Vec<Value>, CallError>) -> RuntimeError {
+    match result {
+        Err(CallError::Runtime(error)) => error,
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+fn wat2wasm_with_names(wat: &str) -> Vec<u8> {
+    wabt::Wat2Wasm::new()
+        .write_debug_names(true)
+        .convert(wat)
+        .expect("WAST not valid or malformed")
+        .as_ref()
+        .to_vec()
+}
+
+/// Stops the execution with a breakpoint before each `nop`.
+struct BreakOnNop;
+
+impl FunctionMiddleware for BreakOnNop {
+    type Error = String;
+    fn feed_event<'a, 'b: 'a>(
+        &mut self,
+        op: Event<'a, 'b>,
+        _module_info: &ModuleInfo,
+        sink: &mut EventSink<'a, 'b>,
+        _source_loc: u32,
+    ) -> Result<(), Self::Error> {
+        if let Event::Wasm(Operator::Nop) = op {
+            sink.push(Event::Internal(InternalEvent::Breakpoint(Box::new(|_| {
+                Err(Box::new(Trap::new(RuntimeErrorKind::UserTrap, "stopped")))
+            }))));
+        }
+        sink.push(op);
+        Ok(())
+    }
+}
+
+#[test]
+fn traps_carry_a_symbolic_backtrace() {
+    let wasm_binary = wat2wasm_with_names(MODULE);
+    let module = compile_with(&wasm_binary, &get_compiler()).unwrap();
+    let instance = module.instantiate(&imports! {}).unwrap();
+
+    let error = runtime_error(instance.call("outer", &[]));
+    assert_eq!(
+        error.kind(),
+        RuntimeErrorKind::WasmTrap(ExceptionCode::Unreachable)
+    );
+    // The payload is still the exception code.
+    assert!(error.0.downcast_ref::<ExceptionCode>().is_some());
+
+    let backtrace = get_last_trap_backtrace().expect("no backtrace");
+    assert_eq!(backtrace.frames.len(), 2);
+    assert_eq!(
+        backtrace.frames[0],
+        WasmFrame {
+            function_index: FuncIndex::new(0),
+            function_name: Some("inner".to_string()),
+            operator_index: backtrace.frames[0].operator_index,
+        }
+    );
+    assert_eq!(backtrace.frames[1].function_index, FuncIndex::new(1));
+    assert_eq!(backtrace.frames[1].function_name, Some("outer".to_string()));
+    assert!(backtrace.to_string().contains("inner @ operator"));
+}
+
+#[test]
+fn backtraces_without_name_section() {
+    let wasm_binary = wasmer_runtime_core_tests::wat2wasm(MODULE).unwrap();
+    let module = compile_with(&wasm_binary, &get_compiler()).unwrap();
+    let instance = module.instantiate(&imports! {}).unwrap();
+
+    runtime_error(instance.call("outer", &[]));
+    let backtrace = get_last_trap_backtrace().expect("no backtrace");
+    assert_eq!(backtrace.frames[0].function_name, None);
+    assert!(backtrace.to_string().contains("<function 0> @ operator"));
+}
+
+#[test]
+fn breakpoint_traps_carry_a_backtrace() {
+    let wasm_binary = wat2wasm_with_names(MODULE);
+    let compiler: StreamingCompiler<MCG, _, _, _, _> = StreamingCompiler::new(|| {
+        let mut chain = MiddlewareChain::new();
+        chain.push(BreakOnNop);
+        chain
+    });
+    let module = compile_with(&wasm_binary, &compiler).unwrap();
+    let instance = module.instantiate(&imports! {}).unwrap();
+
+    let error = runtime_error(instance.call("outer", &[]));
+    assert_eq!(error.kind(), RuntimeErrorKind::UserTrap);
+
+    let backtrace = get_last_trap_backtrace().expect("no backtrace");
+    assert_eq!(backtrace.frames.len(), 2);
+    assert_eq!(backtrace.frames[0].function_name, Some("inner".to_string()));
+    assert_eq!(backtrace.frames[1].function_name, Some("outer".to_string()));
+}
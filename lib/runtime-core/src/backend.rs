@@ -8,7 +8,7 @@ use crate::{
 };
 
 use crate::{
-    cache::{Artifact, Error as CacheError},
+    cache::{Artifact, CompilationFingerprint, Error as CacheError},
     codegen::BreakpointMap,
     module::ModuleInfo,
     sys::Memory,
@@ -69,7 +69,18 @@ impl Token {
     }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    Archive,
+    RkyvSerialize,
+    RkyvDeserialize,
+)]
 pub enum MemoryBoundCheckMode {
     Default,
     Enable,
@@ -86,7 +97,19 @@ impl Default for MemoryBoundCheckMode {
 /// Features usually have a corresponding [WebAssembly proposal][wasm-props].
 ///
 /// [wasm-props]: https://github.com/WebAssembly/proposals
-#[derive(Debug, Default)]
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    Archive,
+    RkyvSerialize,
+    RkyvDeserialize,
+)]
 pub struct Features {
     /// Whether support for the [SIMD proposal][simd-prop] is enabled.
     ///
@@ -96,6 +119,18 @@ pub struct Features {
     ///
     /// [threads-prop]: https://github.com/webassembly/threads
     pub threads: bool,
+    /// Whether support for the [bulk memory proposal][bulk-memory-prop] is enabled.
+    ///
+    /// [bulk-memory-prop]: https://github.com/WebAssembly/bulk-memory-operations
+    pub bulk_memory: bool,
+    /// Whether support for the [multi-value proposal][multi-value-prop] is enabled.
+    ///
+    /// [multi-value-prop]: https://github.com/WebAssembly/multi-value
+    pub multi_value: bool,
+    /// Whether support for the [reference types proposal][reference-types-prop] is enabled.
+    ///
+    /// [reference-types-prop]: https://github.com/WebAssembly/reference-types
+    pub reference_types: bool,
 }
 
 /// Use this to point to a compiler config struct provided by the backend.
@@ -140,6 +175,12 @@ pub struct CompilerConfig {
 
     pub features: Features,
 
+    /// Whether to replace the NaNs produced by float operators with the canonical NaN, so that
+    /// float results are bit-exact across CPUs. Presently only supported by Singlepass.
+    ///
+    /// Deterministic builds reject float operators unless this is enabled.
+    pub nan_canonicalization: bool,
+
     // Target info. Presently only supported by LLVM.
     pub triple: Option<String>,
     pub cpu_name: Option<String>,
@@ -148,6 +189,15 @@ pub struct CompilerConfig {
     pub backend_specific_config: Option<BackendCompilerConfig>,
 
     pub generate_debug_info: bool,
+
+    /// Version of the gas schedule used by the metering middleware, if any. It is recorded in
+    /// the `ModuleInfo` of the compiled module, and thus in its cached artifacts.
+    pub gas_schedule_version: Option<u32>,
+
+    /// Fingerprint of the settings that shape the generated code but are not otherwise known
+    /// to runtime-core, such as the metering configuration. Cached artifacts record it, and
+    /// `load_cache_with_fingerprint` refuses artifacts compiled with a different one.
+    pub fingerprint: CompilationFingerprint,
 }
 
 impl CompilerConfig {
@@ -187,6 +237,10 @@ pub enum ExceptionCode {
     IllegalArithmetic = 4,
     /// Misaligned atomic access trap.
     MisalignedAtomicAccess = 5,
+    /// The native stack was exhausted, e.g. by too deep a recursion.
+    StackOverflow = 6,
+    /// Table out of bounds trap, e.g. from `table.copy` or `table.init`.
+    TableOutOfBounds = 7,
 }
 
 impl fmt::Display for ExceptionCode {
@@ -203,6 +257,8 @@ impl fmt::Display for ExceptionCode {
                 ExceptionCode::CallIndirectOOB => "`call_indirect` out-of-bounds",
                 ExceptionCode::IllegalArithmetic => "illegal arithmetic operation",
                 ExceptionCode::MisalignedAtomicAccess => "misaligned atomic access",
+                ExceptionCode::StackOverflow => "stack overflow",
+                ExceptionCode::TableOutOfBounds => "table out-of-bounds access",
             }
         )
     }
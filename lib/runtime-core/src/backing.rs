@@ -3,24 +3,31 @@ use crate::{
     export::{Context, Export},
     global::Global,
     import::ImportObject,
-    memory::{Memory, MAX_MEMORIES_COUNT},
+    memory::{Memory, MemoryType, MAX_MEMORIES_COUNT},
     module::{DataInitializer, ImportName, ModuleInfo, ModuleInner},
     sig_registry::SigRegistry,
     structures::{BoxedMap, Map, SliceMap, TypedIndex},
     table::Table,
     typed_func::{always_trap, Func},
     types::{
-        GlobalInit, ImportedFuncIndex, ImportedGlobalIndex, ImportedMemoryIndex,
+        FuncIndex, GlobalInit, ImportedFuncIndex, ImportedGlobalIndex, ImportedMemoryIndex,
         ImportedTableIndex, Initializer, LocalFuncIndex, LocalGlobalIndex, LocalMemoryIndex,
         LocalOrImport, LocalTableIndex, SigIndex, Value,
     },
     vm,
 };
-use std::{fmt::Debug, ptr::NonNull, slice};
+use std::{cell::RefCell, collections::HashSet, fmt::Debug, ptr::NonNull, slice};
 
 /// Size of the array for internal instance usage
 pub const INTERNALS_SIZE: usize = 256;
 
+/// Index of the internal field holding the address of the multi-value area of an instance.
+///
+/// The multi-value area holds the values of a branch or of a call with several values while they
+/// are in transit, one 64-bit slot per value. The field is the last one, so that its index does
+/// not depend on the order in which the `InternalField`s are allocated.
+pub const INTERNAL_MULTI_VALUE_AREA: usize = INTERNALS_SIZE - 1;
+
 pub(crate) struct Internals(pub(crate) [u64; INTERNALS_SIZE]);
 
 impl Debug for Internals {
@@ -53,7 +60,18 @@ pub struct LocalBacking {
     pub(crate) dynamic_sigindices: BoxedMap<SigIndex, vm::SigId>,
     pub(crate) local_functions: BoxedMap<LocalFuncIndex, *const vm::Func>,
 
+    /// The functions of the instance, imported ones included, which `ref.func` and tables of
+    /// `externref` reference.
+    pub(crate) func_refs: Box<[vm::Anyfunc]>,
+
     pub(crate) internals: Internals,
+
+    /// The multi-value area, large enough for the params or the results of any signature.
+    pub(crate) multi_value_area: Box<[u64]>,
+
+    /// Indices of the passive data and element segments dropped by `data.drop` and `elem.drop`.
+    pub(crate) dropped_data: RefCell<HashSet<u32>>,
+    pub(crate) dropped_elements: RefCell<HashSet<u32>>,
 }
 
 // Manually implemented because LocalBacking contains raw pointers directly
@@ -75,18 +93,23 @@ impl LocalBacking {
         };
         let mut tables = Self::generate_tables(module);
         let mut globals = Self::generate_globals(module, imports)?;
+        let dynamic_sigindices = Self::generate_sigindices(&module.info);
+        let func_refs = Self::generate_func_refs(module, imports, &dynamic_sigindices, vmctx);
 
         // Ensure all initializers are valid before running finalizers
         Self::validate_memories(module, imports)?;
         Self::validate_tables(module, imports, &mut tables)?;
 
         let vm_memories = Self::finalize_memories(module, imports, &mut memories)?;
-        let vm_tables = Self::finalize_tables(module, imports, &mut tables, vmctx)?;
+        let vm_tables = Self::finalize_tables(module, imports, &mut tables, &func_refs)?;
         let vm_globals = Self::finalize_globals(&mut globals);
 
-        let dynamic_sigindices = Self::generate_sigindices(&module.info);
         let local_functions = Self::generate_local_functions(module);
 
+        let mut internals = Internals([0; INTERNALS_SIZE]);
+        let mut multi_value_area = Self::generate_multi_value_area(&module.info);
+        internals.0[INTERNAL_MULTI_VALUE_AREA] = multi_value_area.as_mut_ptr() as u64;
+
         Ok(Self {
             memories,
             tables,
@@ -98,24 +121,89 @@ impl LocalBacking {
 
             dynamic_sigindices,
             local_functions,
+            func_refs,
 
-            internals: Internals([0; INTERNALS_SIZE]),
+            internals,
+            multi_value_area,
+
+            dropped_data: RefCell::new(HashSet::new()),
+            dropped_elements: RefCell::new(HashSet::new()),
         })
     }
 
-    /// Resets the `LocalBacking` (`Memories` and `Globals`) for an `Instance` using the provided `ModuleInfo`
-    pub(crate) fn reset(&self, module_info: &ModuleInfo) -> RuntimeResult<()> {
-        Self::reset_memories(&module_info, &self.memories)?;
-        Self::reset_globals(&module_info, &self.globals)
+    /// Resets the `LocalBacking` (`Memories`, `Tables` and `Globals`) for an `Instance` using the
+    /// provided `ModuleInner`, so that it is left in the same state as after its instantiation.
+    pub(crate) fn reset(&self, module: &ModuleInner, imports: &ImportBacking) -> RuntimeResult<()> {
+        Self::reset_memories(&module.info, imports, &self.memories)?;
+        Self::reset_tables(module, imports, &self.tables, &self.func_refs)?;
+        Self::reset_globals(&module.info, imports, &self.globals)?;
+
+        self.dropped_data.borrow_mut().clear();
+        self.dropped_elements.borrow_mut().clear();
+        Ok(())
+    }
+
+    /// Takes a copy-on-write snapshot of the local memories, which `reset` restores afterwards
+    /// instead of zeroing the memories and re-applying the data initializers.
+    pub(crate) fn snapshot_memories(&self, module_info: &ModuleInfo) -> RuntimeResult<()> {
+        // Fail before taking any snapshot, so that `reset` never restores only some memories
+        Self::check_memories_resettable(module_info, &self.memories)?;
+
+        for (_index, memory) in self.memories.iter() {
+            memory.take_snapshot()?;
+        }
+        Ok(())
+    }
+
+    /// Checks that `reset` can restore the memories, so that it fails before changing any of them.
+    fn check_memories_resettable(
+        module_info: &ModuleInfo,
+        memories: &SliceMap<LocalMemoryIndex, Memory>,
+    ) -> RuntimeResult<()> {
+        for (_index, memory) in memories.iter() {
+            if memory.descriptor().memory_type() != MemoryType::Dynamic {
+                return Err(RuntimeError(Box::new(
+                    "Resetting memories with a maximum size is not supported",
+                )));
+            }
+        }
+
+        let initializes_imported_memory =
+            module_info.data_initializers.iter().any(|init| {
+                match init.memory_index.local_or_import(module_info) {
+                    LocalOrImport::Local(_) => false,
+                    LocalOrImport::Import(_) => true,
+                }
+            });
+        if initializes_imported_memory {
+            return Err(RuntimeError(Box::new(
+                "Imported memory reset is not supported",
+            )));
+        }
+
+        Ok(())
     }
 
     fn reset_memories(
         module_info: &ModuleInfo,
+        imports: &ImportBacking,
         memories: &SliceMap<LocalMemoryIndex, Memory>,
     ) -> RuntimeResult<()> {
+        Self::check_memories_resettable(module_info, memories)?;
+
+        if memories
+            .iter()
+            .all(|(_index, memory)| memory.has_snapshot())
+        {
+            for (_index, memory) in memories.iter() {
+                memory.restore_snapshot()?;
+            }
+            return Ok(());
+        }
+
         Self::zero_memories(memories);
         Self::shrink_memories(memories)?;
-        Self::reinitialize_memories(module_info, memories)
+        Self::reinitialize_memories(module_info, imports, memories)
     }
 
     fn shrink_memories(memories: &SliceMap<LocalMemoryIndex, Memory>) -> RuntimeResult<()> {
@@ -136,6 +224,7 @@ impl LocalBacking {
 
     fn reinitialize_memories(
         module_info: &ModuleInfo,
+        imports: &ImportBacking,
         memories: &SliceMap<LocalMemoryIndex, Memory>,
     ) -> RuntimeResult<()> {
         for data_initializer in module_info.data_initializers.iter() {
@@ -145,14 +234,9 @@ impl LocalBacking {
                 data,
             } = data_initializer;
 
-            let offset = match base {
-                Initializer::Const(Value::I32(value)) => *value as usize,
-                Initializer::Const(_) => {
-                    return Err(RuntimeError(Box::new("Const initializer must be an i32")))
-                }
-                Initializer::GetGlobal(_index) => {
-                    return Err(RuntimeError(Box::new("Initializer is not supported")))
-                }
+            let offset = match Self::get_initializer_value(base, imports)? {
+                Value::I32(value) => value as usize,
+                _ => return Err(RuntimeError(Box::new("Initializer must be an i32"))),
             };
 
             match memory_index.local_or_import(&module_info) {
@@ -176,23 +260,57 @@ impl LocalBacking {
         Ok(())
     }
 
+    fn reset_tables(
+        module: &ModuleInner,
+        imports: &ImportBacking,
+        tables: &SliceMap<LocalTableIndex, Table>,
+        func_refs: &[vm::Anyfunc],
+    ) -> RuntimeResult<()> {
+        for (_index, table) in tables.iter() {
+            table.reset_to_minimum();
+        }
+
+        for init in &module.info.elem_initializers {
+            let init_base = match Self::get_initializer_value(&init.base, imports)? {
+                Value::I32(offset) => offset as u32 as usize,
+                _ => return Err(RuntimeError(Box::new("Initializer must be an i32"))),
+            };
+
+            // Imported tables belong to the host and may be shared with other instances, so only
+            // the local ones are restored.
+            if let LocalOrImport::Local(local_table_index) =
+                init.table_index.local_or_import(&module.info)
+            {
+                let table = match tables.get(local_table_index) {
+                    Some(table) => table,
+                    None => return Err(RuntimeError(Box::new("Undefined table"))),
+                };
+
+                table
+                    .write_functions(
+                        init_base,
+                        &Self::get_func_refs(func_refs, init.elements.iter().cloned().map(Some)),
+                    )
+                    .map_err(|()| RuntimeError(Box::new("elements segment does not fit")))?;
+            }
+        }
+
+        Ok(())
+    }
+
     fn reset_globals(
         module_info: &ModuleInfo,
+        imports: &ImportBacking,
         globals: &SliceMap<LocalGlobalIndex, Global>,
     ) -> RuntimeResult<()> {
         for (index, global_init) in module_info.globals.iter() {
             let GlobalInit { desc, init } = global_init;
 
-            let value = match init {
-                Initializer::Const(value) => value.clone(),
-                Initializer::GetGlobal(_index) => {
-                    return Err(RuntimeError(Box::new("Initializer is not supported")))
-                }
-            };
+            let value = Self::get_initializer_value(init, imports)?;
 
             if desc.mutable {
                 match globals.get(index) {
-                    Some(global) => global.set(value),
+                    Some(global) => global.set_internal(value),
                     None => return Err(RuntimeError(Box::new("Undefined global"))),
                 }
             }
@@ -201,6 +319,21 @@ impl LocalBacking {
         Ok(())
     }
 
+    fn get_initializer_value(
+        initializer: &Initializer,
+        imports: &ImportBacking,
+    ) -> RuntimeResult<Value> {
+        match initializer {
+            Initializer::Const(value) => Ok(value.clone()),
+            Initializer::GetGlobal(import_global_index) => {
+                match imports.globals.get(*import_global_index) {
+                    Some(global) => Ok(global.get()),
+                    None => Err(RuntimeError(Box::new("Undefined imported global"))),
+                }
+            }
+        }
+    }
+
     fn generate_local_functions(module: &ModuleInner) -> BoxedMap<LocalFuncIndex, *const vm::Func> {
         (0..module.info.func_assoc.len() - module.info.imported_functions.len())
             .map(|index| {
@@ -226,6 +359,17 @@ impl LocalBacking {
             .into_boxed_map()
     }
 
+    fn generate_multi_value_area(info: &ModuleInfo) -> Box<[u64]> {
+        let size = info
+            .signatures
+            .iter()
+            .map(|(_, signature)| signature.params().len().max(signature.returns().len()))
+            .max()
+            .unwrap_or(0);
+
+        vec![0; size].into_boxed_slice()
+    }
+
     fn generate_memories(
         module: &ModuleInner,
     ) -> CreationResult<BoxedMap<LocalMemoryIndex, Memory>> {
@@ -444,7 +588,7 @@ impl LocalBacking {
         module: &ModuleInner,
         imports: &ImportBacking,
         tables: &mut SliceMap<LocalTableIndex, Table>,
-        vmctx: *mut vm::Ctx,
+        func_refs: &[vm::Anyfunc],
     ) -> LinkResult<BoxedMap<LocalTableIndex, *mut vm::LocalTable>> {
         for init in &module.info.elem_initializers {
             let init_base = match init.base {
@@ -470,73 +614,20 @@ impl LocalBacking {
                 }
             } as usize;
 
-            match init.table_index.local_or_import(&module.info) {
-                LocalOrImport::Local(local_table_index) => {
-                    let table = &tables[local_table_index];
-                    table.anyfunc_direct_access_mut(|elements| {
-                        for (i, &func_index) in init.elements.iter().enumerate() {
-                            let sig_index = module.info.func_assoc[func_index];
-                            // let signature = &module.info.signatures[sig_index];
-                            let signature = SigRegistry
-                                .lookup_signature_ref(&module.info.signatures[sig_index]);
-                            let sig_id =
-                                vm::SigId(SigRegistry.lookup_sig_index(signature).index() as u32);
-
-                            let (func, ctx) = match func_index.local_or_import(&module.info) {
-                                LocalOrImport::Local(local_func_index) => (
-                                    module
-                                        .runnable_module
-                                        .get_func(&module.info, local_func_index)
-                                        .unwrap()
-                                        .as_ptr()
-                                        as *const vm::Func,
-                                    vmctx,
-                                ),
-                                LocalOrImport::Import(imported_func_index) => {
-                                    let vm::ImportedFunc { func, func_ctx } =
-                                        imports.vm_functions[imported_func_index];
-                                    (func, unsafe { func_ctx.as_ref() }.vmctx.as_ptr())
-                                }
-                            };
-
-                            elements[init_base + i] = vm::Anyfunc { func, ctx, sig_id };
-                        }
-                    });
-                }
-                LocalOrImport::Import(import_table_index) => {
-                    let table = &imports.tables[import_table_index];
-
-                    table.anyfunc_direct_access_mut(|elements| {
-                        for (i, &func_index) in init.elements.iter().enumerate() {
-                            let sig_index = module.info.func_assoc[func_index];
-                            let signature = SigRegistry
-                                .lookup_signature_ref(&module.info.signatures[sig_index]);
-                            // let signature = &module.info.signatures[sig_index];
-                            let sig_id =
-                                vm::SigId(SigRegistry.lookup_sig_index(signature).index() as u32);
-
-                            let (func, ctx) = match func_index.local_or_import(&module.info) {
-                                LocalOrImport::Local(local_func_index) => (
-                                    module
-                                        .runnable_module
-                                        .get_func(&module.info, local_func_index)
-                                        .unwrap()
-                                        .as_ptr()
-                                        as *const vm::Func,
-                                    vmctx,
-                                ),
-                                LocalOrImport::Import(imported_func_index) => {
-                                    let vm::ImportedFunc { func, func_ctx } =
-                                        imports.vm_functions[imported_func_index];
-                                    (func, unsafe { func_ctx.as_ref() }.vmctx.as_ptr())
-                                }
-                            };
-
-                            elements[init_base + i] = vm::Anyfunc { func, ctx, sig_id };
-                        }
-                    });
-                }
-            }
+            let table = match init.table_index.local_or_import(&module.info) {
+                LocalOrImport::Local(local_table_index) => &tables[local_table_index],
+                LocalOrImport::Import(import_table_index) => &imports.tables[import_table_index],
+            };
+            table
+                .write_functions(
+                    init_base,
+                    &Self::get_func_refs(func_refs, init.elements.iter().cloned().map(Some)),
+                )
+                .map_err(|()| {
+                    vec![LinkError::Generic {
+                        message: "elements segment does not fit".to_string(),
+                    }]
+                })?;
         }
 
         Ok(tables
@@ -546,6 +637,51 @@ impl LocalBacking {
             .into_boxed_map())
     }
 
+    /// Returns the references to the functions of an element segment, `None` standing for a
+    /// null element.
+    pub(crate) fn get_func_refs<'a>(
+        func_refs: &'a [vm::Anyfunc],
+        func_indices: impl IntoIterator<Item = Option<FuncIndex>>,
+    ) -> Vec<Option<&'a vm::Anyfunc>> {
+        func_indices
+            .into_iter()
+            .map(|func_index| func_index.map(|func_index| &func_refs[func_index.index()]))
+            .collect()
+    }
+
+    fn generate_func_refs(
+        module: &ModuleInner,
+        imports: &ImportBacking,
+        dynamic_sigindices: &SliceMap<SigIndex, vm::SigId>,
+        vmctx: *mut vm::Ctx,
+    ) -> Box<[vm::Anyfunc]> {
+        let mut func_refs = Vec::with_capacity(module.info.func_assoc.len());
+
+        for (func_index, &sig_index) in module.info.func_assoc.iter() {
+            let sig_id = dynamic_sigindices[sig_index];
+
+            let (func, ctx) = match func_index.local_or_import(&module.info) {
+                LocalOrImport::Local(local_func_index) => (
+                    module
+                        .runnable_module
+                        .get_func(&module.info, local_func_index)
+                        .unwrap()
+                        .as_ptr() as *const vm::Func,
+                    vmctx,
+                ),
+                LocalOrImport::Import(imported_func_index) => {
+                    let vm::ImportedFunc { func, func_ctx } =
+                        imports.vm_functions[imported_func_index];
+                    (func, unsafe { func_ctx.as_ref() }.vmctx.as_ptr())
+                }
+            };
+
+            func_refs.push(vm::Anyfunc { func, ctx, sig_id });
+        }
+
+        func_refs.into_boxed_slice()
+    }
+
     fn generate_globals(
         module: &ModuleInner,
         imports: &ImportBacking,
@@ -569,13 +705,7 @@ impl LocalBacking {
                 }
             };
 
-            let global = if global_init.desc.mutable {
-                Global::new_mutable(value)
-            } else {
-                Global::new(value)
-            };
-
-            globals.push(global);
+            globals.push(Global::new_internal(value, global_init.desc.mutable));
         }
 
         Ok(globals.into_boxed_map())
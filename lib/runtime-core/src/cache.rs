@@ -32,6 +32,13 @@ pub enum Error {
     InvalidatedCache,
     /// The current backend does not support caching.
     UnsupportedBackend(String),
+    /// The cached binary was compiled with different compilation settings.
+    MismatchedFingerprint {
+        /// The fingerprint the binary was expected to have.
+        expected: CompilationFingerprint,
+        /// The fingerprint recorded in the binary.
+        found: CompilationFingerprint,
+    },
 }
 
 impl From<io::Error> for Error {
@@ -92,7 +99,42 @@ impl WasmHash {
     }
 }
 
-const CURRENT_CACHE_VERSION: u64 = 0;
+/// The hash of the compilation settings that shape the generated code of a module, such as
+/// the metering configuration and its opcode costs.
+///
+/// Stored in the header of a cache binary, so that a binary compiled under some settings is
+/// never loaded under others. The default fingerprint stands for "no particular settings".
+#[derive(
+    Debug,
+    Default,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+    Archive,
+    RkyvSerialize,
+    RkyvDeserialize,
+)]
+pub struct CompilationFingerprint([u8; 32]);
+
+impl CompilationFingerprint {
+    /// Hash a stable binary encoding of the compilation settings.
+    pub fn generate(settings: &[u8]) -> Self {
+        let hash = blake3::hash(settings);
+        CompilationFingerprint(hash.into())
+    }
+
+    /// Create the hexadecimal representation of the
+    /// stored hash.
+    pub fn encode(self) -> String {
+        hex::encode(&self.0 as &[u8])
+    }
+}
+
+const CURRENT_CACHE_VERSION: u64 = 1;
 static WASMER_CACHE_MAGIC: [u8; 8] = *b"WASMER\0\0";
 
 /// The header of a cache file.
@@ -101,6 +143,7 @@ struct ArtifactHeader {
     magic: [u8; 8], // [W, A, S, M, E, R, \0, \0]
     version: u64,
     data_len: u64,
+    fingerprint: [u8; 32],
 }
 
 impl ArtifactHeader {
@@ -191,6 +234,18 @@ impl Artifact {
         &self.inner.info
     }
 
+    /// The fingerprint of the settings the `Artifact` was compiled with.
+    pub fn fingerprint(&self) -> CompilationFingerprint {
+        self.inner.info.fingerprint
+    }
+
+    /// Reads the fingerprint from the header of a serialized `Artifact`, without
+    /// deserializing the rest of it.
+    pub fn peek_fingerprint(bytes: &[u8]) -> Result<CompilationFingerprint, Error> {
+        let (header, _) = ArtifactHeader::read_from_slice(bytes)?;
+        Ok(CompilationFingerprint(header.fingerprint))
+    }
+
     #[doc(hidden)]
     pub fn consume(self) -> (ModuleInfo, Box<[u8]>, Memory) {
         (
@@ -216,6 +271,7 @@ impl Artifact {
             magic: WASMER_CACHE_MAGIC,
             version: CURRENT_CACHE_VERSION,
             data_len: 0,
+            fingerprint: self.fingerprint().0,
         };
 
         let mut buffer = cache_header.as_slice().to_vec();
@@ -239,11 +295,14 @@ pub const WASMER_VERSION_HASH: &'static str =
 mod tests {
     use super::Artifact;
     use super::ArtifactInner;
+    use super::CompilationFingerprint;
+    use super::Error;
     use super::Memory;
     use super::ModuleInfo;
     use std::collections::HashMap;
     use crate::structures::Map;
     use crate::module::StringTable;
+    use crate::backend::{Features, MemoryBoundCheckMode};
     use rkyv::ser::serializers::AllocSerializer;
     use rkyv::ser::Serializer as RkyvSerializer;
     use rkyv::Deserialize;
@@ -301,6 +360,59 @@ mod tests {
         assert_eq!(deserialized_artifact_inner.compiled_code.protection(), artifact_inner.compiled_code.protection());
     }
 
+    #[test]
+    fn test_serde_artifact_fingerprint() {
+        let bytes = make_test_bytes();
+        let memory = make_test_memory(&bytes);
+
+        let fingerprint = CompilationFingerprint::generate(b"test_settings");
+        let mut module_info = make_empty_module_info();
+        module_info.fingerprint = fingerprint;
+        let artifact = Artifact::from_parts(
+            Box::new(module_info),
+            b"test_backend".to_vec().into_boxed_slice(),
+            memory,
+        );
+        assert_eq!(artifact.fingerprint(), fingerprint);
+
+        let serialized = artifact.serialize().unwrap();
+        assert_eq!(Artifact::peek_fingerprint(&serialized).unwrap(), fingerprint);
+        assert_ne!(Artifact::peek_fingerprint(&serialized).unwrap(), CompilationFingerprint::default());
+
+        let deserialized_artifact = Artifact::deserialize(&serialized).unwrap();
+        assert_eq!(deserialized_artifact.fingerprint(), fingerprint);
+
+        match Artifact::peek_fingerprint(&serialized[..8]) {
+            Err(Error::InvalidFile(_)) => {}
+            _ => panic!("expected an invalid file error"),
+        }
+    }
+
+    #[test]
+    fn test_serde_artifact_features() {
+        let bytes = make_test_bytes();
+        let memory = make_test_memory(&bytes);
+
+        let features = Features {
+            simd: true,
+            bulk_memory: true,
+            ..Default::default()
+        };
+        let mut module_info = make_empty_module_info();
+        module_info.features = features;
+        module_info.memory_bound_check_mode = MemoryBoundCheckMode::Disable;
+        let artifact = Artifact::from_parts(
+            Box::new(module_info),
+            b"test_backend".to_vec().into_boxed_slice(),
+            memory,
+        );
+
+        let serialized = artifact.serialize().unwrap();
+        let deserialized_artifact = Artifact::deserialize(&serialized).unwrap();
+        assert_eq!(deserialized_artifact.info().features, features);
+        assert_eq!(deserialized_artifact.info().memory_bound_check_mode, MemoryBoundCheckMode::Disable);
+    }
+
     fn make_empty_module_info() -> ModuleInfo {
         ModuleInfo {
             memories: Map::new(),
@@ -313,6 +425,8 @@ mod tests {
             exports: Default::default(),
             data_initializers: Vec::new(),
             elem_initializers: Vec::new(),
+            passive_data: HashMap::new(),
+            passive_elements: HashMap::new(),
             start_func: None,
             func_assoc: Map::new(),
             signatures: Map::new(),
@@ -321,7 +435,12 @@ mod tests {
             name_table: StringTable::new(),
             em_symbol_map: None,
             custom_sections: HashMap::new(),
+            func_names: HashMap::new(),
             generate_debug_info: false,
+            gas_schedule_version: None,
+            fingerprint: Default::default(),
+            features: Default::default(),
+            memory_bound_check_mode: Default::default(),
             #[cfg(feature = "generate-debug-information")]
             debug_info_manager: crate::jit_debug::JitCodeDebugInfoManager::new(),
         }
@@ -4,7 +4,7 @@
 use crate::fault::FaultInfo;
 use crate::{
     backend::RunnableModule,
-    backend::{CacheGen, Compiler, CompilerConfig, Features, Token},
+    backend::{CacheGen, Compiler, CompilerConfig, Token},
     cache::{Artifact, Error as CacheError},
     error::{CompileError, CompileResult},
     module::{ModuleInfo, ModuleInner},
@@ -252,25 +252,30 @@ impl<
     }
 }
 
-/// Create a new `ValidatingParserConfig` with the given features.
-pub fn validating_parser_config(features: &Features) -> wasmparser::ValidatingParserConfig {
+/// Create a new `ValidatingParserConfig` with the features of the given `CompilerConfig`.
+///
+/// Float operators are only accepted by deterministic builds when NaN canonicalization is on.
+pub fn validating_parser_config(
+    compiler_config: &CompilerConfig,
+) -> wasmparser::ValidatingParserConfig {
+    let features = &compiler_config.features;
     wasmparser::ValidatingParserConfig {
         operator_config: wasmparser::OperatorValidatorConfig {
             enable_threads: features.threads,
-            enable_reference_types: false,
+            enable_reference_types: features.reference_types,
             enable_simd: features.simd,
-            enable_bulk_memory: false,
-            enable_multi_value: false,
+            enable_bulk_memory: features.bulk_memory,
+            enable_multi_value: features.multi_value,
 
             #[cfg(feature = "deterministic-execution")]
-            deterministic_only: true,
+            deterministic_only: !compiler_config.nan_canonicalization,
         },
     }
 }
 
-fn validate_with_features(bytes: &[u8], features: &Features) -> CompileResult<()> {
+fn validate_with_config(bytes: &[u8], compiler_config: &CompilerConfig) -> CompileResult<()> {
     let mut parser =
-        wasmparser::ValidatingParser::new(bytes, Some(validating_parser_config(features)));
+        wasmparser::ValidatingParser::new(bytes, Some(validating_parser_config(compiler_config)));
     loop {
         let state = parser.read();
         match *state {
@@ -299,7 +304,7 @@ impl<
         _: Token,
     ) -> CompileResult<ModuleInner> {
         if MCG::requires_pre_validation() {
-            validate_with_features(wasm, &compiler_config.features)?;
+            validate_with_config(wasm, &compiler_config)?;
         }
 
         let mut mcg = match MCG::backend_id() {
@@ -1,9 +1,13 @@
 //! The error module contains the data structures and helper functions used to implement errors that
 //! are produced and returned from the wasmer runtime core.
 use crate::backend::ExceptionCode;
-use crate::types::{FuncSig, GlobalDescriptor, MemoryDescriptor, TableDescriptor, Type};
+use crate::module::ModuleInfo;
+use crate::state::ExecutionStateImage;
+use crate::structures::TypedIndex;
+use crate::types::{FuncIndex, FuncSig, GlobalDescriptor, MemoryDescriptor, TableDescriptor, Type};
 use core::borrow::Borrow;
 use std::any::Any;
+use std::cell::RefCell;
 
 /// Aliases the standard `Result` type as `Result` within this module.
 pub type Result<T> = std::result::Result<T, Error>;
@@ -213,6 +217,36 @@ impl std::error::Error for LinkError {}
 /// Comparing two `RuntimeError`s always evaluates to false.
 pub struct RuntimeError(pub Box<dyn Any + Send>);
 
+impl RuntimeError {
+    /// Classifies the reason of this error, see `RuntimeErrorKind`.
+    pub fn kind(&self) -> RuntimeErrorKind {
+        let data = &*self.0;
+        if let Some(trap) = data.downcast_ref::<Trap>() {
+            trap.kind
+        } else if let Some(exc_code) = data.downcast_ref::<ExceptionCode>() {
+            RuntimeErrorKind::WasmTrap(*exc_code)
+        } else if data.is::<HostPanic>() {
+            RuntimeErrorKind::HostPanic
+        } else if let Some(runtime_error) = data.downcast_ref::<RuntimeError>() {
+            runtime_error.kind()
+        } else if data.is::<String>() || data.is::<&str>() {
+            RuntimeErrorKind::UserTrap
+        } else {
+            RuntimeErrorKind::Unknown
+        }
+    }
+
+    fn payload_message(data: &(dyn Any + Send)) -> &str {
+        if let Some(s) = data.downcast_ref::<String>() {
+            s
+        } else if let Some(s) = data.downcast_ref::<&str>() {
+            s
+        } else {
+            "unknown error"
+        }
+    }
+}
+
 impl PartialEq for RuntimeError {
     fn eq(&self, _other: &RuntimeError) -> bool {
         false
@@ -226,8 +260,14 @@ impl std::fmt::Display for RuntimeError {
             write!(f, "\"{}\"", s)
         } else if let Some(s) = data.downcast_ref::<&str>() {
             write!(f, "\"{}\"", s)
+        } else if let Some(trap) = data.downcast_ref::<Trap>() {
+            write!(f, "\"{}\"", trap.message)
         } else if let Some(exc_code) = data.downcast_ref::<ExceptionCode>() {
             write!(f, "Caught exception of type \"{:?}\".", exc_code)
+        } else if let Some(host_panic) = data.downcast_ref::<HostPanic>() {
+            write!(f, "host function panicked: {}", RuntimeError::payload_message(&*host_panic.0))
+        } else if let Some(runtime_error) = data.downcast_ref::<RuntimeError>() {
+            write!(f, "{}", runtime_error)
         } else {
             write!(f, "unknown error")
         }
@@ -242,6 +282,130 @@ impl std::fmt::Debug for RuntimeError {
 
 impl std::error::Error for RuntimeError {}
 
+/// The reason why a call into WebAssembly failed, as returned by `RuntimeError::kind`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RuntimeErrorKind {
+    /// The gas limit of the metering middleware was exceeded.
+    OutOfGas,
+    /// The limits on `memory.grow` set by the opcode control middleware were exceeded.
+    MemoryGrowLimit,
+    /// The call depth limit set by the stack height middleware was exceeded.
+    StackHeightLimit,
+    /// The limits on `table.grow` set by the opcode control middleware were exceeded.
+    TableGrowLimit,
+    /// A host function trapped on purpose, either by returning an error or through `Trap`.
+    UserTrap,
+    /// The WebAssembly code trapped with the given exception code.
+    WasmTrap(ExceptionCode),
+    /// A host function panicked.
+    HostPanic,
+    /// Any other failure.
+    Unknown,
+}
+
+/// A `RuntimeError` payload tagged with its `RuntimeErrorKind`.
+///
+/// Raised by the middlewares that abort execution, and by hosts that trap explicitly.
+#[derive(Debug, Clone)]
+pub struct Trap {
+    /// The reason of the trap.
+    pub kind: RuntimeErrorKind,
+    /// A human-readable description of the trap.
+    pub message: String,
+}
+
+impl Trap {
+    /// Creates a new trap payload.
+    pub fn new<S: Into<String>>(kind: RuntimeErrorKind, message: S) -> Trap {
+        Trap {
+            kind,
+            message: message.into(),
+        }
+    }
+}
+
+/// A `RuntimeError` payload wrapping the payload of a panicking host function.
+pub struct HostPanic(pub Box<dyn Any + Send>);
+
+thread_local! {
+    static LAST_TRAP_BACKTRACE: RefCell<Option<WasmBacktrace>> = RefCell::new(None);
+}
+
+/// Returns the wasm frames active when the last `RuntimeError` caught on this thread was raised,
+/// if it was raised by the WebAssembly code and its frames are known.
+///
+/// The backtrace is kept apart from the error so that its payload can still be downcast, e.g.
+/// to an `ExceptionCode`.
+pub fn get_last_trap_backtrace() -> Option<WasmBacktrace> {
+    LAST_TRAP_BACKTRACE.with(|backtrace| backtrace.borrow().clone())
+}
+
+/// Sets the backtrace returned by `get_last_trap_backtrace()`.
+#[cfg(unix)]
+pub(crate) fn set_last_trap_backtrace(backtrace: Option<WasmBacktrace>) {
+    LAST_TRAP_BACKTRACE.with(|last_backtrace| *last_backtrace.borrow_mut() = backtrace);
+}
+
+/// The wasm frames active when the WebAssembly code trapped, innermost first.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WasmBacktrace {
+    /// The frames, innermost first.
+    pub frames: Vec<WasmFrame>,
+}
+
+impl WasmBacktrace {
+    /// Symbolicates the frames of `image` with the function names of `module_info`.
+    pub fn new(image: &ExecutionStateImage, module_info: &ModuleInfo) -> WasmBacktrace {
+        let imported_function_count = module_info.imported_functions.len();
+        let frames = image
+            .frames
+            .iter()
+            .map(|frame| {
+                let function_index =
+                    FuncIndex::new(imported_function_count + frame.local_function_id);
+                WasmFrame {
+                    function_index,
+                    function_name: module_info
+                        .function_name(function_index)
+                        .map(str::to_string),
+                    operator_index: frame.wasm_inst_offset,
+                }
+            })
+            .collect();
+        WasmBacktrace { frames }
+    }
+}
+
+impl std::fmt::Display for WasmBacktrace {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (i, frame) in self.frames.iter().enumerate() {
+            writeln!(f, "{:>4}: {}", i, frame)?;
+        }
+        Ok(())
+    }
+}
+
+/// A frame of a `WasmBacktrace`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WasmFrame {
+    /// The index of the function, imported functions included.
+    pub function_index: FuncIndex,
+    /// The name of the function from the name section, if any.
+    pub function_name: Option<String>,
+    /// The index of the current operator in the function body.
+    pub operator_index: usize,
+}
+
+impl std::fmt::Display for WasmFrame {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.function_name {
+            Some(ref name) => write!(f, "{}", name)?,
+            None => write!(f, "<function {}>", self.function_index.index())?,
+        }
+        write!(f, " @ operator {}", self.operator_index)
+    }
+}
+
 /// This error type is produced by resolving a wasm function
 /// given its name.
 ///
@@ -265,6 +429,8 @@ pub enum ResolveError {
         /// Name.
         name: String,
     },
+    /// A function reference other than the null one was passed by the host.
+    HostFuncRef,
 }
 
 impl PartialEq for ResolveError {
@@ -278,6 +444,12 @@ impl std::fmt::Display for ResolveError {
         match self {
             ResolveError::ExportNotFound { name } => write!(f, "Export not found: {}", name),
             ResolveError::ExportWrongType { name } => write!(f, "Export wrong type: {}", name),
+            ResolveError::HostFuncRef => {
+                write!(
+                    f,
+                    "Only the null function reference can be passed by the host"
+                )
+            }
             ResolveError::Signature { expected, found } => {
                 let found = found
                     .as_slice()
@@ -27,7 +27,9 @@ pub mod raw {
     }
 }
 
+use crate::backend::ExceptionCode;
 use crate::codegen::{BreakpointInfo, BreakpointMap};
+use crate::error::{set_last_trap_backtrace, WasmBacktrace};
 use crate::state::x64::{build_instance_image, read_stack, X64Register, GPR};
 use crate::state::{CodeVersion, ExecutionStateImage};
 use crate::vm;
@@ -62,6 +64,7 @@ struct UnwindInfo {
     jmpbuf: SetJmpBuffer, // in
     breakpoints: Option<BreakpointMap>,
     payload: Option<Box<dyn Any + Send>>, // out
+    backtrace: Option<WasmBacktrace>,     // out
 }
 
 /// A store for boundary register preservation.
@@ -193,11 +196,13 @@ pub unsafe fn catch_unsafe_unwind<R, F: FnOnce() -> R>(
         jmpbuf: [0; SETJMP_BUFFER_LEN],
         breakpoints: breakpoints,
         payload: None,
+        backtrace: None,
     });
 
     if raw::setjmp(&mut (*unwind).as_mut().unwrap().jmpbuf as *mut SetJmpBuffer as *mut _) != 0 {
         // error
         let ret = (*unwind).as_mut().unwrap().payload.take().unwrap();
+        set_last_trap_backtrace((*unwind).as_mut().unwrap().backtrace.take());
         *unwind = old;
         Err(ret)
     } else {
@@ -210,11 +215,21 @@ pub unsafe fn catch_unsafe_unwind<R, F: FnOnce() -> R>(
 
 /// Begins an unsafe unwind.
 pub unsafe fn begin_unsafe_unwind(e: Box<dyn Any + Send>) -> ! {
+    begin_unsafe_unwind_with_backtrace(e, None)
+}
+
+/// Begins an unsafe unwind of a trap of the WebAssembly code, whose frames are then returned by
+/// `get_last_trap_backtrace()`.
+unsafe fn begin_unsafe_unwind_with_backtrace(
+    e: Box<dyn Any + Send>,
+    backtrace: Option<WasmBacktrace>,
+) -> ! {
     let unwind = UNWIND.with(|x| x.get());
     let inner = (*unwind)
         .as_mut()
         .expect("not within a catch_unsafe_unwind scope");
     inner.payload = Some(e);
+    inner.backtrace = backtrace;
     raw::longjmp(&mut inner.jmpbuf as *mut SetJmpBuffer as *mut _, 0xffff);
 }
 
@@ -284,11 +299,15 @@ extern "C" fn signal_trap_handler(
 
     let mut should_unwind = false;
     let mut unwind_result: Box<dyn Any + Send> = Box::new(());
+    let mut unwind_backtrace: Option<WasmBacktrace> = None;
 
     unsafe {
         let fault = get_fault_info(siginfo as _, ucontext);
         let early_return = allocate_and_run(TRAP_STACK_SIZE, || {
-            CURRENT_CODE_VERSIONS.with(|versions| {
+            // Calls made outside of tiering push no code version, so the module of the current
+            // context is looked up for the inline breakpoints as well.
+            let ctx_code_version_pushed = push_ctx_code_version(*CURRENT_CTX.with(|x| x.get()));
+            let early_return = CURRENT_CODE_VERSIONS.with(|versions| {
                 let versions = versions.borrow();
                 for v in versions.iter() {
                     let magic_size =
@@ -318,6 +337,7 @@ extern "C" fn signal_trap_handler(
                                     } else if let Some(Err(e)) = out {
                                         should_unwind = true;
                                         unwind_result = e;
+                                        unwind_backtrace = Some(read_backtrace(&fault));
                                     }
                                 }
                             }
@@ -329,10 +349,14 @@ extern "C" fn signal_trap_handler(
                     }
                 }
                 false
-            })
+            });
+            if ctx_code_version_pushed {
+                pop_code_version();
+            }
+            early_return
         });
         if should_unwind {
-            begin_unsafe_unwind(unwind_result);
+            begin_unsafe_unwind_with_backtrace(unwind_result, unwind_backtrace.take());
         }
         if early_return {
             return;
@@ -340,6 +364,7 @@ extern "C" fn signal_trap_handler(
 
         should_unwind = allocate_and_run(TRAP_STACK_SIZE, || {
             let mut is_suspend_signal = false;
+            let mut is_memory_fault = false;
 
             WAS_SIGINT_TRIGGERED.with(|x| x.set(false));
 
@@ -360,12 +385,14 @@ extern "C" fn signal_trap_handler(
                         }
                         Some(Err(e)) => {
                             unwind_result = e;
+                            unwind_backtrace = Some(read_backtrace(&fault));
                             return true;
                         }
                         None => {}
                     }
                 }
                 Ok(SIGSEGV) | Ok(SIGBUS) => {
+                    is_memory_fault = true;
                     if fault.faulting_addr as usize == get_wasm_interrupt_signal_mem() as usize {
                         is_suspend_signal = true;
                         clear_wasm_interrupt();
@@ -383,23 +410,30 @@ extern "C" fn signal_trap_handler(
             // So here we check whether this exception is caused by a suspend signal, return the
             // state image if so, or throw the exception out otherwise.
 
-            let ctx: &mut vm::Ctx = &mut **CURRENT_CTX.with(|x| x.get());
-            let es_image = fault
-                .read_stack(None)
-                .expect("fault.read_stack() failed. Broken invariants?");
-
             if is_suspend_signal {
                 // If this is a suspend signal, we parse the runtime state and return the resulting image.
+                let ctx: &mut vm::Ctx = &mut **CURRENT_CTX.with(|x| x.get());
+                let es_image = fault
+                    .read_stack(None)
+                    .expect("fault.read_stack() failed. Broken invariants?");
                 let image = build_instance_image(ctx, es_image);
                 unwind_result = Box::new(image);
             } else {
                 // Otherwise, this is a real exception and we just throw it to the caller.
-                if !es_image.frames.is_empty() {
-                    eprintln!(
-                        "\n{}",
-                        "Wasmer encountered an error while running your WebAssembly program."
-                    );
-                    es_image.print_backtrace_if_needed();
+                let ctx_code_version_pushed = push_ctx_code_version(*CURRENT_CTX.with(|x| x.get()));
+
+                // Only the embedders which push code versions themselves, like the CLI, report
+                // the error here; the others get the backtrace with the error.
+                if !ctx_code_version_pushed {
+                    if let Some(es_image) = fault.read_stack(None) {
+                        if !es_image.frames.is_empty() {
+                            eprintln!(
+                                "\n{}",
+                                "Wasmer encountered an error while running your WebAssembly program."
+                            );
+                            es_image.print_backtrace_if_needed();
+                        }
+                    }
                 }
 
                 // Look up the exception tables and try to find an exception code.
@@ -420,6 +454,13 @@ extern "C" fn signal_trap_handler(
                 });
                 if let Some(code) = exc_code {
                     unwind_result = Box::new(code);
+                } else if is_memory_fault && is_stack_overflow(&fault) {
+                    unwind_result = Box::new(ExceptionCode::StackOverflow);
+                }
+                unwind_backtrace = Some(read_backtrace(&fault));
+
+                if ctx_code_version_pushed {
+                    pop_code_version();
                 }
             }
 
@@ -427,8 +468,68 @@ extern "C" fn signal_trap_handler(
         });
 
         if should_unwind {
-            begin_unsafe_unwind(unwind_result);
+            begin_unsafe_unwind_with_backtrace(unwind_result, unwind_backtrace.take());
+        }
+    }
+}
+
+/// Pushes a code version for the module of `ctx` when no code version is current, as when
+/// calling an instance outside of tiering, and returns whether it did.
+unsafe fn push_ctx_code_version(ctx: *mut vm::Ctx) -> bool {
+    if ctx.is_null() || CURRENT_CODE_VERSIONS.with(|versions| !versions.borrow().is_empty()) {
+        return false;
+    }
+
+    let runnable_module = &(*(*ctx).module).runnable_module;
+    match (
+        runnable_module.get_module_state_map(),
+        runnable_module.get_code(),
+    ) {
+        (Some(msm), Some(code)) => {
+            push_code_version(CodeVersion {
+                baseline: true,
+                msm,
+                base: code.as_ptr() as usize,
+                backend: "unknown",
+                runnable_module: runnable_module.clone(),
+            });
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Symbolicates the wasm frames of the faulting code with the module of the current context. The
+/// backtrace is empty if the stack cannot be read.
+unsafe fn read_backtrace(fault: &FaultInfo) -> WasmBacktrace {
+    let ctx: *mut vm::Ctx = *CURRENT_CTX.with(|x| x.get());
+    if ctx.is_null() {
+        return WasmBacktrace::default();
+    }
+
+    let ctx_code_version_pushed = push_ctx_code_version(ctx);
+    let backtrace = match fault.read_stack(None) {
+        Some(es_image) => WasmBacktrace::new(&es_image, &(*(*ctx).module).info),
+        None => WasmBacktrace::default(),
+    };
+    if ctx_code_version_pushed {
+        pop_code_version();
+    }
+    backtrace
+}
+
+/// Tells whether a memory fault without an exception code was caused by exhausting the native
+/// stack, in which case the faulting address lies close to the stack pointer.
+fn is_stack_overflow(fault: &FaultInfo) -> bool {
+    const STACK_OVERFLOW_DISTANCE: u64 = 0x10000;
+
+    match fault.known_registers[X64Register::GPR(GPR::RSP).to_index().0] {
+        Some(rsp) => {
+            let faulting_addr = fault.faulting_addr as u64;
+            faulting_addr.saturating_add(STACK_OVERFLOW_DISTANCE) >= rsp
+                && faulting_addr < rsp.saturating_add(STACK_OVERFLOW_DISTANCE)
         }
+        None => false,
     }
 }
 
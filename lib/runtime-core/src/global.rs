@@ -20,6 +20,9 @@ pub struct Global {
 impl Global {
     /// Create a new `Global` value.
     ///
+    /// This method will panic if the value is a function
+    /// reference other than the null one.
+    ///
     /// Usage:
     ///
     /// ```
@@ -28,11 +31,15 @@ impl Global {
     /// let global = Global::new(Value::I32(42));
     /// ```
     pub fn new(value: Value) -> Self {
+        check_host_value(&value);
         Self::new_internal(value, false)
     }
 
     /// Create a new, mutable `Global` value.
     ///
+    /// This method will panic if the value is a function
+    /// reference other than the null one.
+    ///
     /// Usage:
     ///
     /// ```
@@ -41,10 +48,12 @@ impl Global {
     /// let global = Global::new_mutable(Value::I32(42));
     /// ```
     pub fn new_mutable(value: Value) -> Self {
+        check_host_value(&value);
         Self::new_internal(value, true)
     }
 
-    fn new_internal(value: Value, mutable: bool) -> Self {
+    /// Create a new `Global` value that may come from WebAssembly, such as a function reference.
+    pub(crate) fn new_internal(value: Value, mutable: bool) -> Self {
         let desc = GlobalDescriptor {
             mutable,
             ty: value.ty(),
@@ -57,6 +66,7 @@ impl Global {
                 Value::F32(x) => x.to_bits() as u128,
                 Value::F64(x) => x.to_bits() as u128,
                 Value::V128(x) => x,
+                Value::ExternRef(x) | Value::FuncRef(x) => x as u128,
             },
         };
 
@@ -76,8 +86,15 @@ impl Global {
     /// Set the value help by this global.
     ///
     /// This method will panic if the value is
-    /// the wrong type.
+    /// the wrong type, or a function reference
+    /// other than the null one.
     pub fn set(&self, value: Value) {
+        check_host_value(&value);
+        self.set_internal(value)
+    }
+
+    /// Set a value that may come from WebAssembly, such as a function reference.
+    pub(crate) fn set_internal(&self, value: Value) {
         if self.desc.mutable {
             if self.desc.ty == value.ty() {
                 let local_global = vm::LocalGlobal {
@@ -87,6 +104,7 @@ impl Global {
                         Value::F32(x) => x.to_bits() as u128,
                         Value::F64(x) => x.to_bits() as u128,
                         Value::V128(x) => x,
+                        Value::ExternRef(x) | Value::FuncRef(x) => x as u128,
                     },
                 };
                 let mut storage = self.storage.lock().unwrap();
@@ -110,6 +128,8 @@ impl Global {
             Type::F32 => Value::F32(f32::from_bits(data as u32)),
             Type::F64 => Value::F64(f64::from_bits(data as u64)),
             Type::V128 => Value::V128(data),
+            Type::ExternRef => Value::ExternRef(data as u64),
+            Type::FuncRef => Value::FuncRef(data as u64),
         }
     }
 
@@ -120,6 +140,13 @@ impl Global {
     }
 }
 
+/// Panics if the host passes a function reference, which would be read as a function.
+fn check_host_value(value: &Value) {
+    if !value.is_valid_from_host() {
+        panic!("Only the null function reference can be set by the host")
+    }
+}
+
 impl IsExport for Global {
     fn to_export(&self) -> Export {
         Export::Global(self.clone())
@@ -146,9 +146,30 @@ impl Instance {
         Ok(instance)
     }
 
-    /// Reset an `Instance`
+    /// Reset an `Instance`: its memories, tables and globals are restored to the state they
+    /// had right after instantiation.
+    ///
+    /// Memories with a maximum size, and modules with data initializers for imported memories,
+    /// cannot be reset.
     pub fn reset(&mut self) -> RuntimeResult<()> {
-        self.inner.backing.reset(&self.module.info)
+        self.inner
+            .backing
+            .reset(&self.module, &self.inner.import_backing)?;
+
+        // The memories may have moved or shrunk, the cached base and bound must follow
+        unsafe { (*self.inner.vmctx).refresh_memory_base_and_bound() };
+        Ok(())
+    }
+
+    /// Take a copy-on-write snapshot of the local memories of this `Instance`.
+    ///
+    /// From then on, `reset` restores the memories to the snapshot, only copying back the pages
+    /// that were written, instead of zeroing them and re-applying the data initializers. It is
+    /// meant to be called right after instantiation.
+    ///
+    /// Only the memories that `reset` supports can be snapshotted.
+    pub fn snapshot_memories(&mut self) -> RuntimeResult<()> {
+        self.inner.backing.snapshot_memories(&self.module.info)
     }
 
     /// Load an `Instance` using the given loader.
@@ -301,21 +322,9 @@ impl Instance {
                 })?;
 
         if let ExportIndex::Func(func_index) = export_index {
-            let sig_index = *self
-                .module
-                .info
-                .func_assoc
-                .get(*func_index)
-                .expect("broken invariant, incorrect func index");
-            let signature =
-                SigRegistry.lookup_signature_ref(&self.module.info.signatures[sig_index]);
-
-            Ok(DynFunc {
-                signature,
-                module: &self.module,
-                instance_inner: &self.inner,
-                func_index: *func_index,
-            })
+            Ok(self
+                .dyn_func_with_index(*func_index)
+                .expect("broken invariant, incorrect func index"))
         } else {
             Err(ResolveError::ExportWrongType {
                 name: name.to_string(),
@@ -324,6 +333,26 @@ impl Instance {
         }
     }
 
+    /// Returns the representation of the function at `func_index` in the
+    /// function index space of the module, imported functions first, or
+    /// `None` if there is no such function.
+    ///
+    /// Unlike [`dyn_func`], this gives access to functions which are not
+    /// exported.
+    ///
+    /// [`dyn_func`]: struct.Instance.html#method.dyn_func
+    pub fn dyn_func_with_index(&self, func_index: FuncIndex) -> Option<DynFunc> {
+        let sig_index = *self.module.info.func_assoc.get(func_index)?;
+        let signature = SigRegistry.lookup_signature_ref(&self.module.info.signatures[sig_index]);
+
+        Some(DynFunc {
+            signature,
+            module: &self.module,
+            instance_inner: &self.inner,
+            func_index,
+        })
+    }
+
     /// Call an exported WebAssembly function given the export name.
     /// Pass arguments by wrapping each one in the [`Value`] enum.
     /// The returned values are also each wrapped in a [`Value`].
@@ -645,6 +674,9 @@ pub(crate) fn call_func_with_index_inner(
             found: args.iter().map(|val| val.ty()).collect(),
         })?
     }
+    if !args.iter().all(Value::is_valid_from_host) {
+        Err(ResolveError::HostFuncRef)?
+    }
 
     let mut raw_args: SmallVec<[u64; 8]> = SmallVec::new();
     for v in args {
@@ -670,6 +702,9 @@ pub(crate) fn call_func_with_index_inner(
                 hi.clone_from_slice(&bytes[8..16]);
                 raw_args.push(u64::from_le_bytes(hi));
             }
+            Value::ExternRef(r) | Value::FuncRef(r) => {
+                raw_args.push(*r);
+            }
         }
     }
 
@@ -707,6 +742,8 @@ pub(crate) fn call_func_with_index_inner(
         Type::F32 => Value::F32(f32::from_bits(raw as u32)),
         Type::F64 => Value::F64(f64::from_bits(raw)),
         Type::V128 => unreachable!("V128 does not map to any single value"),
+        Type::ExternRef => Value::ExternRef(raw),
+        Type::FuncRef => Value::FuncRef(raw),
     };
 
     match signature.returns() {
@@ -52,6 +52,7 @@ pub mod loader;
 pub mod memory;
 pub mod module;
 pub mod parse;
+pub mod policy;
 mod sig_registry;
 pub mod structures;
 mod sys;
@@ -89,7 +90,7 @@ use std::sync::Arc;
 
 pub use wasmparser;
 
-use self::cache::{Artifact, Error as CacheError};
+use self::cache::{Artifact, CompilationFingerprint, Error as CacheError};
 
 pub mod prelude {
     //! The prelude module is a helper module used to bring commonly used runtime core imports into
@@ -135,7 +136,11 @@ pub fn compile_with_config(
     let token = backend::Token::generate();
     compiler
         .compile(wasm, compiler_config, token)
-        .map(|inner| module::Module::new(Arc::new(inner)))
+        .map(|mut inner| {
+            let inner_info: &mut crate::module::ModuleInfo = &mut inner.info;
+            inner_info.import_custom_sections(wasm).unwrap();
+            module::Module::new(Arc::new(inner))
+        })
 }
 
 /// Perform validation as defined by the
@@ -159,9 +164,9 @@ pub fn validate_and_report_errors_with_features(
     let config = wasmparser::ValidatingParserConfig {
         operator_config: wasmparser::OperatorValidatorConfig {
             enable_simd: features.simd,
-            enable_bulk_memory: false,
-            enable_multi_value: false,
-            enable_reference_types: false,
+            enable_bulk_memory: features.bulk_memory,
+            enable_multi_value: features.multi_value,
+            enable_reference_types: features.reference_types,
             enable_threads: features.threads,
 
             #[cfg(feature = "deterministic-execution")]
@@ -184,6 +189,23 @@ pub unsafe fn load_cache_with(
     cache: Artifact,
     compiler: &dyn backend::Compiler,
 ) -> std::result::Result<module::Module, CacheError> {
+    load_cache_with_fingerprint(cache, compiler, Default::default())
+}
+
+/// The same as `load_cache_with`, but fails with `cache::Error::MismatchedFingerprint`
+/// unless the `Artifact` was compiled with the given `CompilationFingerprint`.
+pub unsafe fn load_cache_with_fingerprint(
+    cache: Artifact,
+    compiler: &dyn backend::Compiler,
+    fingerprint: CompilationFingerprint,
+) -> std::result::Result<module::Module, CacheError> {
+    if cache.fingerprint() != fingerprint {
+        return Err(CacheError::MismatchedFingerprint {
+            expected: fingerprint,
+            found: cache.fingerprint(),
+        });
+    }
+
     let token = backend::Token::generate();
     compiler
         .from_cache(cache, token)
@@ -26,6 +26,14 @@ pub struct DynamicMemory {
     current: Pages,
     max: Option<Pages>,
     min: Pages,
+    snapshot: Option<Snapshot>,
+}
+
+/// A copy-on-write image of the memory, and whether it is still mapped in `DynamicMemory::memory`.
+struct Snapshot {
+    image: sys::MemorySnapshot,
+    pages: Pages,
+    mapped: bool,
 }
 
 impl DynamicMemory {
@@ -53,6 +61,7 @@ impl DynamicMemory {
             current: desc.minimum,
             max: desc.maximum,
             min: desc.minimum,
+            snapshot: None,
         });
         let storage_ptr: *mut DynamicMemory = &mut *storage;
 
@@ -98,6 +107,9 @@ impl DynamicMemory {
         }
 
         self.memory = new_memory; //The old memory gets dropped.
+        if let Some(snapshot) = &mut self.snapshot {
+            snapshot.mapped = false;
+        }
 
         local.base = self.memory.as_ptr();
         local.bound = new_pages.bytes().0;
@@ -118,11 +130,68 @@ impl DynamicMemory {
         self.memory.split_at(min_size);
 
         local.base = self.memory.as_ptr();
-        local.bound = min_size;
+        local.bound = self.min.bytes().0;
 
         self.current = self.min;
     }
 
+    /// Take a snapshot of the current contents of this memory, which `restore_snapshot` can
+    /// later bring back. The snapshot replaces any previous one.
+    pub fn take_snapshot(&mut self) -> Result<(), String> {
+        let image = sys::MemorySnapshot::new(self.as_slice()).map_err(|e| e.to_string())?;
+        self.memory
+            .map_snapshot(&image)
+            .map_err(|e| e.to_string())?;
+
+        self.snapshot = Some(Snapshot {
+            image,
+            pages: self.current,
+            mapped: true,
+        });
+        Ok(())
+    }
+
+    /// Whether a snapshot was taken with `take_snapshot`.
+    pub fn has_snapshot(&self) -> bool {
+        self.snapshot.is_some()
+    }
+
+    /// Restore the contents and the size this memory had when the snapshot was taken.
+    ///
+    /// As long as the memory did not grow, the snapshot is still mapped and only the pages
+    /// written since are restored. Otherwise, a new memory of the snapshot size is mapped.
+    pub fn restore_snapshot(&mut self, local: &mut vm::LocalMemory) -> Result<(), String> {
+        let snapshot = match &mut self.snapshot {
+            Some(snapshot) => snapshot,
+            None => return Ok(()),
+        };
+
+        if snapshot.mapped {
+            self.memory
+                .restore_snapshot(&snapshot.image)
+                .map_err(|e| e.to_string())?;
+        } else {
+            let bytes = snapshot.pages.bytes().0;
+            let mut memory =
+                sys::Memory::with_size(bytes + DYNAMIC_GUARD_SIZE).map_err(|e| e.to_string())?;
+            unsafe {
+                memory
+                    .protect(0..bytes, sys::Protect::ReadWrite)
+                    .and_then(|_| memory.map_snapshot(&snapshot.image))
+                    .map_err(|e| e.to_string())?;
+            }
+
+            self.memory = memory;
+            snapshot.mapped = true;
+        }
+
+        local.base = self.memory.as_ptr();
+        local.bound = snapshot.pages.bytes().0;
+
+        self.current = snapshot.pages;
+        Ok(())
+    }
+
     /// Get this memory represented as a slice of bytes.
     pub fn as_slice(&self) -> &[u8] {
         unsafe { &self.memory.as_slice()[0..self.current.bytes().0] }
@@ -140,6 +140,34 @@ impl Memory {
         }
     }
 
+    /// Take a copy-on-write snapshot of the current contents of this memory.
+    pub(crate) fn take_snapshot(&self) -> RuntimeResult<()> {
+        match &self.variant {
+            MemoryVariant::Unshared(unshared_mem) => unshared_mem.take_snapshot(),
+            MemoryVariant::Shared(_shared_mem) => Err(RuntimeError(Box::new(
+                "take_snapshot is not supported for shared memories",
+            ))),
+        }
+    }
+
+    /// Whether a snapshot was taken with `take_snapshot`.
+    pub(crate) fn has_snapshot(&self) -> bool {
+        match &self.variant {
+            MemoryVariant::Unshared(unshared_mem) => unshared_mem.has_snapshot(),
+            MemoryVariant::Shared(_shared_mem) => false,
+        }
+    }
+
+    /// Restore the contents and the size this memory had when the snapshot was taken.
+    pub(crate) fn restore_snapshot(&self) -> RuntimeResult<()> {
+        match &self.variant {
+            MemoryVariant::Unshared(unshared_mem) => unshared_mem.restore_snapshot(),
+            MemoryVariant::Shared(_shared_mem) => Err(RuntimeError(Box::new(
+                "restore_snapshot is not supported for shared memories",
+            ))),
+        }
+    }
+
     /// Return a "view" of the currently accessible memory. By
     /// default, the view is unsynchronized, using regular memory
     /// accesses. You can force a memory view to use atomic accesses
@@ -341,6 +369,48 @@ impl UnsharedMemory {
         Ok(())
     }
 
+    /// Take a copy-on-write snapshot of the current contents of this memory.
+    pub fn take_snapshot(&self) -> RuntimeResult<()> {
+        let mut storage = self.internal.storage.lock().unwrap();
+
+        match &mut *storage {
+            UnsharedMemoryStorage::Dynamic(dynamic_memory) => dynamic_memory
+                .take_snapshot()
+                .map_err(|e| RuntimeError(Box::new(e))),
+            UnsharedMemoryStorage::Static(_static_memory) => {
+                Err(RuntimeError(Box::new("Cannot snapshot static memory")))
+            }
+        }
+    }
+
+    /// Whether a snapshot was taken with `take_snapshot`.
+    pub fn has_snapshot(&self) -> bool {
+        let storage = self.internal.storage.lock().unwrap();
+
+        match &*storage {
+            UnsharedMemoryStorage::Dynamic(ref dynamic_memory) => dynamic_memory.has_snapshot(),
+            UnsharedMemoryStorage::Static(_) => false,
+        }
+    }
+
+    /// Restore the contents and the size this memory had when the snapshot was taken.
+    pub fn restore_snapshot(&self) -> RuntimeResult<()> {
+        let mut storage = self.internal.storage.lock().unwrap();
+        let mut local = self.internal.local.get();
+
+        match &mut *storage {
+            UnsharedMemoryStorage::Dynamic(dynamic_memory) => dynamic_memory
+                .restore_snapshot(&mut local)
+                .map_err(|e| RuntimeError(Box::new(e)))?,
+            UnsharedMemoryStorage::Static(_static_memory) => {
+                return Err(RuntimeError(Box::new("Cannot restore static memory")));
+            }
+        }
+
+        self.internal.local.set(local);
+        Ok(())
+    }
+
     pub(crate) fn vm_local_memory(&self) -> *mut vm::LocalMemory {
         self.internal.local.as_ptr()
     }
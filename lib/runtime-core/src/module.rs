@@ -1,8 +1,8 @@
 //! The module module contains the implementation data structures and helper functions used to
 //! manipulate and access wasm modules.
 use crate::{
-    backend::RunnableModule,
-    cache::{Artifact, Error as CacheError},
+    backend::{Features, MemoryBoundCheckMode, RunnableModule},
+    cache::{Artifact, CompilationFingerprint, Error as CacheError},
     error,
     import::ImportObject,
     structures::{Map, TypedIndex},
@@ -62,6 +62,12 @@ pub struct ModuleInfo {
     /// Vector of table initializers.
     pub elem_initializers: Vec<TableInitializer>,
 
+    /// Map of data segment index to the contents of a passive data segment, for `memory.init`.
+    pub passive_data: HashMap<u32, Vec<u8>>,
+    /// Map of element segment index to the functions of a passive element segment, for
+    /// `table.init`, `None` standing for a null element.
+    pub passive_elements: HashMap<u32, Vec<Option<FuncIndex>>>,
+
     /// Index of optional start function.
     pub start_func: Option<FuncIndex>,
 
@@ -83,10 +89,25 @@ pub struct ModuleInfo {
     /// Custom sections.
     pub custom_sections: HashMap<String, Vec<u8>>,
 
+    /// Function names from the `name` custom section, by function index.
+    pub func_names: HashMap<u32, String>,
+
     /// Flag controlling whether or not debug information for use in a debugger
     /// will be generated.
     pub generate_debug_info: bool,
 
+    /// Version of the gas schedule the module was compiled with, if it is metered.
+    pub gas_schedule_version: Option<u32>,
+
+    /// Fingerprint of the compilation settings, recorded in cached artifacts.
+    pub fingerprint: CompilationFingerprint,
+
+    /// The WebAssembly proposals the module was validated and compiled with.
+    pub features: Features,
+
+    /// How the memory accesses of the module were bound checked.
+    pub memory_bound_check_mode: MemoryBoundCheckMode,
+
     #[cfg(feature = "generate-debug-information")]
     #[serde(skip)]
     /// Resource manager of debug information being used by a debugger.
@@ -104,12 +125,38 @@ impl ModuleInfo {
                 let len = reader.bytes_remaining();
                 let bytes = reader.read_bytes(len)?;
                 let data = bytes.to_vec();
+                if name == "name" {
+                    // A malformed name section only loses the names, it does not fail compilation.
+                    self.func_names = parse_function_names(&data).unwrap_or_default();
+                }
                 let name = name.to_string();
                 self.custom_sections.insert(name, data);
             }
         }
         Ok(())
     }
+
+    /// Returns the name given to the function at `func_index` by the name section, if any.
+    pub fn function_name(&self, func_index: FuncIndex) -> Option<&str> {
+        self.func_names
+            .get(&(func_index.index() as u32))
+            .map(String::as_str)
+    }
+}
+
+/// Reads the function names of a `name` custom section.
+fn parse_function_names(data: &[u8]) -> crate::error::ParseResult<HashMap<u32, String>> {
+    let mut func_names = HashMap::new();
+    for name in wasmparser::NameSectionReader::new(data, 0)? {
+        if let wasmparser::Name::Function(function_names) = name? {
+            let mut map = function_names.get_map()?;
+            for _ in 0..map.get_count() {
+                let naming = map.read()?;
+                func_names.insert(naming.index, naming.name.to_string());
+            }
+        }
+    }
+    Ok(func_names)
 }
 
 /// A compiled WebAssembly module.
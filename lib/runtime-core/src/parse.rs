@@ -84,6 +84,9 @@ pub fn read_module<
         data_initializers: Vec::new(),
         elem_initializers: Vec::new(),
 
+        passive_data: HashMap::new(),
+        passive_elements: HashMap::new(),
+
         start_func: None,
 
         func_assoc: Map::new(),
@@ -96,20 +99,27 @@ pub fn read_module<
         em_symbol_map: compiler_config.symbol_map.clone(),
 
         custom_sections: HashMap::new(),
+        func_names: HashMap::new(),
 
         generate_debug_info: compiler_config.should_generate_debug_info(),
+        gas_schedule_version: compiler_config.gas_schedule_version,
+        fingerprint: compiler_config.fingerprint,
+        features: compiler_config.features,
+        memory_bound_check_mode: compiler_config.memory_bound_check_mode,
         #[cfg(feature = "generate-debug-information")]
         debug_info_manager: crate::jit_debug::JitCodeDebugInfoManager::new(),
     }));
 
     let mut parser = wasmparser::ValidatingParser::new(
         wasm,
-        Some(validating_parser_config(&compiler_config.features)),
+        Some(validating_parser_config(compiler_config)),
     );
 
     let mut namespace_builder = Some(StringTableBuilder::new());
     let mut name_builder = Some(StringTableBuilder::new());
     let mut func_count: usize = 0;
+    let mut data_count: u32 = 0;
+    let mut elem_count: u32 = 0;
     let mut mcg_info_fed = false;
 
     loop {
@@ -140,9 +150,8 @@ pub fn read_module<
                             .map_err(|x| LoadError::Codegen(format!("{:?}", x)))?;
                     }
                     ImportSectionEntryType::Table(table_ty) => {
-                        assert_eq!(table_ty.element_type, WpType::AnyFunc);
                         let table_desc = TableDescriptor {
-                            element: ElementType::Anyfunc,
+                            element: wp_type_to_element_type(table_ty.element_type)?,
                             minimum: table_ty.limits.initial,
                             maximum: table_ty.limits.maximum,
                         };
@@ -183,7 +192,7 @@ pub fn read_module<
             }
             ParserState::TableSectionEntry(table_ty) => {
                 let table_desc = TableDescriptor {
-                    element: ElementType::Anyfunc,
+                    element: wp_type_to_element_type(table_ty.element_type)?,
                     minimum: table_ty.limits.initial,
                     maximum: table_ty.limits.maximum,
                 };
@@ -371,6 +380,57 @@ pub fn read_module<
                 };
 
                 info.write().unwrap().elem_initializers.push(table_init);
+                elem_count += 1;
+            }
+            ParserState::BeginElementSectionEntry {
+                table: ElemSectionEntryTable::Passive,
+                ty: WpType::AnyFunc,
+            } => {
+                let mut elements: Option<Vec<Option<FuncIndex>>> = None;
+
+                loop {
+                    let state = parser.read();
+                    match *state {
+                        ParserState::Error(ref err) => return Err(err.into()),
+                        ParserState::ElementSectionEntryBody(ref _elements) => {
+                            elements = Some(
+                                _elements
+                                    .iter()
+                                    .map(|elem_idx| match elem_idx {
+                                        ElementItem::Null => None,
+                                        ElementItem::Func(idx) => {
+                                            Some(FuncIndex::new(*idx as usize))
+                                        }
+                                    })
+                                    .collect(),
+                            );
+                        }
+                        ParserState::EndElementSectionEntry => break,
+                        _ => unreachable!(),
+                    }
+                }
+
+                info.write()
+                    .unwrap()
+                    .passive_elements
+                    .insert(elem_count, elements.unwrap());
+                elem_count += 1;
+            }
+            ParserState::BeginElementSectionEntry {
+                table: ElemSectionEntryTable::Declared,
+                ..
+            } => {
+                // Declared segments only allow `ref.func` to reference their functions, which it
+                // can do for any function here, but they still take an element segment index.
+                loop {
+                    match *parser.read() {
+                        ParserState::Error(ref err) => return Err(err.into()),
+                        ParserState::ElementSectionEntryBody(_) => {}
+                        ParserState::EndElementSectionEntry => break,
+                        _ => unreachable!(),
+                    }
+                }
+                elem_count += 1;
             }
             ParserState::BeginElementSectionEntry {
                 table: ElemSectionEntryTable::Active(table_index),
@@ -411,12 +471,49 @@ pub fn read_module<
                     data,
                 };
                 info.write().unwrap().data_initializers.push(data_init);
+                data_count += 1;
+            }
+            ParserState::BeginPassiveDataSectionEntry => {
+                let mut data: Vec<u8> = vec![];
+
+                loop {
+                    let state = parser.read();
+                    match *state {
+                        ParserState::Error(ref err) => return Err(err.into()),
+                        ParserState::DataSectionEntryBodyChunk(chunk) => {
+                            data.extend_from_slice(chunk);
+                        }
+                        ParserState::BeginDataSectionEntryBody(_)
+                        | ParserState::EndDataSectionEntryBody => {}
+                        ParserState::EndDataSectionEntry => break,
+                        _ => unreachable!(),
+                    }
+                }
+
+                info.write().unwrap().passive_data.insert(data_count, data);
+                data_count += 1;
             }
             ParserState::BeginGlobalSectionEntry(ty) => {
+                let desc = GlobalDescriptor {
+                    mutable: ty.mutable,
+                    ty: wp_type_to_type(ty.content_type)?,
+                };
                 let init = loop {
                     let state = parser.read();
                     match *state {
                         ParserState::Error(ref err) => return Err(err.into()),
+                        // The null reference takes the type of the global.
+                        ParserState::InitExpressionOperator(Operator::RefNull) => {
+                            match Value::null_ref(desc.ty) {
+                                Some(null) => break Initializer::Const(null),
+                                None => {
+                                    return Err(LoadError::Parse(
+                                        "ref.null initializes a global of a non-reference type"
+                                            .to_string(),
+                                    ))
+                                }
+                            }
+                        }
                         ParserState::InitExpressionOperator(ref op) => {
                             break eval_init_expr(op)?;
                         }
@@ -424,10 +521,6 @@ pub fn read_module<
                         _ => unreachable!(),
                     }
                 };
-                let desc = GlobalDescriptor {
-                    mutable: ty.mutable,
-                    ty: wp_type_to_type(ty.content_type)?,
-                };
 
                 let global_init = GlobalInit { desc, init };
 
@@ -462,6 +555,8 @@ pub fn wp_type_to_type(ty: WpType) -> Result<Type, LoadError> {
         WpType::F32 => Ok(Type::F32),
         WpType::F64 => Ok(Type::F64),
         WpType::V128 => Ok(Type::V128),
+        WpType::AnyRef => Ok(Type::ExternRef),
+        WpType::AnyFunc => Ok(Type::FuncRef),
         _ => {
             return Err(LoadError::Parse(
                 "broken invariant, invalid type".to_string(),
@@ -478,6 +573,20 @@ pub fn type_to_wp_type(ty: Type) -> WpType {
         Type::F32 => WpType::F32,
         Type::F64 => WpType::F64,
         Type::V128 => WpType::V128,
+        Type::ExternRef => WpType::AnyRef,
+        Type::FuncRef => WpType::AnyFunc,
+    }
+}
+
+/// Convert the element type of a table given as a `WpType` to an `ElementType`.
+fn wp_type_to_element_type(ty: WpType) -> Result<ElementType, LoadError> {
+    match ty {
+        WpType::AnyFunc => Ok(ElementType::Anyfunc),
+        WpType::AnyRef => Ok(ElementType::ExternRef),
+        _ => Err(LoadError::Parse(format!(
+            "type \"{:?}\" is not supported in tables",
+            ty
+        ))),
     }
 }
 
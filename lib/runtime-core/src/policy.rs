@@ -0,0 +1,394 @@
+//! The policy module checks a wasm binary against a declarative `ModulePolicy` before it is
+//! compiled, and reports every rule the module breaks.
+
+use crate::{
+    error::{CompileError, CompileResult},
+    structures::TypedIndex,
+    types::{FuncIndex, GlobalIndex, MemoryIndex, SigIndex},
+};
+use std::collections::{HashMap, HashSet};
+use wasmparser::{
+    ImportSectionEntryType, MemoryType, Operator, Parser, ParserState, Type as WpType, WasmDecoder,
+};
+
+/// The rules a module must follow to be accepted.
+///
+/// The default policy accepts every module.
+#[derive(Debug, Clone, Default)]
+pub struct ModulePolicy {
+    /// The imports allowed, by namespace. A namespace mapped to `None` allows all of its names.
+    /// Every import is allowed when this is `None`.
+    pub allowed_imports: Option<HashMap<String, Option<HashSet<String>>>>,
+    /// The maximum number of functions defined by the module, imports excluded.
+    pub max_functions: Option<u32>,
+    /// The maximum number of locals declared by a function, params excluded.
+    pub max_locals: Option<u32>,
+    /// The maximum initial size of a memory, in pages.
+    pub max_initial_memory_pages: Option<u32>,
+    /// The maximum maximum size of a memory, in pages. Memories without a maximum size break
+    /// this rule.
+    pub max_maximum_memory_pages: Option<u32>,
+    /// Whether a start function is rejected.
+    pub forbid_start_function: bool,
+    /// Whether floats are rejected, be it in operators, function types, globals or locals.
+    pub forbid_floats: bool,
+    /// The names of the exports the module must have.
+    pub required_exports: Vec<String>,
+}
+
+/// A rule of a `ModulePolicy` broken by a module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PolicyViolation {
+    /// An import is not in the allowed imports.
+    ForbiddenImport {
+        /// Index of the import in the import section.
+        index: u32,
+        /// Namespace of the import.
+        namespace: String,
+        /// Name of the import.
+        name: String,
+    },
+    /// The module defines too many functions.
+    TooManyFunctions {
+        /// Number of functions defined by the module.
+        count: u32,
+        /// Maximum number of functions.
+        max: u32,
+    },
+    /// A function declares too many locals.
+    TooManyLocals {
+        /// Index of the function.
+        function: FuncIndex,
+        /// Number of locals declared by the function.
+        count: u32,
+        /// Maximum number of locals.
+        max: u32,
+    },
+    /// A memory is initially too large.
+    InitialMemoryTooLarge {
+        /// Index of the memory.
+        memory: MemoryIndex,
+        /// Initial size of the memory, in pages.
+        pages: u32,
+        /// Maximum initial size, in pages.
+        max: u32,
+    },
+    /// A memory may grow too large.
+    MaximumMemoryTooLarge {
+        /// Index of the memory.
+        memory: MemoryIndex,
+        /// Maximum size of the memory in pages, `None` if it is unbounded.
+        pages: Option<u32>,
+        /// Maximum maximum size, in pages.
+        max: u32,
+    },
+    /// The module has a start function.
+    StartFunction {
+        /// Index of the start function.
+        function: FuncIndex,
+    },
+    /// A function uses float operators. It is reported once per function.
+    FloatOperator {
+        /// Index of the function.
+        function: FuncIndex,
+        /// Offset of the first float operator of the function in the wasm binary.
+        offset: usize,
+    },
+    /// A function type takes or returns floats.
+    FloatSignature {
+        /// Index of the function type.
+        signature: SigIndex,
+    },
+    /// A global holds a float.
+    FloatGlobal {
+        /// Index of the global, imports included.
+        global: GlobalIndex,
+    },
+    /// A function declares float locals.
+    FloatLocal {
+        /// Index of the function.
+        function: FuncIndex,
+    },
+    /// A required export is missing.
+    MissingExport {
+        /// Name of the export.
+        name: String,
+    },
+}
+
+/// Checks the wasm binary `wasm` against `policy`, and returns the rules it breaks. An empty list
+/// means the module is accepted.
+///
+/// The module is not validated; malformed binaries fail with `CompileError::ValidationError`.
+pub fn check_module_policy(
+    wasm: &[u8],
+    policy: &ModulePolicy,
+) -> CompileResult<Vec<PolicyViolation>> {
+    let mut violations = vec![];
+    let mut exports = HashSet::new();
+    let mut import_count: u32 = 0;
+    let mut imported_func_count: usize = 0;
+    let mut memory_count: usize = 0;
+    let mut signature_count: usize = 0;
+    let mut global_count: usize = 0;
+    let mut func_count: u32 = 0;
+    let mut func_body_count: usize = 0;
+    let mut float_found = false;
+
+    let mut parser = Parser::new(wasm);
+    loop {
+        let offset = parser.current_position();
+        match *parser.read() {
+            ParserState::EndWasm => break,
+            ParserState::Error(ref err) => {
+                return Err(CompileError::ValidationError {
+                    msg: err.message().to_string(),
+                })
+            }
+            ParserState::TypeSectionEntry(ref ty) => {
+                if policy.forbid_floats
+                    && ty.params.iter().chain(ty.returns.iter()).any(is_float_type)
+                {
+                    violations.push(PolicyViolation::FloatSignature {
+                        signature: SigIndex::new(signature_count),
+                    });
+                }
+                signature_count += 1;
+            }
+            ParserState::ImportSectionEntry { module, field, ty } => {
+                if !is_import_allowed(policy, module, field) {
+                    violations.push(PolicyViolation::ForbiddenImport {
+                        index: import_count,
+                        namespace: module.to_string(),
+                        name: field.to_string(),
+                    });
+                }
+                match ty {
+                    ImportSectionEntryType::Function(_) => imported_func_count += 1,
+                    ImportSectionEntryType::Memory(memory_ty) => {
+                        let memory = MemoryIndex::new(memory_count);
+                        memory_count += 1;
+                        check_memory(policy, memory, &memory_ty, &mut violations);
+                    }
+                    ImportSectionEntryType::Global(global_ty) => {
+                        let global = GlobalIndex::new(global_count);
+                        global_count += 1;
+                        check_global(policy, global, global_ty.content_type, &mut violations);
+                    }
+                    _ => {}
+                }
+                import_count += 1;
+            }
+            ParserState::FunctionSectionEntry(_) => {
+                func_count += 1;
+            }
+            ParserState::MemorySectionEntry(memory_ty) => {
+                let memory = MemoryIndex::new(memory_count);
+                memory_count += 1;
+                check_memory(policy, memory, &memory_ty, &mut violations);
+            }
+            ParserState::BeginGlobalSectionEntry(global_ty) => {
+                let global = GlobalIndex::new(global_count);
+                global_count += 1;
+                check_global(policy, global, global_ty.content_type, &mut violations);
+            }
+            ParserState::ExportSectionEntry { field, .. } => {
+                exports.insert(field.to_string());
+            }
+            ParserState::StartSectionEntry(start_index) => {
+                if policy.forbid_start_function {
+                    violations.push(PolicyViolation::StartFunction {
+                        function: FuncIndex::new(start_index as usize),
+                    });
+                }
+            }
+            ParserState::BeginFunctionBody { .. } => {
+                float_found = false;
+            }
+            ParserState::FunctionBodyLocals { ref locals } => {
+                if policy.forbid_floats && locals.iter().any(|(_, ty)| is_float_type(ty)) {
+                    violations.push(PolicyViolation::FloatLocal {
+                        function: FuncIndex::new(imported_func_count + func_body_count),
+                    });
+                }
+                if let Some(max) = policy.max_locals {
+                    let count = locals
+                        .iter()
+                        .fold(0u32, |count, &(n, _)| count.saturating_add(n));
+                    if count > max {
+                        violations.push(PolicyViolation::TooManyLocals {
+                            function: FuncIndex::new(imported_func_count + func_body_count),
+                            count,
+                            max,
+                        });
+                    }
+                }
+            }
+            ParserState::CodeOperator(ref op) => {
+                if policy.forbid_floats && !float_found && is_float_operator(op) {
+                    float_found = true;
+                    violations.push(PolicyViolation::FloatOperator {
+                        function: FuncIndex::new(imported_func_count + func_body_count),
+                        offset,
+                    });
+                }
+            }
+            ParserState::EndFunctionBody => {
+                func_body_count += 1;
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(max) = policy.max_functions {
+        if func_count > max {
+            violations.push(PolicyViolation::TooManyFunctions {
+                count: func_count,
+                max,
+            });
+        }
+    }
+
+    for name in &policy.required_exports {
+        if !exports.contains(name) {
+            violations.push(PolicyViolation::MissingExport { name: name.clone() });
+        }
+    }
+
+    Ok(violations)
+}
+
+fn is_import_allowed(policy: &ModulePolicy, namespace: &str, name: &str) -> bool {
+    match policy.allowed_imports {
+        None => true,
+        Some(ref allowed_imports) => match allowed_imports.get(namespace) {
+            None => false,
+            Some(None) => true,
+            Some(Some(names)) => names.contains(name),
+        },
+    }
+}
+
+fn check_memory(
+    policy: &ModulePolicy,
+    memory: MemoryIndex,
+    memory_ty: &MemoryType,
+    violations: &mut Vec<PolicyViolation>,
+) {
+    if let Some(max) = policy.max_initial_memory_pages {
+        if memory_ty.limits.initial > max {
+            violations.push(PolicyViolation::InitialMemoryTooLarge {
+                memory,
+                pages: memory_ty.limits.initial,
+                max,
+            });
+        }
+    }
+    if let Some(max) = policy.max_maximum_memory_pages {
+        match memory_ty.limits.maximum {
+            Some(pages) if pages <= max => {}
+            pages => violations.push(PolicyViolation::MaximumMemoryTooLarge { memory, pages, max }),
+        }
+    }
+}
+
+fn check_global(
+    policy: &ModulePolicy,
+    global: GlobalIndex,
+    ty: WpType,
+    violations: &mut Vec<PolicyViolation>,
+) {
+    if policy.forbid_floats && is_float_type(&ty) {
+        violations.push(PolicyViolation::FloatGlobal { global });
+    }
+}
+
+fn is_float_type(ty: &WpType) -> bool {
+    match *ty {
+        WpType::F32 | WpType::F64 => true,
+        _ => false,
+    }
+}
+
+/// Returns whether `op` takes or produces floats.
+fn is_float_operator(op: &Operator) -> bool {
+    match *op {
+        Operator::F32Load { .. }
+        | Operator::F64Load { .. }
+        | Operator::F32Store { .. }
+        | Operator::F64Store { .. }
+        | Operator::F32Const { .. }
+        | Operator::F64Const { .. }
+        | Operator::F32Eq
+        | Operator::F32Ne
+        | Operator::F32Lt
+        | Operator::F32Gt
+        | Operator::F32Le
+        | Operator::F32Ge
+        | Operator::F64Eq
+        | Operator::F64Ne
+        | Operator::F64Lt
+        | Operator::F64Gt
+        | Operator::F64Le
+        | Operator::F64Ge
+        | Operator::F32Abs
+        | Operator::F32Neg
+        | Operator::F32Ceil
+        | Operator::F32Floor
+        | Operator::F32Trunc
+        | Operator::F32Nearest
+        | Operator::F32Sqrt
+        | Operator::F32Add
+        | Operator::F32Sub
+        | Operator::F32Mul
+        | Operator::F32Div
+        | Operator::F32Min
+        | Operator::F32Max
+        | Operator::F32Copysign
+        | Operator::F64Abs
+        | Operator::F64Neg
+        | Operator::F64Ceil
+        | Operator::F64Floor
+        | Operator::F64Trunc
+        | Operator::F64Nearest
+        | Operator::F64Sqrt
+        | Operator::F64Add
+        | Operator::F64Sub
+        | Operator::F64Mul
+        | Operator::F64Div
+        | Operator::F64Min
+        | Operator::F64Max
+        | Operator::F64Copysign
+        | Operator::I32TruncF32S
+        | Operator::I32TruncF32U
+        | Operator::I32TruncF64S
+        | Operator::I32TruncF64U
+        | Operator::I64TruncF32S
+        | Operator::I64TruncF32U
+        | Operator::I64TruncF64S
+        | Operator::I64TruncF64U
+        | Operator::F32ConvertI32S
+        | Operator::F32ConvertI32U
+        | Operator::F32ConvertI64S
+        | Operator::F32ConvertI64U
+        | Operator::F32DemoteF64
+        | Operator::F64ConvertI32S
+        | Operator::F64ConvertI32U
+        | Operator::F64ConvertI64S
+        | Operator::F64ConvertI64U
+        | Operator::F64PromoteF32
+        | Operator::I32ReinterpretF32
+        | Operator::I64ReinterpretF64
+        | Operator::F32ReinterpretI32
+        | Operator::F64ReinterpretI64
+        | Operator::I32TruncSatF32S
+        | Operator::I32TruncSatF32U
+        | Operator::I32TruncSatF64S
+        | Operator::I32TruncSatF64U
+        | Operator::I64TruncSatF32S
+        | Operator::I64TruncSatF32U
+        | Operator::I64TruncSatF64S
+        | Operator::I64TruncSatF64U => true,
+        _ => false,
+    }
+}
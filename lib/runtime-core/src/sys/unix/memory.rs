@@ -6,7 +6,14 @@ use nix::libc;
 use page_size;
 use rkyv::{Archive, Deserialize as RkyvDeserialize, Serialize as RkyvSerialize};
 use std::ops::{Bound, RangeBounds};
-use std::{fs::File, os::unix::io::IntoRawFd, path::Path, ptr, slice, sync::Arc};
+use std::{
+    fs::File,
+    io::Write,
+    os::unix::io::{FromRawFd, IntoRawFd},
+    path::Path,
+    ptr, slice,
+    sync::Arc,
+};
 
 unsafe impl Send for Memory {}
 unsafe impl Sync for Memory {}
@@ -253,6 +260,145 @@ impl Memory {
     pub fn as_ptr(&self) -> *mut u8 {
         self.ptr
     }
+
+    /// Maps the given snapshot copy-on-write at the start of this memory. Reads see the
+    /// contents of the snapshot, while writes only affect this memory.
+    pub fn map_snapshot(&mut self, snapshot: &MemorySnapshot) -> Result<(), MemoryProtectionError> {
+        assert!(snapshot.size <= self.size);
+
+        if snapshot.size == 0 {
+            return Ok(());
+        }
+
+        let ptr = unsafe {
+            libc::mmap(
+                self.ptr as _,
+                snapshot.size,
+                Protect::ReadWrite.to_protect_const() as i32,
+                libc::MAP_PRIVATE | libc::MAP_FIXED,
+                snapshot.fd.0,
+                0,
+            )
+        };
+
+        if ptr == -1 as _ {
+            Err(MemoryProtectionError::ProtectionFailed(
+                self.ptr as usize,
+                snapshot.size,
+                errno::errno().to_string(),
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Restores the contents of a snapshot previously mapped with `map_snapshot`.
+    ///
+    /// Only the pages written since the snapshot was mapped hold private copies; discarding
+    /// them makes the next accesses fault the untouched snapshot pages back in, so the cost
+    /// depends on the number of dirty pages rather than on the size of the memory.
+    #[cfg(target_os = "linux")]
+    pub fn restore_snapshot(
+        &mut self,
+        snapshot: &MemorySnapshot,
+    ) -> Result<(), MemoryProtectionError> {
+        assert!(snapshot.size <= self.size);
+
+        if snapshot.size == 0 {
+            return Ok(());
+        }
+
+        let success = unsafe { libc::madvise(self.ptr as _, snapshot.size, libc::MADV_DONTNEED) };
+
+        if success == -1 {
+            Err(MemoryProtectionError::ProtectionFailed(
+                self.ptr as usize,
+                snapshot.size,
+                errno::errno().to_string(),
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Restores the contents of a snapshot previously mapped with `map_snapshot`.
+    ///
+    /// `MADV_DONTNEED` does not drop private pages on every platform, so the snapshot is mapped
+    /// again instead, which also leaves the kernel to release the dirty pages only.
+    #[cfg(not(target_os = "linux"))]
+    pub fn restore_snapshot(
+        &mut self,
+        snapshot: &MemorySnapshot,
+    ) -> Result<(), MemoryProtectionError> {
+        self.map_snapshot(snapshot)
+    }
+}
+
+/// An immutable image of a memory, backed by an anonymous file so that it can be mapped
+/// copy-on-write into any number of memories.
+#[derive(Debug)]
+pub struct MemorySnapshot {
+    fd: RawFd,
+    size: usize,
+}
+
+impl MemorySnapshot {
+    /// Create a new snapshot holding a copy of the given contents.
+    pub fn new(contents: &[u8]) -> Result<Self, MemoryCreationError> {
+        let size = round_up_to_page_size(contents.len(), page_size::get());
+
+        let mut file = unsafe { File::from_raw_fd(create_anonymous_file()?) };
+        file.write_all(contents)?;
+        file.set_len(size as u64)?;
+
+        Ok(Self {
+            fd: RawFd::from_file(file),
+            size,
+        })
+    }
+
+    /// Gets the size of this snapshot, rounded up to the page size.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn create_anonymous_file() -> Result<i32, MemoryCreationError> {
+    let fd = unsafe {
+        libc::memfd_create(
+            b"wasmer-memory-snapshot\0".as_ptr() as *const libc::c_char,
+            libc::MFD_CLOEXEC,
+        )
+    };
+
+    if fd == -1 {
+        Err(std::io::Error::last_os_error().into())
+    } else {
+        Ok(fd)
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn create_anonymous_file() -> Result<i32, MemoryCreationError> {
+    use std::os::unix::ffi::OsStringExt;
+
+    let mut template = std::env::temp_dir()
+        .join("wasmer-memory-snapshot-XXXXXX")
+        .into_os_string()
+        .into_vec();
+    template.push(0);
+
+    let fd = unsafe { libc::mkstemp(template.as_mut_ptr() as *mut libc::c_char) };
+
+    if fd == -1 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+
+    // The file only needs to live as long as its descriptor.
+    unsafe { libc::unlink(template.as_ptr() as *const libc::c_char) };
+
+    Ok(fd)
 }
 
 impl Drop for Memory {
@@ -1,3 +1,3 @@
 mod memory;
 
-pub use self::memory::{Memory, Protect};
+pub use self::memory::{Memory, MemorySnapshot, Protect};
@@ -170,6 +170,46 @@ impl Memory {
     pub fn as_ptr(&self) -> *mut u8 {
         self.ptr
     }
+
+    /// Copies the given snapshot at the start of this memory.
+    pub fn map_snapshot(&mut self, snapshot: &MemorySnapshot) -> Result<(), MemoryProtectionError> {
+        assert!(snapshot.contents.len() <= self.size);
+
+        unsafe {
+            self.as_slice_mut()[..snapshot.contents.len()].copy_from_slice(&snapshot.contents);
+        }
+        Ok(())
+    }
+
+    /// Restores the contents of a snapshot previously mapped with `map_snapshot`.
+    ///
+    /// There is no copy-on-write mapping here, so the whole snapshot is copied again.
+    pub fn restore_snapshot(
+        &mut self,
+        snapshot: &MemorySnapshot,
+    ) -> Result<(), MemoryProtectionError> {
+        self.map_snapshot(snapshot)
+    }
+}
+
+/// An immutable image of a memory.
+#[derive(Debug)]
+pub struct MemorySnapshot {
+    contents: Vec<u8>,
+}
+
+impl MemorySnapshot {
+    /// Create a new snapshot holding a copy of the given contents.
+    pub fn new(contents: &[u8]) -> Result<Self, MemoryCreationError> {
+        Ok(Self {
+            contents: contents.to_vec(),
+        })
+    }
+
+    /// Gets the size of this snapshot.
+    pub fn size(&self) -> usize {
+        self.contents.len()
+    }
 }
 
 impl Drop for Memory {
@@ -7,7 +7,7 @@ use crate::{
     vm,
 };
 
-use std::{ptr, sync::Arc};
+use std::{collections::HashMap, ptr, sync::Arc};
 
 enum AnyfuncInner<'a> {
     Host {
@@ -48,6 +48,9 @@ impl<'a> From<DynFunc<'a>> for Anyfunc<'a> {
 pub struct AnyfuncTable {
     pub(crate) backing: Vec<vm::Anyfunc>,
     max: Option<u32>,
+    /// Copies of the elements read as references, keyed by function pointer, context and
+    /// signature, so that references stay valid when the table changes.
+    references: HashMap<(usize, usize, u32), Box<vm::Anyfunc>>,
 }
 
 impl AnyfuncTable {
@@ -60,6 +63,7 @@ impl AnyfuncTable {
         let mut storage = Box::new(AnyfuncTable {
             backing: vec![vm::Anyfunc::null(); initial_table_backing_len],
             max: desc.maximum,
+            references: HashMap::new(),
         });
 
         let storage_ptr: *mut AnyfuncTable = &mut *storage;
@@ -79,7 +83,7 @@ impl AnyfuncTable {
         &mut self.backing
     }
 
-    pub fn grow(&mut self, delta: u32, local: &mut vm::LocalTable) -> Option<u32> {
+    pub fn grow(&mut self, delta: u32, reference: u64, local: &mut vm::LocalTable) -> Option<u32> {
         let starting_len = self.backing.len() as u32;
 
         let new_len = starting_len.checked_add(delta)?;
@@ -90,7 +94,8 @@ impl AnyfuncTable {
             }
         }
 
-        self.backing.resize(new_len as usize, vm::Anyfunc::null());
+        self.backing
+            .resize(new_len as usize, Self::dereference(reference));
 
         local.base = self.backing.as_mut_ptr() as *mut u8;
         local.count = self.backing.len();
@@ -98,6 +103,49 @@ impl AnyfuncTable {
         Some(starting_len)
     }
 
+    pub fn reset(&mut self, size: u32, local: &mut vm::LocalTable) {
+        self.backing.clear();
+        self.backing.resize(size as usize, vm::Anyfunc::null());
+
+        local.base = self.backing.as_mut_ptr() as *mut u8;
+        local.count = self.backing.len();
+    }
+
+    /// Returns a reference to the function at `index`, or `0` if the element is null.
+    pub fn get_reference(&mut self, index: u32) -> Option<u64> {
+        let anyfunc = *self.backing.get(index as usize)?;
+        if anyfunc.func.is_null() {
+            return Some(0);
+        }
+
+        let key = (
+            anyfunc.func as usize,
+            anyfunc.ctx as usize,
+            anyfunc.sig_id.0,
+        );
+        let reference = self
+            .references
+            .entry(key)
+            .or_insert_with(|| Box::new(anyfunc));
+        Some(&**reference as *const vm::Anyfunc as u64)
+    }
+
+    /// Sets the element at `index` to the function referenced by `reference`, or to null if it
+    /// is `0`.
+    pub fn set_reference(&mut self, index: u32, reference: u64) -> Result<(), ()> {
+        let slot = self.backing.get_mut(index as usize).ok_or(())?;
+        *slot = Self::dereference(reference);
+        Ok(())
+    }
+
+    fn dereference(reference: u64) -> vm::Anyfunc {
+        if reference == 0 {
+            vm::Anyfunc::null()
+        } else {
+            unsafe { *(reference as *const vm::Anyfunc) }
+        }
+    }
+
     pub fn set(&mut self, index: u32, element: Anyfunc) -> Result<(), ()> {
         if let Some(slot) = self.backing.get_mut(index as usize) {
             let anyfunc = match element.inner {
@@ -0,0 +1,72 @@
+use crate::{error::CreationError, types::TableDescriptor, vm};
+
+/// A table of opaque references to host values, where `0` is the null reference.
+pub struct ExternRefTable {
+    pub(crate) backing: Vec<u64>,
+    max: Option<u32>,
+}
+
+impl ExternRefTable {
+    pub fn new(
+        desc: TableDescriptor,
+        local: &mut vm::LocalTable,
+    ) -> Result<Box<Self>, CreationError> {
+        let mut storage = Box::new(ExternRefTable {
+            backing: vec![0; desc.minimum as usize],
+            max: desc.maximum,
+        });
+
+        let storage_ptr: *mut ExternRefTable = &mut *storage;
+
+        local.base = storage.backing.as_mut_ptr() as *mut u8;
+        local.count = storage.backing.len();
+        local.table = storage_ptr as *mut ();
+
+        Ok(storage)
+    }
+
+    pub fn current_size(&self) -> u32 {
+        self.backing.len() as u32
+    }
+
+    pub fn internal_buffer(&mut self) -> &mut [u64] {
+        &mut self.backing
+    }
+
+    pub fn grow(&mut self, delta: u32, reference: u64, local: &mut vm::LocalTable) -> Option<u32> {
+        let starting_len = self.backing.len() as u32;
+
+        let new_len = starting_len.checked_add(delta)?;
+
+        if let Some(max) = self.max {
+            if new_len > max {
+                return None;
+            }
+        }
+
+        self.backing.resize(new_len as usize, reference);
+
+        local.base = self.backing.as_mut_ptr() as *mut u8;
+        local.count = self.backing.len();
+
+        Some(starting_len)
+    }
+
+    pub fn reset(&mut self, size: u32, local: &mut vm::LocalTable) {
+        self.backing.clear();
+        self.backing.resize(size as usize, 0);
+
+        local.base = self.backing.as_mut_ptr() as *mut u8;
+        local.count = self.backing.len();
+    }
+
+    pub fn get_reference(&self, index: u32) -> Option<u64> {
+        self.backing.get(index as usize).cloned()
+    }
+
+    pub fn set_reference(&mut self, index: u32, reference: u64) -> Result<(), ()> {
+        let slot = self.backing.get_mut(index as usize).ok_or(())?;
+        *slot = reference;
+        Ok(())
+    }
+}
@@ -4,7 +4,7 @@ use crate::{
     error::CreationError,
     export::Export,
     import::IsExport,
-    types::{ElementType, TableDescriptor},
+    types::{ElementType, TableDescriptor, Value},
     vm,
 };
 use std::{
@@ -13,15 +13,19 @@ use std::{
 };
 
 mod anyfunc;
+mod externref;
 
 pub use self::anyfunc::Anyfunc;
 pub(crate) use self::anyfunc::AnyfuncTable;
+pub(crate) use self::externref::ExternRefTable;
 use crate::error::GrowError;
 
 /// Kind of table element.
 pub enum Element<'a> {
     /// Anyfunc.
     Anyfunc(Anyfunc<'a>),
+    /// Opaque reference to a host value, or null if `0`.
+    ExternRef(u64),
 }
 
 /// Kind of table storage.
@@ -29,6 +33,8 @@ pub enum Element<'a> {
 pub enum TableStorage {
     /// This is intended to be a caller-checked Anyfunc.
     Anyfunc(Box<AnyfuncTable>),
+    /// Opaque references to host values.
+    ExternRef(Box<ExternRefTable>),
 }
 
 /// Container with a descriptor and a reference to a table storage.
@@ -76,6 +82,9 @@ impl Table {
 
         let storage = match desc.element {
             ElementType::Anyfunc => TableStorage::Anyfunc(AnyfuncTable::new(desc, &mut local)?),
+            ElementType::ExternRef => {
+                TableStorage::ExternRef(ExternRefTable::new(desc, &mut local)?)
+            }
         };
 
         Ok(Self {
@@ -90,26 +99,109 @@ impl Table {
     }
 
     /// Set the element at index.
+    ///
+    /// Fails if the index is out of bounds or if the element does not match the element type of
+    /// the table.
     pub fn set(&self, index: u32, element: Element) -> Result<(), ()> {
+        let mut storage = self.storage.lock().unwrap();
+        match (&mut *storage, element) {
+            ((TableStorage::Anyfunc(ref mut anyfunc_table), _), Element::Anyfunc(anyfunc)) => {
+                anyfunc_table.set(index, anyfunc)
+            }
+            (
+                (TableStorage::ExternRef(ref mut externref_table), _),
+                Element::ExternRef(reference),
+            ) => externref_table.set_reference(index, reference),
+            _ => Err(()),
+        }
+    }
+
+    /// Get the element at index, as a `Value::FuncRef` or a `Value::ExternRef`.
+    pub fn get(&self, index: u32) -> Option<Value> {
+        match self.desc.element {
+            ElementType::Anyfunc => self.get_reference(index).map(Value::FuncRef),
+            ElementType::ExternRef => self.get_reference(index).map(Value::ExternRef),
+        }
+    }
+
+    /// Returns the element at `index` as a reference, as `table.get` does.
+    ///
+    /// References to functions point to a copy of the element owned by the table, which stays
+    /// valid as long as the table does.
+    pub(crate) fn get_reference(&self, index: u32) -> Option<u64> {
+        let mut storage = self.storage.lock().unwrap();
+        match &mut *storage {
+            (TableStorage::Anyfunc(ref mut anyfunc_table), _) => anyfunc_table.get_reference(index),
+            (TableStorage::ExternRef(ref externref_table), _) => {
+                externref_table.get_reference(index)
+            }
+        }
+    }
+
+    /// Sets the element at `index` to a reference, as `table.set` does.
+    pub(crate) fn set_reference(&self, index: u32, reference: u64) -> Result<(), ()> {
         let mut storage = self.storage.lock().unwrap();
         match &mut *storage {
             (TableStorage::Anyfunc(ref mut anyfunc_table), _) => {
-                match element {
-                    Element::Anyfunc(anyfunc) => anyfunc_table.set(index, anyfunc),
-                    // _ => panic!("wrong element type for anyfunc table"),
-                }
+                anyfunc_table.set_reference(index, reference)
+            }
+            (TableStorage::ExternRef(ref mut externref_table), _) => {
+                externref_table.set_reference(index, reference)
             }
         }
     }
 
-    pub(crate) fn anyfunc_direct_access_mut<F, R>(&self, f: F) -> R
-    where
-        F: FnOnce(&mut [vm::Anyfunc]) -> R,
-    {
+    /// Sets the `len` elements from `index` to a reference, as `table.fill` does.
+    ///
+    /// Nothing is written if the range is out of bounds.
+    pub(crate) fn fill_with_reference(
+        &self,
+        index: u32,
+        reference: u64,
+        len: u32,
+    ) -> Result<(), ()> {
+        if index as u64 + len as u64 > self.size() as u64 {
+            return Err(());
+        }
+
+        for i in index..index + len {
+            self.set_reference(i, reference)?;
+        }
+        Ok(())
+    }
+
+    /// Writes `functions` from `offset`: tables of functions get copies of them, and tables of
+    /// references get pointers to them, so they must live as long as the table. `None` writes
+    /// a null element.
+    ///
+    /// Nothing is written if they do not fit.
+    pub(crate) fn write_functions(
+        &self,
+        offset: usize,
+        functions: &[Option<&vm::Anyfunc>],
+    ) -> Result<(), ()> {
         let mut storage = self.storage.lock().unwrap();
         match &mut *storage {
-            (TableStorage::Anyfunc(ref mut anyfunc_table), _) => f(anyfunc_table.internal_buffer()),
+            (TableStorage::Anyfunc(ref mut anyfunc_table), _) => {
+                let elements = anyfunc_table.internal_buffer();
+                if elements.len() < offset + functions.len() {
+                    return Err(());
+                }
+                for (element, function) in elements[offset..].iter_mut().zip(functions) {
+                    *element = function.map_or(vm::Anyfunc::null(), |function| *function);
+                }
+            }
+            (TableStorage::ExternRef(ref mut externref_table), _) => {
+                let elements = externref_table.internal_buffer();
+                if elements.len() < offset + functions.len() {
+                    return Err(());
+                }
+                for (element, function) in elements[offset..].iter_mut().zip(functions) {
+                    *element = function.map_or(0, |function| function as *const vm::Anyfunc as u64);
+                }
+            }
         }
+        Ok(())
     }
 
     /// The current size of this table.
@@ -117,11 +209,18 @@ impl Table {
         let storage = self.storage.lock().unwrap();
         match &*storage {
             (TableStorage::Anyfunc(ref anyfunc_table), _) => anyfunc_table.current_size(),
+            (TableStorage::ExternRef(ref externref_table), _) => externref_table.current_size(),
         }
     }
 
     /// Grow this table by `delta`.
     pub fn grow(&self, delta: u32) -> Result<u32, GrowError> {
+        self.grow_with_reference(delta, 0)
+    }
+
+    /// Grow this table by `delta`, initializing the new elements to a reference, as `table.grow`
+    /// does.
+    pub(crate) fn grow_with_reference(&self, delta: u32, reference: u64) -> Result<u32, GrowError> {
         if delta == 0 {
             return Ok(self.size());
         }
@@ -129,11 +228,27 @@ impl Table {
         let mut storage = self.storage.lock().unwrap();
         match &mut *storage {
             (TableStorage::Anyfunc(ref mut anyfunc_table), ref mut local) => anyfunc_table
-                .grow(delta, local)
+                .grow(delta, reference, local)
+                .ok_or(GrowError::TableGrowError),
+            (TableStorage::ExternRef(ref mut externref_table), ref mut local) => externref_table
+                .grow(delta, reference, local)
                 .ok_or(GrowError::TableGrowError),
         }
     }
 
+    /// Shrinks this table back to its minimum size and clears all of its elements.
+    pub(crate) fn reset_to_minimum(&self) {
+        let mut storage = self.storage.lock().unwrap();
+        match &mut *storage {
+            (TableStorage::Anyfunc(ref mut anyfunc_table), ref mut local) => {
+                anyfunc_table.reset(self.desc.minimum, local)
+            }
+            (TableStorage::ExternRef(ref mut externref_table), ref mut local) => {
+                externref_table.reset(self.desc.minimum, local)
+            }
+        }
+    }
+
     /// Get a mutable pointer to underlying table storage.
     pub fn vm_local_table(&mut self) -> *mut vm::LocalTable {
         let mut storage = self.storage.lock().unwrap();
@@ -168,7 +283,7 @@ impl fmt::Debug for Table {
 #[cfg(test)]
 mod table_tests {
 
-    use super::{ElementType, Table, TableDescriptor};
+    use super::{Element, ElementType, Table, TableDescriptor, Value};
 
     #[test]
     fn test_initial_table_size() {
@@ -180,4 +295,22 @@ mod table_tests {
         .unwrap();
         assert_eq!(table.size(), 10);
     }
+
+    #[test]
+    fn test_externref_table() {
+        let table = Table::new(TableDescriptor {
+            element: ElementType::ExternRef,
+            minimum: 1,
+            maximum: Some(2),
+        })
+        .unwrap();
+        assert_eq!(table.grow_with_reference(1, 42), Ok(1));
+        assert!(table.grow(1).is_err());
+        assert_eq!(table.get(0), Some(Value::ExternRef(0)));
+        assert_eq!(table.get(1), Some(Value::ExternRef(42)));
+        assert_eq!(table.get(2), None);
+
+        table.set(0, Element::ExternRef(7)).unwrap();
+        assert_eq!(table.get(0), Some(Value::ExternRef(7)));
+    }
 }
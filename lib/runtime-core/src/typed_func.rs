@@ -1,7 +1,7 @@
 //! The typed func module implements a way of representing a wasm function
 //! with the correct types from rust. Function calls using a typed func have a low overhead.
 use crate::{
-    error::RuntimeError,
+    error::{HostPanic, RuntimeError},
     export::{Context, Export, FuncPointer},
     import::IsExport,
     types::{FuncSig, NativeWasmType, Type, WasmExternType},
@@ -89,6 +89,9 @@ pub trait WasmTypeList {
     /// the WebAssembly function.
     fn empty_ret_array() -> Self::RetArray;
 
+    /// Transforms Rust values into an array of returned values.
+    fn into_ret_array(self) -> Self::RetArray;
+
     /// Transforms C values into Rust values.
     fn from_c_struct(c_struct: Self::CStruct) -> Self;
 
@@ -304,48 +307,107 @@ impl<'a> DynamicFunc<'a> {
     where
         F: Fn(&mut vm::Ctx, &[crate::types::Value]) -> Vec<crate::types::Value> + 'static,
     {
+        use crate::error::{RuntimeErrorKind, Trap};
         use crate::trampoline_x64::{CallContext, TrampolineBufferBuilder};
         use crate::types::Value;
-        use std::convert::TryFrom;
 
         struct PolymorphicContext {
             arg_types: Vec<Type>,
+            ret_types: Vec<Type>,
+            multi_value: bool,
             func: Box<dyn Fn(&mut vm::Ctx, &[Value]) -> Vec<Value>>,
         }
+        /// Checks the values returned by the host function against its signature, since they
+        /// are written as is where the generated code expects its results.
+        fn check_returns(ret_types: &[Type], rets: &[Value]) -> Result<(), String> {
+            if rets.len() != ret_types.len() {
+                return Err(format!(
+                    "a polymorphic host function returned {} values, but its signature has {} results",
+                    rets.len(),
+                    ret_types.len()
+                ));
+            }
+            for (i, (ret, ty)) in rets.iter().zip(ret_types).enumerate() {
+                if ret.ty() != *ty {
+                    return Err(format!(
+                        "a polymorphic host function returned a {:?} as result {}, but its signature expects a {:?}",
+                        ret.ty(),
+                        i,
+                        ty
+                    ));
+                }
+                if !ret.is_valid_from_host() {
+                    return Err(
+                        "a polymorphic host function returned a function reference other than the null one"
+                            .to_string(),
+                    );
+                }
+                if *ty == Type::V128 {
+                    return Err(
+                        "128-bit return values from polymorphic host functions are not supported"
+                            .to_string(),
+                    );
+                }
+            }
+            Ok(())
+        }
         unsafe extern "C" fn enter_host_polymorphic(
             ctx: *const CallContext,
             args: *const u64,
         ) -> u64 {
             let ctx = &*(ctx as *const PolymorphicContext);
             let vmctx = &mut *(*args.offset(0) as *mut vm::Ctx);
-            let args: Vec<Value> = ctx
-                .arg_types
-                .iter()
-                .enumerate()
-                .map(|(i, t)| {
-                    let i = i + 1; // skip vmctx
-                    match *t {
-                        Type::I32 => Value::I32(*args.offset(i as _) as i32),
-                        Type::I64 => Value::I64(*args.offset(i as _) as i64),
-                        Type::F32 => Value::F32(f32::from_bits(*args.offset(i as _) as u32)),
-                        Type::F64 => Value::F64(f64::from_bits(*args.offset(i as _) as u64)),
-                        Type::V128 => {
-                            todo!("enter_host_polymorphic: 128-bit types are not supported")
+            // A function with several results receives the address of the
+            // buffer for its results as first argument after `vmctx`.
+            let (results, args) = if ctx.multi_value {
+                (*args.offset(1) as *mut u64, args.offset(1))
+            } else {
+                (ptr::null_mut(), args)
+            };
+            // A panic must not unwind through the generated code, so it is turned into a trap,
+            // as well as results that do not match the signature.
+            let rets = match panic::catch_unwind(panic::AssertUnwindSafe(|| {
+                let args: Vec<Value> = ctx
+                    .arg_types
+                    .iter()
+                    .enumerate()
+                    .map(|(i, t)| {
+                        let i = i + 1; // skip vmctx
+                        match *t {
+                            Type::I32 => Value::I32(*args.offset(i as _) as i32),
+                            Type::I64 => Value::I64(*args.offset(i as _) as i64),
+                            Type::F32 => Value::F32(f32::from_bits(*args.offset(i as _) as u32)),
+                            Type::F64 => Value::F64(f64::from_bits(*args.offset(i as _) as u64)),
+                            Type::V128 => {
+                                todo!("enter_host_polymorphic: 128-bit types are not supported")
+                            }
+                            Type::ExternRef => Value::ExternRef(*args.offset(i as _)),
+                            Type::FuncRef => Value::FuncRef(*args.offset(i as _)),
                         }
-                    }
-                })
-                .collect();
-            let rets = (ctx.func)(vmctx, &args);
-            if rets.len() == 0 {
-                0
-            } else if rets.len() == 1 {
-                u64::try_from(rets[0].to_u128()).expect(
-                    "128-bit return value from polymorphic host functions is not yet supported",
-                )
+                    })
+                    .collect();
+                (ctx.func)(&mut *vmctx, &args)
+            })) {
+                Ok(rets) => rets,
+                Err(err) => (&*vmctx.module)
+                    .runnable_module
+                    .do_early_trap(Box::new(HostPanic(err))),
+            };
+            if let Err(message) = check_returns(&ctx.ret_types, &rets) {
+                (&*vmctx.module)
+                    .runnable_module
+                    .do_early_trap(Box::new(Trap::new(RuntimeErrorKind::UserTrap, message)));
+            }
+
+            // The values are not 128-bit wide, as checked above, and the upper bits of the
+            // 32-bit ones are ignored.
+            if ctx.multi_value {
+                for (i, ret) in rets.iter().enumerate() {
+                    *results.add(i) = ret.to_u128() as u64;
+                }
+                results as u64
             } else {
-                panic!(
-                    "multiple return values from polymorphic host functions is not yet supported"
-                );
+                rets.first().map_or(0, |ret| ret.to_u128() as u64)
             }
         }
 
@@ -355,15 +417,18 @@ impl<'a> DynamicFunc<'a> {
         }
 
         let mut builder = TrampolineBufferBuilder::new();
+        let multi_value = signature.returns().len() > 1;
         let ctx: Box<PolymorphicContext> = Box::new(PolymorphicContext {
             arg_types: signature.params().to_vec(),
+            ret_types: signature.returns().to_vec(),
+            multi_value,
             func: Box::new(func),
         });
         let ctx = Box::into_raw(ctx);
         builder.add_callinfo_trampoline(
             enter_host_polymorphic,
             ctx as *const _,
-            (signature.params().len() + 1) as u32, // +vmctx
+            (signature.params().len() + 1 + multi_value as usize) as u32, // +vmctx, +results
         );
         let ptr = builder
             .insert_global()
@@ -430,6 +495,10 @@ impl WasmTypeList for Infallible {
         unreachable!()
     }
 
+    fn into_ret_array(self) -> Self::RetArray {
+        unreachable!()
+    }
+
     fn from_c_struct(_: Self::CStruct) -> Self {
         unreachable!()
     }
@@ -484,6 +553,13 @@ macro_rules! impl_traits {
                 [0; count_idents!( $( $x ),* )]
             }
 
+            #[allow(unused_parens, non_snake_case)]
+            fn into_ret_array(self) -> Self::RetArray {
+                let ( $( $x ),* ) = self;
+
+                [ $( WasmExternType::to_native($x).to_binary() ),* ]
+            }
+
             fn from_c_struct(c_struct: Self::CStruct) -> Self {
                 #[allow(non_snake_case)]
                 let $struct_name ( $( $x ),* ) = c_struct;
@@ -566,6 +642,52 @@ macro_rules! impl_traits {
                     // Get the pointer to this `wrap` function.
                     let self_pointer = wrap::<$( $x, )* Rets, Trap, FN> as *const vm::Func;
 
+                    call::<$( $x, )* Rets, Trap, FN>(self_pointer, vmctx $( , $x )*).into_c_struct()
+                }
+
+                // The `wrap_multi_value` function is the wrapper used
+                // when the imported function has several results. They
+                // are written to the `results` buffer, with one 64-bit
+                // slot per result, instead of being returned as a
+                // `Rets::CStruct`.
+                #[cfg_attr(nightly, unwind(allowed))]
+                extern fn wrap_multi_value<$( $x, )* Rets, Trap, FN>(
+                    vmctx: &vm::Ctx,
+                    results: *mut u64 $( , $x: <$x as WasmExternType>::Native )*
+                ) -> *mut u64
+                where
+                    $( $x: WasmExternType, )*
+                    Rets: WasmTypeList,
+                    Trap: TrapEarly<Rets>,
+                    FN: Fn(&mut vm::Ctx, $( $x, )*) -> Trap,
+                {
+                    // Get the pointer to this `wrap_multi_value` function.
+                    let self_pointer =
+                        wrap_multi_value::<$( $x, )* Rets, Trap, FN> as *const vm::Func;
+
+                    let mut returns =
+                        call::<$( $x, )* Rets, Trap, FN>(self_pointer, vmctx $( , $x )*)
+                            .into_ret_array();
+                    let returns = returns.as_mut();
+
+                    unsafe { ptr::copy_nonoverlapping(returns.as_ptr(), results, returns.len()) };
+
+                    results
+                }
+
+                // The `call` function calls the imported function
+                // behind `self_pointer`, i.e. behind one of the
+                // wrappers above, and manages the trapping.
+                fn call<$( $x, )* Rets, Trap, FN>(
+                    self_pointer: *const vm::Func,
+                    vmctx: &vm::Ctx $( , $x: <$x as WasmExternType>::Native )*
+                ) -> Rets
+                where
+                    $( $x: WasmExternType, )*
+                    Rets: WasmTypeList,
+                    Trap: TrapEarly<Rets>,
+                    FN: Fn(&mut vm::Ctx, $( $x, )*) -> Trap,
+                {
                     // Get the collection of imported functions.
                     let vm_imported_functions = unsafe { &(*vmctx.import_backing).vm_functions };
 
@@ -615,12 +737,12 @@ macro_rules! impl_traits {
                             }
                         )
                     ) {
-                        Ok(Ok(returns)) => return returns.into_c_struct(),
+                        Ok(Ok(returns)) => return returns,
                         Ok(Err(err)) => {
                             let b: Box<_> = err.into();
                             b as Box<dyn Any + Send>
                         },
-                        Err(err) => err,
+                        Err(err) => Box::new(HostPanic(err)),
                     };
 
                     // At this point, there is an error that needs to
@@ -644,10 +766,13 @@ macro_rules! impl_traits {
                         NonNull::new(Box::into_raw(Box::new(self))).map(NonNull::cast)
                     };
 
-                (
-                    NonNull::new(wrap::<$( $x, )* Rets, Trap, Self> as *mut vm::Func).unwrap(),
-                    func_env
-                )
+                let wrapper = if Rets::types().len() > 1 {
+                    wrap_multi_value::<$( $x, )* Rets, Trap, Self> as *mut vm::Func
+                } else {
+                    wrap::<$( $x, )* Rets, Trap, Self> as *mut vm::Func
+                };
+
+                (NonNull::new(wrapper).unwrap(), func_env)
             }
         }
 
@@ -682,6 +807,52 @@ macro_rules! impl_traits {
                     // Get the pointer to this `wrap` function.
                     let self_pointer = wrap::<$( $x, )* Rets, Trap, FN> as *const vm::Func;
 
+                    call::<$( $x, )* Rets, Trap, FN>(self_pointer, vmctx $( , $x )*).into_c_struct()
+                }
+
+                // The `wrap_multi_value` function is the wrapper used
+                // when the imported function has several results. They
+                // are written to the `results` buffer, with one 64-bit
+                // slot per result, instead of being returned as a
+                // `Rets::CStruct`.
+                #[cfg_attr(nightly, unwind(allowed))]
+                extern fn wrap_multi_value<$( $x, )* Rets, Trap, FN>(
+                    vmctx: &vm::Ctx,
+                    results: *mut u64 $( , $x: <$x as WasmExternType>::Native )*
+                ) -> *mut u64
+                where
+                    $( $x: WasmExternType, )*
+                    Rets: WasmTypeList,
+                    Trap: TrapEarly<Rets>,
+                    FN: Fn($( $x, )*) -> Trap,
+                {
+                    // Get the pointer to this `wrap_multi_value` function.
+                    let self_pointer =
+                        wrap_multi_value::<$( $x, )* Rets, Trap, FN> as *const vm::Func;
+
+                    let mut returns =
+                        call::<$( $x, )* Rets, Trap, FN>(self_pointer, vmctx $( , $x )*)
+                            .into_ret_array();
+                    let returns = returns.as_mut();
+
+                    unsafe { ptr::copy_nonoverlapping(returns.as_ptr(), results, returns.len()) };
+
+                    results
+                }
+
+                // The `call` function calls the imported function
+                // behind `self_pointer`, i.e. behind one of the
+                // wrappers above, and manages the trapping.
+                fn call<$( $x, )* Rets, Trap, FN>(
+                    self_pointer: *const vm::Func,
+                    vmctx: &vm::Ctx $( , $x: <$x as WasmExternType>::Native )*
+                ) -> Rets
+                where
+                    $( $x: WasmExternType, )*
+                    Rets: WasmTypeList,
+                    Trap: TrapEarly<Rets>,
+                    FN: Fn($( $x, )*) -> Trap,
+                {
                     // Get the collection of imported functions.
                     let vm_imported_functions = unsafe { &(*vmctx.import_backing).vm_functions };
 
@@ -728,12 +899,12 @@ macro_rules! impl_traits {
                             }
                         )
                     ) {
-                        Ok(Ok(returns)) => return returns.into_c_struct(),
+                        Ok(Ok(returns)) => return returns,
                         Ok(Err(err)) => {
                             let b: Box<_> = err.into();
                             b as Box<dyn Any + Send>
                         },
-                        Err(err) => err,
+                        Err(err) => Box::new(HostPanic(err)),
                     };
 
                     // At this point, there is an error that needs to
@@ -757,10 +928,13 @@ macro_rules! impl_traits {
                         NonNull::new(Box::into_raw(Box::new(self))).map(NonNull::cast)
                     };
 
-                (
-                    NonNull::new(wrap::<$( $x, )* Rets, Trap, Self> as *mut vm::Func).unwrap(),
-                    func_env
-                )
+                let wrapper = if Rets::types().len() > 1 {
+                    wrap_multi_value::<$( $x, )* Rets, Trap, Self> as *mut vm::Func
+                } else {
+                    wrap::<$( $x, )* Rets, Trap, Self> as *mut vm::Func
+                };
+
+                (NonNull::new(wrapper).unwrap(), func_env)
             }
         }
 
@@ -18,6 +18,10 @@ pub enum Type {
     F64,
     /// The `v128` type.
     V128,
+    /// The `externref` type, an opaque reference to a host value.
+    ExternRef,
+    /// The `funcref` type, a reference to a function.
+    FuncRef,
 }
 
 impl std::fmt::Display for Type {
@@ -42,9 +46,23 @@ pub enum Value {
     F64(f64),
     /// The `v128` type.
     V128(u128),
+    /// The `externref` type. The reference is opaque to WebAssembly; `0` is the null reference.
+    ExternRef(u64),
+    /// The `funcref` type. The reference is opaque to the host, which can only pass the null
+    /// reference `0` to WebAssembly.
+    FuncRef(u64),
 }
 
 impl Value {
+    /// Whether the host may pass this value to WebAssembly: any function reference but the null
+    /// one would be read as a function.
+    pub fn is_valid_from_host(&self) -> bool {
+        match *self {
+            Value::FuncRef(reference) => reference == 0,
+            _ => true,
+        }
+    }
+
     /// The `Type` of this `Value`.
     pub fn ty(&self) -> Type {
         match self {
@@ -53,6 +71,8 @@ impl Value {
             Value::F32(_) => Type::F32,
             Value::F64(_) => Type::F64,
             Value::V128(_) => Type::V128,
+            Value::ExternRef(_) => Type::ExternRef,
+            Value::FuncRef(_) => Type::FuncRef,
         }
     }
 
@@ -64,6 +84,17 @@ impl Value {
             Value::F32(x) => f32::to_bits(x) as u128,
             Value::F64(x) => f64::to_bits(x) as u128,
             Value::V128(x) => x,
+            Value::ExternRef(x) => x as u128,
+            Value::FuncRef(x) => x as u128,
+        }
+    }
+
+    /// The null reference of the reference type `ty`, if it is one.
+    pub fn null_ref(ty: Type) -> Option<Self> {
+        match ty {
+            Type::ExternRef => Some(Value::ExternRef(0)),
+            Type::FuncRef => Some(Value::FuncRef(0)),
+            _ => None,
         }
     }
 }
@@ -251,6 +282,8 @@ convert_value_impl!(u8, i8, u16, i16, u32, i32, u64, i64, f32, f64);
 pub enum ElementType {
     /// Any wasm function.
     Anyfunc,
+    /// Opaque references to host values.
+    ExternRef,
 }
 
 /// Describes the properties of a table including the element types, minimum and optional maximum,
@@ -1,6 +1,6 @@
 //! The runtime vm module contains data structures and helper functions used during runtime to
 //! execute wasm instance functions.
-pub use crate::backing::{ImportBacking, LocalBacking, INTERNALS_SIZE};
+pub use crate::backing::{ImportBacking, LocalBacking, INTERNALS_SIZE, INTERNAL_MULTI_VALUE_AREA};
 use crate::{
     error::CallResult,
     instance::call_func_with_index_inner,
@@ -163,9 +163,12 @@ impl InternalField {
         let inner: *mut usize = self.inner.get();
         self.init.call_once(|| {
             let idx = INTERNAL_FIELDS.fetch_add(1, Ordering::SeqCst);
-            if idx >= INTERNALS_SIZE {
+            if idx >= INTERNAL_MULTI_VALUE_AREA {
                 INTERNAL_FIELDS.fetch_sub(1, Ordering::SeqCst);
-                panic!("at most {} internal fields are supported", INTERNALS_SIZE);
+                panic!(
+                    "at most {} internal fields are supported",
+                    INTERNAL_MULTI_VALUE_AREA
+                );
             } else {
                 unsafe {
                     *inner = idx;
@@ -183,6 +186,32 @@ pub struct Intrinsics {
     pub memory_grow: *const Func,
     /// Const pointer to memory size `Func`.
     pub memory_size: *const Func,
+    /// Const pointer to memory copy `Func`.
+    pub memory_copy: *const Func,
+    /// Const pointer to memory fill `Func`.
+    pub memory_fill: *const Func,
+    /// Const pointer to memory init `Func`.
+    pub memory_init: *const Func,
+    /// Const pointer to data drop `Func`.
+    pub data_drop: *const Func,
+    /// Const pointer to table copy `Func`.
+    pub table_copy: *const Func,
+    /// Const pointer to table init `Func`.
+    pub table_init: *const Func,
+    /// Const pointer to elem drop `Func`.
+    pub elem_drop: *const Func,
+    /// Const pointer to table get `Func`.
+    pub table_get: *const Func,
+    /// Const pointer to table set `Func`.
+    pub table_set: *const Func,
+    /// Const pointer to table grow `Func`.
+    pub table_grow: *const Func,
+    /// Const pointer to table size `Func`.
+    pub table_size: *const Func,
+    /// Const pointer to table fill `Func`.
+    pub table_fill: *const Func,
+    /// Const pointer to ref func `Func`.
+    pub ref_func: *const Func,
     /*pub memory_grow: unsafe extern "C" fn(
         ctx: &mut Ctx,
         memory_index: usize,
@@ -207,32 +236,108 @@ impl Intrinsics {
     pub const fn offset_memory_size() -> u8 {
         (1 * ::std::mem::size_of::<usize>()) as u8
     }
+    /// Offset of the `memory_copy` field.
+    pub const fn offset_memory_copy() -> u8 {
+        (2 * ::std::mem::size_of::<usize>()) as u8
+    }
+    /// Offset of the `memory_fill` field.
+    pub const fn offset_memory_fill() -> u8 {
+        (3 * ::std::mem::size_of::<usize>()) as u8
+    }
+    /// Offset of the `memory_init` field.
+    pub const fn offset_memory_init() -> u8 {
+        (4 * ::std::mem::size_of::<usize>()) as u8
+    }
+    /// Offset of the `data_drop` field.
+    pub const fn offset_data_drop() -> u8 {
+        (5 * ::std::mem::size_of::<usize>()) as u8
+    }
+    /// Offset of the `table_copy` field.
+    pub const fn offset_table_copy() -> u8 {
+        (6 * ::std::mem::size_of::<usize>()) as u8
+    }
+    /// Offset of the `table_init` field.
+    pub const fn offset_table_init() -> u8 {
+        (7 * ::std::mem::size_of::<usize>()) as u8
+    }
+    /// Offset of the `elem_drop` field.
+    pub const fn offset_elem_drop() -> u8 {
+        (8 * ::std::mem::size_of::<usize>()) as u8
+    }
+    /// Offset of the `table_get` field.
+    pub const fn offset_table_get() -> u8 {
+        (9 * ::std::mem::size_of::<usize>()) as u8
+    }
+    /// Offset of the `table_set` field.
+    pub const fn offset_table_set() -> u8 {
+        (10 * ::std::mem::size_of::<usize>()) as u8
+    }
+    /// Offset of the `table_grow` field.
+    pub const fn offset_table_grow() -> u8 {
+        (11 * ::std::mem::size_of::<usize>()) as u8
+    }
+    /// Offset of the `table_size` field.
+    pub const fn offset_table_size() -> u8 {
+        (12 * ::std::mem::size_of::<usize>()) as u8
+    }
+    /// Offset of the `table_fill` field.
+    pub const fn offset_table_fill() -> u8 {
+        (13 * ::std::mem::size_of::<usize>()) as u8
+    }
+    /// Offset of the `ref_func` field.
+    pub const fn offset_ref_func() -> u8 {
+        (14 * ::std::mem::size_of::<usize>()) as u8
+    }
 }
 
+/// Bulk memory and reference types intrinsics, which are the same for every kind of memory.
+const INTRINSICS_BULK_MEMORY: Intrinsics = Intrinsics {
+    memory_grow: ptr::null(),
+    memory_size: ptr::null(),
+    memory_copy: vmcalls::memory_copy as _,
+    memory_fill: vmcalls::memory_fill as _,
+    memory_init: vmcalls::memory_init as _,
+    data_drop: vmcalls::data_drop as _,
+    table_copy: vmcalls::table_copy as _,
+    table_init: vmcalls::table_init as _,
+    elem_drop: vmcalls::elem_drop as _,
+    table_get: vmcalls::table_get as _,
+    table_set: vmcalls::table_set as _,
+    table_grow: vmcalls::table_grow as _,
+    table_size: vmcalls::table_size as _,
+    table_fill: vmcalls::table_fill as _,
+    ref_func: vmcalls::ref_func as _,
+};
+/// Intrinsics of a module without memory; only the operations on tables are reachable.
+pub static INTRINSICS_NO_MEMORY: Intrinsics = INTRINSICS_BULK_MEMORY;
 /// Local static memory intrinsics
 pub static INTRINSICS_LOCAL_STATIC_MEMORY: Intrinsics = Intrinsics {
     memory_grow: vmcalls::local_static_memory_grow as _,
     memory_size: vmcalls::local_static_memory_size as _,
+    ..INTRINSICS_BULK_MEMORY
 };
 /// Local dynamic memory intrinsics
 pub static INTRINSICS_LOCAL_DYNAMIC_MEMORY: Intrinsics = Intrinsics {
     memory_grow: vmcalls::local_dynamic_memory_grow as _,
     memory_size: vmcalls::local_dynamic_memory_size as _,
+    ..INTRINSICS_BULK_MEMORY
 };
 /// Imported static memory intrinsics
 pub static INTRINSICS_IMPORTED_STATIC_MEMORY: Intrinsics = Intrinsics {
     memory_grow: vmcalls::imported_static_memory_grow as _,
     memory_size: vmcalls::imported_static_memory_size as _,
+    ..INTRINSICS_BULK_MEMORY
 };
 /// Imported dynamic memory intrinsics
 pub static INTRINSICS_IMPORTED_DYNAMIC_MEMORY: Intrinsics = Intrinsics {
     memory_grow: vmcalls::imported_dynamic_memory_grow as _,
     memory_size: vmcalls::imported_dynamic_memory_size as _,
+    ..INTRINSICS_BULK_MEMORY
 };
 
 fn get_intrinsics_for_module(m: &ModuleInfo) -> *const Intrinsics {
     if m.memories.is_empty() && m.imported_memories.is_empty() {
-        ptr::null()
+        &INTRINSICS_NO_MEMORY
     } else {
         match MemoryIndex::new(0).local_or_import(m) {
             LocalOrImport::Local(local_mem_index) => {
@@ -419,6 +524,26 @@ impl Ctx {
         (self.memory(mem_index), &mut *(self.data as *mut T))
     }
 
+    /// Reloads the base and the bound of the first memory cached in the `InternalCtx`, as the
+    /// memory grow calls do, after the memory was moved or resized from outside of wasm.
+    ///
+    /// # Safety
+    ///
+    /// The `memories` and `imported_memories` arrays of the `InternalCtx` must be valid.
+    pub(crate) unsafe fn refresh_memory_base_and_bound(&mut self) {
+        let module = &*self.module;
+        if module.info.memories.is_empty() && module.info.imported_memories.is_empty() {
+            return;
+        }
+
+        let mem = match MemoryIndex::new(0).local_or_import(&module.info) {
+            LocalOrImport::Local(index) => *self.internal.memories.add(index.index()),
+            LocalOrImport::Import(index) => *self.internal.imported_memories.add(index.index()),
+        };
+        self.internal.memory_base = (*mem).base;
+        self.internal.memory_bound = (*mem).bound;
+    }
+
     /// Gives access to the emscripten symbol map, used for debugging
     pub unsafe fn borrow_symbol_map(&self) -> &Option<HashMap<u32, String>> {
         &(*self.module).info.em_symbol_map
@@ -1026,8 +1151,13 @@ mod vm_ctx_tests {
 
             dynamic_sigindices: Map::new().into_boxed_map(),
             local_functions: Map::new().into_boxed_map(),
+            func_refs: Box::new([]),
 
             internals: crate::backing::Internals([0; crate::backing::INTERNALS_SIZE]),
+            multi_value_area: Box::new([]),
+
+            dropped_data: Default::default(),
+            dropped_elements: Default::default(),
         };
 
         let mut import_backing = ImportBacking {
@@ -1118,6 +1248,9 @@ mod vm_ctx_tests {
                 data_initializers: Vec::new(),
                 elem_initializers: Vec::new(),
 
+                passive_data: HashMap::new(),
+                passive_elements: HashMap::new(),
+
                 start_func: None,
 
                 func_assoc: Map::new(),
@@ -1130,8 +1263,13 @@ mod vm_ctx_tests {
                 em_symbol_map: None,
 
                 custom_sections: HashMap::new(),
+                func_names: HashMap::new(),
 
                 generate_debug_info: false,
+                gas_schedule_version: None,
+                fingerprint: Default::default(),
+                features: Default::default(),
+                memory_bound_check_mode: Default::default(),
                 #[cfg(feature = "generate-debug-information")]
                 debug_info_manager: crate::jit_debug::JitCodeDebugInfoManager::new(),
             },
@@ -3,12 +3,19 @@
 #![allow(clippy::cast_ptr_alignment)]
 
 use crate::{
+    backend::ExceptionCode,
+    backing::LocalBacking,
     memory::{DynamicMemory, StaticMemory},
     structures::TypedIndex,
-    types::{ImportedMemoryIndex, LocalMemoryIndex, LocalTableIndex},
+    table::Table,
+    types::{
+        ElementType, FuncIndex, ImportedMemoryIndex, LocalMemoryIndex, LocalOrImport,
+        LocalTableIndex, TableIndex,
+    },
     units::Pages,
     vm,
 };
+use std::{ptr, slice};
 
 // +*****************************+
 // |       LOCAL MEMORIES        |
@@ -208,19 +215,356 @@ pub unsafe extern "C" fn imported_dynamic_memory_size(
 // |        LOCAL TABLES         |
 // +*****************************+
 
+/// Increase the size of the local table with offset `table_index` by `delta` null elements.
+///
+/// This function returns the number of elements before growing if successful, or `-1` if the
+/// grow failed.
+///
+/// # Safety
+///
+/// The offset given by `table_index` is not bounds-checked.
 pub unsafe extern "C" fn local_table_grow(
     ctx: &mut vm::Ctx,
     table_index: LocalTableIndex,
     delta: u32,
 ) -> i32 {
-    let _ = table_index;
-    let _ = delta;
-    let _ = ctx;
-    unimplemented!("vmcalls::local_table_grow")
+    let local_backing = &*(ctx.local_backing as *const LocalBacking);
+    match local_backing.tables[table_index].grow(delta) {
+        Ok(old_size) => old_size as i32,
+        Err(_) => -1,
+    }
 }
 
+/// Get the size of the local table with offset `table_index`, in elements.
+///
+/// # Safety
+///
+/// The offset given by `table_index` is not bounds-checked.
 pub unsafe extern "C" fn local_table_size(ctx: &vm::Ctx, table_index: LocalTableIndex) -> u32 {
-    let _ = table_index;
-    let _ = ctx;
-    unimplemented!("vmcalls::local_table_size")
+    let local_backing = &*(ctx.local_backing as *const LocalBacking);
+    local_backing.tables[table_index].size()
+}
+
+// +*****************************+
+// |         BULK MEMORY         |
+// +*****************************+
+
+/// Aborts the execution of the WebAssembly code with the given exception code.
+unsafe fn trap(ctx: &vm::Ctx, code: ExceptionCode) -> ! {
+    (*ctx.module).runnable_module.do_early_trap(Box::new(code))
+}
+
+/// Returns whether `[offset, offset + len)` lies within `[0, bound)`.
+fn in_bounds(offset: u32, len: u32, bound: usize) -> bool {
+    offset as u64 + len as u64 <= bound as u64
+}
+
+/// Copy `len` bytes from `src` to `dst` in the memory 0, as `memory.copy` does. The ranges may
+/// overlap.
+///
+/// # Safety
+///
+/// Must only be called from the generated code of an instance that has a memory.
+pub unsafe extern "C" fn memory_copy(ctx: &mut vm::Ctx, dst: u32, src: u32, len: u32) {
+    let bound = ctx.internal.memory_bound;
+    if !in_bounds(src, len, bound) || !in_bounds(dst, len, bound) {
+        trap(ctx, ExceptionCode::MemoryOutOfBounds);
+    }
+
+    let base = ctx.internal.memory_base;
+    ptr::copy(base.add(src as usize), base.add(dst as usize), len as usize);
+}
+
+/// Set `len` bytes at `dst` in the memory 0 to `value`, as `memory.fill` does.
+///
+/// # Safety
+///
+/// Must only be called from the generated code of an instance that has a memory.
+pub unsafe extern "C" fn memory_fill(ctx: &mut vm::Ctx, dst: u32, value: u32, len: u32) {
+    if !in_bounds(dst, len, ctx.internal.memory_bound) {
+        trap(ctx, ExceptionCode::MemoryOutOfBounds);
+    }
+
+    let base = ctx.internal.memory_base;
+    ptr::write_bytes(base.add(dst as usize), value as u8, len as usize);
+}
+
+/// Copy `len` bytes from `src` in the passive data segment `segment` to `dst` in the memory 0,
+/// as `memory.init` does.
+///
+/// Active segments, and segments dropped by `data.drop`, are considered empty.
+///
+/// # Safety
+///
+/// Must only be called from the generated code of an instance that has a memory.
+pub unsafe extern "C" fn memory_init(
+    ctx: &mut vm::Ctx,
+    segment: u32,
+    dst: u32,
+    src: u32,
+    len: u32,
+) {
+    let local_backing = &*(ctx.local_backing as *const LocalBacking);
+    let is_dropped = local_backing.dropped_data.borrow().contains(&segment);
+    let data: &[u8] = match (*ctx.module).info.passive_data.get(&segment) {
+        Some(data) if !is_dropped => data,
+        _ => &[],
+    };
+
+    if !in_bounds(src, len, data.len()) || !in_bounds(dst, len, ctx.internal.memory_bound) {
+        trap(ctx, ExceptionCode::MemoryOutOfBounds);
+    }
+
+    let base = ctx.internal.memory_base;
+    ptr::copy_nonoverlapping(
+        data.as_ptr().add(src as usize),
+        base.add(dst as usize),
+        len as usize,
+    );
+}
+
+/// Drop the passive data segment `segment`, as `data.drop` does.
+///
+/// # Safety
+///
+/// Must only be called from the generated code of an instance.
+pub unsafe extern "C" fn data_drop(ctx: &mut vm::Ctx, segment: u32) {
+    let local_backing = &*(ctx.local_backing as *const LocalBacking);
+    local_backing.dropped_data.borrow_mut().insert(segment);
+}
+
+/// Returns the table `table_index`, local or imported.
+unsafe fn get_table<'a>(ctx: &vm::Ctx, table_index: u32) -> &'a Table {
+    let table_index = TableIndex::new(table_index as usize);
+    match table_index.local_or_import(&(*ctx.module).info) {
+        LocalOrImport::Local(local_table_index) => {
+            let local_backing = &*(ctx.local_backing as *const LocalBacking);
+            &local_backing.tables[local_table_index]
+        }
+        LocalOrImport::Import(imported_table_index) => {
+            &(*ctx.import_backing).tables[imported_table_index]
+        }
+    }
+}
+
+/// Returns the elements of the table `table_index`, local or imported, which must be of type `T`.
+unsafe fn get_table_elements<'a, T>(ctx: &vm::Ctx, table_index: u32) -> &'a mut [T] {
+    let table_index = TableIndex::new(table_index as usize);
+    let local_table = match table_index.local_or_import(&(*ctx.module).info) {
+        LocalOrImport::Local(local_table_index) => {
+            *ctx.internal.tables.add(local_table_index.index())
+        }
+        LocalOrImport::Import(imported_table_index) => {
+            *ctx.internal.imported_tables.add(imported_table_index.index())
+        }
+    };
+
+    slice::from_raw_parts_mut((*local_table).base as *mut T, (*local_table).count)
+}
+
+/// Copy `len` elements of type `T` from `src` in the table `src_table` to `dst` in the table
+/// `dst_table`. The ranges may overlap.
+unsafe fn copy_table_elements<T>(
+    ctx: &vm::Ctx,
+    dst_table: u32,
+    src_table: u32,
+    dst: u32,
+    src: u32,
+    len: u32,
+) {
+    let dst_elements = get_table_elements::<T>(ctx, dst_table);
+    let src_elements = get_table_elements::<T>(ctx, src_table);
+    if !in_bounds(src, len, src_elements.len()) || !in_bounds(dst, len, dst_elements.len()) {
+        trap(ctx, ExceptionCode::TableOutOfBounds);
+    }
+
+    ptr::copy(
+        src_elements.as_ptr().add(src as usize),
+        dst_elements.as_mut_ptr().add(dst as usize),
+        len as usize,
+    );
+}
+
+/// Copy `len` elements from `src` in the table `src_table` to `dst` in the table `dst_table`, as
+/// `table.copy` does. The ranges may overlap.
+///
+/// # Safety
+///
+/// Must only be called from the generated code of an instance that has both tables. Validation
+/// guarantees that the elements of the source table fit in the destination table: functions may
+/// be copied to a table of `externref` as references, but references are never copied to a table
+/// of functions, where they would be read as functions.
+pub unsafe extern "C" fn table_copy(
+    ctx: &mut vm::Ctx,
+    dst_table: u32,
+    src_table: u32,
+    dst: u32,
+    src: u32,
+    len: u32,
+) {
+    let dst_table_ref = get_table(ctx, dst_table);
+    let src_table_ref = get_table(ctx, src_table);
+    match (
+        dst_table_ref.descriptor().element,
+        src_table_ref.descriptor().element,
+    ) {
+        (ElementType::Anyfunc, ElementType::Anyfunc) => {
+            copy_table_elements::<vm::Anyfunc>(ctx, dst_table, src_table, dst, src, len)
+        }
+        (ElementType::ExternRef, ElementType::ExternRef) => {
+            copy_table_elements::<u64>(ctx, dst_table, src_table, dst, src, len)
+        }
+        // References could point to anything, see the safety section above.
+        (ElementType::Anyfunc, ElementType::ExternRef) => trap(ctx, ExceptionCode::Unreachable),
+        // Functions are converted to references, and the tables are distinct.
+        (ElementType::ExternRef, ElementType::Anyfunc) => {
+            if !in_bounds(src, len, src_table_ref.size() as usize)
+                || !in_bounds(dst, len, dst_table_ref.size() as usize)
+            {
+                trap(ctx, ExceptionCode::TableOutOfBounds);
+            }
+            for i in 0..len {
+                let reference = src_table_ref.get_reference(src + i).unwrap_or(0);
+                let _ = dst_table_ref.set_reference(dst + i, reference);
+            }
+        }
+    }
+}
+
+/// Copy `len` functions from `src` in the passive element segment `segment` to `dst` in the
+/// table `table`, as `table.init` does.
+///
+/// Active segments, and segments dropped by `elem.drop`, are considered empty.
+///
+/// # Safety
+///
+/// Must only be called from the generated code of an instance that has the table.
+pub unsafe extern "C" fn table_init(
+    ctx: &mut vm::Ctx,
+    segment: u32,
+    table: u32,
+    dst: u32,
+    src: u32,
+    len: u32,
+) {
+    let module = &*ctx.module;
+    let local_backing = &*(ctx.local_backing as *const LocalBacking);
+    let is_dropped = local_backing.dropped_elements.borrow().contains(&segment);
+    let functions: &[Option<FuncIndex>] = match module.info.passive_elements.get(&segment) {
+        Some(functions) if !is_dropped => functions,
+        _ => &[],
+    };
+
+    let table = get_table(ctx, table);
+    if !in_bounds(src, len, functions.len()) || !in_bounds(dst, len, table.size() as usize) {
+        trap(ctx, ExceptionCode::TableOutOfBounds);
+    }
+
+    let (src, dst, len) = (src as usize, dst as usize, len as usize);
+    let _ = table.write_functions(
+        dst,
+        &LocalBacking::get_func_refs(
+            &local_backing.func_refs,
+            functions[src..src + len].iter().cloned(),
+        ),
+    );
+}
+
+/// Drop the passive element segment `segment`, as `elem.drop` does.
+///
+/// # Safety
+///
+/// Must only be called from the generated code of an instance.
+pub unsafe extern "C" fn elem_drop(ctx: &mut vm::Ctx, segment: u32) {
+    let local_backing = &*(ctx.local_backing as *const LocalBacking);
+    local_backing.dropped_elements.borrow_mut().insert(segment);
+}
+
+// +*****************************+
+// |       REFERENCE TYPES       |
+// +*****************************+
+
+/// Returns the element at `index` in the table `table`, as `table.get` does.
+///
+/// # Safety
+///
+/// Must only be called from the generated code of an instance that has the table.
+pub unsafe extern "C" fn table_get(ctx: &mut vm::Ctx, table: u32, index: u32) -> u64 {
+    match get_table(ctx, table).get_reference(index) {
+        Some(reference) => reference,
+        None => trap(ctx, ExceptionCode::TableOutOfBounds),
+    }
+}
+
+/// Set the element at `index` in the table `table` to `reference`, as `table.set` does.
+///
+/// # Safety
+///
+/// Must only be called from the generated code of an instance that has the table.
+pub unsafe extern "C" fn table_set(ctx: &mut vm::Ctx, table: u32, index: u32, reference: u64) {
+    if get_table(ctx, table)
+        .set_reference(index, reference)
+        .is_err()
+    {
+        trap(ctx, ExceptionCode::TableOutOfBounds);
+    }
+}
+
+/// Grow the table `table` by `delta` elements set to `reference`, as `table.grow` does.
+///
+/// This function returns the number of elements before growing if successful, or `-1` if the
+/// grow failed.
+///
+/// # Safety
+///
+/// Must only be called from the generated code of an instance that has the table.
+pub unsafe extern "C" fn table_grow(
+    ctx: &mut vm::Ctx,
+    table: u32,
+    reference: u64,
+    delta: u32,
+) -> i32 {
+    match get_table(ctx, table).grow_with_reference(delta, reference) {
+        Ok(old_size) => old_size as i32,
+        Err(_) => -1,
+    }
+}
+
+/// Returns the size of the table `table`, in elements, as `table.size` does.
+///
+/// # Safety
+///
+/// Must only be called from the generated code of an instance that has the table.
+pub unsafe extern "C" fn table_size(ctx: &mut vm::Ctx, table: u32) -> u32 {
+    get_table(ctx, table).size()
+}
+
+/// Set `len` elements from `dst` in the table `table` to `reference`, as `table.fill` does.
+///
+/// # Safety
+///
+/// Must only be called from the generated code of an instance that has the table.
+pub unsafe extern "C" fn table_fill(
+    ctx: &mut vm::Ctx,
+    table: u32,
+    dst: u32,
+    reference: u64,
+    len: u32,
+) {
+    if get_table(ctx, table)
+        .fill_with_reference(dst, reference, len)
+        .is_err()
+    {
+        trap(ctx, ExceptionCode::TableOutOfBounds);
+    }
+}
+
+/// Returns a reference to the function `function_index`, as `ref.func` does.
+///
+/// # Safety
+///
+/// Must only be called from the generated code of an instance that has the function.
+pub unsafe extern "C" fn ref_func(ctx: &mut vm::Ctx, function_index: u32) -> u64 {
+    let local_backing = &*(ctx.local_backing as *const LocalBacking);
+    &local_backing.func_refs[function_index as usize] as *const vm::Anyfunc as u64
 }
@@ -0,0 +1,302 @@
+//! The interface types module binds the [`wasmer-interface-types`] interpreter to
+//! runtime instances, so that the adapters of a module can be executed against it.
+//!
+//! # Usage:
+//! ```
+//! # use std::convert::TryFrom;
+//! # use wasmer_runtime::interface_types::{interfaces, Instance, InterfaceValue, Interpreter};
+//! # fn run_adapters(instance: &wasmer_runtime::Instance) -> Result<(), String> {
+//! let module = instance.module();
+//! if let Some(interfaces) = interfaces(module.info()) {
+//!     let interfaces = interfaces?;
+//!     let mut adapted_instance = Instance::new(instance).with_types(interfaces.types.clone());
+//!
+//!     for adapter in interfaces.adapters {
+//!         let interpreter = Interpreter::try_from(&adapter.instructions)?;
+//!         let outputs = interpreter.run(&[InterfaceValue::I32(42)], &mut adapted_instance)?;
+//!         // ...
+//! #       drop(outputs);
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`wasmer-interface-types`]: https://crates.io/crates/wasmer-interface-types
+
+use std::{cell::Cell, collections::HashMap, ops::Deref};
+use wasmer_interface_types::{
+    ast::{self, Interfaces},
+    decoders::binary,
+    interpreter::wasm::{
+        self,
+        structures::{LocalImportIndex, TypedIndex},
+    },
+};
+use wasmer_runtime_core::{
+    instance::DynFunc,
+    memory,
+    module::{ExportIndex, ModuleInfo},
+    structures::TypedIndex as _,
+    types::{FuncIndex, Type, Value},
+};
+
+pub use wasmer_interface_types::interpreter::wasm::values::{InterfaceType, InterfaceValue};
+
+/// The name of the custom section holding the interface types of a module.
+pub const CUSTOM_SECTION_NAME: &str = "interface-types";
+
+/// Decodes the interface types of a module from its `interface-types`
+/// custom section, if it has one.
+pub fn interfaces(module_info: &ModuleInfo) -> Option<Result<Interfaces, String>> {
+    let bytes = module_info.custom_sections.get(CUSTOM_SECTION_NAME)?;
+
+    Some(match binary::parse::<()>(bytes) {
+        Ok((rest, _)) if !rest.is_empty() => Err(format!(
+            "the `{}` custom section has {} trailing bytes",
+            CUSTOM_SECTION_NAME,
+            rest.len()
+        )),
+        Ok((_, interfaces)) => Ok(interfaces),
+        Err(_) => Err(format!(
+            "the `{}` custom section is malformed",
+            CUSTOM_SECTION_NAME
+        )),
+    })
+}
+
+/// An interpreter running adapter instructions against an [`Instance`].
+///
+/// [`Instance`]: struct.Instance.html
+pub type Interpreter<'a> = wasmer_interface_types::interpreter::Interpreter<
+    Instance<'a>,
+    Function<'a>,
+    Function<'a>,
+    Memory<'a>,
+    MemoryView<'a>,
+>;
+
+/// A function of a runtime instance, callable by the interpreter either as an
+/// export or through its index.
+pub struct Function<'a> {
+    function: DynFunc<'a>,
+    inputs: Vec<InterfaceType>,
+    outputs: Vec<InterfaceType>,
+}
+
+impl<'a> Function<'a> {
+    /// Wraps `function`, or returns `None` if its signature has a type which
+    /// cannot be represented as an interface type.
+    fn new(function: DynFunc<'a>) -> Option<Self> {
+        let signature = function.signature();
+        let inputs = signature
+            .params()
+            .iter()
+            .map(interface_type)
+            .collect::<Option<_>>()?;
+        let outputs = signature
+            .returns()
+            .iter()
+            .map(interface_type)
+            .collect::<Option<_>>()?;
+
+        Some(Self {
+            function,
+            inputs,
+            outputs,
+        })
+    }
+
+    fn call(&self, arguments: &[InterfaceValue]) -> Result<Vec<InterfaceValue>, ()> {
+        let arguments = arguments
+            .iter()
+            .map(value)
+            .collect::<Option<Vec<_>>>()
+            .ok_or(())?;
+
+        self.function
+            .call(&arguments)
+            .map(|outputs| outputs.iter().map(interface_value).collect())
+            .map_err(|_| ())
+    }
+}
+
+impl wasm::structures::Export for Function<'_> {
+    fn inputs_cardinality(&self) -> usize {
+        self.inputs.len()
+    }
+
+    fn outputs_cardinality(&self) -> usize {
+        self.outputs.len()
+    }
+
+    fn inputs(&self) -> &[InterfaceType] {
+        &self.inputs
+    }
+
+    fn outputs(&self) -> &[InterfaceType] {
+        &self.outputs
+    }
+
+    fn call(&self, arguments: &[InterfaceValue]) -> Result<Vec<InterfaceValue>, ()> {
+        Function::call(self, arguments)
+    }
+}
+
+impl wasm::structures::LocalImport for Function<'_> {
+    fn inputs_cardinality(&self) -> usize {
+        self.inputs.len()
+    }
+
+    fn outputs_cardinality(&self) -> usize {
+        self.outputs.len()
+    }
+
+    fn inputs(&self) -> &[InterfaceType] {
+        &self.inputs
+    }
+
+    fn outputs(&self) -> &[InterfaceType] {
+        &self.outputs
+    }
+
+    fn call(&self, arguments: &[InterfaceValue]) -> Result<Vec<InterfaceValue>, ()> {
+        Function::call(self, arguments)
+    }
+}
+
+/// A view of the bytes of a runtime memory.
+pub struct MemoryView<'a>(memory::MemoryView<'a, u8>);
+
+impl wasm::structures::MemoryView for MemoryView<'_> {}
+
+impl Deref for MemoryView<'_> {
+    type Target = [Cell<u8>];
+
+    fn deref(&self) -> &[Cell<u8>] {
+        &self.0
+    }
+}
+
+/// A memory of a runtime instance.
+pub struct Memory<'a>(&'a memory::Memory);
+
+impl<'a> wasm::structures::Memory<MemoryView<'a>> for Memory<'a> {
+    fn view(&self) -> MemoryView<'a> {
+        MemoryView(self.0.view())
+    }
+}
+
+/// A runtime instance, as seen by the interface types interpreter.
+pub struct Instance<'a> {
+    functions: Vec<Option<Function<'a>>>,
+    exports: HashMap<String, usize>,
+    memories: Vec<Memory<'a>>,
+    types: Vec<ast::Type>,
+}
+
+impl<'a> Instance<'a> {
+    /// Exposes the functions and the memories of `instance` to the
+    /// interpreter.
+    ///
+    /// Functions taking or returning a `v128` or a reference cannot be
+    /// called by adapters.
+    pub fn new(instance: &'a wasmer_runtime_core::Instance) -> Self {
+        let module = instance.module();
+        let info = module.info();
+
+        let functions = (0..info.func_assoc.len())
+            .map(|index| {
+                instance
+                    .dyn_func_with_index(FuncIndex::new(index))
+                    .and_then(Function::new)
+            })
+            .collect();
+
+        let exports = info
+            .exports
+            .iter()
+            .filter_map(|(name, export_index)| match export_index {
+                ExportIndex::Func(func_index) => Some((name.clone(), func_index.index())),
+                _ => None,
+            })
+            .collect();
+
+        let memories = (0..info.imported_memories.len() + info.memories.len())
+            .map(|index| Memory(instance.context().memory(index as u32)))
+            .collect();
+
+        Self {
+            functions,
+            exports,
+            memories,
+            types: Vec::new(),
+        }
+    }
+
+    /// Gives the interpreter access to `types`, typically the types of
+    /// the module interfaces, so that record instructions can refer to
+    /// them by index.
+    pub fn with_types(mut self, types: Vec<ast::Type>) -> Self {
+        self.types = types;
+
+        self
+    }
+}
+
+impl<'a> wasm::structures::Instance<Function<'a>, Function<'a>, Memory<'a>, MemoryView<'a>>
+    for Instance<'a>
+{
+    fn export(&self, export_name: &str) -> Option<&Function<'a>> {
+        let index = *self.exports.get(export_name)?;
+
+        self.functions[index].as_ref()
+    }
+
+    fn local_or_import<I: TypedIndex + LocalImportIndex>(
+        &mut self,
+        index: I,
+    ) -> Option<&Function<'a>> {
+        self.functions.get(index.index())?.as_ref()
+    }
+
+    fn memory(&self, index: usize) -> Option<&Memory<'a>> {
+        self.memories.get(index)
+    }
+
+    fn wit_type(&self, index: u32) -> Option<&ast::Type> {
+        self.types.get(index as usize)
+    }
+}
+
+fn interface_type(ty: &Type) -> Option<InterfaceType> {
+    match ty {
+        Type::I32 => Some(InterfaceType::I32),
+        Type::I64 => Some(InterfaceType::I64),
+        Type::F32 => Some(InterfaceType::F32),
+        Type::F64 => Some(InterfaceType::F64),
+        Type::V128 | Type::ExternRef | Type::FuncRef => None,
+    }
+}
+
+fn interface_value(value: &Value) -> InterfaceValue {
+    match value {
+        Value::I32(value) => InterfaceValue::I32(*value),
+        Value::I64(value) => InterfaceValue::I64(*value),
+        Value::F32(value) => InterfaceValue::F32(*value),
+        Value::F64(value) => InterfaceValue::F64(*value),
+        Value::V128(_) | Value::ExternRef(_) | Value::FuncRef(_) => {
+            unreachable!("functions using `v128` or references are not exposed")
+        }
+    }
+}
+
+fn value(value: &InterfaceValue) -> Option<Value> {
+    match value {
+        InterfaceValue::I32(value) => Some(Value::I32(*value)),
+        InterfaceValue::I64(value) => Some(Value::I64(*value)),
+        InterfaceValue::F32(value) => Some(Value::F32(*value)),
+        InterfaceValue::F64(value) => Some(Value::F64(*value)),
+        _ => None,
+    }
+}
@@ -151,6 +151,9 @@ pub mod types {
 
 pub mod cache;
 
+#[cfg(feature = "interface-types")]
+pub mod interface_types;
+
 pub use wasmer_runtime_core::backend::{Compiler, CompilerConfig};
 
 /// Enum used to select which compiler should be used to generate code.
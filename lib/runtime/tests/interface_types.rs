@@ -0,0 +1,186 @@
+#![cfg(feature = "interface-types")]
+
+use std::convert::TryFrom;
+use wabt::wat2wasm;
+use wasmer_interface_types::{
+    decoders::wat::{parse, Buffer},
+    encoders::binary::ToBytes,
+    interpreter::{stack::Stackable, Instruction},
+};
+use wasmer_runtime::{
+    compile, imports,
+    interface_types::{interfaces, Instance, InterfaceValue, Interpreter, CUSTOM_SECTION_NAME},
+};
+
+static WAT: &'static str = r#"
+    (module
+      (func $double (param i32) (result i32)
+        get_local 0
+        get_local 0
+        i32.add)
+      (func (export "sum") (param i32 i32) (result i32)
+        get_local 0
+        get_local 1
+        i32.add)
+      (func (export "alloc") (param i32) (result i32)
+        i32.const 256)
+      (memory (export "memory") 1)
+      (data (i32.const 16) "Hello, World!"))
+"#;
+
+fn run(
+    wasm: &[u8],
+    instructions: &Vec<Instruction>,
+    inputs: &[InterfaceValue],
+) -> Result<Vec<InterfaceValue>, String> {
+    let instance = compile(wasm).unwrap().instantiate(&imports! {}).unwrap();
+    let mut adapted_instance = Instance::new(&instance);
+    let interpreter = Interpreter::try_from(instructions)?;
+
+    interpreter
+        .run(inputs, &mut adapted_instance)
+        .map(|stack| stack.as_slice().to_vec())
+}
+
+fn append_custom_section(wasm: &mut Vec<u8>, name: &str, data: &[u8]) {
+    fn uleb(bytes: &mut Vec<u8>, mut value: usize) {
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                bytes.push(byte);
+                break;
+            }
+            bytes.push(byte | 0x80);
+        }
+    }
+
+    let mut payload = Vec::new();
+    uleb(&mut payload, name.len());
+    payload.extend_from_slice(name.as_bytes());
+    payload.extend_from_slice(data);
+
+    wasm.push(0);
+    uleb(wasm, payload.len());
+    wasm.extend_from_slice(&payload);
+}
+
+#[test]
+fn adapter_calls_an_export() {
+    let wasm = wat2wasm(WAT).unwrap();
+    let instructions = vec![
+        Instruction::ArgumentGet { index: 0 },
+        Instruction::ArgumentGet { index: 1 },
+        Instruction::CallExport { export_name: "sum" },
+    ];
+
+    assert_eq!(
+        run(
+            &wasm,
+            &instructions,
+            &[InterfaceValue::I32(3), InterfaceValue::I32(4)]
+        ),
+        Ok(vec![InterfaceValue::I32(7)])
+    );
+}
+
+#[test]
+fn adapter_checks_the_export_signature() {
+    let wasm = wat2wasm(WAT).unwrap();
+    let instructions = vec![
+        Instruction::ArgumentGet { index: 0 },
+        Instruction::ArgumentGet { index: 1 },
+        Instruction::CallExport { export_name: "sum" },
+    ];
+
+    assert!(run(
+        &wasm,
+        &instructions,
+        &[InterfaceValue::I64(3), InterfaceValue::I32(4)]
+    )
+    .is_err());
+}
+
+#[test]
+fn adapter_reads_a_string_from_memory() {
+    let wasm = wat2wasm(WAT).unwrap();
+    let instructions = vec![
+        Instruction::ArgumentGet { index: 0 },
+        Instruction::ArgumentGet { index: 1 },
+        Instruction::ReadUtf8,
+    ];
+
+    assert_eq!(
+        run(
+            &wasm,
+            &instructions,
+            &[InterfaceValue::I32(16), InterfaceValue::I32(13)]
+        ),
+        Ok(vec![InterfaceValue::String("Hello, World!".to_string())])
+    );
+}
+
+#[test]
+fn adapter_writes_a_string_into_memory() {
+    let wasm = wat2wasm(WAT).unwrap();
+    let instance = compile(&wasm).unwrap().instantiate(&imports! {}).unwrap();
+    let mut adapted_instance = Instance::new(&instance);
+    let instructions = vec![
+        Instruction::ArgumentGet { index: 0 },
+        Instruction::WriteUtf8 {
+            allocator_name: "alloc",
+        },
+        Instruction::CallExport { export_name: "sum" },
+    ];
+    let interpreter = Interpreter::try_from(&instructions).unwrap();
+
+    let stack = interpreter
+        .run(
+            &[InterfaceValue::String("wasmer".to_string())],
+            &mut adapted_instance,
+        )
+        .unwrap();
+    assert_eq!(stack.as_slice(), &[InterfaceValue::I32(256 + 6)]);
+
+    let memory = instance.context().memory(0);
+    let bytes = memory.view::<u8>()[256..262]
+        .iter()
+        .map(|cell| cell.get())
+        .collect::<Vec<_>>();
+    assert_eq!(bytes, b"wasmer");
+}
+
+#[test]
+fn adapter_from_the_custom_section_calls_a_function_by_index() {
+    let buffer = Buffer::new(
+        r#"(@interface type (func (param i32) (result i32)))
+
+(@interface func (type 0)
+  arg.get 0
+  call 0)"#,
+    )
+    .unwrap();
+    let mut section = Vec::new();
+    parse(&buffer).unwrap().to_bytes(&mut section).unwrap();
+
+    let mut wasm = wat2wasm(WAT).unwrap();
+    append_custom_section(&mut wasm, CUSTOM_SECTION_NAME, &section);
+
+    let module = compile(&wasm).unwrap();
+    let instance = module.instantiate(&imports! {}).unwrap();
+    let interfaces = interfaces(module.info()).unwrap().unwrap();
+    assert_eq!(interfaces.adapters.len(), 1);
+
+    let interpreter = Interpreter::try_from(&interfaces.adapters[0].instructions).unwrap();
+    let stack = interpreter
+        .run(&[InterfaceValue::I32(21)], &mut Instance::new(&instance))
+        .unwrap();
+    assert_eq!(stack.as_slice(), &[InterfaceValue::I32(42)]);
+}
+
+#[test]
+fn modules_without_interface_types() {
+    let module = compile(&wat2wasm(WAT).unwrap()).unwrap();
+
+    assert!(interfaces(module.info()).is_none());
+}
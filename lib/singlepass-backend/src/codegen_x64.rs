@@ -14,7 +14,7 @@ use std::{
     collections::{BTreeMap, HashMap},
     ffi::c_void,
     iter, mem,
-    ptr::NonNull,
+    ptr::{self, NonNull},
     slice,
     sync::{Arc, RwLock},
     usize,
@@ -51,10 +51,10 @@ use wasmer_runtime_core::{
     structures::{Map, TypedIndex},
     typed_func::{Trampoline, Wasm},
     types::{
-        FuncIndex, FuncSig, GlobalIndex, LocalFuncIndex, LocalOrImport, MemoryIndex, SigIndex,
-        TableIndex, Type,
+        ElementType, FuncIndex, FuncSig, GlobalIndex, LocalFuncIndex, LocalOrImport, MemoryIndex,
+        SigIndex, TableIndex, Type,
     },
-    vm::{self, LocalGlobal, LocalTable, INTERNALS_SIZE},
+    vm::{self, LocalGlobal, LocalTable, INTERNALS_SIZE, INTERNAL_MULTI_VALUE_AREA},
     wasmparser::{MemoryImmediate, Operator, Type as WpType, TypeOrFuncType as WpTypeOrFuncType},
 };
 
@@ -293,12 +293,24 @@ pub struct ControlFrame {
     pub label: DynamicLabel,
     pub loop_like: bool,
     pub if_else: IfElseState,
+    pub params: SmallVec<[WpType; 1]>,
     pub returns: SmallVec<[WpType; 1]>,
     pub value_stack_depth: usize,
     pub state: MachineState,
     pub state_diff_id: usize,
 }
 
+impl ControlFrame {
+    /// Returns the types of the values carried by a branch to this frame.
+    pub fn branch_types(&self) -> &[WpType] {
+        if self.loop_like {
+            &self.params
+        } else {
+            &self.returns
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone)]
 pub enum IfElseState {
     None,
@@ -399,149 +411,168 @@ impl RunnableModule for X64ExecutionContext {
             args: *const u64,
             rets: *mut u64,
             error_out: *mut Option<Box<dyn Any + Send>>,
-            num_params_plus_one: Option<NonNull<c_void>>,
+            sig: Option<NonNull<c_void>>,
         ) -> bool {
             let rm: &Box<dyn RunnableModule> = &(&*(*ctx).module).runnable_module;
 
-            let args =
-                slice::from_raw_parts(args, num_params_plus_one.unwrap().as_ptr() as usize - 1);
-
-            let ret = match fault::catch_unsafe_unwind(
-                || {
-                    // Puts the arguments onto the stack and calls Wasm entry.
-                    #[cfg(target_arch = "x86_64")]
-                    {
-                        let args_reverse: SmallVec<[u64; 8]> = args.iter().cloned().rev().collect();
-                        CONSTRUCT_STACK_AND_CALL_WASM(
-                            args_reverse.as_ptr(),
-                            args_reverse.as_ptr().offset(args_reverse.len() as isize),
-                            ctx,
-                            func.as_ptr(),
-                        )
-                    }
+            let sig = &*(sig.unwrap().as_ptr() as *const FuncSig);
+            let args = slice::from_raw_parts(args, sig.params().len());
+
+            // A function with several results writes them to the buffer whose address is
+            // passed right after vmctx.
+            let mut results: SmallVec<[u64; 8]> = smallvec![0; sig.returns().len()];
+            let args: SmallVec<[u64; 8]> = if sig.returns().len() > 1 {
+                iter::once(results.as_mut_ptr() as u64)
+                    .chain(args.iter().cloned())
+                    .collect()
+            } else {
+                args.iter().cloned().collect()
+            };
 
-                    // FIXME: Currently we are doing a hack here to convert between native aarch64 and
-                    // "emulated" x86 ABIs. Ideally, this should be done using handwritten assembly.
-                    #[cfg(target_arch = "aarch64")]
-                    {
-                        struct CallCtx<'a> {
-                            args: &'a [u64],
-                            ctx: *mut vm::Ctx,
-                            callable: NonNull<vm::Func>,
+            // The fault handler finds the module of the faulting code through the current context.
+            let ret = match fault::with_ctx(ctx, || {
+                fault::catch_unsafe_unwind(
+                    || {
+                        // Puts the arguments onto the stack and calls Wasm entry.
+                        #[cfg(target_arch = "x86_64")]
+                        {
+                            let args_reverse: SmallVec<[u64; 8]> =
+                                args.iter().cloned().rev().collect();
+                            CONSTRUCT_STACK_AND_CALL_WASM(
+                                args_reverse.as_ptr(),
+                                args_reverse.as_ptr().offset(args_reverse.len() as isize),
+                                ctx,
+                                func.as_ptr(),
+                            )
                         }
-                        extern "C" fn call_fn(f: *mut u8) -> u64 {
-                            unsafe {
-                                let f = &*(f as *const CallCtx);
-                                let callable: extern "C" fn(
-                                    u64,
-                                    u64,
-                                    u64,
-                                    u64,
-                                    u64,
-                                    u64,
-                                    u64,
-                                    u64,
-                                    u64,
-                                    u64,
-                                    u64,
-                                    u64,
-                                    u64,
-                                    u64,
-                                    u64,
-                                    u64,
-                                    u64,
-                                    u64,
-                                    u64,
-                                    u64,
-                                    u64,
-                                    u64,
-                                    u64,
-                                    u64,
-                                    u64,
-                                    u64,
-                                    u64,
-                                    u64,
-                                    u64,
-                                    u64,
-                                    u64,
-                                    u64,
-                                    u64,
-                                )
-                                    -> u64 = std::mem::transmute(f.callable);
-                                let mut args = f.args.iter();
-                                callable(
-                                    f.ctx as u64,
-                                    args.next().cloned().unwrap_or(0),
-                                    args.next().cloned().unwrap_or(0),
-                                    args.next().cloned().unwrap_or(0),
-                                    args.next().cloned().unwrap_or(0),
-                                    args.next().cloned().unwrap_or(0),
-                                    args.next().cloned().unwrap_or(0),
-                                    args.next().cloned().unwrap_or(0),
-                                    args.next().cloned().unwrap_or(0),
-                                    args.next().cloned().unwrap_or(0),
-                                    args.next().cloned().unwrap_or(0),
-                                    args.next().cloned().unwrap_or(0),
-                                    args.next().cloned().unwrap_or(0),
-                                    args.next().cloned().unwrap_or(0),
-                                    args.next().cloned().unwrap_or(0),
-                                    args.next().cloned().unwrap_or(0),
-                                    args.next().cloned().unwrap_or(0),
-                                    args.next().cloned().unwrap_or(0),
-                                    args.next().cloned().unwrap_or(0),
-                                    args.next().cloned().unwrap_or(0),
-                                    args.next().cloned().unwrap_or(0),
-                                    args.next().cloned().unwrap_or(0),
-                                    args.next().cloned().unwrap_or(0),
-                                    args.next().cloned().unwrap_or(0),
-                                    args.next().cloned().unwrap_or(0),
-                                    args.next().cloned().unwrap_or(0),
-                                    args.next().cloned().unwrap_or(0),
-                                    args.next().cloned().unwrap_or(0),
-                                    args.next().cloned().unwrap_or(0),
-                                    args.next().cloned().unwrap_or(0),
-                                    args.next().cloned().unwrap_or(0),
-                                    args.next().cloned().unwrap_or(0),
-                                    args.next().cloned().unwrap_or(0),
-                                )
+
+                        // FIXME: Currently we are doing a hack here to convert between native aarch64 and
+                        // "emulated" x86 ABIs. Ideally, this should be done using handwritten assembly.
+                        #[cfg(target_arch = "aarch64")]
+                        {
+                            struct CallCtx<'a> {
+                                args: &'a [u64],
+                                ctx: *mut vm::Ctx,
+                                callable: NonNull<vm::Func>,
                             }
+                            extern "C" fn call_fn(f: *mut u8) -> u64 {
+                                unsafe {
+                                    let f = &*(f as *const CallCtx);
+                                    let callable: extern "C" fn(
+                                        u64,
+                                        u64,
+                                        u64,
+                                        u64,
+                                        u64,
+                                        u64,
+                                        u64,
+                                        u64,
+                                        u64,
+                                        u64,
+                                        u64,
+                                        u64,
+                                        u64,
+                                        u64,
+                                        u64,
+                                        u64,
+                                        u64,
+                                        u64,
+                                        u64,
+                                        u64,
+                                        u64,
+                                        u64,
+                                        u64,
+                                        u64,
+                                        u64,
+                                        u64,
+                                        u64,
+                                        u64,
+                                        u64,
+                                        u64,
+                                        u64,
+                                        u64,
+                                        u64,
+                                    )
+                                        -> u64 = std::mem::transmute(f.callable);
+                                    let mut args = f.args.iter();
+                                    callable(
+                                        f.ctx as u64,
+                                        args.next().cloned().unwrap_or(0),
+                                        args.next().cloned().unwrap_or(0),
+                                        args.next().cloned().unwrap_or(0),
+                                        args.next().cloned().unwrap_or(0),
+                                        args.next().cloned().unwrap_or(0),
+                                        args.next().cloned().unwrap_or(0),
+                                        args.next().cloned().unwrap_or(0),
+                                        args.next().cloned().unwrap_or(0),
+                                        args.next().cloned().unwrap_or(0),
+                                        args.next().cloned().unwrap_or(0),
+                                        args.next().cloned().unwrap_or(0),
+                                        args.next().cloned().unwrap_or(0),
+                                        args.next().cloned().unwrap_or(0),
+                                        args.next().cloned().unwrap_or(0),
+                                        args.next().cloned().unwrap_or(0),
+                                        args.next().cloned().unwrap_or(0),
+                                        args.next().cloned().unwrap_or(0),
+                                        args.next().cloned().unwrap_or(0),
+                                        args.next().cloned().unwrap_or(0),
+                                        args.next().cloned().unwrap_or(0),
+                                        args.next().cloned().unwrap_or(0),
+                                        args.next().cloned().unwrap_or(0),
+                                        args.next().cloned().unwrap_or(0),
+                                        args.next().cloned().unwrap_or(0),
+                                        args.next().cloned().unwrap_or(0),
+                                        args.next().cloned().unwrap_or(0),
+                                        args.next().cloned().unwrap_or(0),
+                                        args.next().cloned().unwrap_or(0),
+                                        args.next().cloned().unwrap_or(0),
+                                        args.next().cloned().unwrap_or(0),
+                                        args.next().cloned().unwrap_or(0),
+                                        args.next().cloned().unwrap_or(0),
+                                    )
+                                }
+                            }
+                            let mut cctx = CallCtx {
+                                args: &args,
+                                ctx: ctx,
+                                callable: func,
+                            };
+                            use libc::{
+                                mmap, munmap, MAP_ANON, MAP_NORESERVE, MAP_PRIVATE, PROT_READ,
+                                PROT_WRITE,
+                            };
+                            const STACK_SIZE: usize = 1048576 * 1024; // 1GB of virtual address space for stack.
+                            let stack_ptr = mmap(
+                                ::std::ptr::null_mut(),
+                                STACK_SIZE,
+                                PROT_READ | PROT_WRITE,
+                                MAP_PRIVATE | MAP_ANON | MAP_NORESERVE,
+                                -1,
+                                0,
+                            );
+                            if stack_ptr as isize == -1 {
+                                panic!("unable to allocate stack");
+                            }
+                            // TODO: Mark specific regions in the stack as PROT_NONE.
+                            let ret = SWITCH_STACK(
+                                (stack_ptr as *mut u8).offset(STACK_SIZE as isize) as *mut u64,
+                                call_fn,
+                                &mut cctx as *mut CallCtx as *mut u8,
+                            );
+                            munmap(stack_ptr, STACK_SIZE);
+                            ret
                         }
-                        let mut cctx = CallCtx {
-                            args: &args,
-                            ctx: ctx,
-                            callable: func,
-                        };
-                        use libc::{
-                            mmap, munmap, MAP_ANON, MAP_NORESERVE, MAP_PRIVATE, PROT_READ,
-                            PROT_WRITE,
-                        };
-                        const STACK_SIZE: usize = 1048576 * 1024; // 1GB of virtual address space for stack.
-                        let stack_ptr = mmap(
-                            ::std::ptr::null_mut(),
-                            STACK_SIZE,
-                            PROT_READ | PROT_WRITE,
-                            MAP_PRIVATE | MAP_ANON | MAP_NORESERVE,
-                            -1,
-                            0,
-                        );
-                        if stack_ptr as isize == -1 {
-                            panic!("unable to allocate stack");
-                        }
-                        // TODO: Mark specific regions in the stack as PROT_NONE.
-                        let ret = SWITCH_STACK(
-                            (stack_ptr as *mut u8).offset(STACK_SIZE as isize) as *mut u64,
-                            call_fn,
-                            &mut cctx as *mut CallCtx as *mut u8,
-                        );
-                        munmap(stack_ptr, STACK_SIZE);
-                        ret
-                    }
-                },
-                rm.get_breakpoints(),
-            ) {
+                    },
+                    rm.get_breakpoints(),
+                )
+            }) {
                 Ok(x) => {
                     if !rets.is_null() {
-                        *rets = x;
+                        if sig.returns().len() > 1 {
+                            ptr::copy_nonoverlapping(results.as_ptr(), rets, results.len());
+                        } else {
+                            *rets = x;
+                        }
                     }
                     true
                 }
@@ -566,7 +597,7 @@ impl RunnableModule for X64ExecutionContext {
             Wasm::from_raw_parts(
                 dummy_trampoline,
                 invoke,
-                NonNull::new((self.signatures.get(sig_index).unwrap().params().len() + 1) as _), // +1 to keep it non-zero
+                NonNull::new(self.signatures.get(sig_index).unwrap() as *const FuncSig as _),
             )
         })
     }
@@ -662,6 +693,7 @@ struct CodegenConfig {
     enforce_stack_check: bool,
     track_state: bool,
     full_preemption: bool,
+    nan_canonicalization: bool,
 }
 
 impl ModuleCodeGenerator<X64FunctionCode, X64ExecutionContext, CodegenError>
@@ -952,6 +984,7 @@ impl ModuleCodeGenerator<X64FunctionCode, X64ExecutionContext, CodegenError>
             enforce_stack_check: config.enforce_stack_check,
             track_state: config.track_state,
             full_preemption: config.full_preemption,
+            nan_canonicalization: config.nan_canonicalization,
         }));
         Ok(())
     }
@@ -1953,18 +1986,245 @@ impl X64FunctionCode {
         Ok(())
     }
 
-    /// Emits a System V call sequence, specialized for labels as the call target.
-    fn emit_call_sysv_label<I: Iterator<Item = Location>>(
+    /// Loads the address of the multi-value area of the instance into `gpr`.
+    fn emit_load_multi_value_area(a: &mut Assembler, gpr: GPR) {
+        a.emit_mov(
+            Size::S64,
+            Location::Memory(
+                Machine::get_vmctx_reg(),
+                vm::Ctx::offset_internals() as i32,
+            ),
+            Location::GPR(gpr),
+        );
+        a.emit_mov(
+            Size::S64,
+            Location::Memory(gpr, (INTERNAL_MULTI_VALUE_AREA * 8) as i32),
+            Location::GPR(gpr),
+        );
+    }
+
+    /// Returns the parameters of a call, preceded by a placeholder for the results pointer if
+    /// the callee has several results.
+    ///
+    /// The placeholder is overwritten by `emit_load_results_pointer`, once all the parameters
+    /// are in place.
+    fn get_call_params(
+        params: &[Location],
+        multi_value: bool,
+    ) -> impl Iterator<Item = Location> + '_ {
+        let results_pointer = if multi_value {
+            Some(Location::Imm32(0))
+        } else {
+            None
+        };
+        results_pointer.into_iter().chain(params.iter().cloned())
+    }
+
+    /// Passes the multi-value area as the buffer for the results of the callee, right after
+    /// vmctx.
+    fn emit_load_results_pointer(a: &mut Assembler) {
+        match Machine::get_param_location(1) {
+            Location::GPR(gpr) => Self::emit_load_multi_value_area(a, gpr),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Moves the values carried by a branch to where the target label expects them.
+    ///
+    /// A single value goes through RAX, and several values go through the multi-value area.
+    fn emit_store_transferred_values(a: &mut Assembler, m: &mut Machine, values: &[Location]) {
+        match values.len() {
+            0 => {}
+            1 => a.emit_mov(Size::S64, values[0], Location::GPR(GPR::RAX)),
+            _ => {
+                let area = m.acquire_temp_gpr().unwrap();
+                Self::emit_load_multi_value_area(a, area);
+                for (i, value) in values.iter().enumerate() {
+                    Self::emit_relaxed_binop(
+                        a,
+                        m,
+                        Assembler::emit_mov,
+                        Size::S64,
+                        *value,
+                        Location::Memory(area, (i * 8) as i32),
+                    );
+                }
+                m.release_temp_gpr(area);
+            }
+        }
+    }
+
+    /// Pushes copies of the params of an `if` frame, which stay right below the frame, onto the
+    /// value stack.
+    fn emit_push_params_copies(
         a: &mut Assembler,
         m: &mut Machine,
-        label: DynamicLabel,
-        params: I,
-        state_context: Option<(&mut FunctionStateMap, &mut [ControlFrame])>,
+        value_stack: &mut Vec<Location>,
+        frame: &ControlFrame,
+    ) {
+        let params: SmallVec<[Location; 8]> = value_stack
+            [frame.value_stack_depth - frame.params.len()..frame.value_stack_depth]
+            .iter()
+            .cloned()
+            .collect();
+        let tys: SmallVec<[_; 1]> = frame
+            .params
+            .iter()
+            .enumerate()
+            .map(|(i, ty)| (*ty, MachineValue::WasmStack(value_stack.len() + i)))
+            .collect();
+        let copies = m.acquire_locations(a, &tys, false);
+
+        for (param, copy) in params.iter().zip(copies.iter()) {
+            Self::emit_relaxed_binop(a, m, Assembler::emit_mov, Size::S64, *param, *copy);
+        }
+
+        value_stack.extend(copies);
+    }
+
+    /// Pushes the values stored by `emit_store_transferred_values`, or returned by a call, onto
+    /// the value stack.
+    fn emit_load_transferred_values(
+        a: &mut Assembler,
+        m: &mut Machine,
+        value_stack: &mut Vec<Location>,
+        types: &[WpType],
+    ) {
+        let tys: SmallVec<[_; 1]> = types
+            .iter()
+            .enumerate()
+            .map(|(i, ty)| (*ty, MachineValue::WasmStack(value_stack.len() + i)))
+            .collect();
+        let locs = m.acquire_locations(a, &tys, false);
+
+        match locs.len() {
+            0 => {}
+            1 => a.emit_mov(Size::S64, Location::GPR(GPR::RAX), locs[0]),
+            _ => {
+                let area = m.acquire_temp_gpr().unwrap();
+                Self::emit_load_multi_value_area(a, area);
+                for (i, loc) in locs.iter().enumerate() {
+                    Self::emit_relaxed_binop(
+                        a,
+                        m,
+                        Assembler::emit_mov,
+                        Size::S64,
+                        Location::Memory(area, (i * 8) as i32),
+                        *loc,
+                    );
+                }
+                m.release_temp_gpr(area);
+            }
+        }
+
+        value_stack.extend(locs);
+    }
+
+    /// Emits a call to the intrinsic at `intrinsic_offset` in `vm::Intrinsics`, passing the
+    /// `immediates` followed by the `operands_count` values on top of the value stack.
+    fn emit_intrinsic_call(
+        a: &mut Assembler,
+        m: &mut Machine,
+        value_stack: &mut Vec<Location>,
+        intrinsic_offset: u8,
+        immediates: &[u32],
+        operands_count: usize,
     ) -> Result<(), CodegenError> {
-        Self::emit_call_sysv(a, m, |a| a.emit_call_label(label), params, state_context)?;
+        let operands = value_stack.split_off(value_stack.len() - operands_count);
+
+        m.release_locations_only_regs(&operands);
+
+        a.emit_mov(
+            Size::S64,
+            Location::Memory(Machine::get_vmctx_reg(), vm::Ctx::offset_intrinsics() as i32),
+            Location::GPR(GPR::RAX),
+        );
+        a.emit_mov(
+            Size::S64,
+            Location::Memory(GPR::RAX, intrinsic_offset as i32),
+            Location::GPR(GPR::RAX),
+        );
+
+        m.release_locations_only_osr_state(operands_count);
+
+        Self::emit_call_sysv(
+            a,
+            m,
+            |a| {
+                let label = a.get_label();
+                let after = a.get_label();
+                a.emit_jmp(Condition::None, after);
+                a.emit_label(label);
+                a.emit_host_redirection(GPR::RAX);
+                a.emit_label(after);
+                a.emit_call_label(label);
+            },
+            immediates
+                .iter()
+                .map(|&immediate| Location::Imm32(immediate))
+                .chain(operands.iter().cloned()),
+            None,
+        )?;
+
+        m.release_locations_only_stack(a, &operands);
         Ok(())
     }
 
+    /// Pushes the result of the intrinsic called by `emit_intrinsic_call`, of type `ty`, on the
+    /// value stack.
+    fn emit_push_intrinsic_result(
+        a: &mut Assembler,
+        m: &mut Machine,
+        value_stack: &mut Vec<Location>,
+        ty: WpType,
+    ) {
+        let ret = m.acquire_locations(
+            a,
+            &[(ty, MachineValue::WasmStack(value_stack.len()))],
+            false,
+        )[0];
+        value_stack.push(ret);
+        a.emit_mov(Size::S64, Location::GPR(GPR::RAX), ret);
+    }
+
+    /// Replaces the float of size `sz` at `loc` with the canonical NaN if it is a NaN.
+    fn emit_canonicalize_nan(a: &mut Assembler, m: &mut Machine, sz: Size, loc: Location) {
+        let tmp_value = m.acquire_temp_xmm().unwrap();
+        let tmp_mask = m.acquire_temp_xmm().unwrap();
+        let tmp_nan = m.acquire_temp_xmm().unwrap();
+        let tmpg = m.acquire_temp_gpr().unwrap();
+
+        Self::emit_relaxed_binop(a, m, Assembler::emit_mov, sz, loc, Location::XMM(tmp_value));
+        match sz {
+            Size::S32 => {
+                a.emit_vcmpunordss(tmp_value, XMMOrMemory::XMM(tmp_value), tmp_mask);
+                a.emit_mov(
+                    Size::S64,
+                    Location::Imm32(0x7FC0_0000), // Canonical NaN
+                    Location::GPR(tmpg),
+                );
+                a.emit_mov(Size::S64, Location::GPR(tmpg), Location::XMM(tmp_nan));
+                a.emit_vblendvps(tmp_mask, XMMOrMemory::XMM(tmp_nan), tmp_value, tmp_value);
+            }
+            _ => {
+                a.emit_vcmpunordsd(tmp_value, XMMOrMemory::XMM(tmp_value), tmp_mask);
+                a.emit_mov(
+                    Size::S64,
+                    Location::Imm64(0x7FF8_0000_0000_0000), // Canonical NaN
+                    Location::GPR(tmpg),
+                );
+                a.emit_mov(Size::S64, Location::GPR(tmpg), Location::XMM(tmp_nan));
+                a.emit_vblendvpd(tmp_mask, XMMOrMemory::XMM(tmp_nan), tmp_value, tmp_value);
+            }
+        }
+        Self::emit_relaxed_binop(a, m, Assembler::emit_mov, sz, Location::XMM(tmp_value), loc);
+
+        m.release_temp_gpr(tmpg);
+        m.release_temp_xmm(tmp_nan);
+        m.release_temp_xmm(tmp_mask);
+        m.release_temp_xmm(tmp_value);
+    }
+
     /// Emits a memory operation.
     fn emit_memory_op<F: FnOnce(&mut Assembler, &mut Machine, GPR) -> Result<(), CodegenError>>(
         module_info: &ModuleInfo,
@@ -1991,7 +2251,11 @@ impl X64FunctionCode {
                 MemoryType::Static | MemoryType::SharedStatic => false,
             },
             MemoryBoundCheckMode::Enable => true,
-            MemoryBoundCheckMode::Disable => false,
+            // Dynamic memories have no guard pages to catch out of bounds accesses.
+            MemoryBoundCheckMode::Disable => match mem_desc.memory_type() {
+                MemoryType::Dynamic => true,
+                MemoryType::Static | MemoryType::SharedStatic => false,
+            },
         };
 
         let tmp_addr = m.acquire_temp_gpr().unwrap();
@@ -2529,9 +2793,12 @@ impl FunctionCodeGenerator<CodegenError> for X64FunctionCode {
             };
         }
 
-        self.locals = self
-            .machine
-            .init_locals(a, self.num_locals, self.num_params);
+        self.locals = self.machine.init_locals(
+            a,
+            self.num_locals,
+            self.num_params,
+            self.returns.len() > 1,
+        );
 
         self.machine.state.register_values
             [X64Register::GPR(Machine::get_vmctx_reg()).to_index().0] = MachineValue::Vmctx;
@@ -2557,6 +2824,7 @@ impl FunctionCodeGenerator<CodegenError> for X64FunctionCode {
             label: a.get_label(),
             loop_like: false,
             if_else: IfElseState::None,
+            params: smallvec![],
             returns: self.returns.clone(),
             value_stack_depth: 0,
             state: self.machine.state.clone(),
@@ -6320,33 +6588,36 @@ impl FunctionCodeGenerator<CodegenError> for X64FunctionCode {
 
                 self.machine.release_locations_only_osr_state(params.len());
 
-                Self::emit_call_sysv_label(
+                let multi_value = return_types.len() > 1;
+                Self::emit_call_sysv(
                     a,
                     &mut self.machine,
-                    label,
-                    params.iter().map(|x| *x),
+                    |a| {
+                        if multi_value {
+                            Self::emit_load_results_pointer(a);
+                        }
+                        a.emit_call_label(label);
+                    },
+                    Self::get_call_params(&params, multi_value),
                     Some((&mut self.fsm, &mut self.control_stack)),
                 )?;
 
                 self.machine.release_locations_only_stack(a, &params);
 
-                if return_types.len() > 0 {
-                    let ret = self.machine.acquire_locations(
-                        a,
-                        &[(
-                            return_types[0],
-                            MachineValue::WasmStack(self.value_stack.len()),
-                        )],
-                        false,
-                    )[0];
-                    self.value_stack.push(ret);
-                    a.emit_mov(Size::S64, Location::GPR(GPR::RAX), ret);
-                }
+                Self::emit_load_transferred_values(
+                    a,
+                    &mut self.machine,
+                    &mut self.value_stack,
+                    &return_types,
+                );
             }
             Operator::CallIndirect { index, table_index } => {
-                if table_index != 0 {
+                if get_table_element_type(module_info, table_index) != ElementType::Anyfunc {
                     return Err(CodegenError {
-                        message: format!("CallIndirect: table_index is not 0"),
+                        message: format!(
+                            "CallIndirect: table {} is not a table of functions",
+                            table_index
+                        ),
                     });
                 }
                 let sig = self.signatures.get(SigIndex::new(index as usize)).unwrap();
@@ -6368,20 +6639,24 @@ impl FunctionCodeGenerator<CodegenError> for X64FunctionCode {
                 let table_count = self.machine.acquire_temp_gpr().unwrap();
                 let sigidx = self.machine.acquire_temp_gpr().unwrap();
 
+                let (tables_offset, table_index) =
+                    match TableIndex::new(table_index as usize).local_or_import(module_info) {
+                        LocalOrImport::Local(local_table_index) => {
+                            (vm::Ctx::offset_tables(), local_table_index.index())
+                        }
+                        LocalOrImport::Import(import_table_index) => (
+                            vm::Ctx::offset_imported_tables(),
+                            import_table_index.index(),
+                        ),
+                    };
                 a.emit_mov(
                     Size::S64,
-                    Location::Memory(
-                        Machine::get_vmctx_reg(),
-                        match TableIndex::new(0).local_or_import(module_info) {
-                            LocalOrImport::Local(_) => vm::Ctx::offset_tables(),
-                            LocalOrImport::Import(_) => vm::Ctx::offset_imported_tables(),
-                        } as i32,
-                    ),
+                    Location::Memory(Machine::get_vmctx_reg(), tables_offset as i32),
                     Location::GPR(table_base),
                 );
                 a.emit_mov(
                     Size::S64,
-                    Location::Memory(table_base, 0),
+                    Location::Memory(table_base, (table_index * 8) as i32),
                     Location::GPR(table_base),
                 );
                 a.emit_mov(
@@ -6459,10 +6734,14 @@ impl FunctionCodeGenerator<CodegenError> for X64FunctionCode {
 
                 self.machine.release_locations_only_osr_state(params.len());
 
+                let multi_value = return_types.len() > 1;
                 Self::emit_call_sysv(
                     a,
                     &mut self.machine,
                     |a| {
+                        if multi_value {
+                            Self::emit_load_results_pointer(a);
+                        }
                         if a.arch_requires_indirect_call_trampoline() {
                             a.arch_emit_indirect_call_with_trampoline(Location::Memory(
                                 GPR::RAX,
@@ -6475,24 +6754,18 @@ impl FunctionCodeGenerator<CodegenError> for X64FunctionCode {
                             ));
                         }
                     },
-                    params.iter().map(|x| *x),
+                    Self::get_call_params(&params, multi_value),
                     Some((&mut self.fsm, &mut self.control_stack)),
                 )?;
 
                 self.machine.release_locations_only_stack(a, &params);
 
-                if return_types.len() > 0 {
-                    let ret = self.machine.acquire_locations(
-                        a,
-                        &[(
-                            return_types[0],
-                            MachineValue::WasmStack(self.value_stack.len()),
-                        )],
-                        false,
-                    )[0];
-                    self.value_stack.push(ret);
-                    a.emit_mov(Size::S64, Location::GPR(GPR::RAX), ret);
-                }
+                Self::emit_load_transferred_values(
+                    a,
+                    &mut self.machine,
+                    &mut self.value_stack,
+                    &return_types,
+                );
             }
             Operator::If { ty } => {
                 let label_end = a.get_label();
@@ -6501,19 +6774,15 @@ impl FunctionCodeGenerator<CodegenError> for X64FunctionCode {
                 let cond =
                     get_location_released(a, &mut self.machine, self.value_stack.pop().unwrap());
 
+                // Both branches consume the params, so the params stay below the frame and each
+                // branch works on copies of them.
+                let (params, returns) = get_block_type(&self.signatures, ty);
                 let frame = ControlFrame {
                     label: label_end,
                     loop_like: false,
                     if_else: IfElseState::If(label_else),
-                    returns: match ty {
-                        WpTypeOrFuncType::Type(WpType::EmptyBlockType) => smallvec![],
-                        WpTypeOrFuncType::Type(inner_ty) => smallvec![inner_ty],
-                        _ => {
-                            return Err(CodegenError {
-                                message: format!("If: multi-value returns not yet implemented"),
-                            })
-                        }
-                    },
+                    params,
+                    returns,
                     value_stack_depth: self.value_stack.len(),
                     state: self.machine.state.clone(),
                     state_diff_id: Self::get_state_diff(
@@ -6532,20 +6801,16 @@ impl FunctionCodeGenerator<CodegenError> for X64FunctionCode {
                     cond,
                 );
                 a.emit_jmp(Condition::Equal, label_else);
+
+                let frame = self.control_stack.last().unwrap();
+                Self::emit_push_params_copies(a, &mut self.machine, &mut self.value_stack, frame);
             }
             Operator::Else => {
                 let mut frame = self.control_stack.last_mut().unwrap();
 
-                if !was_unreachable && frame.returns.len() > 0 {
-                    let loc = *self.value_stack.last().unwrap();
-                    Self::emit_relaxed_binop(
-                        a,
-                        &mut self.machine,
-                        Assembler::emit_mov,
-                        Size::S64,
-                        loc,
-                        Location::GPR(GPR::RAX),
-                    );
+                if !was_unreachable {
+                    let values = &self.value_stack[self.value_stack.len() - frame.returns.len()..];
+                    Self::emit_store_transferred_values(a, &mut self.machine, values);
                 }
 
                 let released: &[Location] = &self.value_stack[frame.value_stack_depth..];
@@ -6564,6 +6829,8 @@ impl FunctionCodeGenerator<CodegenError> for X64FunctionCode {
                         })
                     }
                 }
+
+                Self::emit_push_params_copies(a, &mut self.machine, &mut self.value_stack, frame);
             }
             Operator::Select => {
                 let cond =
@@ -6616,20 +6883,15 @@ impl FunctionCodeGenerator<CodegenError> for X64FunctionCode {
                 a.emit_label(end_label);
             }
             Operator::Block { ty } => {
+                let (params, returns) = get_block_type(&self.signatures, ty);
+                let value_stack_depth = self.value_stack.len() - params.len();
                 let frame = ControlFrame {
                     label: a.get_label(),
                     loop_like: false,
                     if_else: IfElseState::None,
-                    returns: match ty {
-                        WpTypeOrFuncType::Type(WpType::EmptyBlockType) => smallvec![],
-                        WpTypeOrFuncType::Type(inner_ty) => smallvec![inner_ty],
-                        _ => {
-                            return Err(CodegenError {
-                                message: format!("Block: multi-value returns not yet implemented"),
-                            })
-                        }
-                    },
-                    value_stack_depth: self.value_stack.len(),
+                    params,
+                    returns,
+                    value_stack_depth,
                     state: self.machine.state.clone(),
                     state_diff_id: Self::get_state_diff(
                         &self.machine,
@@ -6640,6 +6902,14 @@ impl FunctionCodeGenerator<CodegenError> for X64FunctionCode {
                 self.control_stack.push(frame);
             }
             Operator::Loop { ty } => {
+                // Branches to the loop carry its params, so they enter the loop the same way.
+                let (params, returns) = get_block_type(&self.signatures, ty);
+                let values = self
+                    .value_stack
+                    .split_off(self.value_stack.len() - params.len());
+                Self::emit_store_transferred_values(a, &mut self.machine, &values);
+                self.machine.release_locations(a, &values);
+
                 let label = a.get_label();
                 let state_diff_id =
                     Self::get_state_diff(&self.machine, &mut self.fsm, &mut self.control_stack);
@@ -6649,22 +6919,16 @@ impl FunctionCodeGenerator<CodegenError> for X64FunctionCode {
                     label: label,
                     loop_like: true,
                     if_else: IfElseState::None,
-                    returns: match ty {
-                        WpTypeOrFuncType::Type(WpType::EmptyBlockType) => smallvec![],
-                        WpTypeOrFuncType::Type(inner_ty) => smallvec![inner_ty],
-                        _ => {
-                            return Err(CodegenError {
-                                message: format!("Loop: multi-value returns not yet implemented"),
-                            })
-                        }
-                    },
+                    params: params.clone(),
+                    returns,
                     value_stack_depth: self.value_stack.len(),
                     state: self.machine.state.clone(),
                     state_diff_id,
                 });
                 a.emit_label(label);
 
-                // Check interrupt signal without branching
+                // Check interrupt signal without branching. RCX is used since RAX may hold the
+                // param of the loop.
                 if self.config.full_preemption {
                     a.emit_mov(
                         Size::S64,
@@ -6672,7 +6936,7 @@ impl FunctionCodeGenerator<CodegenError> for X64FunctionCode {
                             Machine::get_vmctx_reg(),
                             vm::Ctx::offset_interrupt_signal_mem() as i32,
                         ),
-                        Location::GPR(GPR::RAX),
+                        Location::GPR(GPR::RCX),
                     );
                     self.fsm.loop_offsets.insert(
                         a.get_offset().0,
@@ -6688,10 +6952,17 @@ impl FunctionCodeGenerator<CodegenError> for X64FunctionCode {
                     );
                     a.emit_mov(
                         Size::S64,
-                        Location::Memory(GPR::RAX, 0),
-                        Location::GPR(GPR::RAX),
+                        Location::Memory(GPR::RCX, 0),
+                        Location::GPR(GPR::RCX),
                     );
                 }
+
+                Self::emit_load_transferred_values(
+                    a,
+                    &mut self.machine,
+                    &mut self.value_stack,
+                    &params,
+                );
             }
             Operator::Nop => {}
             Operator::MemorySize { reserved } => {
@@ -6781,6 +7052,191 @@ impl FunctionCodeGenerator<CodegenError> for X64FunctionCode {
                 self.value_stack.push(ret);
                 a.emit_mov(Size::S64, Location::GPR(GPR::RAX), ret);
             }
+            Operator::MemoryCopy { .. } => {
+                Self::emit_intrinsic_call(
+                    a,
+                    &mut self.machine,
+                    &mut self.value_stack,
+                    vm::Intrinsics::offset_memory_copy(),
+                    &[],
+                    3,
+                )?;
+            }
+            Operator::MemoryFill { .. } => {
+                Self::emit_intrinsic_call(
+                    a,
+                    &mut self.machine,
+                    &mut self.value_stack,
+                    vm::Intrinsics::offset_memory_fill(),
+                    &[],
+                    3,
+                )?;
+            }
+            Operator::MemoryInit { segment, .. } => {
+                Self::emit_intrinsic_call(
+                    a,
+                    &mut self.machine,
+                    &mut self.value_stack,
+                    vm::Intrinsics::offset_memory_init(),
+                    &[segment],
+                    3,
+                )?;
+            }
+            Operator::DataDrop { segment } => {
+                Self::emit_intrinsic_call(
+                    a,
+                    &mut self.machine,
+                    &mut self.value_stack,
+                    vm::Intrinsics::offset_data_drop(),
+                    &[segment],
+                    0,
+                )?;
+            }
+            Operator::TableCopy {
+                dst_table,
+                src_table,
+            } => {
+                // References cannot be converted back to functions.
+                if get_table_element_type(module_info, dst_table) == ElementType::Anyfunc
+                    && get_table_element_type(module_info, src_table) != ElementType::Anyfunc
+                {
+                    return Err(CodegenError {
+                        message: format!(
+                            "TableCopy: cannot copy table {} of references to table {} of functions",
+                            src_table, dst_table
+                        ),
+                    });
+                }
+                Self::emit_intrinsic_call(
+                    a,
+                    &mut self.machine,
+                    &mut self.value_stack,
+                    vm::Intrinsics::offset_table_copy(),
+                    &[dst_table, src_table],
+                    3,
+                )?;
+            }
+            Operator::TableInit { segment, table } => {
+                Self::emit_intrinsic_call(
+                    a,
+                    &mut self.machine,
+                    &mut self.value_stack,
+                    vm::Intrinsics::offset_table_init(),
+                    &[segment, table],
+                    3,
+                )?;
+            }
+            Operator::ElemDrop { segment } => {
+                Self::emit_intrinsic_call(
+                    a,
+                    &mut self.machine,
+                    &mut self.value_stack,
+                    vm::Intrinsics::offset_elem_drop(),
+                    &[segment],
+                    0,
+                )?;
+            }
+            Operator::RefNull => {
+                self.value_stack.push(Location::Imm64(0));
+                self.machine
+                    .state
+                    .wasm_stack
+                    .push(WasmAbstractValue::Const(0));
+            }
+            Operator::RefIsNull => Self::emit_cmpop_i64_dynamic_b(
+                a,
+                &mut self.machine,
+                &mut self.value_stack,
+                Condition::Equal,
+                Location::Imm64(0),
+            )?,
+            Operator::RefFunc { function_index } => {
+                Self::emit_intrinsic_call(
+                    a,
+                    &mut self.machine,
+                    &mut self.value_stack,
+                    vm::Intrinsics::offset_ref_func(),
+                    &[function_index],
+                    0,
+                )?;
+                Self::emit_push_intrinsic_result(
+                    a,
+                    &mut self.machine,
+                    &mut self.value_stack,
+                    WpType::AnyFunc,
+                );
+            }
+            Operator::TableGet { table } => {
+                Self::emit_intrinsic_call(
+                    a,
+                    &mut self.machine,
+                    &mut self.value_stack,
+                    vm::Intrinsics::offset_table_get(),
+                    &[table],
+                    1,
+                )?;
+                Self::emit_push_intrinsic_result(
+                    a,
+                    &mut self.machine,
+                    &mut self.value_stack,
+                    match get_table_element_type(module_info, table) {
+                        ElementType::Anyfunc => WpType::AnyFunc,
+                        ElementType::ExternRef => WpType::AnyRef,
+                    },
+                );
+            }
+            Operator::TableSet { table } => {
+                Self::emit_intrinsic_call(
+                    a,
+                    &mut self.machine,
+                    &mut self.value_stack,
+                    vm::Intrinsics::offset_table_set(),
+                    &[table],
+                    2,
+                )?;
+            }
+            Operator::TableGrow { table } => {
+                Self::emit_intrinsic_call(
+                    a,
+                    &mut self.machine,
+                    &mut self.value_stack,
+                    vm::Intrinsics::offset_table_grow(),
+                    &[table],
+                    2,
+                )?;
+                Self::emit_push_intrinsic_result(
+                    a,
+                    &mut self.machine,
+                    &mut self.value_stack,
+                    WpType::I32,
+                );
+            }
+            Operator::TableSize { table } => {
+                Self::emit_intrinsic_call(
+                    a,
+                    &mut self.machine,
+                    &mut self.value_stack,
+                    vm::Intrinsics::offset_table_size(),
+                    &[table],
+                    0,
+                )?;
+                Self::emit_push_intrinsic_result(
+                    a,
+                    &mut self.machine,
+                    &mut self.value_stack,
+                    WpType::I32,
+                );
+            }
+            Operator::TableFill { table } => {
+                Self::emit_intrinsic_call(
+                    a,
+                    &mut self.machine,
+                    &mut self.value_stack,
+                    vm::Intrinsics::offset_table_fill(),
+                    &[table],
+                    3,
+                )?;
+            }
             Operator::I32Load { ref memarg } => {
                 let target =
                     get_location_released(a, &mut self.machine, self.value_stack.pop().unwrap());
@@ -7541,22 +7997,8 @@ impl FunctionCodeGenerator<CodegenError> for X64FunctionCode {
             }
             Operator::Return => {
                 let frame = &self.control_stack[0];
-                if frame.returns.len() > 0 {
-                    if frame.returns.len() != 1 {
-                        return Err(CodegenError {
-                            message: format!("Return: incorrect frame.returns"),
-                        });
-                    }
-                    let loc = *self.value_stack.last().unwrap();
-                    Self::emit_relaxed_binop(
-                        a,
-                        &mut self.machine,
-                        Assembler::emit_mov,
-                        Size::S64,
-                        loc,
-                        Location::GPR(GPR::RAX),
-                    );
-                }
+                let values = &self.value_stack[self.value_stack.len() - frame.returns.len()..];
+                Self::emit_store_transferred_values(a, &mut self.machine, values);
                 let released = &self.value_stack[frame.value_stack_depth..];
                 self.machine.release_locations_keep_state(a, released);
                 a.emit_jmp(Condition::None, frame.label);
@@ -7565,15 +8007,9 @@ impl FunctionCodeGenerator<CodegenError> for X64FunctionCode {
             Operator::Br { relative_depth } => {
                 let frame =
                     &self.control_stack[self.control_stack.len() - 1 - (relative_depth as usize)];
-                if !frame.loop_like && frame.returns.len() > 0 {
-                    if frame.returns.len() != 1 {
-                        return Err(CodegenError {
-                            message: format!("Br: incorrect frame.returns"),
-                        });
-                    }
-                    let loc = *self.value_stack.last().unwrap();
-                    a.emit_mov(Size::S64, loc, Location::GPR(GPR::RAX));
-                }
+                let values =
+                    &self.value_stack[self.value_stack.len() - frame.branch_types().len()..];
+                Self::emit_store_transferred_values(a, &mut self.machine, values);
                 let released = &self.value_stack[frame.value_stack_depth..];
                 self.machine.release_locations_keep_state(a, released);
                 a.emit_jmp(Condition::None, frame.label);
@@ -7595,15 +8031,9 @@ impl FunctionCodeGenerator<CodegenError> for X64FunctionCode {
 
                 let frame =
                     &self.control_stack[self.control_stack.len() - 1 - (relative_depth as usize)];
-                if !frame.loop_like && frame.returns.len() > 0 {
-                    if frame.returns.len() != 1 {
-                        return Err(CodegenError {
-                            message: format!("BrIf: incorrect frame.returns"),
-                        });
-                    }
-                    let loc = *self.value_stack.last().unwrap();
-                    a.emit_mov(Size::S64, loc, Location::GPR(GPR::RAX));
-                }
+                let values =
+                    &self.value_stack[self.value_stack.len() - frame.branch_types().len()..];
+                Self::emit_store_transferred_values(a, &mut self.machine, values);
                 let released = &self.value_stack[frame.value_stack_depth..];
                 self.machine.release_locations_keep_state(a, released);
                 a.emit_jmp(Condition::None, frame.label);
@@ -7643,18 +8073,9 @@ impl FunctionCodeGenerator<CodegenError> for X64FunctionCode {
                     table.push(label);
                     let frame =
                         &self.control_stack[self.control_stack.len() - 1 - (*target as usize)];
-                    if !frame.loop_like && frame.returns.len() > 0 {
-                        if frame.returns.len() != 1 {
-                            return Err(CodegenError {
-                                message: format!(
-                                    "BrTable: incorrect frame.returns for {:?}",
-                                    target
-                                ),
-                            });
-                        }
-                        let loc = *self.value_stack.last().unwrap();
-                        a.emit_mov(Size::S64, loc, Location::GPR(GPR::RAX));
-                    }
+                    let values =
+                        &self.value_stack[self.value_stack.len() - frame.branch_types().len()..];
+                    Self::emit_store_transferred_values(a, &mut self.machine, values);
                     let released = &self.value_stack[frame.value_stack_depth..];
                     self.machine.release_locations_keep_state(a, released);
                     a.emit_jmp(Condition::None, frame.label);
@@ -7664,15 +8085,9 @@ impl FunctionCodeGenerator<CodegenError> for X64FunctionCode {
                 {
                     let frame = &self.control_stack
                         [self.control_stack.len() - 1 - (default_target as usize)];
-                    if !frame.loop_like && frame.returns.len() > 0 {
-                        if frame.returns.len() != 1 {
-                            return Err(CodegenError {
-                                message: format!("BrTable: incorrect frame.returns"),
-                            });
-                        }
-                        let loc = *self.value_stack.last().unwrap();
-                        a.emit_mov(Size::S64, loc, Location::GPR(GPR::RAX));
-                    }
+                    let values =
+                        &self.value_stack[self.value_stack.len() - frame.branch_types().len()..];
+                    Self::emit_store_transferred_values(a, &mut self.machine, values);
                     let released = &self.value_stack[frame.value_stack_depth..];
                     self.machine.release_locations_keep_state(a, released);
                     a.emit_jmp(Condition::None, frame.label);
@@ -7690,20 +8105,34 @@ impl FunctionCodeGenerator<CodegenError> for X64FunctionCode {
             Operator::End => {
                 let frame = self.control_stack.pop().unwrap();
 
-                if !was_unreachable && frame.returns.len() > 0 {
-                    let loc = *self.value_stack.last().unwrap();
-                    Self::emit_relaxed_binop(
-                        a,
-                        &mut self.machine,
-                        Assembler::emit_mov,
-                        Size::S64,
-                        loc,
-                        Location::GPR(GPR::RAX),
-                    );
+                if !was_unreachable {
+                    let values = &self.value_stack[self.value_stack.len() - frame.returns.len()..];
+                    Self::emit_store_transferred_values(a, &mut self.machine, values);
                 }
 
                 if self.control_stack.len() == 0 {
                     a.emit_label(frame.label);
+                    if let Some(results_pointer) = self.machine.get_results_pointer_location() {
+                        // Copy the results to the buffer given by the caller, and return its
+                        // address.
+                        let area = self.machine.acquire_temp_gpr().unwrap();
+                        let results = self.machine.acquire_temp_gpr().unwrap();
+                        Self::emit_load_multi_value_area(a, area);
+                        a.emit_mov(Size::S64, results_pointer, Location::GPR(results));
+                        for i in 0..frame.returns.len() {
+                            Self::emit_relaxed_binop(
+                                a,
+                                &mut self.machine,
+                                Assembler::emit_mov,
+                                Size::S64,
+                                Location::Memory(area, (i * 8) as i32),
+                                Location::Memory(results, (i * 8) as i32),
+                            );
+                        }
+                        a.emit_mov(Size::S64, Location::GPR(results), Location::GPR(GPR::RAX));
+                        self.machine.release_temp_gpr(results);
+                        self.machine.release_temp_gpr(area);
+                    }
                     self.machine.finalize_locals(a, &self.locals);
                     a.emit_mov(Size::S64, Location::GPR(GPR::RBP), Location::GPR(GPR::RSP));
                     a.emit_pop(Size::S64, Location::GPR(GPR::RBP));
@@ -7713,31 +8142,41 @@ impl FunctionCodeGenerator<CodegenError> for X64FunctionCode {
                     self.machine.release_locations(a, released);
                     self.value_stack.truncate(frame.value_stack_depth);
 
-                    if !frame.loop_like {
-                        a.emit_label(frame.label);
-                    }
-
-                    if let IfElseState::If(label) = frame.if_else {
-                        a.emit_label(label);
+                    match frame.if_else {
+                        IfElseState::If(label) if frame.params.len() > 0 => {
+                            // Without an else branch, the params are the results.
+                            a.emit_jmp(Condition::None, frame.label);
+                            a.emit_label(label);
+                            let params = &self.value_stack
+                                [frame.value_stack_depth - frame.params.len()..];
+                            Self::emit_store_transferred_values(a, &mut self.machine, params);
+                            a.emit_label(frame.label);
+                        }
+                        IfElseState::If(label) => {
+                            a.emit_label(frame.label);
+                            a.emit_label(label);
+                        }
+                        _ if !frame.loop_like => a.emit_label(frame.label),
+                        _ => {}
                     }
 
-                    if frame.returns.len() > 0 {
-                        if frame.returns.len() != 1 {
-                            return Err(CodegenError {
-                                message: format!("End: incorrect frame.returns"),
-                            });
+                    // The params of an `if` frame stay below it until its end.
+                    match frame.if_else {
+                        IfElseState::If(_) | IfElseState::Else => {
+                            let depth = frame.value_stack_depth - frame.params.len();
+                            let released = &self.value_stack[depth..];
+                            self.machine.release_locations(a, released);
+                            self.value_stack.truncate(depth);
                         }
-                        let loc = self.machine.acquire_locations(
-                            a,
-                            &[(
-                                frame.returns[0],
-                                MachineValue::WasmStack(self.value_stack.len()),
-                            )],
-                            false,
-                        )[0];
-                        a.emit_mov(Size::S64, Location::GPR(GPR::RAX), loc);
-                        self.value_stack.push(loc);
+                        IfElseState::None => {}
                     }
+
+                    Self::emit_load_transferred_values(
+                        a,
+                        &mut self.machine,
+                        &mut self.value_stack,
+                        &frame.returns,
+                    );
                 }
             }
             Operator::AtomicFence { flags: _ } => {
@@ -9915,6 +10354,18 @@ impl FunctionCodeGenerator<CodegenError> for X64FunctionCode {
             }
         }
 
+        if self.config.nan_canonicalization {
+            if let Some(sz) = get_nan_canonicalization_size(op) {
+                if !a.arch_supports_canonicalize_nan() {
+                    return Err(CodegenError {
+                        message: format!("NaN canonicalization is not supported on this target"),
+                    });
+                }
+                let loc = *self.value_stack.last().unwrap();
+                Self::emit_canonicalize_nan(a, &mut self.machine, sz, loc);
+            }
+        }
+
         Ok(())
     }
 }
@@ -9926,6 +10377,66 @@ fn type_to_wp_type(ty: Type) -> WpType {
         Type::F32 => WpType::F32,
         Type::F64 => WpType::F64,
         Type::V128 => WpType::V128,
+        Type::ExternRef => WpType::AnyRef,
+        Type::FuncRef => WpType::AnyFunc,
+    }
+}
+
+/// Returns the size of the float produced by `op` if it may be a NaN whose bits depend on the CPU.
+///
+/// The bitwise operators (`abs`, `neg`, `copysign`, loads and reinterpretations) keep the bits of
+/// their operand, and `min`/`max` already produce the canonical NaN.
+fn get_nan_canonicalization_size(op: &Operator) -> Option<Size> {
+    match *op {
+        Operator::F32Add
+        | Operator::F32Sub
+        | Operator::F32Mul
+        | Operator::F32Div
+        | Operator::F32Sqrt
+        | Operator::F32Ceil
+        | Operator::F32Floor
+        | Operator::F32Trunc
+        | Operator::F32Nearest
+        | Operator::F32DemoteF64 => Some(Size::S32),
+        Operator::F64Add
+        | Operator::F64Sub
+        | Operator::F64Mul
+        | Operator::F64Div
+        | Operator::F64Sqrt
+        | Operator::F64Ceil
+        | Operator::F64Floor
+        | Operator::F64Trunc
+        | Operator::F64Nearest
+        | Operator::F64PromoteF32 => Some(Size::S64),
+        _ => None,
+    }
+}
+
+/// Returns the element type of the table `table_index`, local or imported.
+fn get_table_element_type(module_info: &ModuleInfo, table_index: u32) -> ElementType {
+    match TableIndex::new(table_index as usize).local_or_import(module_info) {
+        LocalOrImport::Local(local_table_index) => module_info.tables[local_table_index].element,
+        LocalOrImport::Import(import_table_index) => {
+            module_info.imported_tables[import_table_index].1.element
+        }
+    }
+}
+
+/// Returns the types of the params and of the results of a block.
+fn get_block_type(
+    signatures: &Map<SigIndex, FuncSig>,
+    ty: WpTypeOrFuncType,
+) -> (SmallVec<[WpType; 1]>, SmallVec<[WpType; 1]>) {
+    match ty {
+        WpTypeOrFuncType::Type(WpType::EmptyBlockType) => (smallvec![], smallvec![]),
+        WpTypeOrFuncType::Type(inner_ty) => (smallvec![], smallvec![inner_ty]),
+        WpTypeOrFuncType::FuncType(sig_index) => {
+            let sig = signatures.get(SigIndex::new(sig_index as usize)).unwrap();
+            (
+                sig.params().iter().cloned().map(type_to_wp_type).collect(),
+                sig.returns().iter().cloned().map(type_to_wp_type).collect(),
+            )
+        }
     }
 }
 
@@ -13,6 +13,7 @@ pub struct Machine {
     used_xmms: HashSet<XMM>,
     stack_offset: MachineStackOffset,
     save_area_offset: Option<MachineStackOffset>,
+    results_pointer_location: Option<Location>,
     pub state: MachineState,
     pub(crate) track_state: bool,
 }
@@ -24,6 +25,7 @@ impl Machine {
             used_xmms: HashSet::new(),
             stack_offset: MachineStackOffset(0),
             save_area_offset: None,
+            results_pointer_location: None,
             state: x64::new_machine_state(),
             track_state: true,
         }
@@ -45,6 +47,12 @@ impl Machine {
         GPR::R15
     }
 
+    /// Returns the location where the address of the buffer for the results of the current
+    /// function is saved, if it has several results.
+    pub fn get_results_pointer_location(&self) -> Option<Location> {
+        self.results_pointer_location
+    }
+
     /// Picks an unused general purpose register for local/stack/argument use.
     ///
     /// This method does not mark the register as used.
@@ -152,7 +160,9 @@ impl Machine {
         for (ty, mv) in tys {
             let loc = match *ty {
                 WpType::F32 | WpType::F64 => self.pick_xmm().map(Location::XMM),
-                WpType::I32 | WpType::I64 => self.pick_gpr().map(Location::GPR),
+                WpType::I32 | WpType::I64 | WpType::AnyRef | WpType::AnyFunc | WpType::NullRef => {
+                    self.pick_gpr().map(Location::GPR)
+                }
                 _ => unreachable!(),
             };
 
@@ -328,6 +338,7 @@ impl Machine {
         a: &mut E,
         n: usize,
         n_params: usize,
+        results_pointer: bool,
     ) -> Vec<Location> {
         // Use callee-saved registers for locals.
         fn get_local_location(idx: usize) -> Location {
@@ -343,9 +354,13 @@ impl Machine {
         let mut locations: Vec<Location> = vec![];
         let mut allocated: usize = 0;
 
+        // A function with several results receives the address of the buffer for its results
+        // right after vmctx, so its parameters are shifted by one.
+        let first_param = if results_pointer { 2 } else { 1 };
+
         // Determine locations for parameters.
         for i in 0..n_params {
-            let loc = Self::get_param_location(i + 1);
+            let loc = Self::get_param_location(i + first_param);
             locations.push(match loc {
                 Location::GPR(_) => {
                     let old_idx = allocated;
@@ -389,6 +404,17 @@ impl Machine {
             })
             .count();
 
+        // Reserve one more slot for the results pointer.
+        let num_mem_slots = if results_pointer {
+            self.state.stack_values.push(MachineValue::Undefined);
+            let loc = Location::Memory(GPR::RBP, -(((num_mem_slots + 1) * 8) as i32));
+            self.results_pointer_location = Some(loc);
+            num_mem_slots + 1
+        } else {
+            self.results_pointer_location = None;
+            num_mem_slots
+        };
+
         // Move RSP down to reserve space for machine stack slots.
         if num_mem_slots > 0 {
             a.emit_sub(
@@ -399,6 +425,11 @@ impl Machine {
             self.stack_offset.0 += num_mem_slots * 8;
         }
 
+        // Save the results pointer before its register is reused.
+        if let Some(loc) = self.results_pointer_location {
+            a.emit_mov(Size::S64, Self::get_param_location(1), loc);
+        }
+
         // Save callee-saved registers.
         for loc in locations.iter() {
             if let Location::GPR(x) = *loc {
@@ -422,7 +453,7 @@ impl Machine {
 
         // Load in-register parameters into the allocated locations.
         for i in 0..n_params {
-            let loc = Self::get_param_location(i + 1);
+            let loc = Self::get_param_location(i + first_param);
             match loc {
                 Location::GPR(_) => {
                     a.emit_mov(Size::S64, loc, locations[i]);
@@ -325,6 +325,9 @@ mod tests {
                             features: Features {
                                 simd: true,
                                 threads: true,
+                                bulk_memory: true,
+                                multi_value: true,
+                                reference_types: true,
                             },
                             ..Default::default()
                         };
@@ -763,6 +766,9 @@ mod tests {
                             features: Features {
                                 simd: true,
                                 threads: true,
+                                bulk_memory: true,
+                                multi_value: true,
+                                reference_types: true,
                             },
                             ..Default::default()
                         };
@@ -815,6 +821,9 @@ mod tests {
                             features: Features {
                                 simd: true,
                                 threads: true,
+                                bulk_memory: true,
+                                multi_value: true,
+                                reference_types: true,
                             },
                             ..Default::default()
                         };
@@ -866,6 +875,9 @@ mod tests {
                         features: Features {
                             simd: true,
                             threads: true,
+                            bulk_memory: true,
+                            multi_value: true,
+                            reference_types: true,
                         },
                         ..Default::default()
                     };
@@ -961,6 +973,9 @@ mod tests {
                             features: Features {
                                 simd: true,
                                 threads: true,
+                                bulk_memory: true,
+                                multi_value: true,
+                                reference_types: true,
                             },
                             ..Default::default()
                         };
@@ -1144,6 +1159,8 @@ mod tests {
             wasmer_runtime::types::Value::F32(x) => format!("{:#x}", x.to_bits()),
             wasmer_runtime::types::Value::F64(x) => format!("{:#x}", x.to_bits()),
             wasmer_runtime::types::Value::V128(x) => format!("{:#x}", x),
+            wasmer_runtime::types::Value::ExternRef(x) => format!("{:#x}", x),
+            wasmer_runtime::types::Value::FuncRef(x) => format!("{:#x}", x),
         }
     }
 
@@ -1154,6 +1171,8 @@ mod tests {
         F32(u32),
         F64(u64),
         V128(u128),
+        ExternRef(u64),
+        FuncRef(u64),
     }
 
     fn convert_wasmer_value(other: wasmer_runtime::types::Value) -> SpectestValue {
@@ -1163,6 +1182,8 @@ mod tests {
             wasmer_runtime::types::Value::F32(v) => SpectestValue::F32(v.to_bits()),
             wasmer_runtime::types::Value::F64(v) => SpectestValue::F64(v.to_bits()),
             wasmer_runtime::types::Value::V128(v) => SpectestValue::V128(v),
+            wasmer_runtime::types::Value::ExternRef(v) => SpectestValue::ExternRef(v),
+            wasmer_runtime::types::Value::FuncRef(v) => SpectestValue::FuncRef(v),
         }
     }
 
@@ -1193,6 +1214,8 @@ mod tests {
             SpectestValue::F32(v) => format!("{:#x}", v),
             SpectestValue::F64(v) => format!("{:#x}", v),
             SpectestValue::V128(v) => format!("{:#x}", v),
+            SpectestValue::ExternRef(v) => format!("{:#x}", v),
+            SpectestValue::FuncRef(v) => format!("{:#x}", v),
         }
     }
 
@@ -2,7 +2,7 @@
 use wasmer_runtime::{compile, Ctx, Func};
 use wasmer_wasi::{state::*, *};
 
-use std::ffi::c_void;
+use std::{cell::Cell, ffi::c_void};
 
 #[cfg(not(feature = "singlepass"))]
 #[test]
@@ -57,6 +57,185 @@ fn serializing_works() {
     assert_eq!(result, true as i32);
 }
 
+#[test]
+fn sock_recv_scatters_into_iovecs() {
+    const IOVECS: usize = 0x2_0000;
+
+    let wasm_binary = wabt::wat2wasm(
+        r#"
+        (module
+          (import "wasi_unstable" "sock_recv"
+            (func $sock_recv (param i32 i32 i32 i32 i32 i32) (result i32)))
+          (memory (export "memory") 16)
+          ;; the number of received bytes is stored at 0, the flags at 4
+          (func (export "recv") (param i32 i32 i32 i32) (result i32)
+            (call $sock_recv
+              (get_local 0) (get_local 1) (get_local 2) (get_local 3)
+              (i32.const 0) (i32.const 4))))
+        "#,
+    )
+    .unwrap();
+    let module = compile(&wasm_binary).unwrap();
+
+    let data = (0..100_000).map(|i| (i % 251) as u8).collect::<Vec<u8>>();
+    let state = WasiState::new("test_prog")
+        .preopen_socket(Box::new(LoopbackSocket::with_data(b"hello world")))
+        .preopen_socket(Box::new(LoopbackSocket::with_data(&data)))
+        .build()
+        .unwrap();
+    let import_object = generate_import_object_from_state(state, WasiVersion::Snapshot0);
+    let instance = module.instantiate(&import_object).unwrap();
+    let recv: Func<(i32, i32, i32, i32), i32> = instance.func("recv").unwrap();
+
+    let memory = instance.context().memory(0);
+    let write_iovecs = |iovecs: &[(u32, u32)]| {
+        let view = memory.view::<u32>();
+        for (i, &(buf, buf_len)) in iovecs.iter().enumerate() {
+            view[IOVECS / 4 + 2 * i].set(buf);
+            view[IOVECS / 4 + 2 * i + 1].set(buf_len);
+        }
+    };
+    let bytes_received = || memory.view::<u32>()[0].get();
+    let read = |start: usize, len: usize| -> Vec<u8> {
+        memory.view::<u8>()[start..start + len]
+            .iter()
+            .map(Cell::get)
+            .collect()
+    };
+    let fd = VIRTUAL_ROOT_FD as i32 + 1;
+
+    write_iovecs(&[(0x100, 3), (0x200, 5)]);
+    assert_eq!(recv.call(fd, IOVECS as i32, 2, 0), Ok(0));
+    assert_eq!(bytes_received(), 8);
+    assert_eq!(read(0x100, 3), b"hel");
+    assert_eq!(read(0x200, 5), b"lo wo");
+
+    // overlapping iovecs adding up to more than 4 GiB
+    write_iovecs(&vec![(0x100, 0x1_0000); 70_000]);
+    let wait_all = types::__WASI_SOCK_RECV_WAITALL as i32;
+    assert_eq!(recv.call(fd + 1, IOVECS as i32, 70_000, wait_all), Ok(0));
+    assert_eq!(bytes_received(), 100_000);
+    // the data after the first iovec is written again from its start
+    assert_eq!(read(0x100, 34_464), &data[0x1_0000..]);
+    assert_eq!(
+        read(0x100 + 34_464, 0x1_0000 - 34_464),
+        &data[34_464..0x1_0000]
+    );
+}
+
+#[test]
+fn path_unlink_file_keeps_directories() {
+    let wasm_binary = wabt::wat2wasm(
+        r#"
+        (module
+          (import "wasi_unstable" "path_unlink_file"
+            (func $path_unlink_file (param i32 i32 i32) (result i32)))
+          (memory (export "memory") 1)
+          (func (export "unlink") (param i32 i32 i32) (result i32)
+            (call $path_unlink_file (get_local 0) (get_local 1) (get_local 2))))
+        "#,
+    )
+    .unwrap();
+    let module = compile(&wasm_binary).unwrap();
+
+    let mut mem_fs = MemFs::new();
+    mem_fs.create_file("dir/file.txt", "hello").unwrap();
+    let state = WasiState::new("test_prog")
+        .mount_mem_fs("data", mem_fs)
+        .unwrap()
+        .build()
+        .unwrap();
+    let import_object = generate_import_object_from_state(state, WasiVersion::Snapshot0);
+    let instance = module.instantiate(&import_object).unwrap();
+    let unlink: Func<(i32, i32, i32), i32> = instance.func("unlink").unwrap();
+
+    let memory = instance.context().memory(0);
+    let fd = VIRTUAL_ROOT_FD + 1;
+    let call_unlink = |path: &str| {
+        for (cell, byte) in memory.view::<u8>()[0x100..].iter().zip(path.bytes()) {
+            cell.set(byte);
+        }
+        unlink.call(fd as i32, 0x100, path.len() as i32).unwrap()
+    };
+    let snapshot = || {
+        get_wasi_state(instance.context())
+            .fs
+            .mem_fs_snapshot(fd)
+            .unwrap()
+    };
+
+    assert_eq!(call_unlink("dir"), types::__WASI_EISDIR as i32);
+    assert_eq!(snapshot().read_file("dir/file.txt"), Some(&b"hello"[..]));
+
+    assert_eq!(call_unlink("dir/file.txt"), types::__WASI_ESUCCESS as i32);
+    assert_eq!(snapshot().read_file("dir/file.txt"), None);
+    assert!(snapshot().get("dir").is_some());
+}
+
+#[test]
+fn path_rename_rejects_moving_a_directory_inside_itself() {
+    let wasm_binary = wabt::wat2wasm(
+        r#"
+        (module
+          (import "wasi_unstable" "path_rename"
+            (func $path_rename (param i32 i32 i32 i32 i32 i32) (result i32)))
+          (memory (export "memory") 1)
+          (func (export "rename") (param i32 i32 i32 i32 i32 i32) (result i32)
+            (call $path_rename
+              (get_local 0) (get_local 1) (get_local 2)
+              (get_local 3) (get_local 4) (get_local 5))))
+        "#,
+    )
+    .unwrap();
+    let module = compile(&wasm_binary).unwrap();
+
+    let mut mem_fs = MemFs::new();
+    mem_fs.create_file("a/file.txt", "hello").unwrap();
+    let state = WasiState::new("test_prog")
+        .mount_mem_fs("data", mem_fs)
+        .unwrap()
+        .build()
+        .unwrap();
+    let import_object = generate_import_object_from_state(state, WasiVersion::Snapshot0);
+    let instance = module.instantiate(&import_object).unwrap();
+    let rename: Func<(i32, i32, i32, i32, i32, i32), i32> = instance.func("rename").unwrap();
+
+    let memory = instance.context().memory(0);
+    let fd = VIRTUAL_ROOT_FD + 1;
+    let call_rename = |source: &str, target: &str| {
+        let view = memory.view::<u8>();
+        for (cell, byte) in view[0x100..].iter().zip(source.bytes()) {
+            cell.set(byte);
+        }
+        for (cell, byte) in view[0x200..].iter().zip(target.bytes()) {
+            cell.set(byte);
+        }
+        rename
+            .call(
+                fd as i32,
+                0x100,
+                source.len() as i32,
+                fd as i32,
+                0x200,
+                target.len() as i32,
+            )
+            .unwrap()
+    };
+    let snapshot = || {
+        get_wasi_state(instance.context())
+            .fs
+            .mem_fs_snapshot(fd)
+            .unwrap()
+    };
+
+    assert_eq!(call_rename("a", "a/b"), types::__WASI_EINVAL as i32);
+    assert_eq!(snapshot().read_file("a/file.txt"), Some(&b"hello"[..]));
+
+    assert_eq!(call_rename("a", "b"), types::__WASI_ESUCCESS as i32);
+    assert_eq!(snapshot().read_file("b/file.txt"), Some(&b"hello"[..]));
+    assert!(snapshot().get("a").is_none());
+}
+
 #[allow(clippy::mut_from_ref)]
 pub(crate) fn get_wasi_state(ctx: &Ctx) -> &mut WasiState {
     unsafe { state::get_wasi_state(&mut *(ctx as *const Ctx as *mut Ctx)) }
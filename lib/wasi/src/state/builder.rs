@@ -1,6 +1,6 @@
 //! Builder system for configuring a [`WasiState`] and creating it.
 
-use crate::state::{WasiFile, WasiFs, WasiFsError, WasiState};
+use crate::state::{MemFs, WasiFile, WasiFs, WasiFsError, WasiSocket, WasiState};
 use crate::syscalls::types::{__WASI_STDERR_FILENO, __WASI_STDIN_FILENO, __WASI_STDOUT_FILENO};
 use std::path::{Path, PathBuf};
 
@@ -35,10 +35,12 @@ pub struct WasiStateBuilder {
     args: Vec<Vec<u8>>,
     envs: Vec<Vec<u8>>,
     preopens: Vec<PreopenedDir>,
+    mem_fs_mounts: Vec<(String, MemFs)>,
     setup_fs_fn: Option<Box<dyn Fn(&mut WasiFs) -> Result<(), String> + Send>>,
     stdout_override: Option<Box<dyn WasiFile>>,
     stderr_override: Option<Box<dyn WasiFile>>,
     stdin_override: Option<Box<dyn WasiFile>>,
+    preopened_sockets: Vec<Box<dyn WasiSocket>>,
 }
 
 impl std::fmt::Debug for WasiStateBuilder {
@@ -47,10 +49,12 @@ impl std::fmt::Debug for WasiStateBuilder {
             .field("args", &self.args)
             .field("envs", &self.envs)
             .field("preopens", &self.preopens)
+            .field("mem_fs_mounts", &self.mem_fs_mounts)
             .field("setup_fs_fn exists", &self.setup_fs_fn.is_some())
             .field("stdout_override exists", &self.stdout_override.is_some())
             .field("stderr_override exists", &self.stderr_override.is_some())
             .field("stdin_override exists", &self.stdin_override.is_some())
+            .field("preopened_sockets", &self.preopened_sockets)
             .finish()
     }
 }
@@ -268,6 +272,26 @@ impl WasiStateBuilder {
         Ok(self)
     }
 
+    /// Preopen a directory that only exists in memory with the contents of
+    /// `mem_fs`, the WASI module sees it as `alias`.
+    ///
+    /// Changes the WASI module makes to it never reach the host file system,
+    /// use [`WasiFs::mem_fs_snapshot`] on its fd to get them back.  In-memory
+    /// directories get fds after the preopened host directories, in the order
+    /// in which they are mounted.
+    ///
+    /// [`WasiFs::mem_fs_snapshot`]: struct.WasiFs.html#method.mem_fs_snapshot
+    pub fn mount_mem_fs(
+        &mut self,
+        alias: &str,
+        mem_fs: MemFs,
+    ) -> Result<&mut Self, WasiStateCreationError> {
+        validate_mapped_dir_alias(alias)?;
+        self.mem_fs_mounts.push((alias.to_string(), mem_fs));
+
+        Ok(self)
+    }
+
     /// Overwrite the default WASI `stdout`, if you want to hold on to the
     /// original `stdout` use [`WasiFs::swap_file`] after building.
     pub fn stdout(&mut self, new_file: Box<dyn WasiFile>) -> &mut Self {
@@ -292,6 +316,18 @@ impl WasiStateBuilder {
         self
     }
 
+    /// Preopen a stream socket.
+    /// The socket gets the first free fd after the preopened directories, in
+    /// the order in which sockets are preopened.  Use [`LoopbackSocket`] to
+    /// give the WASI module a socket without network access.
+    ///
+    /// [`LoopbackSocket`]: struct.LoopbackSocket.html
+    pub fn preopen_socket(&mut self, socket: Box<dyn WasiSocket>) -> &mut Self {
+        self.preopened_sockets.push(socket);
+
+        self
+    }
+
     /// Setup the WASI filesystem before running
     // TODO: improve ergonomics on this function
     pub fn setup_fs(
@@ -358,6 +394,11 @@ impl WasiStateBuilder {
         #[allow(deprecated)]
         let mut wasi_fs = WasiFs::new_with_preopen(&self.preopens)
             .map_err(WasiStateCreationError::WasiFsCreationError)?;
+        for (alias, mem_fs) in self.mem_fs_mounts.iter() {
+            wasi_fs
+                .mount_mem_fs(alias, mem_fs)
+                .map_err(WasiStateCreationError::WasiFsCreationError)?;
+        }
         // set up the file system, overriding base files and calling the setup function
         if let Some(stdin_override) = self.stdin_override.take() {
             wasi_fs
@@ -374,6 +415,11 @@ impl WasiStateBuilder {
                 .swap_file(__WASI_STDERR_FILENO, stderr_override)
                 .map_err(WasiStateCreationError::WasiFsError)?;
         }
+        for socket in self.preopened_sockets.drain(..) {
+            wasi_fs
+                .open_socket(socket)
+                .map_err(|e| WasiStateCreationError::WasiFsError(WasiFsError::from_wasi_err(e)))?;
+        }
         if let Some(f) = &self.setup_fs_fn {
             f(&mut wasi_fs).map_err(WasiStateCreationError::WasiFsSetupError)?;
         }
@@ -508,6 +554,61 @@ mod test {
         }
     }
 
+    #[test]
+    fn preopened_sockets() {
+        use crate::state::{Kind, LoopbackSocket, VIRTUAL_ROOT_FD};
+        use crate::syscalls::types::__WASI_FILETYPE_SOCKET_STREAM;
+
+        let state = create_wasi_state("test_prog")
+            .preopen_socket(Box::new(LoopbackSocket::new()))
+            .preopen_socket(Box::new(LoopbackSocket::with_data(b"hello")))
+            .build()
+            .unwrap();
+
+        let first_fd = VIRTUAL_ROOT_FD + 1;
+        for fd in &[first_fd, first_fd + 1] {
+            let fd_entry = state.fs.get_fd(*fd).unwrap();
+            assert!(match state.fs.inodes[fd_entry.inode].kind {
+                Kind::Socket { .. } => true,
+                _ => false,
+            });
+            assert_eq!(
+                state.fs.fdstat(*fd).unwrap().fs_filetype,
+                __WASI_FILETYPE_SOCKET_STREAM
+            );
+            // sockets must not be mistaken for preopened directories
+            assert!(state.fs.prestat_fd(*fd).is_err());
+        }
+    }
+
+    #[test]
+    fn mem_fs_mounts() {
+        use crate::state::VIRTUAL_ROOT_FD;
+
+        let mut mem_fs = MemFs::new();
+        mem_fs.create_file("dir/file.txt", "hello").unwrap();
+
+        let state = create_wasi_state("test_prog")
+            .mount_mem_fs("data", mem_fs.clone())
+            .unwrap()
+            .build()
+            .unwrap();
+        let fd = VIRTUAL_ROOT_FD + 1;
+        assert!(state.fs.prestat_fd(fd).is_ok());
+        assert_eq!(state.fs.mem_fs_snapshot(fd), Ok(mem_fs));
+
+        let output = create_wasi_state("test_prog")
+            .mount_mem_fs("data", MemFs::new())
+            .unwrap()
+            .mount_mem_fs("data", MemFs::new())
+            .unwrap()
+            .build();
+        match output {
+            Err(WasiStateCreationError::WasiFsCreationError(_)) => assert!(true),
+            _ => assert!(false),
+        }
+    }
+
     #[test]
     fn nul_character_in_args() {
         let output = create_wasi_state("test_prog").arg("--h\0elp").build();
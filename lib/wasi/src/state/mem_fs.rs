@@ -0,0 +1,216 @@
+//! A file system that only exists in memory, for running WASI programs
+//! without giving them access to the host file system.
+
+use crate::state::WasiFsError;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::{Component, Path},
+};
+
+/// Timestamps of a file or directory in a [`MemFs`], in nanoseconds since the
+/// Unix epoch
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemFsMetadata {
+    pub accessed: u64,
+    pub modified: u64,
+    pub created: u64,
+}
+
+/// A file or directory in a [`MemFs`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MemFsEntry {
+    File {
+        contents: Vec<u8>,
+        metadata: MemFsMetadata,
+    },
+    Dir {
+        entries: BTreeMap<String, MemFsEntry>,
+        metadata: MemFsMetadata,
+    },
+}
+
+/// A directory tree that only exists in memory.
+///
+/// Mount it as a preopened directory with [`WasiStateBuilder::mount_mem_fs`],
+/// WASI programs can then use it like any other directory without anything
+/// ever touching the host disk.  Take a snapshot of the changes they made with
+/// [`WasiFs::mem_fs_snapshot`].
+///
+/// Usage:
+/// ```no_run
+/// # use wasmer_wasi::state::{MemFs, WasiFsError};
+/// # fn main() -> Result<(), WasiFsError> {
+/// let mut mem_fs = MemFs::new();
+/// mem_fs
+///    .create_dir("logs")?
+///    .create_file("config/settings.toml", "verbose = true")?;
+///
+/// assert_eq!(mem_fs.read_file("config/settings.toml"), Some(&b"verbose = true"[..]));
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`WasiStateBuilder::mount_mem_fs`]: struct.WasiStateBuilder.html#method.mount_mem_fs
+/// [`WasiFs::mem_fs_snapshot`]: struct.WasiFs.html#method.mem_fs_snapshot
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemFs {
+    pub(crate) entries: BTreeMap<String, MemFsEntry>,
+    pub(crate) metadata: MemFsMetadata,
+}
+
+impl MemFs {
+    /// Create an empty file system
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create the directory at `path` and any missing parent directories
+    pub fn create_dir<P: AsRef<Path>>(&mut self, path: P) -> Result<&mut Self, WasiFsError> {
+        let names = path_to_names(path.as_ref())?;
+        self.dir_entries_mut(&names)?;
+
+        Ok(self)
+    }
+
+    /// Create a file at `path` containing `contents`, replacing the file that
+    /// is already there.  Missing parent directories are created.
+    pub fn create_file<P, C>(&mut self, path: P, contents: C) -> Result<&mut Self, WasiFsError>
+    where
+        P: AsRef<Path>,
+        C: Into<Vec<u8>>,
+    {
+        let mut names = path_to_names(path.as_ref())?;
+        let name = names.pop().ok_or(WasiFsError::InvalidInput)?;
+        let entries = self.dir_entries_mut(&names)?;
+        if let Some(MemFsEntry::Dir { .. }) = entries.get(&name) {
+            return Err(WasiFsError::AlreadyExists);
+        }
+        entries.insert(
+            name,
+            MemFsEntry::File {
+                contents: contents.into(),
+                metadata: MemFsMetadata::default(),
+            },
+        );
+
+        Ok(self)
+    }
+
+    /// Get the file or directory at `path`
+    pub fn get<P: AsRef<Path>>(&self, path: P) -> Option<&MemFsEntry> {
+        let names = path_to_names(path.as_ref()).ok()?;
+        let (name, parents) = names.split_last()?;
+        let mut entries = &self.entries;
+        for parent in parents {
+            match entries.get(parent)? {
+                MemFsEntry::Dir { entries: inner, .. } => entries = inner,
+                MemFsEntry::File { .. } => return None,
+            }
+        }
+
+        entries.get(name)
+    }
+
+    /// Get the contents of the file at `path`
+    pub fn read_file<P: AsRef<Path>>(&self, path: P) -> Option<&[u8]> {
+        match self.get(path)? {
+            MemFsEntry::File { contents, .. } => Some(contents),
+            MemFsEntry::Dir { .. } => None,
+        }
+    }
+
+    /// The files and directories at the top of the file system
+    pub fn entries(&self) -> &BTreeMap<String, MemFsEntry> {
+        &self.entries
+    }
+
+    /// Get the entries of the directory made of the path components `names`,
+    /// creating the directories that don't exist yet
+    fn dir_entries_mut(
+        &mut self,
+        names: &[String],
+    ) -> Result<&mut BTreeMap<String, MemFsEntry>, WasiFsError> {
+        let mut entries = &mut self.entries;
+        for name in names {
+            let entry = entries
+                .entry(name.clone())
+                .or_insert_with(|| MemFsEntry::Dir {
+                    entries: BTreeMap::new(),
+                    metadata: MemFsMetadata::default(),
+                });
+            match entry {
+                MemFsEntry::Dir { entries: inner, .. } => entries = inner,
+                MemFsEntry::File { .. } => return Err(WasiFsError::BaseNotDirectory),
+            }
+        }
+
+        Ok(entries)
+    }
+}
+
+/// Splits a path inside a [`MemFs`] into the names of its components
+fn path_to_names(path: &Path) -> Result<Vec<String>, WasiFsError> {
+    let mut names = vec![];
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => {
+                names.push(name.to_str().ok_or(WasiFsError::InvalidInput)?.to_string())
+            }
+            Component::RootDir | Component::CurDir => (),
+            Component::ParentDir | Component::Prefix(_) => return Err(WasiFsError::InvalidInput),
+        }
+    }
+
+    Ok(names)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn create_and_read() {
+        let mut mem_fs = MemFs::new();
+        mem_fs
+            .create_file("/a/b/file.txt", "hello")
+            .unwrap()
+            .create_dir("a/c")
+            .unwrap()
+            .create_file("a/b/file.txt", "replaced")
+            .unwrap();
+
+        assert_eq!(mem_fs.read_file("a/b/file.txt"), Some(&b"replaced"[..]));
+        assert!(match mem_fs.get("a/c") {
+            Some(MemFsEntry::Dir { entries, .. }) => entries.is_empty(),
+            _ => false,
+        });
+        assert_eq!(mem_fs.read_file("a/b"), None);
+        assert_eq!(mem_fs.get("a/b/missing"), None);
+        assert_eq!(mem_fs.entries().len(), 1);
+    }
+
+    #[test]
+    fn invalid_paths() {
+        let mut mem_fs = MemFs::new();
+        mem_fs.create_file("file", "").unwrap();
+
+        assert_eq!(
+            mem_fs.create_dir("file/dir").unwrap_err(),
+            WasiFsError::BaseNotDirectory
+        );
+        assert_eq!(
+            mem_fs.create_file("../escape", "").unwrap_err(),
+            WasiFsError::InvalidInput
+        );
+        mem_fs.create_dir("dir").unwrap();
+        assert_eq!(
+            mem_fs.create_file("dir", "").unwrap_err(),
+            WasiFsError::AlreadyExists
+        );
+        assert_eq!(
+            mem_fs.create_file("", "").unwrap_err(),
+            WasiFsError::InvalidInput
+        );
+    }
+}
@@ -8,21 +8,26 @@
 //!
 //! A preopened directory is a directory or directory + name combination passed into the
 //! `generate_import_object` function.  These are directories that the caller has given
-//! the WASI module permission to access.
+//! the WASI module permission to access.  A preopened directory can also exist only in memory,
+//! see [`MemFs`].
 //!
 //! You can implement `WasiFile` for your own types to get custom behavior and extend WASI, see the
 //! [WASI plugin example](https://github.com/wasmerio/wasmer/blob/master/examples/plugin.rs).
 
 mod builder;
+mod mem_fs;
+mod socket;
 mod types;
 
 pub use self::builder::*;
+pub use self::mem_fs::*;
+pub use self::socket::*;
 pub use self::types::*;
 use crate::syscalls::types::*;
 use generational_arena::Arena;
 pub use generational_arena::Index as Inode;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::{
     borrow::Borrow,
     cell::Cell,
@@ -50,6 +55,12 @@ const STDOUT_DEFAULT_RIGHTS: __wasi_rights_t = __WASI_RIGHT_FD_DATASYNC
     | __WASI_RIGHT_FD_FILESTAT_GET
     | __WASI_RIGHT_POLL_FD_READWRITE;
 const STDERR_DEFAULT_RIGHTS: __wasi_rights_t = STDOUT_DEFAULT_RIGHTS;
+const SOCKET_DEFAULT_RIGHTS: __wasi_rights_t = __WASI_RIGHT_FD_READ
+    | __WASI_RIGHT_FD_WRITE
+    | __WASI_RIGHT_FD_FDSTAT_SET_FLAGS
+    | __WASI_RIGHT_FD_FILESTAT_GET
+    | __WASI_RIGHT_POLL_FD_READWRITE
+    | __WASI_RIGHT_SOCK_SHUTDOWN;
 
 /// Get WasiState from a Ctx
 ///
@@ -97,6 +108,9 @@ pub enum Kind {
         path: PathBuf,
         /// The entries of a directory are lazily filled.
         entries: HashMap<String, Inode>,
+        /// The directory only exists in memory: `entries` is always complete
+        /// and `path` is not a path on the host system
+        in_memory: bool,
     },
     /// The same as Dir but without the irrelevant bits
     /// The root is immutable after creation; generally the Kind::Root
@@ -122,6 +136,10 @@ pub enum Kind {
     Buffer {
         buffer: Vec<u8>,
     },
+    /// A stream socket, only reachable through its fd
+    Socket {
+        handle: Box<dyn WasiSocket>,
+    },
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -202,6 +220,7 @@ impl WasiFs {
                     parent: Some(root_inode),
                     path: dir.clone(),
                     entries: Default::default(),
+                    in_memory: false,
                 }
             } else {
                 return Err(format!(
@@ -252,6 +271,7 @@ impl WasiFs {
                     parent: Some(root_inode),
                     path: real_dir.clone(),
                     entries: Default::default(),
+                    in_memory: false,
                 }
             } else {
                 return Err(format!(
@@ -318,6 +338,7 @@ impl WasiFs {
                     parent: Some(root_inode),
                     path: path.clone(),
                     entries: Default::default(),
+                    in_memory: false,
                 }
             } else {
                 return Err(format!(
@@ -326,48 +347,7 @@ impl WasiFs {
                 ));
             };
 
-            let rights = {
-                // TODO: review tell' and fd_readwrite
-                let mut rights =
-                    __WASI_RIGHT_FD_ADVISE | __WASI_RIGHT_FD_TELL | __WASI_RIGHT_FD_SEEK;
-                if *read {
-                    rights |= __WASI_RIGHT_FD_READ
-                        | __WASI_RIGHT_PATH_OPEN
-                        | __WASI_RIGHT_FD_READDIR
-                        | __WASI_RIGHT_PATH_READLINK
-                        | __WASI_RIGHT_PATH_FILESTAT_GET
-                        | __WASI_RIGHT_FD_FILESTAT_GET
-                        | __WASI_RIGHT_PATH_LINK_SOURCE
-                        | __WASI_RIGHT_PATH_RENAME_SOURCE
-                        | __WASI_RIGHT_POLL_FD_READWRITE
-                        | __WASI_RIGHT_SOCK_SHUTDOWN;
-                }
-                if *write {
-                    rights |= __WASI_RIGHT_FD_FDSTAT_SET_FLAGS
-                        | __WASI_RIGHT_FD_WRITE
-                        | __WASI_RIGHT_FD_SYNC
-                        | __WASI_RIGHT_FD_ALLOCATE
-                        | __WASI_RIGHT_PATH_OPEN
-                        | __WASI_RIGHT_PATH_RENAME_TARGET
-                        | __WASI_RIGHT_PATH_FILESTAT_SET_SIZE
-                        | __WASI_RIGHT_PATH_FILESTAT_SET_TIMES
-                        | __WASI_RIGHT_FD_FILESTAT_SET_SIZE
-                        | __WASI_RIGHT_FD_FILESTAT_SET_TIMES
-                        | __WASI_RIGHT_PATH_REMOVE_DIRECTORY
-                        | __WASI_RIGHT_PATH_UNLINK_FILE
-                        | __WASI_RIGHT_POLL_FD_READWRITE
-                        | __WASI_RIGHT_SOCK_SHUTDOWN;
-                }
-                if *create {
-                    rights |= __WASI_RIGHT_PATH_CREATE_DIRECTORY
-                        | __WASI_RIGHT_PATH_CREATE_FILE
-                        | __WASI_RIGHT_PATH_LINK_TARGET
-                        | __WASI_RIGHT_PATH_OPEN
-                        | __WASI_RIGHT_PATH_RENAME_TARGET;
-                }
-
-                rights
-            };
+            let rights = preopened_dir_rights(*read, *write, *create);
             let inode = if let Some(alias) = &alias {
                 wasi_fs.create_inode(kind, true, alias.clone())
             } else {
@@ -566,6 +546,7 @@ impl WasiFs {
                         parent: Some(cur_inode),
                         path: PathBuf::from(""),
                         entries: HashMap::new(),
+                        in_memory: true,
                     };
 
                     let inode =
@@ -703,6 +684,11 @@ impl WasiFs {
                     Err(__WASI_EBADF)
                 }
             }
+            Kind::Buffer { buffer } => {
+                let new_size = buffer.len() as u64;
+                self.inodes[fd.inode].stat.st_size = new_size;
+                Ok(new_size as __wasi_filesize_t)
+            }
             Kind::Dir { .. } | Kind::Root { .. } => Err(__WASI_EISDIR),
             _ => Err(__WASI_EINVAL),
         }
@@ -725,11 +711,11 @@ impl WasiFs {
         &mut self,
         base: __wasi_fd_t,
         path: &str,
-        mut symlink_count: u32,
+        symlink_count: u32,
         follow_symlinks: bool,
     ) -> Result<Inode, __wasi_errno_t> {
         if symlink_count > MAX_SYMLINKS {
-            return Err(__WASI_EMLINK);
+            return Err(__WASI_ELOOP);
         }
 
         let base_dir = self.get_fd(base)?;
@@ -738,171 +724,166 @@ impl WasiFs {
         let mut cur_inode = base_dir.inode;
         let n_components = path.components().count();
         // TODO: rights checks
-        'path_iter: for (i, component) in path.components().enumerate() {
-            // used to terminate symlink resolution properly
+        for (i, component) in path.components().enumerate() {
+            // a symlink in the last component is only resolved when requested
             let last_component = i + 1 == n_components;
             // for each component traverse file structure
             // loading inodes as necessary
-            'symlink_resolution: while symlink_count < MAX_SYMLINKS {
-                match &mut self.inodes[cur_inode].kind {
-                    Kind::Buffer { .. } => unimplemented!("state::get_inode_at_path for buffers"),
-                    Kind::Dir {
-                        ref mut entries,
-                        ref path,
-                        ref parent,
-                        ..
-                    } => {
-                        match component.as_os_str().to_string_lossy().borrow() {
-                            ".." => {
-                                if let Some(p) = parent {
-                                    cur_inode = *p;
-                                    continue 'path_iter;
-                                } else {
-                                    return Err(__WASI_EACCES);
-                                }
-                            }
-                            "." => continue 'path_iter,
-                            _ => (),
-                        }
-                        // used for full resolution of symlinks
-                        let mut loop_for_symlink = false;
-                        if let Some(entry) =
-                            entries.get(component.as_os_str().to_string_lossy().as_ref())
-                        {
-                            cur_inode = *entry;
-                        } else {
-                            let file = {
-                                let mut cd = path.clone();
-                                cd.push(component);
-                                cd
-                            };
-                            let metadata = file.symlink_metadata().ok().ok_or(__WASI_EINVAL)?;
-                            let file_type = metadata.file_type();
-                            // we want to insert newly opened dirs and files, but not transient symlinks
-                            // TODO: explain why (think about this deeply when well rested)
-                            let mut should_insert = false;
-
-                            let kind = if file_type.is_dir() {
-                                should_insert = true;
-                                // load DIR
-                                Kind::Dir {
-                                    parent: Some(cur_inode),
-                                    path: file.clone(),
-                                    entries: Default::default(),
-                                }
-                            } else if file_type.is_file() {
-                                should_insert = true;
-                                // load file
-                                Kind::File {
-                                    handle: None,
-                                    path: file.clone(),
-                                    fd: None,
-                                }
-                            } else if file_type.is_symlink() {
-                                let link_value = file.read_link().ok().ok_or(__WASI_EIO)?;
-                                debug!("attempting to decompose path {:?}", link_value);
-
-                                let (pre_open_dir_fd, relative_path) = if link_value.is_relative() {
-                                    self.path_into_pre_open_and_relative_path(&file)?
-                                } else {
-                                    unimplemented!("Absolute symlinks are not yet supported");
-                                };
-                                loop_for_symlink = true;
-                                symlink_count += 1;
-                                Kind::Symlink {
-                                    base_po_dir: pre_open_dir_fd,
-                                    path_to_symlink: relative_path,
-                                    relative_path: link_value,
-                                }
+            match &mut self.inodes[cur_inode].kind {
+                Kind::Dir {
+                    ref mut entries,
+                    ref path,
+                    ref parent,
+                    in_memory,
+                } => {
+                    match component.as_os_str().to_string_lossy().borrow() {
+                        ".." => {
+                            if let Some(p) = parent {
+                                cur_inode = *p;
+                                continue;
                             } else {
-                                unimplemented!("state::get_inode_at_path unknown file type: not file, directory, or symlink");
-                            };
-
-                            let new_inode =
-                                self.create_inode(kind, false, file.to_string_lossy().to_string())?;
-                            if should_insert {
-                                if let Kind::Dir {
-                                    ref mut entries, ..
-                                } = &mut self.inodes[cur_inode].kind
-                                {
-                                    entries.insert(
-                                        component.as_os_str().to_string_lossy().to_string(),
-                                        new_inode,
-                                    );
-                                }
-                            }
-                            cur_inode = new_inode;
-
-                            if loop_for_symlink && follow_symlinks {
-                                debug!("Following symlink to {:?}", cur_inode);
-                                continue 'symlink_resolution;
+                                return Err(__WASI_EACCES);
                             }
                         }
+                        "." => continue,
+                        _ => (),
                     }
-                    Kind::Root { entries } => {
-                        match component.as_os_str().to_string_lossy().borrow() {
-                            // the root's parent is the root
-                            ".." => continue 'path_iter,
-                            // the root's current directory is the root
-                            "." => continue 'path_iter,
-                            _ => (),
-                        }
-
-                        if let Some(entry) =
-                            entries.get(component.as_os_str().to_string_lossy().as_ref())
-                        {
-                            cur_inode = *entry;
+                    if let Some(entry) =
+                        entries.get(component.as_os_str().to_string_lossy().as_ref())
+                    {
+                        cur_inode = *entry;
+                    } else if *in_memory {
+                        return Err(__WASI_ENOENT);
+                    } else {
+                        let file = {
+                            let mut cd = path.clone();
+                            cd.push(component);
+                            cd
+                        };
+                        let metadata = file.symlink_metadata().ok().ok_or(__WASI_EINVAL)?;
+                        let file_type = metadata.file_type();
+                        // we want to insert newly opened dirs and files, but not transient symlinks
+                        // TODO: explain why (think about this deeply when well rested)
+                        let mut should_insert = false;
+
+                        let kind = if file_type.is_dir() {
+                            should_insert = true;
+                            // load DIR
+                            Kind::Dir {
+                                parent: Some(cur_inode),
+                                path: file.clone(),
+                                entries: Default::default(),
+                                in_memory: false,
+                            }
+                        } else if file_type.is_file() {
+                            should_insert = true;
+                            // load file
+                            Kind::File {
+                                handle: None,
+                                path: file.clone(),
+                                fd: None,
+                            }
+                        } else if file_type.is_symlink() {
+                            let link_value = file.read_link().ok().ok_or(__WASI_EIO)?;
+                            debug!("attempting to decompose path {:?}", link_value);
+
+                            // absolute symlinks could point anywhere on the host,
+                            // following them would escape the sandbox
+                            if !link_value.is_relative() {
+                                return Err(__WASI_ENOTCAPABLE);
+                            }
+                            let (pre_open_dir_fd, relative_path) =
+                                self.path_into_pre_open_and_relative_path(&file)?;
+                            Kind::Symlink {
+                                base_po_dir: pre_open_dir_fd,
+                                path_to_symlink: relative_path,
+                                relative_path: link_value,
+                            }
                         } else {
-                            return Err(__WASI_EINVAL);
-                        }
-                    }
-                    Kind::File { .. } => {
-                        return Err(__WASI_ENOTDIR);
-                    }
-                    Kind::Symlink {
-                        base_po_dir,
-                        path_to_symlink,
-                        relative_path,
-                    } => {
-                        let new_base_dir = *base_po_dir;
-                        // allocate to reborrow mutabily to recur
-                        let new_path = {
-                            /*if let Kind::Root { .. } = self.inodes[base_po_dir].kind {
-                                assert!(false, "symlinks should never be relative to the root");
-                            }*/
-                            let mut base = path_to_symlink.clone();
-                            // remove the symlink file itself from the path, leaving just the path from the base
-                            // to the dir containing the symlink
-                            base.pop();
-                            base.push(relative_path);
-                            base.to_string_lossy().to_string()
+                            // sockets, fifos and devices on the host can't be represented
+                            return Err(__WASI_ENOTSUP);
                         };
-                        debug!("Following symlink recursively");
-                        let symlink_inode = self.get_inode_at_path_inner(
-                            new_base_dir,
-                            &new_path,
-                            symlink_count + 1,
-                            follow_symlinks,
-                        )?;
-                        cur_inode = symlink_inode;
-                        // if we're at the very end and we found a file, then we're done
-                        // TODO: figure out if this should also happen for directories?
-                        if let Kind::File { .. } = &self.inodes[cur_inode].kind {
-                            // check if on last step
-                            if last_component {
-                                break 'symlink_resolution;
+
+                        let new_inode =
+                            self.create_inode(kind, false, file.to_string_lossy().to_string())?;
+                        if should_insert {
+                            if let Kind::Dir {
+                                ref mut entries, ..
+                            } = &mut self.inodes[cur_inode].kind
+                            {
+                                entries.insert(
+                                    component.as_os_str().to_string_lossy().to_string(),
+                                    new_inode,
+                                );
                             }
                         }
-                        continue 'symlink_resolution;
+                        cur_inode = new_inode;
+                    }
+                }
+                Kind::Root { entries } => {
+                    match component.as_os_str().to_string_lossy().borrow() {
+                        // the root's parent is the root
+                        ".." => continue,
+                        // the root's current directory is the root
+                        "." => continue,
+                        _ => (),
                     }
+
+                    if let Some(entry) =
+                        entries.get(component.as_os_str().to_string_lossy().as_ref())
+                    {
+                        cur_inode = *entry;
+                    } else {
+                        return Err(__WASI_EINVAL);
+                    }
+                }
+                // symlinks have already been resolved by the previous iteration
+                Kind::File { .. }
+                | Kind::Buffer { .. }
+                | Kind::Socket { .. }
+                | Kind::Symlink { .. } => {
+                    return Err(__WASI_ENOTDIR);
                 }
-                break 'symlink_resolution;
+            }
+
+            if !last_component || follow_symlinks {
+                cur_inode = self.resolve_symlink(cur_inode, symlink_count)?;
             }
         }
 
         Ok(cur_inode)
     }
 
+    /// Returns the inode that `inode` points to if it is a symlink, following
+    /// chains of symlinks, or `inode` itself otherwise.
+    ///
+    /// `symlink_count` is the number of symlinks already followed to reach
+    /// `inode`, once it exceeds [`MAX_SYMLINKS`] resolution fails with `ELOOP`.
+    fn resolve_symlink(
+        &mut self,
+        inode: Inode,
+        symlink_count: u32,
+    ) -> Result<Inode, __wasi_errno_t> {
+        let (base_po_dir, target_path) = match &self.inodes[inode].kind {
+            Kind::Symlink {
+                base_po_dir,
+                path_to_symlink,
+                relative_path,
+            } => {
+                let mut target_path = path_to_symlink.clone();
+                // remove the symlink file itself from the path, leaving just the path from the base
+                // to the dir containing the symlink
+                target_path.pop();
+                target_path.push(relative_path);
+                (*base_po_dir, target_path.to_string_lossy().to_string())
+            }
+            _ => return Ok(inode),
+        };
+
+        debug!("Following symlink recursively");
+        self.get_inode_at_path_inner(base_po_dir, &target_path, symlink_count + 1, true)
+    }
+
     /// Splits a path into the first preopened directory that is a parent of it,
     /// if such a preopened directory exists, and the rest of the path.
     ///
@@ -920,7 +901,8 @@ impl WasiFs {
             let po_inode = self.fd_map[po_fd].inode;
             let po_path = match &self.inodes[po_inode].kind {
                 Kind::Dir { path, .. } => &**path,
-                Kind::Root { .. } => Path::new("/"),
+                // the virtual root is not a host directory, so nothing on the host is inside it
+                Kind::Root { .. } => continue,
                 _ => unreachable!("Preopened FD that's not a directory or the root"),
             };
             // stem path based on it
@@ -995,10 +977,9 @@ impl WasiFs {
 
     /// gets a host file from a base directory and a path
     /// this function ensures the fs remains sandboxed
-    // NOTE: follow symlinks is super weird right now
-    // even if it's false, it still follows symlinks, just not the last
-    // symlink so
-    // This will be resolved when we have tests asserting the correct behavior
+    ///
+    /// Symlinks in the middle of the path are always followed, `follow_symlinks`
+    /// controls whether a symlink in the last component is followed too.
     pub(crate) fn get_inode_at_path(
         &mut self,
         base: __wasi_fd_t,
@@ -1010,11 +991,12 @@ impl WasiFs {
 
     /// Returns the parent Dir or Root that the file at a given path is in and the file name
     /// stripped off
+    ///
+    /// Symlinks leading to the parent are followed, the file itself is not resolved.
     pub(crate) fn get_parent_inode_at_path(
         &mut self,
         base: __wasi_fd_t,
         path: &Path,
-        follow_symlinks: bool,
     ) -> Result<(Inode, String), __wasi_errno_t> {
         let mut parent_dir = std::path::PathBuf::new();
         let mut components = path.components().rev();
@@ -1027,7 +1009,7 @@ impl WasiFs {
         for comp in components.rev() {
             parent_dir.push(comp);
         }
-        self.get_inode_at_path(base, &parent_dir.to_string_lossy(), follow_symlinks)
+        self.get_inode_at_path(base, &parent_dir.to_string_lossy(), true)
             .map(|v| (v, new_entity_name))
     }
 
@@ -1094,9 +1076,10 @@ impl WasiFs {
 
         Ok(__wasi_fdstat_t {
             fs_filetype: match self.inodes[fd.inode].kind {
-                Kind::File { .. } => __WASI_FILETYPE_REGULAR_FILE,
+                Kind::File { .. } | Kind::Buffer { .. } => __WASI_FILETYPE_REGULAR_FILE,
                 Kind::Dir { .. } => __WASI_FILETYPE_DIRECTORY,
                 Kind::Symlink { .. } => __WASI_FILETYPE_SYMBOLIC_LINK,
+                Kind::Socket { .. } => __WASI_FILETYPE_SOCKET_STREAM,
                 _ => __WASI_FILETYPE_UNKNOWN,
             },
             fs_flags: fd.flags,
@@ -1158,7 +1141,8 @@ impl WasiFs {
                     }
                     // TODO: verify this behavior
                     Kind::Dir { .. } => return Err(__WASI_EISDIR),
-                    Kind::Symlink { .. } => unimplemented!("WasiFs::flush Kind::Symlink"),
+                    // `path_open` resolves symlinks, so no fd refers to one
+                    Kind::Symlink { .. } => return Err(__WASI_EBADF),
                     Kind::Buffer { .. } => (),
                     _ => return Err(__WASI_EIO),
                 }
@@ -1227,6 +1211,168 @@ impl WasiFs {
         Ok(idx)
     }
 
+    /// Gives the WASI program access to `socket` through a new fd, which is
+    /// returned
+    pub fn open_socket(
+        &mut self,
+        socket: Box<dyn WasiSocket>,
+    ) -> Result<__wasi_fd_t, __wasi_errno_t> {
+        let inode =
+            self.create_inode(Kind::Socket { handle: socket }, false, "socket".to_string())?;
+
+        self.create_fd(SOCKET_DEFAULT_RIGHTS, 0, 0, Fd::READ | Fd::WRITE, inode)
+    }
+
+    /// Mounts `mem_fs` in the virtual root as a preopened directory named
+    /// `alias` that only exists in memory, returns its fd
+    pub(crate) fn mount_mem_fs(
+        &mut self,
+        alias: &str,
+        mem_fs: &MemFs,
+    ) -> Result<__wasi_fd_t, String> {
+        let root_inode = self
+            .get_fd(VIRTUAL_ROOT_FD)
+            .map_err(|e| format!("Could not get the virtual root: WASI error code: {}", e))?
+            .inode;
+        if let Kind::Root { entries } = &self.inodes[root_inode].kind {
+            if entries.contains_key(alias) {
+                return Err(format!(
+                    "A preopened directory named \"{}\" already exists",
+                    alias
+                ));
+            }
+        }
+
+        let inode = self.create_mem_fs_dir(
+            root_inode,
+            Path::new("/").join(alias),
+            alias.to_string(),
+            &mem_fs.entries,
+            mem_fs.metadata,
+        );
+        self.inodes[inode].is_preopened = true;
+        let rights = preopened_dir_rights(true, true, true);
+        let fd_flags = Fd::READ | Fd::WRITE | Fd::APPEND | Fd::TRUNCATE | Fd::CREATE;
+        let fd = self
+            .create_fd(rights, rights, 0, fd_flags, inode)
+            .map_err(|e| format!("Could not open fd for in-memory dir {:?}: {}", alias, e))?;
+        if let Kind::Root { entries } = &mut self.inodes[root_inode].kind {
+            entries.insert(alias.to_string(), inode);
+        }
+        self.preopen_fds.push(fd);
+
+        Ok(fd)
+    }
+
+    /// Creates the inodes of an in-memory directory and of everything in it
+    fn create_mem_fs_dir(
+        &mut self,
+        parent: Inode,
+        path: PathBuf,
+        name: String,
+        entries: &BTreeMap<String, MemFsEntry>,
+        metadata: MemFsMetadata,
+    ) -> Inode {
+        let kind = Kind::Dir {
+            parent: Some(parent),
+            path: path.clone(),
+            entries: HashMap::new(),
+            in_memory: true,
+        };
+        let inode = self.create_mem_fs_inode(kind, name, metadata);
+        for (child_name, entry) in entries {
+            let child = match entry {
+                MemFsEntry::Dir { entries, metadata } => self.create_mem_fs_dir(
+                    inode,
+                    path.join(child_name),
+                    child_name.clone(),
+                    entries,
+                    *metadata,
+                ),
+                MemFsEntry::File { contents, metadata } => self.create_mem_fs_inode(
+                    Kind::Buffer {
+                        buffer: contents.clone(),
+                    },
+                    child_name.clone(),
+                    *metadata,
+                ),
+            };
+            if let Kind::Dir { entries, .. } = &mut self.inodes[inode].kind {
+                entries.insert(child_name.clone(), child);
+            }
+        }
+
+        inode
+    }
+
+    /// Creates the inode of an in-memory file or directory with the timestamps
+    /// from `metadata`
+    fn create_mem_fs_inode(&mut self, kind: Kind, name: String, metadata: MemFsMetadata) -> Inode {
+        let mut stat = self.get_stat_for_kind(&kind).unwrap_or_default();
+        stat.st_ino = self.get_next_inode_index();
+        stat.st_atim = metadata.accessed;
+        stat.st_mtim = metadata.modified;
+        stat.st_ctim = metadata.created;
+
+        self.inodes.insert(InodeVal {
+            stat,
+            is_preopened: false,
+            name,
+            kind,
+        })
+    }
+
+    /// Takes a snapshot of the in-memory directory that `fd` refers to, such
+    /// as the preopened directory of a [`MemFs`] mounted with
+    /// [`WasiStateBuilder::mount_mem_fs`].
+    ///
+    /// Symlinks are left out of the snapshot.
+    pub fn mem_fs_snapshot(&self, fd: __wasi_fd_t) -> Result<MemFs, __wasi_errno_t> {
+        let inode = self.get_fd(fd)?.inode;
+        match self.mem_fs_entry(inode)? {
+            MemFsEntry::Dir { entries, metadata } => Ok(MemFs { entries, metadata }),
+            MemFsEntry::File { .. } => Err(__WASI_ENOTDIR),
+        }
+    }
+
+    /// Converts an in-memory inode and everything in it into a [`MemFsEntry`]
+    fn mem_fs_entry(&self, inode: Inode) -> Result<MemFsEntry, __wasi_errno_t> {
+        let inode_val = &self.inodes[inode];
+        let metadata = MemFsMetadata {
+            accessed: inode_val.stat.st_atim,
+            modified: inode_val.stat.st_mtim,
+            created: inode_val.stat.st_ctim,
+        };
+        match &inode_val.kind {
+            Kind::Dir {
+                entries,
+                in_memory: true,
+                ..
+            } => {
+                let mut mem_fs_entries = BTreeMap::new();
+                for (name, child) in entries {
+                    match &self.inodes[*child].kind {
+                        Kind::Dir { .. } | Kind::Buffer { .. } => {
+                            mem_fs_entries.insert(name.clone(), self.mem_fs_entry(*child)?);
+                        }
+                        // a `MemFs` can't hold symlinks
+                        _ => (),
+                    }
+                }
+                Ok(MemFsEntry::Dir {
+                    entries: mem_fs_entries,
+                    metadata,
+                })
+            }
+            Kind::Buffer { buffer } => Ok(MemFsEntry::File {
+                contents: buffer.clone(),
+                metadata,
+            }),
+            // the contents of host files and directories are not in memory
+            _ => Err(__WASI_EINVAL),
+        }
+    }
+
     /// Low level function to remove an inode, that is it deletes the WASI FS's
     /// knowledge of a file.
     ///
@@ -1239,6 +1385,57 @@ impl WasiFs {
         self.inodes.remove(inode)
     }
 
+    /// Returns true if `inode` is `dir` or is below it.
+    pub(crate) fn is_in_dir(&self, inode: Inode, dir: Inode) -> bool {
+        let mut current = Some(inode);
+        while let Some(ancestor) = current {
+            if ancestor == dir {
+                return true;
+            }
+            current = match &self.inodes[ancestor].kind {
+                Kind::Dir { parent, .. } => *parent,
+                _ => None,
+            };
+        }
+        false
+    }
+
+    /// Updates the directory at `inode` after it was moved to `new_path` on the
+    /// host: its parent becomes `new_parent` and the host paths of it and all of
+    /// its loaded descendants are rewritten to be under `new_path`.
+    pub(crate) fn move_dir(&mut self, inode: Inode, new_parent: Inode, new_path: PathBuf) {
+        let old_path = match &mut self.inodes[inode].kind {
+            Kind::Dir { parent, path, .. } => {
+                *parent = Some(new_parent);
+                std::mem::replace(path, new_path.clone())
+            }
+            _ => return,
+        };
+        let rebase = |path: &mut PathBuf| {
+            if let Ok(rest) = path.strip_prefix(&old_path) {
+                *path = new_path.join(rest);
+            }
+        };
+
+        let mut dirs = vec![inode];
+        while let Some(dir) = dirs.pop() {
+            let children: Vec<Inode> = match &self.inodes[dir].kind {
+                Kind::Dir { entries, .. } => entries.values().cloned().collect(),
+                _ => continue,
+            };
+            for child in children {
+                match &mut self.inodes[child].kind {
+                    Kind::Dir { path, .. } => {
+                        rebase(path);
+                        dirs.push(child);
+                    }
+                    Kind::File { path, .. } => rebase(path),
+                    _ => (),
+                }
+            }
+        }
+    }
+
     fn create_virtual_root(&mut self) -> Inode {
         let stat = __wasi_filestat_t {
             st_filetype: __WASI_FILETYPE_DIRECTORY,
@@ -1339,7 +1536,28 @@ impl WasiFs {
                 }
                 None => path.metadata().ok()?,
             },
+            Kind::Dir {
+                in_memory: true, ..
+            } => {
+                return Some(__wasi_filestat_t {
+                    st_filetype: __WASI_FILETYPE_DIRECTORY,
+                    ..__wasi_filestat_t::default()
+                })
+            }
             Kind::Dir { path, .. } => path.metadata().ok()?,
+            Kind::Buffer { buffer } => {
+                return Some(__wasi_filestat_t {
+                    st_filetype: __WASI_FILETYPE_REGULAR_FILE,
+                    st_size: buffer.len() as u64,
+                    ..__wasi_filestat_t::default()
+                })
+            }
+            Kind::Socket { .. } => {
+                return Some(__wasi_filestat_t {
+                    st_filetype: __WASI_FILETYPE_SOCKET_STREAM,
+                    ..__wasi_filestat_t::default()
+                })
+            }
             Kind::Symlink {
                 base_po_dir,
                 path_to_symlink,
@@ -1442,7 +1660,16 @@ impl WasiFs {
                 }
             }
             Kind::Root { .. } => return Err(__WASI_EACCES),
-            Kind::Symlink { .. } | Kind::Buffer { .. } => return Err(__WASI_EINVAL),
+            Kind::Buffer { .. } => {
+                // the contents stay in the inode for the next `path_open`
+                self.fd_map.remove(&fd);
+            }
+            Kind::Symlink { .. } => return Err(__WASI_EINVAL),
+            Kind::Socket { .. } => {
+                // sockets only live as long as their fd, dropping the inode closes them
+                let inode = self.fd_map.remove(&fd).ok_or(__WASI_EBADF)?.inode;
+                self.inodes.remove(inode);
+            }
         }
 
         Ok(())
@@ -1502,6 +1729,49 @@ impl WasiState {
     }
 }
 
+/// The rights of a preopened directory with the given permissions
+fn preopened_dir_rights(read: bool, write: bool, create: bool) -> __wasi_rights_t {
+    // TODO: review tell' and fd_readwrite
+    let mut rights = __WASI_RIGHT_FD_ADVISE | __WASI_RIGHT_FD_TELL | __WASI_RIGHT_FD_SEEK;
+    if read {
+        rights |= __WASI_RIGHT_FD_READ
+            | __WASI_RIGHT_PATH_OPEN
+            | __WASI_RIGHT_FD_READDIR
+            | __WASI_RIGHT_PATH_READLINK
+            | __WASI_RIGHT_PATH_FILESTAT_GET
+            | __WASI_RIGHT_FD_FILESTAT_GET
+            | __WASI_RIGHT_PATH_LINK_SOURCE
+            | __WASI_RIGHT_PATH_RENAME_SOURCE
+            | __WASI_RIGHT_POLL_FD_READWRITE
+            | __WASI_RIGHT_SOCK_SHUTDOWN;
+    }
+    if write {
+        rights |= __WASI_RIGHT_FD_FDSTAT_SET_FLAGS
+            | __WASI_RIGHT_FD_WRITE
+            | __WASI_RIGHT_FD_SYNC
+            | __WASI_RIGHT_FD_ALLOCATE
+            | __WASI_RIGHT_PATH_OPEN
+            | __WASI_RIGHT_PATH_RENAME_TARGET
+            | __WASI_RIGHT_PATH_FILESTAT_SET_SIZE
+            | __WASI_RIGHT_PATH_FILESTAT_SET_TIMES
+            | __WASI_RIGHT_FD_FILESTAT_SET_SIZE
+            | __WASI_RIGHT_FD_FILESTAT_SET_TIMES
+            | __WASI_RIGHT_PATH_REMOVE_DIRECTORY
+            | __WASI_RIGHT_PATH_UNLINK_FILE
+            | __WASI_RIGHT_POLL_FD_READWRITE
+            | __WASI_RIGHT_SOCK_SHUTDOWN;
+    }
+    if create {
+        rights |= __WASI_RIGHT_PATH_CREATE_DIRECTORY
+            | __WASI_RIGHT_PATH_CREATE_FILE
+            | __WASI_RIGHT_PATH_LINK_TARGET
+            | __WASI_RIGHT_PATH_OPEN
+            | __WASI_RIGHT_PATH_RENAME_TARGET;
+    }
+
+    rights
+}
+
 pub fn host_file_type_to_wasi_file_type(file_type: fs::FileType) -> __wasi_filetype_t {
     // TODO: handle other file types
     if file_type.is_dir() {
@@ -1514,3 +1784,112 @@ pub fn host_file_type_to_wasi_file_type(file_type: fs::FileType) -> __wasi_filet
         __WASI_FILETYPE_UNKNOWN
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_resolution() {
+        use std::os::unix::fs::symlink;
+
+        let dir = std::env::temp_dir().join(format!("wasi_symlinks_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir(&dir).unwrap();
+        fs::write(dir.join("file"), b"contents").unwrap();
+        symlink("file", dir.join("link")).unwrap();
+        symlink("link", dir.join("link_to_link")).unwrap();
+        symlink("loop_b", dir.join("loop_a")).unwrap();
+        symlink("loop_a", dir.join("loop_b")).unwrap();
+        symlink(dir.join("file"), dir.join("absolute")).unwrap();
+
+        let mut state = WasiState::new("test_prog")
+            .preopen_dir(&dir)
+            .unwrap()
+            .build()
+            .unwrap();
+        let fd = VIRTUAL_ROOT_FD + 1;
+
+        for path in &["link", "link_to_link"] {
+            let inode = state.fs.get_inode_at_path(fd, path, true).unwrap();
+            assert!(match &state.fs.inodes[inode].kind {
+                Kind::File { path, .. } => path == &dir.join("file"),
+                _ => false,
+            });
+            // the last component is only followed on request
+            let inode = state.fs.get_inode_at_path(fd, path, false).unwrap();
+            assert!(match state.fs.inodes[inode].kind {
+                Kind::Symlink { .. } => true,
+                _ => false,
+            });
+        }
+
+        assert_eq!(
+            state.fs.get_inode_at_path(fd, "loop_a", true),
+            Err(__WASI_ELOOP)
+        );
+        // symlinks leading to the last component are always followed
+        assert_eq!(
+            state.fs.get_inode_at_path(fd, "loop_a/file", false),
+            Err(__WASI_ELOOP)
+        );
+        assert_eq!(
+            state.fs.get_inode_at_path(fd, "absolute", true),
+            Err(__WASI_ENOTCAPABLE)
+        );
+        assert_eq!(
+            state.fs.get_inode_at_path(fd, "link/file", true),
+            Err(__WASI_ENOTDIR)
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn mem_fs_resolution() {
+        let mut mem_fs = MemFs::new();
+        mem_fs.create_file("dir/file.txt", "hello").unwrap();
+
+        let mut state = WasiState::new("test_prog")
+            .mount_mem_fs("data", mem_fs)
+            .unwrap()
+            .build()
+            .unwrap();
+        let fd = VIRTUAL_ROOT_FD + 1;
+
+        let inode = state
+            .fs
+            .get_inode_at_path(fd, "dir/../dir/file.txt", true)
+            .unwrap();
+        assert!(match &state.fs.inodes[inode].kind {
+            Kind::Buffer { buffer } => buffer == b"hello",
+            _ => false,
+        });
+        assert_eq!(state.fs.inodes[inode].stat.st_size, 5);
+        assert_eq!(
+            state
+                .fs
+                .get_inode_at_path(VIRTUAL_ROOT_FD, "data/dir/file.txt", true),
+            Ok(inode)
+        );
+        // nothing is ever looked up on the host
+        assert_eq!(
+            state.fs.get_inode_at_path(fd, "dir/missing", true),
+            Err(__WASI_ENOENT)
+        );
+        assert_eq!(
+            state.fs.get_inode_at_path(fd, "dir/file.txt/file", true),
+            Err(__WASI_ENOTDIR)
+        );
+
+        if let Kind::Buffer { buffer } = &mut state.fs.inodes[inode].kind {
+            buffer.extend_from_slice(b", world");
+        }
+        let snapshot = state.fs.mem_fs_snapshot(fd).unwrap();
+        assert_eq!(
+            snapshot.read_file("dir/file.txt"),
+            Some(&b"hello, world"[..])
+        );
+    }
+}
@@ -0,0 +1,153 @@
+//! Stream sockets for WASI, used by the `sock_*` syscalls.
+
+use crate::state::WasiFsError;
+use serde::{Deserialize, Serialize};
+use std::{collections::VecDeque, net::Shutdown};
+
+/// A stream socket exposed to WASI programs.
+///
+/// This trait relies on your socket closing when it goes out of scope via `Drop`
+#[typetag::serde(tag = "type")]
+pub trait WasiSocket: std::fmt::Debug + Send {
+    /// Receive data into `buf`, returning the number of bytes received.
+    ///
+    /// `Ok(0)` means that no more data will ever be received, either because
+    /// the peer shut down its writing half or because the reading half of this
+    /// socket was shut down.  If no data is available yet, return
+    /// `WasiFsError::WouldBlock` instead of blocking.
+    ///
+    /// When `peek` is true, the received data must stay queued so that the
+    /// next call receives it again.
+    fn recv(&mut self, buf: &mut [u8], peek: bool) -> Result<usize, WasiFsError>;
+
+    /// Send the data in `buf`, returning the number of bytes sent
+    fn send(&mut self, buf: &[u8]) -> Result<usize, WasiFsError>;
+
+    /// Shut down the reading half, the writing half, or both halves of the socket
+    fn shutdown(&mut self, how: Shutdown) -> Result<(), WasiFsError>;
+
+    /// Returns the number of bytes that can be received.  This function must not block
+    fn bytes_available(&self) -> Result<usize, WasiFsError>;
+}
+
+/// An in-memory socket connected to itself: everything sent on it is
+/// received back on it.
+///
+/// This is the default socket backend; it lets programs using sockets run
+/// without any network access.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct LoopbackSocket {
+    buffer: VecDeque<u8>,
+    read_shutdown: bool,
+    write_shutdown: bool,
+}
+
+impl LoopbackSocket {
+    /// Create a loopback socket with nothing queued
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a loopback socket with `data` already queued to be received
+    pub fn with_data(data: &[u8]) -> Self {
+        Self {
+            buffer: data.iter().cloned().collect(),
+            ..Self::default()
+        }
+    }
+}
+
+#[typetag::serde]
+impl WasiSocket for LoopbackSocket {
+    fn recv(&mut self, buf: &mut [u8], peek: bool) -> Result<usize, WasiFsError> {
+        if self.read_shutdown {
+            return Ok(0);
+        }
+        if self.buffer.is_empty() {
+            // nothing can arrive anymore once the only writer is shut down
+            return if self.write_shutdown {
+                Ok(0)
+            } else {
+                Err(WasiFsError::WouldBlock)
+            };
+        }
+
+        let amount = buf.len().min(self.buffer.len());
+        for (byte, queued) in buf.iter_mut().zip(self.buffer.iter()) {
+            *byte = *queued;
+        }
+        if !peek {
+            self.buffer.drain(..amount);
+        }
+
+        Ok(amount)
+    }
+
+    fn send(&mut self, buf: &[u8]) -> Result<usize, WasiFsError> {
+        if self.write_shutdown {
+            return Err(WasiFsError::BrokenPipe);
+        }
+        // data sent after the reading half was shut down is discarded
+        if !self.read_shutdown {
+            self.buffer.extend(buf);
+        }
+
+        Ok(buf.len())
+    }
+
+    fn shutdown(&mut self, how: Shutdown) -> Result<(), WasiFsError> {
+        match how {
+            Shutdown::Read => self.read_shutdown = true,
+            Shutdown::Write => self.write_shutdown = true,
+            Shutdown::Both => {
+                self.read_shutdown = true;
+                self.write_shutdown = true;
+            }
+        }
+        if self.read_shutdown {
+            self.buffer.clear();
+        }
+
+        Ok(())
+    }
+
+    fn bytes_available(&self) -> Result<usize, WasiFsError> {
+        Ok(self.buffer.len())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn loopback_receives_what_it_sends() {
+        let mut socket = LoopbackSocket::new();
+        let mut buf = [0; 8];
+        assert_eq!(socket.recv(&mut buf, false), Err(WasiFsError::WouldBlock));
+
+        assert_eq!(socket.send(b"hello"), Ok(5));
+        assert_eq!(socket.bytes_available(), Ok(5));
+        assert_eq!(socket.recv(&mut buf[..3], true), Ok(3));
+        assert_eq!(&buf[..3], b"hel");
+        assert_eq!(socket.recv(&mut buf, false), Ok(5));
+        assert_eq!(&buf[..5], b"hello");
+        assert_eq!(socket.bytes_available(), Ok(0));
+    }
+
+    #[test]
+    fn loopback_shutdown() {
+        let mut socket = LoopbackSocket::with_data(b"data");
+        socket.shutdown(Shutdown::Write).unwrap();
+        assert_eq!(socket.send(b"more"), Err(WasiFsError::BrokenPipe));
+
+        let mut buf = [0; 8];
+        assert_eq!(socket.recv(&mut buf, false), Ok(4));
+        // end of stream once the queued data is consumed
+        assert_eq!(socket.recv(&mut buf, false), Ok(0));
+
+        let mut socket = LoopbackSocket::with_data(b"data");
+        socket.shutdown(Shutdown::Read).unwrap();
+        assert_eq!(socket.recv(&mut buf, false), Ok(0));
+    }
+}
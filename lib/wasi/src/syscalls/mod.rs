@@ -12,15 +12,17 @@ use crate::{
     ptr::{Array, WasmPtr},
     state::{
         self, host_file_type_to_wasi_file_type, iterate_poll_events, poll, Fd, HostFile, Inode,
-        InodeVal, Kind, PollEvent, PollEventBuilder, WasiFile, WasiFsError, WasiState,
+        InodeVal, Kind, PollEvent, PollEventBuilder, WasiFile, WasiFsError, WasiSocket, WasiState,
         MAX_SYMLINKS,
     },
     ExitCode,
 };
 use std::borrow::Borrow;
 use std::cell::Cell;
+use std::collections::hash_map::Entry;
 use std::convert::{Infallible, TryInto};
 use std::io::{self, Read, Seek, Write};
+use std::net::Shutdown;
 use wasmer_runtime_core::{memory::Memory, vm::Ctx};
 
 #[cfg(any(target_os = "freebsd", target_os = "linux", target_os = "macos"))]
@@ -84,6 +86,88 @@ fn read_bytes<T: Read>(
     Ok(bytes_read)
 }
 
+/// The size of the buffer receiving data from a socket before it is written to
+/// the iovecs, large enough to hold any UDP datagram
+const RECV_CHUNK_SIZE: usize = 64 * 1024;
+
+fn recv_bytes(
+    socket: &mut dyn WasiSocket,
+    memory: &Memory,
+    iovs_arr_cell: &[Cell<__wasi_iovec_t>],
+    ri_flags: __wasi_riflags_t,
+) -> Result<u32, __wasi_errno_t> {
+    // check all the buffers before receiving so that no data is lost on failure
+    let bufs = iovs_arr_cell
+        .iter()
+        .map(|iov| {
+            let iov_inner = iov.get();
+            iov_inner.buf.deref(memory, 0, iov_inner.buf_len)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let peek = ri_flags & __WASI_SOCK_RECV_PEEK != 0;
+    let wait_all = ri_flags & __WASI_SOCK_RECV_WAITALL != 0 && !peek;
+
+    // iovecs may overlap, so their total length is only bounded by the result type
+    let capacity = bufs
+        .iter()
+        .map(|buf| buf.len())
+        .sum::<usize>()
+        .min(u32::max_value() as usize);
+    let mut chunk = vec![0; capacity.min(RECV_CHUNK_SIZE)];
+    let mut bytes_received = 0;
+    // position of the next byte to write in the iovecs
+    let (mut buf_index, mut buf_offset) = (0, 0);
+    while bytes_received < capacity {
+        let chunk_len = chunk.len().min(capacity - bytes_received);
+        let mut received = match socket.recv(&mut chunk[..chunk_len], peek) {
+            Ok(0) => break,
+            Ok(n) => &chunk[..n],
+            // a partial message is better than losing the data received so far
+            Err(WasiFsError::WouldBlock) if bytes_received > 0 => break,
+            Err(e) => return Err(e.into_wasi_err()),
+        };
+        bytes_received += received.len();
+
+        while !received.is_empty() {
+            let buf = &bufs[buf_index][buf_offset..];
+            let (head, tail) = received.split_at(received.len().min(buf.len()));
+            for (cell, byte) in buf.iter().zip(head) {
+                cell.set(*byte);
+            }
+            received = tail;
+            buf_offset += head.len();
+            if buf_offset == bufs[buf_index].len() {
+                buf_index += 1;
+                buf_offset = 0;
+            }
+        }
+
+        if !wait_all {
+            break;
+        }
+    }
+
+    Ok(bytes_received as u32)
+}
+
+fn send_bytes(
+    socket: &mut dyn WasiSocket,
+    memory: &Memory,
+    iovs_arr_cell: &[Cell<__wasi_ciovec_t>],
+) -> Result<u32, __wasi_errno_t> {
+    let mut data = vec![];
+    for iov in iovs_arr_cell {
+        let iov_inner = iov.get();
+        let bytes = iov_inner.buf.deref(memory, 0, iov_inner.buf_len)?;
+        data.extend(bytes.iter().map(Cell::get));
+    }
+
+    socket
+        .send(&data)
+        .map(|bytes_sent| bytes_sent as u32)
+        .map_err(WasiFsError::into_wasi_err)
+}
+
 /// checks that `rights_check_set` is a subset of `rights_set`
 fn has_rights(rights_set: __wasi_rights_t, rights_check_set: __wasi_rights_t) -> bool {
     rights_set | rights_check_set == rights_set
@@ -352,6 +436,7 @@ pub fn fd_allocate(
         }
         Kind::Symlink { .. } => return __WASI_EBADF,
         Kind::Dir { .. } | Kind::Root { .. } => return __WASI_EISDIR,
+        Kind::Socket { .. } => return __WASI_EINVAL,
     }
     state.fs.inodes[inode].stat.st_size = new_size;
     debug!("New file size: {}", new_size);
@@ -534,7 +619,8 @@ pub fn fd_filestat_set_size(
         return __WASI_EACCES;
     }
 
-    match &mut state.fs.inodes[inode].kind {
+    let inode = &mut state.fs.inodes[inode];
+    match &mut inode.kind {
         Kind::File { handle, .. } => {
             if let Some(handle) = handle {
                 wasi_try!(handle.set_len(st_size).map_err(WasiFsError::into_wasi_err));
@@ -544,11 +630,13 @@ pub fn fd_filestat_set_size(
         }
         Kind::Buffer { buffer } => {
             buffer.resize(st_size as usize, 0);
+            inode.stat.st_mtim = wasi_try!(get_current_time_in_nanos());
         }
         Kind::Symlink { .. } => return __WASI_EBADF,
         Kind::Dir { .. } | Kind::Root { .. } => return __WASI_EISDIR,
+        Kind::Socket { .. } => return __WASI_EINVAL,
     }
-    state.fs.inodes[inode].stat.st_size = st_size;
+    inode.stat.st_size = st_size;
 
     __WASI_ESUCCESS
 }
@@ -693,10 +781,13 @@ pub fn fd_pread(
                     }
                 }
                 Kind::Dir { .. } | Kind::Root { .. } => return __WASI_EISDIR,
-                Kind::Symlink { .. } => unimplemented!("Symlinks in wasi::fd_pread"),
+                // `path_open` resolves symlinks, so no fd refers to one
+                Kind::Symlink { .. } => return __WASI_EBADF,
                 Kind::Buffer { buffer } => {
-                    wasi_try!(read_bytes(&buffer[(offset as usize)..], memory, iov_cells))
+                    let remaining = buffer.get((offset as usize)..).unwrap_or(&[]);
+                    wasi_try!(read_bytes(remaining, memory, iov_cells))
                 }
+                Kind::Socket { .. } => return __WASI_ESPIPE,
             }
         }
     };
@@ -771,7 +862,9 @@ pub fn fd_prestat_dir_name(
                 __WASI_EOVERFLOW
             }
         }
-        Kind::Symlink { .. } | Kind::Buffer { .. } | Kind::File { .. } => __WASI_ENOTDIR,
+        Kind::Symlink { .. } | Kind::Buffer { .. } | Kind::File { .. } | Kind::Socket { .. } => {
+            __WASI_ENOTDIR
+        }
     }
 }
 
@@ -847,12 +940,18 @@ pub fn fd_pwrite(
                     // TODO: verify
                     return __WASI_EISDIR;
                 }
-                Kind::Symlink { .. } => unimplemented!("Symlinks in wasi::fd_pwrite"),
-                Kind::Buffer { buffer } => wasi_try!(write_bytes(
-                    &mut buffer[(offset as usize)..],
-                    memory,
-                    iovs_arr_cell
-                )),
+                // `path_open` resolves symlinks, so no fd refers to one
+                Kind::Symlink { .. } => return __WASI_EBADF,
+                Kind::Buffer { buffer } => {
+                    // writing past the end grows the buffer
+                    let mut cursor = std::io::Cursor::new(buffer);
+                    cursor.set_position(offset);
+                    let bytes_written = wasi_try!(write_bytes(&mut cursor, memory, iovs_arr_cell));
+                    inode.stat.st_size = cursor.get_ref().len() as u64;
+                    inode.stat.st_mtim = wasi_try!(get_current_time_in_nanos());
+                    bytes_written
+                }
+                Kind::Socket { .. } => return __WASI_ESPIPE,
             };
 
             bytes_written
@@ -924,9 +1023,18 @@ pub fn fd_read(
                     // TODO: verify
                     return __WASI_EISDIR;
                 }
-                Kind::Symlink { .. } => unimplemented!("Symlinks in wasi::fd_read"),
+                // `path_open` resolves symlinks, so no fd refers to one
+                Kind::Symlink { .. } => return __WASI_EBADF,
                 Kind::Buffer { buffer } => {
-                    wasi_try!(read_bytes(&buffer[offset..], memory, iovs_arr_cell))
+                    let remaining = buffer.get(offset..).unwrap_or(&[]);
+                    wasi_try!(read_bytes(remaining, memory, iovs_arr_cell))
+                }
+                Kind::Socket { handle } => {
+                    // sockets have no offset to update
+                    let bytes_read =
+                        wasi_try!(recv_bytes(handle.as_mut(), memory, iovs_arr_cell, 0));
+                    nread_cell.set(bytes_read);
+                    return __WASI_ESUCCESS;
                 }
             };
 
@@ -976,35 +1084,54 @@ pub fn fd_readdir(
     let mut buf_idx = 0;
 
     let entries: Vec<(String, u8, u64)> = match &state.fs.inodes[working_dir.inode].kind {
-        Kind::Dir { path, entries, .. } => {
+        Kind::Dir {
+            path,
+            entries,
+            in_memory,
+            ..
+        } => {
             // TODO: refactor this code
             // we need to support multiple calls,
             // simple and obviously correct implementation for now:
             // maintain consistent order via lexacographic sorting
-            let fs_info = wasi_try!(wasi_try!(std::fs::read_dir(path).map_err(|_| __WASI_EIO))
-                .collect::<Result<Vec<_>, _>>()
-                .map_err(|_| __WASI_EIO));
-            let mut entry_vec = wasi_try!(fs_info
-                .into_iter()
-                .map(|entry| Ok((
-                    entry.file_name().to_string_lossy().to_string(),
-                    host_file_type_to_wasi_file_type(entry.file_type().map_err(|_| __WASI_EIO)?),
-                    0, // TODO: inode
-                )))
-                .collect::<Result<Vec<(String, u8, u64)>, _>>());
-            entry_vec.extend(
+            let mut entry_vec = if *in_memory {
+                // in-memory directories always know all of their entries
                 entries
                     .iter()
-                    .filter(|(_, inode)| state.fs.inodes[**inode].is_preopened)
                     .map(|(name, inode)| {
                         let entry = &state.fs.inodes[*inode];
-                        (
-                            format!("{}", entry.name),
-                            entry.stat.st_filetype,
-                            entry.stat.st_ino,
-                        )
-                    }),
-            );
+                        (name.clone(), entry.stat.st_filetype, entry.stat.st_ino)
+                    })
+                    .collect::<Vec<(String, u8, u64)>>()
+            } else {
+                let fs_info = wasi_try!(wasi_try!(std::fs::read_dir(path).map_err(|_| __WASI_EIO))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| __WASI_EIO));
+                let mut entry_vec = wasi_try!(fs_info
+                    .into_iter()
+                    .map(|entry| Ok((
+                        entry.file_name().to_string_lossy().to_string(),
+                        host_file_type_to_wasi_file_type(
+                            entry.file_type().map_err(|_| __WASI_EIO)?
+                        ),
+                        0, // TODO: inode
+                    )))
+                    .collect::<Result<Vec<(String, u8, u64)>, _>>());
+                entry_vec.extend(
+                    entries
+                        .iter()
+                        .filter(|(_, inode)| state.fs.inodes[**inode].is_preopened)
+                        .map(|(name, inode)| {
+                            let entry = &state.fs.inodes[*inode];
+                            (
+                                format!("{}", entry.name),
+                                entry.stat.st_filetype,
+                                entry.stat.st_ino,
+                            )
+                        }),
+                );
+                entry_vec
+            };
             entry_vec.sort_by(|a, b| a.0.cmp(&b.0));
             entry_vec
         }
@@ -1027,7 +1154,9 @@ pub fn fd_readdir(
                 })
                 .collect()
         }
-        Kind::File { .. } | Kind::Symlink { .. } | Kind::Buffer { .. } => return __WASI_ENOTDIR,
+        Kind::File { .. } | Kind::Symlink { .. } | Kind::Buffer { .. } | Kind::Socket { .. } => {
+            return __WASI_ENOTDIR
+        }
     };
 
     for (entry_path_str, wasi_file_type, ino) in entries.iter().skip(cookie as usize) {
@@ -1132,18 +1261,16 @@ pub fn fd_seek(
                         return __WASI_EINVAL;
                     }
                 }
-                Kind::Symlink { .. } => {
-                    unimplemented!("wasi::fd_seek not implemented for symlinks")
-                }
+                // `path_open` resolves symlinks, so no fd refers to one
+                Kind::Symlink { .. } => return __WASI_EBADF,
                 Kind::Dir { .. } | Kind::Root { .. } => {
                     // TODO: check this
                     return __WASI_EINVAL;
                 }
-                Kind::Buffer { .. } => {
-                    // seeking buffers probably makes sense
-                    // TODO: implement this
-                    return __WASI_EINVAL;
+                Kind::Buffer { ref buffer } => {
+                    fd_entry.offset = (buffer.len() as i64 + offset) as u64;
                 }
+                Kind::Socket { .. } => return __WASI_ESPIPE,
             }
         }
         __WASI_WHENCE_SET => fd_entry.offset = offset as u64,
@@ -1184,7 +1311,7 @@ pub fn fd_sync(ctx: &mut Ctx, fd: __wasi_fd_t) -> __wasi_errno_t {
             }
         }
         Kind::Root { .. } | Kind::Dir { .. } => return __WASI_EISDIR,
-        Kind::Buffer { .. } | Kind::Symlink { .. } => return __WASI_EINVAL,
+        Kind::Buffer { .. } | Kind::Symlink { .. } | Kind::Socket { .. } => return __WASI_EINVAL,
     }
 
     __WASI_ESUCCESS
@@ -1294,9 +1421,23 @@ pub fn fd_write(
                     // TODO: verify
                     return __WASI_EISDIR;
                 }
-                Kind::Symlink { .. } => unimplemented!("Symlinks in wasi::fd_write"),
+                // `path_open` resolves symlinks, so no fd refers to one
+                Kind::Symlink { .. } => return __WASI_EBADF,
                 Kind::Buffer { buffer } => {
-                    wasi_try!(write_bytes(&mut buffer[offset..], memory, iovs_arr_cell))
+                    // writing past the end grows the buffer
+                    let mut cursor = std::io::Cursor::new(buffer);
+                    cursor.set_position(offset as u64);
+                    let bytes_written = wasi_try!(write_bytes(&mut cursor, memory, iovs_arr_cell));
+                    // the size is synced below
+                    inode.stat.st_mtim = wasi_try!(get_current_time_in_nanos());
+                    bytes_written
+                }
+                Kind::Socket { handle } => {
+                    // sockets have neither an offset nor a size to update
+                    let bytes_written =
+                        wasi_try!(send_bytes(handle.as_mut(), memory, iovs_arr_cell));
+                    nwritten_cell.set(bytes_written);
+                    return __WASI_ESUCCESS;
                 }
             };
 
@@ -1368,6 +1509,7 @@ pub fn path_create_directory(
                 ref mut entries,
                 path,
                 parent,
+                in_memory,
             } => {
                 match comp.borrow() {
                     ".." => {
@@ -1385,15 +1527,20 @@ pub fn path_create_directory(
                     let mut adjusted_path = path.clone();
                     // TODO: double check this doesn't risk breaking the sandbox
                     adjusted_path.push(comp);
-                    if adjusted_path.exists() && !adjusted_path.is_dir() {
-                        return __WASI_ENOTDIR;
-                    } else if !adjusted_path.exists() {
-                        wasi_try!(std::fs::create_dir(&adjusted_path).ok(), __WASI_EIO);
+                    // in-memory directories don't exist on the host
+                    let in_memory = *in_memory;
+                    if !in_memory {
+                        if adjusted_path.exists() && !adjusted_path.is_dir() {
+                            return __WASI_ENOTDIR;
+                        } else if !adjusted_path.exists() {
+                            wasi_try!(std::fs::create_dir(&adjusted_path).ok(), __WASI_EIO);
+                        }
                     }
                     let kind = Kind::Dir {
                         parent: Some(cur_dir_inode),
                         path: adjusted_path,
                         entries: Default::default(),
+                        in_memory,
                     };
                     let new_inode = wasi_try!(state.fs.create_inode(kind, false, comp.to_string()));
                     // reborrow to insert
@@ -1453,7 +1600,13 @@ pub fn path_filestat_get(
         path_string,
         flags & __WASI_LOOKUP_SYMLINK_FOLLOW != 0,
     ));
-    let stat = if state.fs.inodes[file_inode].is_preopened {
+    // the inode holds the only copy of the metadata of in-memory files and directories
+    let in_memory = match &state.fs.inodes[file_inode].kind {
+        Kind::Dir { in_memory, .. } => *in_memory,
+        Kind::Buffer { .. } => true,
+        _ => false,
+    };
+    let stat = if state.fs.inodes[file_inode].is_preopened || in_memory {
         state.fs.inodes[file_inode].stat.clone()
     } else {
         wasi_try!(state
@@ -1616,9 +1769,7 @@ pub fn path_link(
     ));
     let target_path_arg = std::path::PathBuf::from(new_path_str);
     let (target_parent_inode, new_entry_name) =
-        wasi_try!(state
-            .fs
-            .get_parent_inode_at_path(new_fd, &target_path_arg, false));
+        wasi_try!(state.fs.get_parent_inode_at_path(new_fd, &target_path_arg));
 
     if state.fs.inodes[source_inode].stat.st_nlink == __wasi_linkcount_t::max_value() {
         return __WASI_EMLINK;
@@ -1631,7 +1782,9 @@ pub fn path_link(
             entries.insert(new_entry_name, source_inode);
         }
         Kind::Root { .. } => return __WASI_EINVAL,
-        Kind::File { .. } | Kind::Symlink { .. } | Kind::Buffer { .. } => return __WASI_ENOTDIR,
+        Kind::File { .. } | Kind::Symlink { .. } | Kind::Buffer { .. } | Kind::Socket { .. } => {
+            return __WASI_ENOTDIR
+        }
     }
     state.fs.inodes[source_inode].stat.st_nlink += 1;
 
@@ -1775,7 +1928,25 @@ pub fn path_open(
                     false,
                 )));
             }
-            Kind::Buffer { .. } => unimplemented!("wasi::path_open for Buffer type files"),
+            Kind::Buffer { buffer } => {
+                if o_flags & __WASI_O_DIRECTORY != 0 {
+                    return __WASI_ENOTDIR;
+                }
+                // buffers only exist in memory, so finding one means it already exists
+                if o_flags & __WASI_O_EXCL != 0 {
+                    return __WASI_EEXIST;
+                }
+                open_flags |= Fd::READ;
+                if adjusted_rights & __WASI_RIGHT_FD_WRITE != 0 {
+                    open_flags |= Fd::WRITE;
+                    if o_flags & __WASI_O_TRUNC != 0 {
+                        open_flags |= Fd::TRUNCATE;
+                        buffer.clear();
+                    }
+                }
+            }
+            // only reachable with an empty path relative to the socket's fd
+            Kind::Socket { .. } => return __WASI_ENOTDIR,
             Kind::Dir { .. } | Kind::Root { .. } => {
                 // TODO: adjust these to be correct
                 if o_flags & __WASI_O_EXCL != 0 {
@@ -1784,14 +1955,14 @@ pub fn path_open(
                     }
                 }
             }
-            Kind::Symlink {
-                base_po_dir,
-                path_to_symlink,
-                relative_path,
-            } => {
-                // I think this should return an error (because symlinks should be resolved away by the path traversal)
-                // TODO: investigate this
-                unimplemented!("SYMLINKS IN PATH_OPEN");
+            // path traversal only stops at a symlink when asked not to follow it
+            Kind::Symlink { .. } => return __WASI_ELOOP,
+        }
+        if open_flags & Fd::TRUNCATE != 0 {
+            let inode_val = &mut state.fs.inodes[inode];
+            if let Kind::Buffer { .. } = inode_val.kind {
+                inode_val.stat.st_size = 0;
+                inode_val.stat.st_mtim = wasi_try!(get_current_time_in_nanos());
             }
         }
         inode
@@ -1805,53 +1976,56 @@ pub fn path_open(
             debug!("Creating file");
             // strip end file name
 
-            let (parent_inode, new_entity_name) = wasi_try!(state.fs.get_parent_inode_at_path(
-                dirfd,
-                &path_arg,
-                dirflags & __WASI_LOOKUP_SYMLINK_FOLLOW != 0
-            ));
-            let new_file_host_path = match &state.fs.inodes[parent_inode].kind {
-                Kind::Dir { path, .. } => {
+            let (parent_inode, new_entity_name) =
+                wasi_try!(state.fs.get_parent_inode_at_path(dirfd, &path_arg));
+            let (new_file_host_path, in_memory) = match &state.fs.inodes[parent_inode].kind {
+                Kind::Dir {
+                    path, in_memory, ..
+                } => {
                     let mut new_path = path.clone();
                     new_path.push(&new_entity_name);
-                    new_path
+                    (new_path, *in_memory)
                 }
                 Kind::Root { .. } => return __WASI_EACCES,
                 _ => return __WASI_EINVAL,
             };
-            // once we got the data we need from the parent, we lookup the host file
-            // todo: extra check that opening with write access is okay
-            let handle = {
-                let mut open_options = std::fs::OpenOptions::new();
-                let open_options = open_options
-                    .read(true)
-                    .append(fs_flags & __WASI_FDFLAG_APPEND != 0)
-                    // TODO: ensure these rights are actually valid given parent, etc.
-                    // write access is required for creating a file
-                    .write(true)
-                    .create_new(true);
+            let kind = if in_memory {
                 open_flags |= Fd::READ | Fd::WRITE | Fd::CREATE | Fd::TRUNCATE;
 
-                Some(Box::new(HostFile::new(
-                    wasi_try!(open_options.open(&new_file_host_path).map_err(|e| {
-                        debug!("Error opening file {}", e);
-                        __WASI_EIO
-                    })),
-                    new_file_host_path.clone(),
-                    true,
-                    true,
-                    true,
-                )) as Box<dyn WasiFile>)
-            };
+                Kind::Buffer { buffer: vec![] }
+            } else {
+                // once we got the data we need from the parent, we lookup the host file
+                // todo: extra check that opening with write access is okay
+                let handle = {
+                    let mut open_options = std::fs::OpenOptions::new();
+                    let open_options = open_options
+                        .read(true)
+                        .append(fs_flags & __WASI_FDFLAG_APPEND != 0)
+                        // TODO: ensure these rights are actually valid given parent, etc.
+                        // write access is required for creating a file
+                        .write(true)
+                        .create_new(true);
+                    open_flags |= Fd::READ | Fd::WRITE | Fd::CREATE | Fd::TRUNCATE;
+
+                    Some(Box::new(HostFile::new(
+                        wasi_try!(open_options.open(&new_file_host_path).map_err(|e| {
+                            debug!("Error opening file {}", e);
+                            __WASI_EIO
+                        })),
+                        new_file_host_path.clone(),
+                        true,
+                        true,
+                        true,
+                    )) as Box<dyn WasiFile>)
+                };
 
-            let new_inode = {
-                let kind = Kind::File {
+                Kind::File {
                     handle,
                     path: new_file_host_path,
                     fd: None,
-                };
-                wasi_try!(state.fs.create_inode(kind, false, new_entity_name.clone()))
+                }
             };
+            let new_inode = wasi_try!(state.fs.create_inode(kind, false, new_entity_name.clone()));
 
             if let Kind::Dir {
                 ref mut entries, ..
@@ -1962,21 +2136,25 @@ pub fn path_remove_directory(
     let path_str = get_input_str!(memory, path, path_len);
 
     let inode = wasi_try!(state.fs.get_inode_at_path(fd, path_str, false));
-    let (parent_inode, childs_name) =
-        wasi_try!(state
-            .fs
-            .get_parent_inode_at_path(fd, std::path::Path::new(path_str), false));
+    let (parent_inode, childs_name) = wasi_try!(state
+        .fs
+        .get_parent_inode_at_path(fd, std::path::Path::new(path_str)));
 
-    let host_path_to_remove = match &state.fs.inodes[inode].kind {
-        Kind::Dir { entries, path, .. } => {
+    let (host_path_to_remove, in_memory) = match &state.fs.inodes[inode].kind {
+        Kind::Dir {
+            entries,
+            path,
+            in_memory,
+            ..
+        } => {
             if !entries.is_empty() {
                 return __WASI_ENOTEMPTY;
-            } else {
+            } else if !*in_memory {
                 if wasi_try!(std::fs::read_dir(path).ok(), __WASI_EIO).count() != 0 {
                     return __WASI_ENOTEMPTY;
                 }
             }
-            path.clone()
+            (path.clone(), *in_memory)
         }
         Kind::Root { .. } => return __WASI_EACCES,
         _ => return __WASI_ENOTDIR,
@@ -1986,17 +2164,21 @@ pub fn path_remove_directory(
         Kind::Dir {
             ref mut entries, ..
         } => {
-            let removed_inode = wasi_try!(entries.remove(&childs_name).ok_or(__WASI_EINVAL));
-            // TODO: make this a debug assert in the future
-            assert!(inode == removed_inode);
+            if entries.get(&childs_name) != Some(&inode) {
+                return __WASI_EINVAL;
+            }
+            entries.remove(&childs_name);
         }
         Kind::Root { .. } => return __WASI_EACCES,
-        _ => unreachable!(
-            "Internal logic error in wasi::path_remove_directory, parent is not a directory"
-        ),
+        // the parent of an inode is always a directory
+        _ => return __WASI_EIO,
     }
 
-    if let Err(_) = std::fs::remove_dir(path_str) {
+    // in-memory directories are gone once they're out of their parent
+    if in_memory {
+        return __WASI_ESUCCESS;
+    }
+    if let Err(_) = std::fs::remove_dir(host_path_to_remove) {
         // reinsert to prevent FS from being in bad state
         if let Kind::Dir {
             ref mut entries, ..
@@ -2053,13 +2235,26 @@ pub fn path_rename(
         }
     }
 
+    // this also makes sure the source is loaded into its parent's entries
+    let source_inode = wasi_try!(state.fs.get_inode_at_path(old_fd, source_str, false));
     let (source_parent_inode, source_entry_name) =
-        wasi_try!(state.fs.get_parent_inode_at_path(old_fd, source_path, true));
+        wasi_try!(state.fs.get_parent_inode_at_path(old_fd, source_path));
     let (target_parent_inode, target_entry_name) =
-        wasi_try!(state.fs.get_parent_inode_at_path(new_fd, target_path, true));
+        wasi_try!(state.fs.get_parent_inode_at_path(new_fd, target_path));
+    // a directory cannot be moved inside itself
+    if state.fs.is_in_dir(target_parent_inode, source_inode) {
+        return __WASI_EINVAL;
+    }
 
-    let host_adjusted_target_path = match &state.fs.inodes[target_parent_inode].kind {
-        Kind::Dir { entries, path, .. } => {
+    let (host_adjusted_target_path, target_in_memory) = match &state.fs.inodes[target_parent_inode]
+        .kind
+    {
+        Kind::Dir {
+            entries,
+            path,
+            in_memory,
+            ..
+        } => {
             if entries.contains_key(&target_entry_name) {
                 return __WASI_EEXIST;
             }
@@ -2067,18 +2262,28 @@ pub fn path_rename(
             // remove fd's own name which will be double counted
             out_path.pop();
             out_path.push(target_path);
-            out_path
+            (out_path, *in_memory)
         }
         Kind::Root { .. } => return __WASI_ENOTCAPABLE,
-        Kind::Symlink { .. } | Kind::File { .. } | Kind::Buffer { .. } => {
-            unreachable!("Fatal internal logic error: parent of inode is not a directory")
+        // the parent of an inode is always a directory
+        Kind::Symlink { .. } | Kind::File { .. } | Kind::Buffer { .. } | Kind::Socket { .. } => {
+            return __WASI_EIO
         }
     };
     let source_entry = match &mut state.fs.inodes[source_parent_inode].kind {
-        Kind::Dir { entries, .. } => wasi_try!(entries.remove(&source_entry_name), __WASI_EINVAL),
+        Kind::Dir {
+            entries, in_memory, ..
+        } => {
+            // moving between memory and the host would mean copying everything
+            if *in_memory != target_in_memory {
+                return __WASI_EXDEV;
+            }
+            wasi_try!(entries.remove(&source_entry_name), __WASI_EINVAL)
+        }
         Kind::Root { .. } => return __WASI_ENOTCAPABLE,
-        Kind::Symlink { .. } | Kind::File { .. } | Kind::Buffer { .. } => {
-            unreachable!("Fatal internal logic error: parent of inode is not a directory")
+        // the parent of an inode is always a directory
+        Kind::Symlink { .. } | Kind::File { .. } | Kind::Buffer { .. } | Kind::Socket { .. } => {
+            return __WASI_EIO
         }
     };
 
@@ -2105,18 +2310,42 @@ pub fn path_rename(
                 }
             }
         }
-        Kind::Dir { path, .. } => unimplemented!("wasi::path_rename on Directories"),
+        Kind::Dir {
+            path, in_memory, ..
+        } => {
+            let source_host_path = path.clone();
+            // if the rename failed we have to revert the previous change and then fail
+            if !*in_memory
+                && std::fs::rename(&source_host_path, &host_adjusted_target_path).is_err()
+            {
+                if let Kind::Dir { entries, .. } = &mut state.fs.inodes[source_parent_inode].kind {
+                    entries.insert(source_entry_name, source_entry);
+                }
+                return __WASI_EIO;
+            }
+            state
+                .fs
+                .move_dir(source_entry, target_parent_inode, host_adjusted_target_path);
+        }
         Kind::Buffer { .. } => {}
         Kind::Symlink { .. } => {}
-        Kind::Root { .. } => unreachable!("The root can not be moved"),
+        // the root and sockets are not in any directory
+        Kind::Root { .. } | Kind::Socket { .. } => {
+            if let Kind::Dir { entries, .. } = &mut state.fs.inodes[source_parent_inode].kind {
+                entries.insert(source_entry_name, source_entry);
+            }
+            return __WASI_EIO;
+        }
     }
 
     if let Kind::Dir { entries, .. } = &mut state.fs.inodes[target_parent_inode].kind {
-        let result = entries.insert(target_entry_name, source_entry);
-        assert!(
-            result.is_none(),
-            "Fatal error: race condition on filesystem detected or internal logic error"
-        );
+        // the target entry was checked to be free above
+        match entries.entry(target_entry_name) {
+            Entry::Vacant(entry) => {
+                entry.insert(source_entry);
+            }
+            Entry::Occupied(_) => return __WASI_EIO,
+        }
     }
 
     __WASI_ESUCCESS
@@ -2154,12 +2383,12 @@ pub fn path_symlink(
 
     // get the depth of the parent + 1 (UNDER INVESTIGATION HMMMMMMMM THINK FISH ^ THINK FISH)
     let old_path_path = std::path::Path::new(old_path_str);
-    let (source_inode, _) = wasi_try!(state.fs.get_parent_inode_at_path(fd, old_path_path, true));
+    let (source_inode, _) = wasi_try!(state.fs.get_parent_inode_at_path(fd, old_path_path));
     let depth = wasi_try!(state.fs.path_depth_from_fd(fd, source_inode)) - 1;
 
     let new_path_path = std::path::Path::new(new_path_str);
     let (target_parent_inode, entry_name) =
-        wasi_try!(state.fs.get_parent_inode_at_path(fd, new_path_path, true));
+        wasi_try!(state.fs.get_parent_inode_at_path(fd, new_path_path));
 
     // short circuit if anything is wrong, before we create an inode
     match &state.fs.inodes[target_parent_inode].kind {
@@ -2169,7 +2398,7 @@ pub fn path_symlink(
             }
         }
         Kind::Root { .. } => return __WASI_ENOTCAPABLE,
-        Kind::File { .. } | Kind::Symlink { .. } | Kind::Buffer { .. } => {
+        Kind::File { .. } | Kind::Symlink { .. } | Kind::Buffer { .. } | Kind::Socket { .. } => {
             unreachable!("get_parent_inode_at_path returned something other than a Dir or Root")
         }
     }
@@ -2231,28 +2460,33 @@ pub fn path_unlink_file(
     debug!("Requested file: {}", path_str);
 
     let inode = wasi_try!(state.fs.get_inode_at_path(fd, path_str, false));
-    let (parent_inode, childs_name) =
-        wasi_try!(state
-            .fs
-            .get_parent_inode_at_path(fd, std::path::Path::new(path_str), false));
+    match state.fs.inodes[inode].kind {
+        Kind::File { .. } | Kind::Symlink { .. } | Kind::Buffer { .. } => (),
+        Kind::Dir { .. } | Kind::Root { .. } => return __WASI_EISDIR,
+        // sockets are not in any directory
+        Kind::Socket { .. } => return __WASI_EIO,
+    }
+    let (parent_inode, childs_name) = wasi_try!(state
+        .fs
+        .get_parent_inode_at_path(fd, std::path::Path::new(path_str)));
 
     let removed_inode = match &mut state.fs.inodes[parent_inode].kind {
         Kind::Dir {
             ref mut entries, ..
         } => {
-            let removed_inode = wasi_try!(entries.remove(&childs_name).ok_or(__WASI_EINVAL));
-            // TODO: make this a debug assert in the future
-            assert!(inode == removed_inode);
-            debug_assert!(state.fs.inodes[inode].stat.st_nlink > 0);
-            removed_inode
+            if entries.get(&childs_name) != Some(&inode) {
+                return __WASI_EINVAL;
+            }
+            entries.remove(&childs_name);
+            inode
         }
         Kind::Root { .. } => return __WASI_EACCES,
-        _ => unreachable!(
-            "Internal logic error in wasi::path_unlink_file, parent is not a directory"
-        ),
+        // the parent of an inode is always a directory
+        _ => return __WASI_EIO,
     };
 
-    state.fs.inodes[removed_inode].stat.st_nlink -= 1;
+    let stat = &mut state.fs.inodes[removed_inode].stat;
+    stat.st_nlink = stat.st_nlink.saturating_sub(1);
     if state.fs.inodes[removed_inode].stat.st_nlink == 0 {
         match &mut state.fs.inodes[removed_inode].kind {
             Kind::File { handle, path, .. } => {
@@ -2265,31 +2499,32 @@ pub fn path_unlink_file(
                     wasi_try!(std::fs::remove_file(path).map_err(|_| __WASI_EIO));
                 }
             }
-            Kind::Dir { .. } | Kind::Root { .. } => return __WASI_EISDIR,
             Kind::Symlink { .. } => {
                 // TODO: actually delete real symlinks and do nothing for virtual symlinks
             }
-            _ => unimplemented!("wasi::path_unlink_file for Buffer"),
+            // buffers only exist in memory, removing the inode deletes them
+            Kind::Buffer { .. } => (),
+            // ruled out above
+            Kind::Dir { .. } | Kind::Root { .. } | Kind::Socket { .. } => (),
         }
         // TODO: test this on Windows and actually make it portable
         // make the file an orphan fd if the fd is still open
-        let fd_is_orphaned = if let Kind::File { handle, .. } = &state.fs.inodes[removed_inode].kind
-        {
-            handle.is_some()
-        } else {
-            false
+        let fd_is_orphaned = match &state.fs.inodes[removed_inode].kind {
+            Kind::File { handle, .. } => handle.is_some(),
+            Kind::Buffer { .. } => state
+                .fs
+                .fd_map
+                .values()
+                .any(|fd_entry| fd_entry.inode == removed_inode),
+            _ => false,
+        };
+        let removed_inode_val = match unsafe { state.fs.remove_inode(removed_inode) } {
+            Some(removed_inode_val) => removed_inode_val,
+            None => return __WASI_EIO,
         };
-        let removed_inode_val = unsafe { state.fs.remove_inode(removed_inode) };
-        assert!(
-            removed_inode_val.is_some(),
-            "Inode could not be removed because it doesn't exist"
-        );
 
         if fd_is_orphaned {
-            state
-                .fs
-                .orphan_fds
-                .insert(removed_inode, removed_inode_val.unwrap());
+            state.fs.orphan_fds.insert(removed_inode, removed_inode_val);
         }
     }
 
@@ -2370,7 +2605,8 @@ pub fn poll_oneoff(
                     clock_subs.push(clock_info);
                     None
                 } else {
-                    unimplemented!("Polling not implemented for clocks yet");
+                    // TODO: support polling the other clocks
+                    return __WASI_ENOTSUP;
                 }
             }
         };
@@ -2410,9 +2646,8 @@ pub fn poll_oneoff(
                         Kind::Dir { .. }
                         | Kind::Root { .. }
                         | Kind::Buffer { .. }
-                        | Kind::Symlink { .. } => {
-                            unimplemented!("polling read on non-files not yet supported")
-                        }
+                        | Kind::Symlink { .. }
+                        | Kind::Socket { .. } => return __WASI_ENOTSUP,
                     }
                 }
             };
@@ -2501,7 +2736,8 @@ pub fn proc_exit(ctx: &mut Ctx, code: __wasi_exitcode_t) -> Result<Infallible, E
 }
 pub fn proc_raise(ctx: &mut Ctx, sig: __wasi_signal_t) -> __wasi_errno_t {
     debug!("wasi::proc_raise");
-    unimplemented!("wasi::proc_raise")
+    // raising signals is not supported yet
+    __WASI_ENOTSUP
 }
 
 /// ### `random_get()`
@@ -2535,6 +2771,22 @@ pub fn sched_yield(ctx: &mut Ctx) -> __wasi_errno_t {
     __WASI_ESUCCESS
 }
 
+/// ### `sock_recv()`
+/// Receive a message from a socket
+/// Inputs:
+/// - `__wasi_fd_t sock`
+///     The socket to receive from
+/// - `const __wasi_iovec_t *ri_data`
+///     Vectors where the received data will be stored
+/// - `u32 ri_data_len`
+///     Length of `ri_data`
+/// - `__wasi_riflags_t ri_flags`
+///     `__WASI_SOCK_RECV_PEEK` and/or `__WASI_SOCK_RECV_WAITALL`
+/// Output:
+/// - `u32 *ro_datalen`
+///     Number of bytes stored in `ri_data`
+/// - `__wasi_roflags_t *ro_flags`
+///     Message flags, always empty for stream sockets
 pub fn sock_recv(
     ctx: &mut Ctx,
     sock: __wasi_fd_t,
@@ -2544,9 +2796,46 @@ pub fn sock_recv(
     ro_datalen: WasmPtr<u32>,
     ro_flags: WasmPtr<__wasi_roflags_t>,
 ) -> __wasi_errno_t {
-    debug!("wasi::sock_recv");
-    unimplemented!("wasi::sock_recv")
+    debug!("wasi::sock_recv: sock={}", sock);
+    let (memory, state) = get_memory_and_wasi_state(ctx, 0);
+
+    let iovs_arr_cell = wasi_try!(ri_data.deref(memory, 0, ri_data_len));
+    let ro_datalen_cell = wasi_try!(ro_datalen.deref(memory));
+    let ro_flags_cell = wasi_try!(ro_flags.deref(memory));
+
+    let fd_entry = wasi_try!(state.fs.get_fd(sock));
+    if !has_rights(fd_entry.rights, __WASI_RIGHT_FD_READ) {
+        return __WASI_EACCES;
+    }
+    let inode = fd_entry.inode;
+
+    let bytes_received = match &mut state.fs.inodes[inode].kind {
+        Kind::Socket { handle } => {
+            wasi_try!(recv_bytes(handle.as_mut(), memory, iovs_arr_cell, ri_flags))
+        }
+        _ => return __WASI_ENOTSOCK,
+    };
+
+    ro_datalen_cell.set(bytes_received);
+    ro_flags_cell.set(0);
+
+    __WASI_ESUCCESS
 }
+
+/// ### `sock_send()`
+/// Send a message on a socket
+/// Inputs:
+/// - `__wasi_fd_t sock`
+///     The socket to send on
+/// - `const __wasi_ciovec_t *si_data`
+///     Vectors holding the data to send
+/// - `u32 si_data_len`
+///     Length of `si_data`
+/// - `__wasi_siflags_t si_flags`
+///     Message flags, none are defined yet
+/// Output:
+/// - `u32 *so_datalen`
+///     Number of bytes sent
 pub fn sock_send(
     ctx: &mut Ctx,
     sock: __wasi_fd_t,
@@ -2555,10 +2844,58 @@ pub fn sock_send(
     si_flags: __wasi_siflags_t,
     so_datalen: WasmPtr<u32>,
 ) -> __wasi_errno_t {
-    debug!("wasi::sock_send");
-    unimplemented!("wasi::sock_send")
+    debug!("wasi::sock_send: sock={}", sock);
+    let (memory, state) = get_memory_and_wasi_state(ctx, 0);
+
+    let iovs_arr_cell = wasi_try!(si_data.deref(memory, 0, si_data_len));
+    let so_datalen_cell = wasi_try!(so_datalen.deref(memory));
+
+    let fd_entry = wasi_try!(state.fs.get_fd(sock));
+    if !has_rights(fd_entry.rights, __WASI_RIGHT_FD_WRITE) {
+        return __WASI_EACCES;
+    }
+    let inode = fd_entry.inode;
+
+    let bytes_sent = match &mut state.fs.inodes[inode].kind {
+        Kind::Socket { handle } => wasi_try!(send_bytes(handle.as_mut(), memory, iovs_arr_cell)),
+        _ => return __WASI_ENOTSOCK,
+    };
+
+    so_datalen_cell.set(bytes_sent);
+
+    __WASI_ESUCCESS
 }
+
+/// ### `sock_shutdown()`
+/// Shut down the receiving and/or sending half of a socket
+/// Inputs:
+/// - `__wasi_fd_t sock`
+///     The socket to shut down
+/// - `__wasi_sdflags_t how`
+///     `__WASI_SHUT_RD` and/or `__WASI_SHUT_WR`
 pub fn sock_shutdown(ctx: &mut Ctx, sock: __wasi_fd_t, how: __wasi_sdflags_t) -> __wasi_errno_t {
-    debug!("wasi::sock_shutdown");
-    unimplemented!("wasi::sock_shutdown")
+    debug!("wasi::sock_shutdown: sock={}, how={}", sock, how);
+    let (memory, state) = get_memory_and_wasi_state(ctx, 0);
+
+    let how = match how {
+        __WASI_SHUT_RD => Shutdown::Read,
+        __WASI_SHUT_WR => Shutdown::Write,
+        _ if how == __WASI_SHUT_RD | __WASI_SHUT_WR => Shutdown::Both,
+        _ => return __WASI_EINVAL,
+    };
+
+    let fd_entry = wasi_try!(state.fs.get_fd(sock));
+    if !has_rights(fd_entry.rights, __WASI_RIGHT_SOCK_SHUTDOWN) {
+        return __WASI_EACCES;
+    }
+    let inode = fd_entry.inode;
+
+    match &mut state.fs.inodes[inode].kind {
+        Kind::Socket { handle } => {
+            wasi_try!(handle.shutdown(how).map_err(WasiFsError::into_wasi_err));
+        }
+        _ => return __WASI_ENOTSOCK,
+    }
+
+    __WASI_ESUCCESS
 }
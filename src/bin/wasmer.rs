@@ -92,6 +92,18 @@ struct PrestandardFeatures {
     #[structopt(long = "enable-threads")]
     threads: bool,
 
+    /// Enable support for the bulk memory proposal.
+    #[structopt(long = "enable-bulk-memory")]
+    bulk_memory: bool,
+
+    /// Enable support for the multi-value proposal.
+    #[structopt(long = "enable-multi-value")]
+    multi_value: bool,
+
+    /// Enable support for the reference types proposal.
+    #[structopt(long = "enable-reference-types")]
+    reference_types: bool,
+
     /// Enable support for all pre-standard proposals.
     #[structopt(long = "enable-all")]
     all: bool,
@@ -108,6 +120,15 @@ impl PrestandardFeatures {
         if self.threads || self.all {
             features.enable_threads();
         }
+        if self.bulk_memory || self.all {
+            features.enable_bulk_memory();
+        }
+        if self.multi_value || self.all {
+            features.enable_multi_value();
+        }
+        if self.reference_types || self.all {
+            features.enable_reference_types();
+        }
         features.enable_sign_extension();
         features.enable_sat_float_to_int();
         features
@@ -118,6 +139,9 @@ impl PrestandardFeatures {
         Features {
             simd: self.simd || self.all,
             threads: self.threads || self.all,
+            bulk_memory: self.bulk_memory || self.all,
+            multi_value: self.multi_value || self.all,
+            reference_types: self.reference_types || self.all,
         }
     }
 }
@@ -104,6 +104,13 @@ pub fn parse_args(
                                 )));
                                 None
                             }),
+                        Type::ExternRef | Type::FuncRef => {
+                            arg_error = Some(InvokeError::CouldNotParseArg(format!(
+                                "Failed to parse `{:?}` as a reference",
+                                argument
+                            )));
+                            None
+                        }
                     } {
                         accumulator.push(value);
 